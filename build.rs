@@ -1,9 +1,11 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Compile the proto files
+    // Compile the proto files; the descriptor set backs gRPC server reflection
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
     tonic_build::configure()
         .build_server(true)
         .build_client(true)
         .out_dir("src/api")
+        .file_descriptor_set_path(out_dir.join("lastmile_descriptor.bin"))
         .compile_protos(
             &["proto/lastmile/v1/analytics.proto"],
             &["proto"],
@@ -0,0 +1,734 @@
+//! Exception alerting: late-rate rules with persisted alert state
+//!
+//! [`evaluate`] applies the escalation rules of the prescriptive exception
+//! report to the current data: carriers are compared with the network late
+//! rate, lanes against fixed late-rate thresholds. [`sync`] reconciles the
+//! result with the `exception_alert` table:
+//!
+//! - a breach with no open or acknowledged alert opens a new one;
+//! - a breach that is still present refreshes `last_seen` and its values,
+//!   and reopens an acknowledged alert whose severity went up;
+//! - an open or acknowledged alert whose breach is gone is resolved.
+//!
+//! A resolved alert is never reopened; a later breach of the same rule
+//! starts a new alert with its own `first_seen`, so "new since yesterday"
+//! is a filter on `first_seen`.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Datetime;
+use tracing::warn;
+
+use crate::db::DbConn;
+use crate::filter::ShipmentFilter;
+
+/// Shipments a carrier needs before it is checked
+pub const MIN_CARRIER_VOLUME: i64 = 50;
+
+/// Shipments a lane needs before it is checked
+pub const MIN_LANE_VOLUME: i64 = 20;
+
+/// Lane late rate above which a lane is flagged WARNING
+pub const LANE_WARNING_LATE_RATE: f64 = 0.35;
+
+/// Lane late rate above which a lane is flagged CRITICAL
+pub const LANE_CRITICAL_LATE_RATE: f64 = 0.50;
+
+/// Tries [`sync`] makes when a concurrent sync wins the race for a key
+const SYNC_ATTEMPTS: usize = 3;
+
+/// Unique index on `exception_alert.open_key` (see migration 6)
+const OPEN_KEY_INDEX: &str = "idx_exception_alert_open";
+
+// ============================================================================
+// Rules
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Severity {
+    Watch,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Watch => "WATCH",
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "WATCH" => Some(Severity::Watch),
+            "WARNING" => Some(Severity::Warning),
+            "CRITICAL" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// Carrier severity relative to the network late rate
+pub fn carrier_severity(late_rate: f64, baseline_late_rate: f64) -> Option<Severity> {
+    if late_rate > baseline_late_rate * 1.5 && late_rate > 0.30 {
+        Some(Severity::Critical)
+    } else if late_rate > baseline_late_rate * 1.3 {
+        Some(Severity::Warning)
+    } else if late_rate > baseline_late_rate * 1.1 {
+        Some(Severity::Watch)
+    } else {
+        None
+    }
+}
+
+/// Lane severity from its late rate alone
+pub fn lane_severity(late_rate: f64) -> Option<Severity> {
+    if late_rate > LANE_CRITICAL_LATE_RATE {
+        Some(Severity::Critical)
+    } else if late_rate > LANE_WARNING_LATE_RATE {
+        Some(Severity::Warning)
+    } else {
+        None
+    }
+}
+
+/// Network averages the carrier rules compare against
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertBaseline {
+    pub avg_late_rate: f64,
+    pub avg_delay: f64,
+    pub avg_variance: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CarrierDeviation {
+    pub carrier_ref: String,
+    pub volume: i64,
+    pub late_rate: f64,
+    pub avg_delay: f64,
+    pub variance: f64,
+    pub severity: Option<Severity>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LaneDeviation {
+    pub lane_ref: String,
+    pub origin_zip: String,
+    pub dest_zip: String,
+    pub volume: i64,
+    pub late_rate: f64,
+    pub avg_delay: f64,
+    pub severity: Severity,
+}
+
+/// One rule breach
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExceptionAlert {
+    pub entity_type: String,
+    pub entity_id: String,
+    pub metric: String,
+    pub current_value: f64,
+    pub baseline: f64,
+    pub deviation: f64,
+    pub severity: Severity,
+}
+
+impl ExceptionAlert {
+    /// Identifies the rule and entity across evaluations
+    pub fn key(&self) -> String {
+        format!("{}:{}:{}", self.entity_type, self.entity_id, self.metric)
+    }
+}
+
+/// Everything one evaluation found
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub baseline: AlertBaseline,
+    /// Every carrier with enough volume, worst first
+    pub carriers: Vec<CarrierDeviation>,
+    /// Lanes above the WARNING threshold, worst first
+    pub lanes: Vec<LaneDeviation>,
+    pub alerts: Vec<ExceptionAlert>,
+}
+
+/// Apply the alert rules to the shipments matching `filter`
+pub async fn evaluate(db: &DbConn, filter: &ShipmentFilter) -> Result<Evaluation> {
+    #[derive(Deserialize)]
+    struct BaselineRow {
+        volume: i64,
+        late: i64,
+        avg_delay: Option<f64>,
+        avg_variance: Option<f64>,
+    }
+
+    #[derive(Deserialize)]
+    struct CarrierRow {
+        carrier_ref: String,
+        volume: i64,
+        late: i64,
+        avg_delay: f64,
+        variance: f64,
+    }
+
+    #[derive(Deserialize)]
+    struct LaneRow {
+        lane_ref: String,
+        origin_zip: String,
+        dest_zip: String,
+        volume: i64,
+        late: i64,
+        avg_delay: f64,
+    }
+
+    let mut response = filter
+        .query(db, r#"
+            SELECT
+                count() as volume,
+                count(otd = "Late") as late,
+                math::mean(actual_transit_days - goal_transit_days) as avg_delay,
+                math::variance(actual_transit_days) as avg_variance
+            FROM shipment WHERE {filter}
+            GROUP ALL;
+
+            SELECT
+                carrier_ref,
+                count() as volume,
+                count(otd = "Late") as late,
+                math::mean(actual_transit_days - goal_transit_days) as avg_delay,
+                math::variance(actual_transit_days) as variance
+            FROM shipment WHERE {filter}
+            GROUP BY carrier_ref;
+
+            SELECT
+                lane_ref,
+                origin_zip,
+                dest_zip,
+                count() as volume,
+                count(otd = "Late") as late,
+                math::mean(delay) as avg_delay
+            FROM (
+                SELECT
+                    lane_ref,
+                    (origin_zip ?? origin_zip3) as origin_zip,
+                    (dest_zip ?? dest_zip3) as dest_zip,
+                    otd,
+                    actual_transit_days - goal_transit_days as delay
+                FROM shipment WHERE {filter} AND lane_ref != NONE
+            )
+            WHERE origin_zip != NONE AND dest_zip != NONE
+            GROUP BY lane_ref, origin_zip, dest_zip;
+        "#)
+        .await?;
+    let baseline: Option<BaselineRow> = response.take(0)?;
+    let carrier_rows: Vec<CarrierRow> = response.take(1)?;
+    let lane_rows: Vec<LaneRow> = response.take(2)?;
+
+    let baseline = match baseline {
+        Some(b) if b.volume > 0 => AlertBaseline {
+            avg_late_rate: b.late as f64 / b.volume as f64,
+            avg_delay: b.avg_delay.unwrap_or(0.0),
+            avg_variance: b.avg_variance.unwrap_or(0.0),
+        },
+        _ => AlertBaseline { avg_late_rate: 0.2, avg_delay: 0.5, avg_variance: 4.0 },
+    };
+
+    let mut carriers: Vec<CarrierDeviation> = carrier_rows
+        .into_iter()
+        .filter(|c| c.volume >= MIN_CARRIER_VOLUME)
+        .map(|c| {
+            let late_rate = c.late as f64 / c.volume as f64;
+            CarrierDeviation {
+                severity: carrier_severity(late_rate, baseline.avg_late_rate),
+                carrier_ref: c.carrier_ref,
+                volume: c.volume,
+                late_rate,
+                avg_delay: c.avg_delay,
+                variance: c.variance,
+            }
+        })
+        .collect();
+    carriers.sort_by(|a, b| b.late_rate.total_cmp(&a.late_rate));
+
+    let mut lanes: Vec<LaneDeviation> = lane_rows
+        .into_iter()
+        .filter(|l| l.volume >= MIN_LANE_VOLUME)
+        .filter_map(|l| {
+            let late_rate = l.late as f64 / l.volume as f64;
+            lane_severity(late_rate).map(|severity| LaneDeviation {
+                lane_ref: l.lane_ref,
+                origin_zip: l.origin_zip,
+                dest_zip: l.dest_zip,
+                volume: l.volume,
+                late_rate,
+                avg_delay: l.avg_delay,
+                severity,
+            })
+        })
+        .collect();
+    lanes.sort_by(|a, b| b.late_rate.total_cmp(&a.late_rate));
+
+    let mut alerts = Vec::new();
+    for c in &carriers {
+        if let Some(severity) = c.severity {
+            alerts.push(ExceptionAlert {
+                entity_type: "carrier".to_string(),
+                entity_id: c.carrier_ref.clone(),
+                metric: "late_rate".to_string(),
+                current_value: c.late_rate,
+                baseline: baseline.avg_late_rate,
+                deviation: c.late_rate - baseline.avg_late_rate,
+                severity,
+            });
+        }
+    }
+    for l in &lanes {
+        alerts.push(ExceptionAlert {
+            entity_type: "lane".to_string(),
+            entity_id: l.lane_ref.clone(),
+            metric: "late_rate".to_string(),
+            current_value: l.late_rate,
+            baseline: baseline.avg_late_rate,
+            deviation: l.late_rate - baseline.avg_late_rate,
+            severity: l.severity,
+        });
+    }
+
+    Ok(Evaluation { baseline, carriers, lanes, alerts })
+}
+
+// ============================================================================
+// State
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    Open,
+    Acknowledged,
+    Resolved,
+}
+
+impl AlertState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertState::Open => "open",
+            AlertState::Acknowledged => "acknowledged",
+            AlertState::Resolved => "resolved",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "open" => Some(AlertState::Open),
+            "acknowledged" => Some(AlertState::Acknowledged),
+            "resolved" => Some(AlertState::Resolved),
+            _ => None,
+        }
+    }
+}
+
+/// An alert as stored in `exception_alert`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredAlert {
+    pub id: String,
+    pub key: String,
+    #[serde(flatten)]
+    pub alert: ExceptionAlert,
+    pub state: AlertState,
+    pub first_seen: String,
+    pub last_seen: String,
+    #[serde(default)]
+    pub acknowledged_at: Option<String>,
+    #[serde(default)]
+    pub acknowledged_by: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub resolved_at: Option<String>,
+}
+
+/// What one [`sync`] changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncSummary {
+    pub opened: usize,
+    pub updated: usize,
+    /// Acknowledged alerts reopened because their severity went up
+    pub reopened: usize,
+    pub resolved: usize,
+}
+
+/// Reconcile stored alerts with the breaches in `alerts`.
+///
+/// Ingest and the API server's scheduler can sync at the same time. The
+/// unique `open_key` index stops both from opening an alert for the same key:
+/// the losing transaction fails and is retried against the winner's alerts.
+/// Any other error is returned as is.
+pub async fn sync(db: &DbConn, alerts: &[ExceptionAlert]) -> Result<SyncSummary> {
+    let mut attempt = 1;
+    loop {
+        match try_sync(db, alerts).await {
+            Err(e) if attempt < SYNC_ATTEMPTS && lost_race(&e) => {
+                warn!("Alert sync attempt {} failed ({}), retrying", attempt, e);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether a sync failed because a concurrent one committed first: it
+/// opened an alert for the same key, or wrote the same records
+fn lost_race(e: &anyhow::Error) -> bool {
+    use surrealdb::error::Db;
+    match e.downcast_ref::<surrealdb::Error>() {
+        Some(surrealdb::Error::Db(Db::IndexExists { index, .. })) => index == OPEN_KEY_INDEX,
+        Some(surrealdb::Error::Db(Db::TxRetryable)) => true,
+        // A conflict at commit fails every statement of the transaction
+        Some(surrealdb::Error::Db(Db::QueryNotExecutedDetail { message })) => {
+            *message == Db::TxRetryable.to_string()
+        }
+        // Remote servers send the error as text
+        Some(surrealdb::Error::Api(api)) => api.to_string().contains(OPEN_KEY_INDEX),
+        _ => false,
+    }
+}
+
+async fn try_sync(db: &DbConn, alerts: &[ExceptionAlert]) -> Result<SyncSummary> {
+    #[derive(Deserialize)]
+    struct Active {
+        key: String,
+        severity: Severity,
+        state: AlertState,
+    }
+
+    #[derive(Serialize)]
+    struct Row {
+        key: String,
+        /// Same as `key` until resolved; carries the unique index
+        open_key: String,
+        #[serde(flatten)]
+        alert: ExceptionAlert,
+        state: AlertState,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        first_seen: Option<Datetime>,
+        last_seen: Datetime,
+    }
+
+    let active: Vec<Active> = db
+        .query(r#"SELECT key, severity, state FROM exception_alert WHERE state != "resolved""#)
+        .await?
+        .take(0)?;
+    let active: HashMap<String, Active> = active.into_iter().map(|a| (a.key.clone(), a)).collect();
+
+    let now = Datetime::from(Utc::now());
+    let mut summary = SyncSummary::default();
+    let (mut opened, mut updated) = (Vec::new(), Vec::new());
+    for alert in alerts {
+        let key = alert.key();
+        match active.get(&key) {
+            Some(existing) => {
+                let state = if existing.state == AlertState::Acknowledged && alert.severity > existing.severity {
+                    summary.reopened += 1;
+                    AlertState::Open
+                } else {
+                    summary.updated += 1;
+                    existing.state
+                };
+                updated.push(Row {
+                    open_key: key.clone(),
+                    key,
+                    alert: alert.clone(),
+                    state,
+                    first_seen: None,
+                    last_seen: now.clone(),
+                });
+            }
+            None => {
+                summary.opened += 1;
+                opened.push(Row {
+                    open_key: key.clone(),
+                    key,
+                    alert: alert.clone(),
+                    state: AlertState::Open,
+                    first_seen: Some(now.clone()),
+                    last_seen: now.clone(),
+                });
+            }
+        }
+    }
+    let current: Vec<String> = alerts.iter().map(ExceptionAlert::key).collect();
+    let gone: Vec<String> = active.into_keys().filter(|k| !current.contains(k)).collect();
+    summary.resolved = gone.len();
+
+    db.query(r#"
+        BEGIN TRANSACTION;
+        FOR $row IN $opened {
+            CREATE exception_alert CONTENT $row;
+        };
+        FOR $row IN $updated {
+            UPDATE exception_alert MERGE $row WHERE key = $row.key AND state != "resolved";
+        };
+        UPDATE exception_alert SET state = "resolved", resolved_at = $now, open_key = NONE
+            WHERE key IN $gone AND state != "resolved";
+        COMMIT TRANSACTION;
+    "#)
+    .bind(("opened", opened))
+    .bind(("updated", updated))
+    .bind(("gone", gone))
+    .bind(("now", now))
+    .await?
+    .check()?;
+    Ok(summary)
+}
+
+/// Evaluate the whole network and reconcile the stored alerts
+pub async fn refresh(db: &DbConn) -> Result<SyncSummary> {
+    let evaluation = evaluate(db, &ShipmentFilter::default()).await?;
+    sync(db, &evaluation.alerts).await
+}
+
+/// Which stored alerts to list
+#[derive(Debug, Clone, Default)]
+pub struct AlertQuery {
+    pub state: Option<AlertState>,
+    pub severity: Option<Severity>,
+    pub entity_type: Option<String>,
+    /// Only alerts first seen at or after this time
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+/// Parse `since` as an RFC 3339 timestamp or a date (midnight UTC)
+pub fn parse_since(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|t| t.and_utc())
+        })
+}
+
+const ALERT_FIELDS: &str = "*, meta::id(id) as id";
+
+/// Stored alerts, most severe and most recently seen first
+pub async fn list(db: &DbConn, query: &AlertQuery) -> Result<Vec<StoredAlert>> {
+    let mut conditions = Vec::new();
+    if query.state.is_some() {
+        conditions.push("state = $state");
+    }
+    if query.severity.is_some() {
+        conditions.push("severity = $severity");
+    }
+    if query.entity_type.is_some() {
+        conditions.push("entity_type = $entity_type");
+    }
+    if query.since.is_some() {
+        conditions.push("first_seen >= $since");
+    }
+    let condition = if conditions.is_empty() { String::new() } else { format!("WHERE {}", conditions.join(" AND ")) };
+    let limit = if query.limit.is_some() { "LIMIT $limit" } else { "" };
+    let sql = format!(
+        r#"SELECT {}, (IF severity = "CRITICAL" THEN 0 ELSE IF severity = "WARNING" THEN 1 ELSE 2 END) AS severity_rank
+        FROM exception_alert {} ORDER BY severity_rank, last_seen DESC {}"#,
+        ALERT_FIELDS, condition, limit
+    );
+
+    let alerts: Vec<StoredAlert> = db
+        .query(sql)
+        .bind(("state", query.state.map(|s| s.as_str()).unwrap_or_default()))
+        .bind(("severity", query.severity.map(|s| s.as_str()).unwrap_or_default()))
+        .bind(("entity_type", query.entity_type.clone().unwrap_or_default()))
+        .bind(("since", Datetime::from(query.since.unwrap_or_default())))
+        .bind(("limit", query.limit.unwrap_or_default()))
+        .await?
+        .take(0)?;
+    Ok(alerts)
+}
+
+/// Acknowledge an open alert. Returns `None` if no alert has this ID;
+/// acknowledged and resolved alerts are returned unchanged.
+pub async fn acknowledge(db: &DbConn, id: &str, by: Option<String>, note: Option<String>) -> Result<Option<StoredAlert>> {
+    let sql = format!(
+        r#"
+        UPDATE type::thing("exception_alert", $id)
+            SET state = "acknowledged", acknowledged_at = time::now(),
+                acknowledged_by = $by, note = $note
+            WHERE state = "open";
+        SELECT {} FROM type::thing("exception_alert", $id);
+        "#,
+        ALERT_FIELDS
+    );
+    let mut response = db
+        .query(sql)
+        .bind(("id", id.to_string()))
+        .bind(("by", by))
+        .bind(("note", note))
+        .await?
+        .check()?;
+    let alert: Option<StoredAlert> = response.take(1)?;
+    Ok(alert)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_severity_rules() {
+        assert_eq!(carrier_severity(0.45, 0.25), Some(Severity::Critical));
+        // 1.5x the baseline but not above 30% late
+        assert_eq!(carrier_severity(0.27, 0.18), Some(Severity::Warning));
+        assert_eq!(carrier_severity(0.29, 0.25), Some(Severity::Watch));
+        assert_eq!(carrier_severity(0.25, 0.25), None);
+
+        assert_eq!(lane_severity(0.55), Some(Severity::Critical));
+        assert_eq!(lane_severity(0.40), Some(Severity::Warning));
+        assert_eq!(lane_severity(0.35), None);
+        assert!(Severity::Critical > Severity::Warning && Severity::Warning > Severity::Watch);
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let date = parse_since("2026-10-16").unwrap();
+        assert_eq!(date.to_rfc3339(), "2026-10-16T00:00:00+00:00");
+        let time = parse_since("2026-10-16T08:30:00+02:00").unwrap();
+        assert_eq!(time.to_rfc3339(), "2026-10-16T06:30:00+00:00");
+        assert!(parse_since("yesterday").is_none());
+    }
+
+    /// In-memory database with two carriers on their own lanes: C1 late on
+    /// `c1_late` of 60 shipments, C2 late on 3 of 60. `zip5` stores the
+    /// lane ends the way the ZIP5 schema does, as `origin_zip3`/`dest_zip3`
+    async fn fixture(c1_late: usize, zip5: bool) -> DbConn {
+        let db = crate::db::connect("mem://").await.unwrap();
+        crate::db::init_schema(&db).await.unwrap();
+        let shipments: Vec<serde_json::Value> = (0..120)
+            .map(|i| {
+                let (carrier, origin, dest, late) = if i < 60 {
+                    ("C1", "750", "432", i < c1_late)
+                } else {
+                    ("C2", "152", "100", i < 63)
+                };
+                let (origin_field, dest_field) = if zip5 {
+                    ("origin_zip3", "dest_zip3")
+                } else {
+                    ("origin_zip", "dest_zip")
+                };
+                let mut row = serde_json::json!({
+                    "carrier_ref": carrier,
+                    "lane_ref": format!("{}-{}", origin, dest),
+                    "otd": if late { "Late" } else { "OnTime" },
+                    "goal_transit_days": 3,
+                    "actual_transit_days": if late { 5 } else { 3 },
+                });
+                row[origin_field] = format!("{}xx", origin).into();
+                row[dest_field] = format!("{}xx", dest).into();
+                if zip5 {
+                    row["origin_zip5"] = format!("{}01", origin).into();
+                    row["dest_zip5"] = format!("{}01", dest).into();
+                }
+                row
+            })
+            .collect();
+        db.query("INSERT INTO shipment $shipments")
+            .bind(("shipments", shipments))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_sync_lifecycle() {
+        let db = fixture(45, false).await;
+
+        let opened = refresh(&db).await.unwrap();
+        assert_eq!((opened.opened, opened.resolved), (2, 0));
+        let open = list(&db, &AlertQuery { state: Some(AlertState::Open), ..Default::default() })
+            .await
+            .unwrap();
+        let mut keys: Vec<&str> = open.iter().map(|a| a.key.as_str()).collect();
+        keys.sort();
+        assert_eq!(keys, ["carrier:C1:late_rate", "lane:750-432:late_rate"]);
+        assert!(open.iter().all(|a| a.alert.severity == Severity::Critical));
+
+        let unchanged = refresh(&db).await.unwrap();
+        assert_eq!((unchanged.opened, unchanged.updated), (0, 2));
+
+        db.query("UPDATE shipment SET otd = 'OnTime', actual_transit_days = 3").await.unwrap();
+        let cleared = refresh(&db).await.unwrap();
+        assert_eq!(cleared.resolved, 2);
+        let open = list(&db, &AlertQuery { state: Some(AlertState::Open), ..Default::default() })
+            .await
+            .unwrap();
+        assert!(open.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_order_and_limit() {
+        let db = fixture(45, false).await;
+        refresh(&db).await.unwrap();
+        // A more recent but less severe alert still ranks second
+        db.query(r#"UPDATE exception_alert SET severity = "WARNING", last_seen = time::now() + 1h
+            WHERE key = "carrier:C1:late_rate""#)
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let all = list(&db, &AlertQuery::default()).await.unwrap();
+        let keys: Vec<&str> = all.iter().map(|a| a.key.as_str()).collect();
+        assert_eq!(keys, ["lane:750-432:late_rate", "carrier:C1:late_rate"]);
+        let top = list(&db, &AlertQuery { limit: Some(1), ..Default::default() }).await.unwrap();
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].alert.severity, Severity::Critical);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_refresh_opens_once() {
+        let db = fixture(45, false).await;
+
+        let (first, second) = tokio::join!(refresh(&db), refresh(&db));
+        assert_eq!(first.unwrap().opened + second.unwrap().opened, 2);
+        let active: Vec<String> = db
+            .query(r#"SELECT VALUE key FROM exception_alert WHERE state != "resolved""#)
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(active.len(), 2);
+
+        let duplicate = db
+            .query("CREATE exception_alert SET key = 'carrier:C1:late_rate', open_key = 'carrier:C1:late_rate'")
+            .await
+            .unwrap()
+            .check();
+        assert!(lost_race(&duplicate.unwrap_err().into()));
+        let other = db.query("CREATE exception_alert:1; CREATE exception_alert:1").await.unwrap().check();
+        assert!(!lost_race(&other.unwrap_err().into()));
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_zip5_lanes() {
+        let db = fixture(45, true).await;
+
+        let evaluation = evaluate(&db, &ShipmentFilter::default()).await.unwrap();
+        assert_eq!(evaluation.lanes.len(), 1);
+        let lane = &evaluation.lanes[0];
+        assert_eq!((lane.origin_zip.as_str(), lane.dest_zip.as_str()), ("750xx", "432xx"));
+        assert_eq!(lane.volume, 60);
+
+        let opened = refresh(&db).await.unwrap();
+        assert_eq!(opened.opened, 2);
+    }
+}
@@ -1,13 +1,23 @@
 //! gRPC service implementation for Last-Mile Analytics
 //!
-//! Implements the AnalyticsService gRPC service using the shared service layer.
+//! Implements the AnalyticsService gRPC service using the shared service layer,
+//! alongside the standard `grpc.health.v1` health service and server reflection.
 
 use std::sync::Arc;
+use tonic::server::NamedService;
+use tonic::service::Routes;
 use tonic::{Request, Response, Status};
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
 
-use super::proto::analytics_service_server::AnalyticsService as GrpcAnalyticsService;
+use super::proto::analytics_service_server::{AnalyticsService as GrpcAnalyticsService, AnalyticsServiceServer};
+use super::graph_handlers as graph;
 use super::proto::*;
 use super::service::AnalyticsService;
+use crate::carrier_names::get_carrier_name;
+use crate::delay_model::DelayRequest;
+use crate::eta::{parse_ship_datetime, EtaRequest};
+use crate::location_names::format_lane_short;
 
 pub struct GrpcService {
     service: Arc<AnalyticsService>,
@@ -19,6 +29,38 @@ impl GrpcService {
     }
 }
 
+/// gRPC routes: `lastmile.v1.AnalyticsService`, `grpc.health.v1.Health` and
+/// server reflection (v1 and v1alpha, for older `grpcurl` releases).
+///
+/// Health starts as serving; keep it in step with the database through
+/// [`report_health`].
+pub fn routes(service: Arc<AnalyticsService>) -> anyhow::Result<(Routes, HealthReporter)> {
+    let (health, health_service) = tonic_health::server::health_reporter();
+    let reflection = || {
+        tonic_reflection::server::Builder::configure()
+            .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+            .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+    };
+    let routes = Routes::new(AnalyticsServiceServer::new(GrpcService::new(service)))
+        .add_service(health_service)
+        .add_service(reflection().build_v1()?)
+        .add_service(reflection().build_v1alpha()?);
+    Ok((routes, health))
+}
+
+/// Publish [`AnalyticsService::readiness`] as the health of the whole server
+/// (the empty service name) and of `lastmile.v1.AnalyticsService`; returns
+/// whether it is ready
+pub async fn report_health(health: &mut HealthReporter, service: &AnalyticsService) -> bool {
+    let ready = service.readiness().await.ready;
+    let status = if ready { ServingStatus::Serving } else { ServingStatus::NotServing };
+    health.set_service_status("", status).await;
+    health
+        .set_service_status(AnalyticsServiceServer::<GrpcService>::NAME, status)
+        .await;
+    ready
+}
+
 // Helper to convert internal LaneMetrics to proto LaneMetrics
 fn to_proto_lane(l: super::service::LaneMetrics) -> LaneMetrics {
     LaneMetrics {
@@ -31,11 +73,37 @@ fn to_proto_lane(l: super::service::LaneMetrics) -> LaneMetrics {
         early_rate: (l.early_rate * 1000.0).round() / 10.0,
         on_time_rate: (l.on_time_rate * 1000.0).round() / 10.0,
         late_rate: (l.late_rate * 1000.0).round() / 10.0,
+        on_time_rate_lower: (l.on_time_rate_lower * 1000.0).round() / 10.0,
+        on_time_rate_upper: (l.on_time_rate_upper * 1000.0).round() / 10.0,
+        late_rate_lower: (l.late_rate_lower * 1000.0).round() / 10.0,
+        late_rate_upper: (l.late_rate_upper * 1000.0).round() / 10.0,
         cluster_id: l.cluster_id as u32,
         cluster_name: l.cluster_name,
     }
 }
 
+// Helpers to convert graph responses to their proto messages
+fn to_proto_connections(c: graph::ConnectionStats) -> ConnectionStats {
+    ConnectionStats {
+        total_destinations: c.total_destinations as i64,
+        total_volume: c.total_volume,
+        top_connections: c.top_connections.into_iter().map(|c| Connection {
+            zip5: c.zip5,
+            location: c.location,
+            volume: c.volume,
+            otd_rate: c.otd_rate,
+        }).collect(),
+    }
+}
+
+fn to_proto_location(l: graph::LocationInfo) -> LocationInfo {
+    LocationInfo {
+        zip5: l.zip5,
+        zip3: l.zip3,
+        location: l.location,
+    }
+}
+
 #[tonic::async_trait]
 impl GrpcAnalyticsService for GrpcService {
     async fn get_lanes(
@@ -133,6 +201,8 @@ impl GrpcAnalyticsService for GrpcService {
                     cluster_name: playbook.cluster_name,
                     description: playbook.description,
                     actions: playbook.actions,
+                    strategy: playbook.strategy,
+                    kpis_to_watch: playbook.kpis_to_watch,
                 }),
             })),
             None => Err(Status::not_found(format!("Cluster {} not found", req.cluster_id))),
@@ -188,6 +258,8 @@ impl GrpcAnalyticsService for GrpcService {
                     location: z.location,
                     friction_score: z.friction_score,
                     late_rate: z.late_rate,
+                    late_rate_lower: z.late_rate_lower,
+                    late_rate_upper: z.late_rate_upper,
                     transit_variance: z.transit_variance,
                     volume: z.volume,
                     lane_count: z.lane_count,
@@ -216,7 +288,11 @@ impl GrpcAnalyticsService for GrpcService {
                     terminal: t.terminal,
                     performance_score: t.performance_score,
                     on_time_rate: t.on_time_rate,
+                    on_time_rate_lower: t.on_time_rate_lower,
+                    on_time_rate_upper: t.on_time_rate_upper,
                     late_rate: t.late_rate,
+                    late_rate_lower: t.late_rate_lower,
+                    late_rate_upper: t.late_rate_upper,
                     early_rate: t.early_rate,
                     volume: t.volume,
                     lane_count: t.lane_count,
@@ -306,4 +382,228 @@ impl GrpcAnalyticsService for GrpcService {
             Err(e) => Err(Status::internal(e.to_string())),
         }
     }
+
+    async fn predict_delay(
+        &self,
+        request: Request<PredictDelayRequest>,
+    ) -> Result<Response<PredictDelayResponse>, Status> {
+        let req = request.into_inner();
+        let ship_date = chrono::NaiveDate::parse_from_str(&req.ship_date, "%Y-%m-%d")
+            .map_err(|_| Status::invalid_argument(format!("invalid ship_date '{}', expected YYYY-MM-DD", req.ship_date)))?;
+        let shipment = DelayRequest {
+            origin_zip3: req.origin_zip3,
+            dest_zip3: req.dest_zip3,
+            carrier: req.carrier,
+            mode: req.mode,
+            ship_date,
+            goal_days: req.goal_days,
+        };
+        let features = shipment.features().map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let factors = req.factors.unwrap_or(5) as usize;
+
+        match self.service.predict_delay(&shipment, factors).await {
+            Ok(prediction) => {
+                Ok(Response::new(PredictDelayResponse {
+                    lane: format_lane_short(&features.origin_zip, &features.dest_zip),
+                    carrier: get_carrier_name(&features.carrier_ref),
+                    late_probability: (prediction.late_probability * 10000.0).round() / 10000.0,
+                    expected_delay_days: (prediction.expected_delay_days * 100.0).round() / 100.0,
+                    avg_days_late: (prediction.avg_days_late * 100.0).round() / 100.0,
+                    factors: prediction.factors.into_iter().map(|f| DelayFactor {
+                        feature: f.feature,
+                        log_odds: (f.log_odds * 1000.0).round() / 1000.0,
+                    }).collect(),
+                    source: prediction.source.as_str().to_string(),
+                }))
+            }
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn get_eta(
+        &self,
+        request: Request<GetEtaRequest>,
+    ) -> Result<Response<GetEtaResponse>, Status> {
+        let req = request.into_inner();
+        let ship = parse_ship_datetime(&req.ship).map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let eta_request = EtaRequest {
+            origin_zip3: req.origin_zip3,
+            dest_zip3: req.dest_zip3,
+            carrier: req.carrier,
+            mode: req.mode,
+            ship,
+            distance_miles: req.distance_miles,
+        };
+        eta_request.carrier_mode().map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        match self.service.estimate_eta(&eta_request).await {
+            Ok(Some(estimate)) => Ok(Response::new(GetEtaResponse {
+                lane: estimate.lane,
+                carrier: estimate.carrier,
+                basis: estimate.basis.as_str().to_string(),
+                basis_label: estimate.basis_label,
+                samples: estimate.samples as u64,
+                skip_weekends: estimate.skip_weekends,
+                weekend_delivery_rate: (estimate.weekend_delivery_share * 1000.0).round() / 10.0,
+                departure: estimate.departure.to_string(),
+                quantiles: estimate.quantiles.into_iter().map(|q| EtaQuantile {
+                    percentile: q.percentile as u32,
+                    transit_days: q.transit_days,
+                    delivery_date: q.delivery_date.to_string(),
+                }).collect(),
+            })),
+            Ok(None) => Err(Status::not_found(format!(
+                "No shipment history for {} -> {}; set distance_miles to use the distance bucket",
+                eta_request.origin_zip3, eta_request.dest_zip3
+            ))),
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn get_network_topology(
+        &self,
+        _request: Request<GetNetworkTopologyRequest>,
+    ) -> Result<Response<GetNetworkTopologyResponse>, Status> {
+        match self.service.get_network_topology().await {
+            Ok(topology) => Ok(Response::new(GetNetworkTopologyResponse {
+                nodes: Some(NodeCounts {
+                    shipments: topology.nodes.shipments,
+                    carriers: topology.nodes.carriers,
+                    locations_zip3: topology.nodes.locations_zip3,
+                    locations_zip5: topology.nodes.locations_zip5,
+                    lanes_zip3: topology.nodes.lanes_zip3,
+                    lanes_zip5: topology.nodes.lanes_zip5,
+                }),
+                edges: Some(EdgeCounts {
+                    shipped_by: topology.edges.shipped_by,
+                    origin5_at: topology.edges.origin5_at,
+                    dest5_at: topology.edges.dest5_at,
+                    on_lane5: topology.edges.on_lane5,
+                    connects5: topology.edges.connects5,
+                }),
+                density: Some(NetworkDensity {
+                    avg_shipments_per_carrier: topology.density.avg_shipments_per_carrier,
+                    avg_shipments_per_lane: topology.density.avg_shipments_per_lane,
+                    avg_destinations_per_origin: topology.density.avg_destinations_per_origin,
+                }),
+            })),
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn get_carrier_network(
+        &self,
+        request: Request<GetCarrierNetworkRequest>,
+    ) -> Result<Response<GetCarrierNetworkResponse>, Status> {
+        let req = request.into_inner();
+        if req.carrier_id.trim().is_empty() {
+            return Err(Status::invalid_argument("carrier_id is required"));
+        }
+        let limit = if req.limit > 0 { req.limit as usize } else { 20 };
+
+        match self.service.get_carrier_network(&req.carrier_id, limit).await {
+            Ok(network) => Ok(Response::new(GetCarrierNetworkResponse {
+                carrier_id: network.carrier_id,
+                display_name: network.display_name,
+                total_shipments: network.total_shipments,
+                total_lanes: network.total_lanes as i64,
+                origins: network.origins,
+                destinations: network.destinations,
+                top_lanes: network.top_lanes.into_iter().map(|l| CarrierLane {
+                    lane: l.lane,
+                    origin: l.origin,
+                    destination: l.destination,
+                    volume: l.volume,
+                    otd_rate: l.otd_rate,
+                    avg_transit: l.avg_transit,
+                }).collect(),
+            })),
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn get_location_connections(
+        &self,
+        request: Request<GetLocationConnectionsRequest>,
+    ) -> Result<Response<GetLocationConnectionsResponse>, Status> {
+        let req = request.into_inner();
+        let direction = if req.direction.is_empty() { "both" } else { req.direction.as_str() };
+        if !matches!(direction, "inbound" | "outbound" | "both") {
+            return Err(Status::invalid_argument(format!(
+                "invalid direction '{}', expected inbound, outbound or both", direction
+            )));
+        }
+        let limit = if req.limit > 0 { req.limit as usize } else { 20 };
+
+        match self.service.get_location_connections(&req.zip5, direction, limit).await {
+            Ok(connections) => Ok(Response::new(GetLocationConnectionsResponse {
+                zip5: connections.zip5,
+                location: connections.location,
+                outbound: Some(to_proto_connections(connections.outbound)),
+                inbound: Some(to_proto_connections(connections.inbound)),
+            })),
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn get_reachable_destinations(
+        &self,
+        request: Request<GetReachableDestinationsRequest>,
+    ) -> Result<Response<GetReachableDestinationsResponse>, Status> {
+        let req = request.into_inner();
+        let min_volume = if req.min_volume > 0 { req.min_volume } else { 1 };
+        let limit = if req.limit > 0 { req.limit as usize } else { 50 };
+
+        match self.service.get_reachable_destinations(&req.zip5, min_volume, limit).await {
+            Ok(reachable) => Ok(Response::new(GetReachableDestinationsResponse {
+                origin: reachable.origin,
+                origin_location: reachable.origin_location,
+                total_destinations: reachable.total_destinations as i64,
+                total_carriers: reachable.total_carriers as i64,
+                destinations: reachable.destinations.into_iter().map(|d| ReachableDestination {
+                    zip5: d.zip5,
+                    location: d.location,
+                    volume: d.volume,
+                    carriers: d.carriers,
+                    avg_transit: d.avg_transit,
+                    otd_rate: d.otd_rate,
+                }).collect(),
+            })),
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    async fn trace_shipment(
+        &self,
+        request: Request<TraceShipmentRequest>,
+    ) -> Result<Response<TraceShipmentResponse>, Status> {
+        let req = request.into_inner();
+
+        match self.service.trace_shipment(&req.load_id).await {
+            Ok(Some(trace)) => Ok(Response::new(TraceShipmentResponse {
+                shipment: Some(ShipmentInfo {
+                    load_id: trace.shipment.load_id,
+                    carrier_mode: trace.shipment.carrier_mode,
+                    otd: trace.shipment.otd,
+                    actual_transit_days: trace.shipment.actual_transit_days,
+                    goal_transit_days: trace.shipment.goal_transit_days,
+                    ship_date: trace.shipment.ship_date,
+                    delivery_date: trace.shipment.delivery_date,
+                    is_synthetic: trace.shipment.is_synthetic,
+                }),
+                carrier: Some(CarrierInfo {
+                    carrier_id: trace.carrier.carrier_id,
+                    display_name: trace.carrier.display_name,
+                }),
+                origin: Some(to_proto_location(trace.origin)),
+                destination: Some(to_proto_location(trace.destination)),
+                lane: Some(LaneInfo {
+                    zip5_pair: trace.lane.zip5_pair,
+                    zip3_pair: trace.lane.zip3_pair,
+                }),
+            })),
+            Ok(None) => Err(Status::not_found(format!("Shipment {} not found", req.load_id))),
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
 }
@@ -3,15 +3,22 @@
 //! These handlers use the shared AnalyticsService.
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use super::service::{AnalyticsService, LaneMetrics};
+use super::service::{AnalyticsService, CacheInfo, LaneMetrics, Readiness};
+use crate::alerts::{self, AlertQuery, AlertState, Severity, StoredAlert, SyncSummary};
+use crate::carrier_names::get_carrier_name;
+use crate::delay_model::{DelayFeatures, DelayPrediction, DelayRequest};
+use crate::eta::{parse_ship_datetime, EtaEstimate, EtaRequest};
+use crate::location_names::format_lane_short;
+use crate::simulation::{self, Lever, NetworkSummary, SimulationRequest, SimulationResult};
 
 // ============================================================================
 // Response Types (JSON-serializable versions)
@@ -28,6 +35,10 @@ pub struct LaneResponse {
     pub early_rate: f64,
     pub on_time_rate: f64,
     pub late_rate: f64,
+    pub on_time_rate_lower: f64,
+    pub on_time_rate_upper: f64,
+    pub late_rate_lower: f64,
+    pub late_rate_upper: f64,
     pub cluster_id: u8,
     pub cluster_name: String,
 }
@@ -44,6 +55,10 @@ impl From<LaneMetrics> for LaneResponse {
             early_rate: (l.early_rate * 1000.0).round() / 10.0,
             on_time_rate: (l.on_time_rate * 1000.0).round() / 10.0,
             late_rate: (l.late_rate * 1000.0).round() / 10.0,
+            on_time_rate_lower: (l.on_time_rate_lower * 1000.0).round() / 10.0,
+            on_time_rate_upper: (l.on_time_rate_upper * 1000.0).round() / 10.0,
+            late_rate_lower: (l.late_rate_lower * 1000.0).round() / 10.0,
+            late_rate_upper: (l.late_rate_upper * 1000.0).round() / 10.0,
             cluster_id: l.cluster_id,
             cluster_name: l.cluster_name,
         }
@@ -66,7 +81,9 @@ pub struct PlaybookResponse {
     pub cluster_id: u8,
     pub cluster_name: String,
     pub description: String,
+    pub strategy: String,
     pub actions: Vec<String>,
+    pub kpis_to_watch: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -75,6 +92,8 @@ pub struct FrictionZoneResponse {
     pub location: String,
     pub friction_score: f64,
     pub late_rate: f64,
+    pub late_rate_lower: f64,
+    pub late_rate_upper: f64,
     pub transit_variance: f64,
     pub volume: i64,
     pub lane_count: i64,
@@ -86,7 +105,11 @@ pub struct TerminalResponse {
     pub terminal: String,
     pub performance_score: f64,
     pub on_time_rate: f64,
+    pub on_time_rate_lower: f64,
+    pub on_time_rate_upper: f64,
     pub late_rate: f64,
+    pub late_rate_lower: f64,
+    pub late_rate_upper: f64,
     pub early_rate: f64,
     pub volume: i64,
     pub lane_count: i64,
@@ -164,6 +187,258 @@ pub struct FrictionZonesResponse {
     pub recommendations: Vec<String>,
 }
 
+#[derive(Serialize)]
+pub struct DelayFactorResponse {
+    pub feature: String,
+    pub log_odds: f64,
+    /// "raises" or "lowers" the late risk
+    pub effect: String,
+}
+
+#[derive(Serialize)]
+pub struct PredictDelayResponse {
+    pub lane: String,
+    pub carrier: String,
+    pub mode: String,
+    pub ship_date: String,
+    pub goal_days: i32,
+    /// P(late), 0-1
+    pub late_probability: f64,
+    pub expected_delay_days: f64,
+    pub avg_days_late: f64,
+    pub factors: Vec<DelayFactorResponse>,
+    /// "model" or "empirical"
+    pub source: String,
+}
+
+impl PredictDelayResponse {
+    fn new(request: &DelayRequest, features: &DelayFeatures, prediction: DelayPrediction) -> Self {
+        Self {
+            lane: format_lane_short(&features.origin_zip, &features.dest_zip),
+            carrier: get_carrier_name(&features.carrier_ref),
+            mode: features.mode_label().to_string(),
+            ship_date: request.ship_date.to_string(),
+            goal_days: request.goal_days,
+            late_probability: (prediction.late_probability * 10000.0).round() / 10000.0,
+            expected_delay_days: (prediction.expected_delay_days * 100.0).round() / 100.0,
+            avg_days_late: (prediction.avg_days_late * 100.0).round() / 100.0,
+            factors: prediction.factors.into_iter().map(|f| DelayFactorResponse {
+                effect: if f.log_odds > 0.0 { "raises" } else { "lowers" }.to_string(),
+                feature: f.feature,
+                log_odds: (f.log_odds * 1000.0).round() / 1000.0,
+            }).collect(),
+            source: prediction.source.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct EtaQuantileResponse {
+    pub transit_days: i64,
+    pub delivery_date: String,
+}
+
+#[derive(Serialize)]
+pub struct EtaResponse {
+    pub lane: String,
+    pub carrier: String,
+    pub mode: Option<String>,
+    pub ship: String,
+    /// Day transit starts, the next one when `ship` is after the cutoff
+    pub departure: String,
+    /// "lane_carrier", "lane" or "distance_bucket"
+    pub basis: String,
+    pub basis_label: String,
+    pub samples: usize,
+    pub skip_weekends: bool,
+    /// Percent of the comparison set delivered on a weekend
+    pub weekend_delivery_rate: f64,
+    pub p50: Option<EtaQuantileResponse>,
+    pub p80: Option<EtaQuantileResponse>,
+    pub p95: Option<EtaQuantileResponse>,
+}
+
+impl From<EtaEstimate> for EtaResponse {
+    fn from(e: EtaEstimate) -> Self {
+        let quantile = |p: u8| e.quantile(p).map(|q| EtaQuantileResponse {
+            transit_days: q.transit_days,
+            delivery_date: q.delivery_date.to_string(),
+        });
+        Self {
+            p50: quantile(50),
+            p80: quantile(80),
+            p95: quantile(95),
+            lane: e.lane,
+            carrier: e.carrier,
+            mode: e.mode,
+            ship: e.ship.format("%Y-%m-%dT%H:%M").to_string(),
+            departure: e.departure.to_string(),
+            basis: e.basis.as_str().to_string(),
+            basis_label: e.basis_label,
+            samples: e.samples,
+            skip_weekends: e.skip_weekends,
+            weekend_delivery_rate: (e.weekend_delivery_share * 1000.0).round() / 10.0,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ClusterCountResponse {
+    pub cluster_id: u8,
+    pub cluster_name: String,
+    pub lanes: usize,
+    pub volume: f64,
+}
+
+#[derive(Serialize)]
+pub struct NetworkSummaryResponse {
+    pub lanes: usize,
+    pub shipments: f64,
+    pub on_time: f64,
+    pub late: f64,
+    pub on_time_rate: f64,
+    pub late_rate: f64,
+    pub early_rate: f64,
+    pub clusters: Vec<ClusterCountResponse>,
+}
+
+impl From<NetworkSummary> for NetworkSummaryResponse {
+    fn from(n: NetworkSummary) -> Self {
+        Self {
+            lanes: n.lanes,
+            shipments: n.shipments.round(),
+            on_time: (n.on_time * 10.0).round() / 10.0,
+            late: (n.late * 10.0).round() / 10.0,
+            on_time_rate: (n.on_time_rate * 1000.0).round() / 10.0,
+            late_rate: (n.late_rate * 1000.0).round() / 10.0,
+            early_rate: (n.early_rate * 1000.0).round() / 10.0,
+            clusters: n.clusters.into_iter().map(|c| ClusterCountResponse {
+                cluster_id: c.cluster_id,
+                cluster_name: c.cluster_name,
+                lanes: c.lanes,
+                volume: c.volume.round(),
+            }).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AppliedLeverResponse {
+    pub lever: Lever,
+    pub description: String,
+    pub lanes_changed: usize,
+}
+
+#[derive(Serialize)]
+pub struct RankedLeverResponse {
+    pub rank: usize,
+    /// Can be sent back as one of the request's levers
+    pub lever: Lever,
+    pub description: String,
+    pub on_time_gain: f64,
+    pub late_reduction: f64,
+    pub cumulative_on_time_rate: f64,
+}
+
+#[derive(Serialize)]
+pub struct SimulationResponse {
+    pub baseline: NetworkSummaryResponse,
+    pub projected: NetworkSummaryResponse,
+    pub applied: Vec<AppliedLeverResponse>,
+    pub warnings: Vec<String>,
+    pub target_on_time_rate: Option<f64>,
+    pub target_reached: Option<bool>,
+    pub recommendations: Vec<RankedLeverResponse>,
+    pub with_recommendations: Option<NetworkSummaryResponse>,
+}
+
+impl From<SimulationResult> for SimulationResponse {
+    fn from(r: SimulationResult) -> Self {
+        Self {
+            baseline: r.baseline.into(),
+            projected: r.projected.into(),
+            applied: r.applied.into_iter().map(|a| AppliedLeverResponse {
+                lever: a.lever,
+                description: a.description,
+                lanes_changed: a.lanes_changed,
+            }).collect(),
+            warnings: r.warnings,
+            target_on_time_rate: r.target_on_time_rate.map(|t| (t * 1000.0).round() / 10.0),
+            target_reached: r.target_reached,
+            recommendations: r.recommendations.into_iter().map(|l| RankedLeverResponse {
+                rank: l.rank,
+                lever: l.lever,
+                description: l.description,
+                on_time_gain: (l.on_time_gain * 10.0).round() / 10.0,
+                late_reduction: (l.late_reduction * 10.0).round() / 10.0,
+                cumulative_on_time_rate: (l.cumulative_on_time_rate * 1000.0).round() / 10.0,
+            }).collect(),
+            with_recommendations: r.with_recommendations.map(Into::into),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AlertResponse {
+    pub id: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    /// Lane or carrier display name
+    pub entity_name: String,
+    pub metric: String,
+    pub severity: Severity,
+    pub state: AlertState,
+    /// Rates in percent
+    pub current_value: f64,
+    pub baseline: f64,
+    pub deviation: f64,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub acknowledged_at: Option<String>,
+    pub acknowledged_by: Option<String>,
+    pub note: Option<String>,
+    pub resolved_at: Option<String>,
+}
+
+impl From<StoredAlert> for AlertResponse {
+    fn from(a: StoredAlert) -> Self {
+        let entity_name = match a.alert.entity_type.as_str() {
+            "carrier" => get_carrier_name(&a.alert.entity_id),
+            "lane" => a
+                .alert
+                .entity_id
+                .split_once('-')
+                .map(|(origin, dest)| format_lane_short(origin, dest))
+                .unwrap_or_else(|| a.alert.entity_id.clone()),
+            _ => a.alert.entity_id.clone(),
+        };
+        Self {
+            id: a.id,
+            entity_type: a.alert.entity_type,
+            entity_id: a.alert.entity_id,
+            entity_name,
+            metric: a.alert.metric,
+            severity: a.alert.severity,
+            state: a.state,
+            current_value: (a.alert.current_value * 1000.0).round() / 10.0,
+            baseline: (a.alert.baseline * 1000.0).round() / 10.0,
+            deviation: (a.alert.deviation * 1000.0).round() / 10.0,
+            first_seen: a.first_seen,
+            last_seen: a.last_seen,
+            acknowledged_at: a.acknowledged_at,
+            acknowledged_by: a.acknowledged_by,
+            note: a.note,
+            resolved_at: a.resolved_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AlertsResponse {
+    pub count: usize,
+    pub alerts: Vec<AlertResponse>,
+}
+
 #[derive(Serialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -189,6 +464,54 @@ pub struct ClusterLanesQuery {
     pub limit: Option<usize>,
 }
 
+#[derive(Deserialize)]
+pub struct EtaQuery {
+    pub origin: String,
+    pub dest: String,
+    pub carrier: String,
+    pub mode: Option<String>,
+    /// Ship date or date-time, e.g. 2024-12-02 or 2024-12-02T14:30
+    pub ship: String,
+    pub distance: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct PredictDelayBody {
+    #[serde(flatten)]
+    pub shipment: DelayRequest,
+    /// Contributing factors to return (default 5)
+    pub factors: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct SimulateBody {
+    #[serde(default)]
+    pub levers: Vec<Lever>,
+    /// Target on-time rate in percent, e.g. 75
+    pub target_on_time_rate: Option<f64>,
+    /// Recommendations to return (default 20)
+    pub max_recommendations: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct AlertsQuery {
+    /// open, acknowledged or resolved
+    pub state: Option<String>,
+    /// WATCH, WARNING or CRITICAL
+    pub severity: Option<String>,
+    /// carrier or lane
+    pub entity_type: Option<String>,
+    /// Only alerts first seen since this date or RFC 3339 time
+    pub since: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct AcknowledgeBody {
+    pub by: Option<String>,
+    pub note: Option<String>,
+}
+
 // ============================================================================
 // Handlers
 // ============================================================================
@@ -200,6 +523,13 @@ pub async fn health() -> impl IntoResponse {
     Json(serde_json::json!({"status": "ok"}))
 }
 
+/// GET /api/v1/ready - 503 until the database answers and is migrated
+pub async fn ready(State(service): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let readiness = service.readiness().await;
+    let status = if readiness.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(readiness))
+}
+
 /// GET /api/v1/stats
 pub async fn get_stats(
     State(service): State<AppState>,
@@ -294,7 +624,9 @@ pub async fn get_playbook(
             cluster_id: playbook.cluster_id,
             cluster_name: playbook.cluster_name,
             description: playbook.description,
+            strategy: playbook.strategy,
             actions: playbook.actions,
+            kpis_to_watch: playbook.kpis_to_watch,
         })),
         None => Err((StatusCode::NOT_FOUND, Json(ErrorResponse {
             error: format!("Cluster {} not found. Valid IDs: 1-5", id)
@@ -342,6 +674,8 @@ pub async fn get_friction_zones(
                 location: z.location,
                 friction_score: z.friction_score,
                 late_rate: z.late_rate,
+                late_rate_lower: z.late_rate_lower,
+                late_rate_upper: z.late_rate_upper,
                 transit_variance: z.transit_variance,
                 volume: z.volume,
                 lane_count: z.lane_count,
@@ -372,7 +706,11 @@ pub async fn get_terminals(
                 terminal: t.terminal,
                 performance_score: t.performance_score,
                 on_time_rate: t.on_time_rate,
+                on_time_rate_lower: t.on_time_rate_lower,
+                on_time_rate_upper: t.on_time_rate_upper,
                 late_rate: t.late_rate,
+                late_rate_lower: t.late_rate_lower,
+                late_rate_upper: t.late_rate_upper,
                 early_rate: t.early_rate,
                 volume: t.volume,
                 lane_count: t.lane_count,
@@ -382,7 +720,11 @@ pub async fn get_terminals(
                 terminal: t.terminal,
                 performance_score: t.performance_score,
                 on_time_rate: t.on_time_rate,
+                on_time_rate_lower: t.on_time_rate_lower,
+                on_time_rate_upper: t.on_time_rate_upper,
                 late_rate: t.late_rate,
+                late_rate_lower: t.late_rate_lower,
+                late_rate_upper: t.late_rate_upper,
                 early_rate: t.early_rate,
                 volume: t.volume,
                 lane_count: t.lane_count,
@@ -435,3 +777,190 @@ pub async fn find_similar(
         Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() }))),
     }
 }
+
+/// POST /api/v1/predict/delay
+pub async fn predict_delay(
+    State(service): State<AppState>,
+    Json(body): Json<PredictDelayBody>,
+) -> Result<Json<PredictDelayResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let request = body.shipment;
+    let features = request
+        .features()
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e.to_string() })))?;
+    match service.predict_delay(&request, body.factors.unwrap_or(5)).await {
+        Ok(prediction) => Ok(Json(PredictDelayResponse::new(&request, &features, prediction))),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() }))),
+    }
+}
+
+/// GET /api/v1/eta?origin=X&dest=Y&carrier=Z&ship=YYYY-MM-DD
+pub async fn get_eta(
+    State(service): State<AppState>,
+    Query(params): Query<EtaQuery>,
+) -> Result<Json<EtaResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let ship = parse_ship_datetime(&params.ship)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e.to_string() })))?;
+    let request = EtaRequest {
+        origin_zip3: params.origin,
+        dest_zip3: params.dest,
+        carrier: params.carrier,
+        mode: params.mode,
+        ship,
+        distance_miles: params.distance,
+    };
+    request
+        .carrier_mode()
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e.to_string() })))?;
+    match service.estimate_eta(&request).await {
+        Ok(Some(estimate)) => Ok(Json(EtaResponse::from(estimate))),
+        Ok(None) => Err((StatusCode::NOT_FOUND, Json(ErrorResponse {
+            error: format!("No shipment history for {} -> {}; pass distance=MILES to use the distance bucket",
+                           request.origin_zip3, request.dest_zip3)
+        }))),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() }))),
+    }
+}
+
+/// POST /api/v1/simulate
+pub async fn simulate(
+    State(service): State<AppState>,
+    Json(body): Json<SimulateBody>,
+) -> Result<Json<SimulationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let request = SimulationRequest {
+        levers: body.levers,
+        target_on_time_rate: body.target_on_time_rate.map(|t| t / 100.0),
+        max_recommendations: body.max_recommendations,
+    };
+    simulation::validate(&request)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e.to_string() })))?;
+    match service.simulate(&request).await {
+        Ok(result) => Ok(Json(SimulationResponse::from(result))),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() }))),
+    }
+}
+
+/// Middleware for endpoints served from the cached snapshot: adds `Age`
+/// (seconds since it was computed), `X-Cache-Computed-At` and
+/// `X-Data-Version`
+pub async fn cache_headers(State(service): State<AppState>, request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    if let Some(info) = service.cache_info().await {
+        let headers = response.headers_mut();
+        for (name, value) in [
+            ("age", info.age_seconds.to_string()),
+            ("x-cache-computed-at", info.computed_at),
+            ("x-data-version", info.data_version.to_string()),
+        ] {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(name, value);
+            }
+        }
+    }
+    response
+}
+
+/// Environment variable holding the shared token for admin endpoints
+pub const ADMIN_TOKEN_ENV: &str = "LASTMILE_ADMIN_TOKEN";
+
+/// Middleware for admin endpoints: requires `Authorization: Bearer <token>`
+/// matching [`ADMIN_TOKEN_ENV`]. Without a configured token they are off.
+pub async fn require_admin_token(
+    State(token): State<Option<Arc<str>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(token) = token else {
+        return (StatusCode::FORBIDDEN, Json(ErrorResponse {
+            error: format!("Admin endpoints are disabled; set {} to enable them", ADMIN_TOKEN_ENV),
+        }))
+            .into_response();
+    };
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match given {
+        Some(given) if token_matches(given.trim(), &token) => next.run(request).await,
+        _ => (StatusCode::UNAUTHORIZED, Json(ErrorResponse {
+            error: "Missing or invalid admin token".to_string(),
+        }))
+            .into_response(),
+    }
+}
+
+/// Compare tokens without stopping at the first differing byte
+fn token_matches(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given.bytes().zip(token.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// POST /api/v1/admin/cache/refresh
+pub async fn refresh_cache(
+    State(service): State<AppState>,
+) -> Result<Json<CacheInfo>, (StatusCode, Json<ErrorResponse>)> {
+    match service.refresh_cache().await {
+        Ok(info) => Ok(Json(info)),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() }))),
+    }
+}
+
+/// GET /api/v1/alerts
+pub async fn get_alerts(
+    State(service): State<AppState>,
+    Query(params): Query<AlertsQuery>,
+) -> Result<Json<AlertsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }));
+    let query = AlertQuery {
+        state: params
+            .state
+            .map(|s| AlertState::parse(&s).ok_or_else(|| bad_request(format!("unknown state '{}'", s))))
+            .transpose()?,
+        severity: params
+            .severity
+            .map(|s| Severity::parse(&s).ok_or_else(|| bad_request(format!("unknown severity '{}'", s))))
+            .transpose()?,
+        entity_type: params.entity_type,
+        since: params
+            .since
+            .map(|s| alerts::parse_since(&s).ok_or_else(|| bad_request(format!("since '{}' is not a date or RFC 3339 time", s))))
+            .transpose()?,
+        limit: params.limit,
+    };
+    match service.list_alerts(&query).await {
+        Ok(alerts) => Ok(Json(AlertsResponse {
+            count: alerts.len(),
+            alerts: alerts.into_iter().map(AlertResponse::from).collect(),
+        })),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() }))),
+    }
+}
+
+/// POST /api/v1/alerts/:id/acknowledge
+pub async fn acknowledge_alert(
+    State(service): State<AppState>,
+    Path(id): Path<String>,
+    body: Option<Json<AcknowledgeBody>>,
+) -> Result<Json<AlertResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let Json(body) = body.unwrap_or_default();
+    match service.acknowledge_alert(&id, body.by, body.note).await {
+        Ok(Some(alert)) if alert.state == AlertState::Resolved => Err((StatusCode::CONFLICT, Json(ErrorResponse {
+            error: format!("Alert {} is already resolved", id),
+        }))),
+        Ok(Some(alert)) => Ok(Json(AlertResponse::from(alert))),
+        Ok(None) => Err((StatusCode::NOT_FOUND, Json(ErrorResponse {
+            error: format!("Alert not found: {}", id),
+        }))),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() }))),
+    }
+}
+
+/// POST /api/v1/alerts/evaluate
+pub async fn evaluate_alerts(
+    State(service): State<AppState>,
+) -> Result<Json<SyncSummary>, (StatusCode, Json<ErrorResponse>)> {
+    match service.refresh_alerts().await {
+        Ok(summary) => Ok(Json(summary)),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() }))),
+    }
+}
@@ -25,6 +25,16 @@ pub struct LaneMetrics {
     pub cluster_id: u32,
     #[prost(string, tag = "11")]
     pub cluster_name: ::prost::alloc::string::String,
+    /// 95% Wilson interval on on_time_rate
+    #[prost(double, tag = "12")]
+    pub on_time_rate_lower: f64,
+    #[prost(double, tag = "13")]
+    pub on_time_rate_upper: f64,
+    /// 95% Wilson interval on late_rate
+    #[prost(double, tag = "14")]
+    pub late_rate_lower: f64,
+    #[prost(double, tag = "15")]
+    pub late_rate_upper: f64,
 }
 /// Cluster summary
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -55,6 +65,10 @@ pub struct Playbook {
     pub description: ::prost::alloc::string::String,
     #[prost(string, repeated, tag = "4")]
     pub actions: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "5")]
+    pub strategy: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "6")]
+    pub kpis_to_watch: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 /// Friction zone (problem destination)
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -73,6 +87,11 @@ pub struct FrictionZone {
     pub volume: i64,
     #[prost(int64, tag = "7")]
     pub lane_count: i64,
+    /// 95% Wilson interval on late_rate
+    #[prost(double, tag = "8")]
+    pub late_rate_lower: f64,
+    #[prost(double, tag = "9")]
+    pub late_rate_upper: f64,
 }
 /// Terminal/DC performance
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -93,6 +112,16 @@ pub struct TerminalPerformance {
     pub volume: i64,
     #[prost(int64, tag = "8")]
     pub lane_count: i64,
+    /// 95% Wilson interval on on_time_rate
+    #[prost(double, tag = "9")]
+    pub on_time_rate_lower: f64,
+    #[prost(double, tag = "10")]
+    pub on_time_rate_upper: f64,
+    /// 95% Wilson interval on late_rate
+    #[prost(double, tag = "11")]
+    pub late_rate_lower: f64,
+    #[prost(double, tag = "12")]
+    pub late_rate_upper: f64,
 }
 /// Regional cluster breakdown
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -301,6 +330,340 @@ pub struct GetStatsResponse {
     #[prost(message, optional, tag = "1")]
     pub stats: ::core::option::Option<Stats>,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetEtaRequest {
+    #[prost(string, tag = "1")]
+    pub origin_zip3: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub dest_zip3: ::prost::alloc::string::String,
+    /// Carrier ID or display name
+    #[prost(string, tag = "3")]
+    pub carrier: ::prost::alloc::string::String,
+    /// All modes when unset
+    #[prost(string, optional, tag = "4")]
+    pub mode: ::core::option::Option<::prost::alloc::string::String>,
+    /// YYYY-MM-DD or YYYY-MM-DDTHH:MM; from 17:00 leaves the next day
+    #[prost(string, tag = "5")]
+    pub ship: ::prost::alloc::string::String,
+    /// Picks the distance bucket for lanes without history
+    #[prost(double, optional, tag = "6")]
+    pub distance_miles: ::core::option::Option<f64>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EtaQuantile {
+    #[prost(uint32, tag = "1")]
+    pub percentile: u32,
+    #[prost(int64, tag = "2")]
+    pub transit_days: i64,
+    /// YYYY-MM-DD
+    #[prost(string, tag = "3")]
+    pub delivery_date: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetEtaResponse {
+    #[prost(string, tag = "1")]
+    pub lane: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub carrier: ::prost::alloc::string::String,
+    /// "lane_carrier", "lane" or "distance_bucket"
+    #[prost(string, tag = "3")]
+    pub basis: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub basis_label: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "5")]
+    pub samples: u64,
+    #[prost(bool, tag = "6")]
+    pub skip_weekends: bool,
+    /// Percent delivered on a weekend
+    #[prost(double, tag = "7")]
+    pub weekend_delivery_rate: f64,
+    /// P50, P80, P95
+    #[prost(message, repeated, tag = "8")]
+    pub quantiles: ::prost::alloc::vec::Vec<EtaQuantile>,
+    /// YYYY-MM-DD transit starts
+    #[prost(string, tag = "9")]
+    pub departure: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PredictDelayRequest {
+    #[prost(string, tag = "1")]
+    pub origin_zip3: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub dest_zip3: ::prost::alloc::string::String,
+    /// Carrier ID or display name
+    #[prost(string, tag = "3")]
+    pub carrier: ::prost::alloc::string::String,
+    /// Default: LTL
+    #[prost(string, tag = "4")]
+    pub mode: ::prost::alloc::string::String,
+    /// YYYY-MM-DD
+    #[prost(string, tag = "5")]
+    pub ship_date: ::prost::alloc::string::String,
+    #[prost(int32, tag = "6")]
+    pub goal_days: i32,
+    /// Contributing factors to return (default 5)
+    #[prost(uint32, optional, tag = "7")]
+    pub factors: ::core::option::Option<u32>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DelayFactor {
+    #[prost(string, tag = "1")]
+    pub feature: ::prost::alloc::string::String,
+    #[prost(double, tag = "2")]
+    pub log_odds: f64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PredictDelayResponse {
+    #[prost(string, tag = "1")]
+    pub lane: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub carrier: ::prost::alloc::string::String,
+    /// 0-1
+    #[prost(double, tag = "3")]
+    pub late_probability: f64,
+    #[prost(double, tag = "4")]
+    pub expected_delay_days: f64,
+    #[prost(double, tag = "5")]
+    pub avg_days_late: f64,
+    #[prost(message, repeated, tag = "6")]
+    pub factors: ::prost::alloc::vec::Vec<DelayFactor>,
+    /// "model" or "empirical"
+    #[prost(string, tag = "7")]
+    pub source: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct GetNetworkTopologyRequest {}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct NodeCounts {
+    #[prost(int64, tag = "1")]
+    pub shipments: i64,
+    #[prost(int64, tag = "2")]
+    pub carriers: i64,
+    #[prost(int64, tag = "3")]
+    pub locations_zip3: i64,
+    #[prost(int64, tag = "4")]
+    pub locations_zip5: i64,
+    #[prost(int64, tag = "5")]
+    pub lanes_zip3: i64,
+    #[prost(int64, tag = "6")]
+    pub lanes_zip5: i64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct EdgeCounts {
+    #[prost(int64, tag = "1")]
+    pub shipped_by: i64,
+    #[prost(int64, tag = "2")]
+    pub origin5_at: i64,
+    #[prost(int64, tag = "3")]
+    pub dest5_at: i64,
+    #[prost(int64, tag = "4")]
+    pub on_lane5: i64,
+    #[prost(int64, tag = "5")]
+    pub connects5: i64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct NetworkDensity {
+    #[prost(double, tag = "1")]
+    pub avg_shipments_per_carrier: f64,
+    #[prost(double, tag = "2")]
+    pub avg_shipments_per_lane: f64,
+    #[prost(double, tag = "3")]
+    pub avg_destinations_per_origin: f64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct GetNetworkTopologyResponse {
+    #[prost(message, optional, tag = "1")]
+    pub nodes: ::core::option::Option<NodeCounts>,
+    #[prost(message, optional, tag = "2")]
+    pub edges: ::core::option::Option<EdgeCounts>,
+    #[prost(message, optional, tag = "3")]
+    pub density: ::core::option::Option<NetworkDensity>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetCarrierNetworkRequest {
+    #[prost(string, tag = "1")]
+    pub carrier_id: ::prost::alloc::string::String,
+    /// Lanes to return (default 20)
+    #[prost(int32, tag = "2")]
+    pub limit: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CarrierLane {
+    /// ZIP5 pair
+    #[prost(string, tag = "1")]
+    pub lane: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub origin: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub destination: ::prost::alloc::string::String,
+    #[prost(int64, tag = "4")]
+    pub volume: i64,
+    /// Percent on time
+    #[prost(double, tag = "5")]
+    pub otd_rate: f64,
+    #[prost(double, tag = "6")]
+    pub avg_transit: f64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetCarrierNetworkResponse {
+    #[prost(string, tag = "1")]
+    pub carrier_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub display_name: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub total_shipments: i64,
+    #[prost(int64, tag = "4")]
+    pub total_lanes: i64,
+    #[prost(string, repeated, tag = "5")]
+    pub origins: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "6")]
+    pub destinations: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(message, repeated, tag = "7")]
+    pub top_lanes: ::prost::alloc::vec::Vec<CarrierLane>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetLocationConnectionsRequest {
+    #[prost(string, tag = "1")]
+    pub zip5: ::prost::alloc::string::String,
+    /// "inbound", "outbound" or "both" (default)
+    #[prost(string, tag = "2")]
+    pub direction: ::prost::alloc::string::String,
+    /// Connections per direction (default 20)
+    #[prost(int32, tag = "3")]
+    pub limit: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Connection {
+    #[prost(string, tag = "1")]
+    pub zip5: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub location: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub volume: i64,
+    /// Percent on time
+    #[prost(double, tag = "4")]
+    pub otd_rate: f64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConnectionStats {
+    #[prost(int64, tag = "1")]
+    pub total_destinations: i64,
+    #[prost(int64, tag = "2")]
+    pub total_volume: i64,
+    #[prost(message, repeated, tag = "3")]
+    pub top_connections: ::prost::alloc::vec::Vec<Connection>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetLocationConnectionsResponse {
+    #[prost(string, tag = "1")]
+    pub zip5: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub location: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub outbound: ::core::option::Option<ConnectionStats>,
+    #[prost(message, optional, tag = "4")]
+    pub inbound: ::core::option::Option<ConnectionStats>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetReachableDestinationsRequest {
+    #[prost(string, tag = "1")]
+    pub zip5: ::prost::alloc::string::String,
+    /// Shipments a destination needs (default 1)
+    #[prost(int64, tag = "2")]
+    pub min_volume: i64,
+    /// Max destinations (default 50)
+    #[prost(int32, tag = "3")]
+    pub limit: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReachableDestination {
+    #[prost(string, tag = "1")]
+    pub zip5: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub location: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub volume: i64,
+    #[prost(string, repeated, tag = "4")]
+    pub carriers: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(double, tag = "5")]
+    pub avg_transit: f64,
+    /// Percent on time
+    #[prost(double, tag = "6")]
+    pub otd_rate: f64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetReachableDestinationsResponse {
+    #[prost(string, tag = "1")]
+    pub origin: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub origin_location: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub total_destinations: i64,
+    #[prost(int64, tag = "4")]
+    pub total_carriers: i64,
+    #[prost(message, repeated, tag = "5")]
+    pub destinations: ::prost::alloc::vec::Vec<ReachableDestination>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TraceShipmentRequest {
+    #[prost(string, tag = "1")]
+    pub load_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ShipmentInfo {
+    #[prost(string, tag = "1")]
+    pub load_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub carrier_mode: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub otd: ::prost::alloc::string::String,
+    #[prost(int64, tag = "4")]
+    pub actual_transit_days: i64,
+    #[prost(int64, tag = "5")]
+    pub goal_transit_days: i64,
+    #[prost(string, tag = "6")]
+    pub ship_date: ::prost::alloc::string::String,
+    #[prost(string, tag = "7")]
+    pub delivery_date: ::prost::alloc::string::String,
+    #[prost(bool, tag = "8")]
+    pub is_synthetic: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CarrierInfo {
+    #[prost(string, tag = "1")]
+    pub carrier_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub display_name: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LocationInfo {
+    #[prost(string, tag = "1")]
+    pub zip5: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub zip3: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub location: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LaneInfo {
+    #[prost(string, tag = "1")]
+    pub zip5_pair: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub zip3_pair: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TraceShipmentResponse {
+    #[prost(message, optional, tag = "1")]
+    pub shipment: ::core::option::Option<ShipmentInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub carrier: ::core::option::Option<CarrierInfo>,
+    #[prost(message, optional, tag = "3")]
+    pub origin: ::core::option::Option<LocationInfo>,
+    #[prost(message, optional, tag = "4")]
+    pub destination: ::core::option::Option<LocationInfo>,
+    #[prost(message, optional, tag = "5")]
+    pub lane: ::core::option::Option<LaneInfo>,
+}
 /// Generated client implementations.
 pub mod analytics_service_client {
     #![allow(
@@ -667,50 +1030,233 @@ pub mod analytics_service_client {
                 .insert(GrpcMethod::new("lastmile.v1.AnalyticsService", "GetStats"));
             self.inner.unary(req, path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod analytics_service_server {
-    #![allow(
-        unused_variables,
-        dead_code,
-        missing_docs,
-        clippy::wildcard_imports,
-        clippy::let_unit_value,
-    )]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with AnalyticsServiceServer.
-    #[async_trait]
-    pub trait AnalyticsService: std::marker::Send + std::marker::Sync + 'static {
-        /// Lane operations
-        async fn get_lanes(
-            &self,
-            request: tonic::Request<super::GetLanesRequest>,
+        /// Prediction
+        pub async fn predict_delay(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PredictDelayRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::GetLanesResponse>,
+            tonic::Response<super::PredictDelayResponse>,
             tonic::Status,
-        >;
-        async fn get_lane(
-            &self,
-            request: tonic::Request<super::GetLaneRequest>,
-        ) -> std::result::Result<tonic::Response<super::GetLaneResponse>, tonic::Status>;
-        /// Cluster operations
-        async fn get_clusters(
-            &self,
-            request: tonic::Request<super::GetClustersRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/lastmile.v1.AnalyticsService/PredictDelay",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("lastmile.v1.AnalyticsService", "PredictDelay"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_eta(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetEtaRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetEtaResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/lastmile.v1.AnalyticsService/GetEta",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("lastmile.v1.AnalyticsService", "GetEta"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Graph operations
+        pub async fn get_network_topology(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetNetworkTopologyRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::GetClustersResponse>,
+            tonic::Response<super::GetNetworkTopologyResponse>,
             tonic::Status,
-        >;
-        async fn get_cluster_lanes(
-            &self,
-            request: tonic::Request<super::GetClusterLanesRequest>,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/lastmile.v1.AnalyticsService/GetNetworkTopology",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("lastmile.v1.AnalyticsService", "GetNetworkTopology"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_carrier_network(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetCarrierNetworkRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::GetClusterLanesResponse>,
+            tonic::Response<super::GetCarrierNetworkResponse>,
             tonic::Status,
-        >;
-        async fn get_playbook(
-            &self,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/lastmile.v1.AnalyticsService/GetCarrierNetwork",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("lastmile.v1.AnalyticsService", "GetCarrierNetwork"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_location_connections(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetLocationConnectionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetLocationConnectionsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/lastmile.v1.AnalyticsService/GetLocationConnections",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "lastmile.v1.AnalyticsService",
+                        "GetLocationConnections",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_reachable_destinations(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetReachableDestinationsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetReachableDestinationsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/lastmile.v1.AnalyticsService/GetReachableDestinations",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "lastmile.v1.AnalyticsService",
+                        "GetReachableDestinations",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn trace_shipment(
+            &mut self,
+            request: impl tonic::IntoRequest<super::TraceShipmentRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::TraceShipmentResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/lastmile.v1.AnalyticsService/TraceShipment",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("lastmile.v1.AnalyticsService", "TraceShipment"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod analytics_service_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with AnalyticsServiceServer.
+    #[async_trait]
+    pub trait AnalyticsService: std::marker::Send + std::marker::Sync + 'static {
+        /// Lane operations
+        async fn get_lanes(
+            &self,
+            request: tonic::Request<super::GetLanesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetLanesResponse>,
+            tonic::Status,
+        >;
+        async fn get_lane(
+            &self,
+            request: tonic::Request<super::GetLaneRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetLaneResponse>, tonic::Status>;
+        /// Cluster operations
+        async fn get_clusters(
+            &self,
+            request: tonic::Request<super::GetClustersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetClustersResponse>,
+            tonic::Status,
+        >;
+        async fn get_cluster_lanes(
+            &self,
+            request: tonic::Request<super::GetClusterLanesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetClusterLanesResponse>,
+            tonic::Status,
+        >;
+        async fn get_playbook(
+            &self,
             request: tonic::Request<super::GetPlaybookRequest>,
         ) -> std::result::Result<
             tonic::Response<super::GetPlaybookResponse>,
@@ -761,6 +1307,54 @@ pub mod analytics_service_server {
             tonic::Response<super::GetStatsResponse>,
             tonic::Status,
         >;
+        /// Prediction
+        async fn predict_delay(
+            &self,
+            request: tonic::Request<super::PredictDelayRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PredictDelayResponse>,
+            tonic::Status,
+        >;
+        async fn get_eta(
+            &self,
+            request: tonic::Request<super::GetEtaRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetEtaResponse>, tonic::Status>;
+        /// Graph operations
+        async fn get_network_topology(
+            &self,
+            request: tonic::Request<super::GetNetworkTopologyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetNetworkTopologyResponse>,
+            tonic::Status,
+        >;
+        async fn get_carrier_network(
+            &self,
+            request: tonic::Request<super::GetCarrierNetworkRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetCarrierNetworkResponse>,
+            tonic::Status,
+        >;
+        async fn get_location_connections(
+            &self,
+            request: tonic::Request<super::GetLocationConnectionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetLocationConnectionsResponse>,
+            tonic::Status,
+        >;
+        async fn get_reachable_destinations(
+            &self,
+            request: tonic::Request<super::GetReachableDestinationsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetReachableDestinationsResponse>,
+            tonic::Status,
+        >;
+        async fn trace_shipment(
+            &self,
+            request: tonic::Request<super::TraceShipmentRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::TraceShipmentResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct AnalyticsServiceServer<T> {
@@ -1337,6 +1931,341 @@ pub mod analytics_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/lastmile.v1.AnalyticsService/PredictDelay" => {
+                    #[allow(non_camel_case_types)]
+                    struct PredictDelaySvc<T: AnalyticsService>(pub Arc<T>);
+                    impl<
+                        T: AnalyticsService,
+                    > tonic::server::UnaryService<super::PredictDelayRequest>
+                    for PredictDelaySvc<T> {
+                        type Response = super::PredictDelayResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PredictDelayRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AnalyticsService>::predict_delay(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = PredictDelaySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/lastmile.v1.AnalyticsService/GetEta" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetEtaSvc<T: AnalyticsService>(pub Arc<T>);
+                    impl<
+                        T: AnalyticsService,
+                    > tonic::server::UnaryService<super::GetEtaRequest>
+                    for GetEtaSvc<T> {
+                        type Response = super::GetEtaResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetEtaRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AnalyticsService>::get_eta(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetEtaSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/lastmile.v1.AnalyticsService/GetNetworkTopology" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNetworkTopologySvc<T: AnalyticsService>(pub Arc<T>);
+                    impl<
+                        T: AnalyticsService,
+                    > tonic::server::UnaryService<super::GetNetworkTopologyRequest>
+                    for GetNetworkTopologySvc<T> {
+                        type Response = super::GetNetworkTopologyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetNetworkTopologyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AnalyticsService>::get_network_topology(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetNetworkTopologySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/lastmile.v1.AnalyticsService/GetCarrierNetwork" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetCarrierNetworkSvc<T: AnalyticsService>(pub Arc<T>);
+                    impl<
+                        T: AnalyticsService,
+                    > tonic::server::UnaryService<super::GetCarrierNetworkRequest>
+                    for GetCarrierNetworkSvc<T> {
+                        type Response = super::GetCarrierNetworkResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetCarrierNetworkRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AnalyticsService>::get_carrier_network(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetCarrierNetworkSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/lastmile.v1.AnalyticsService/GetLocationConnections" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetLocationConnectionsSvc<T: AnalyticsService>(pub Arc<T>);
+                    impl<
+                        T: AnalyticsService,
+                    > tonic::server::UnaryService<super::GetLocationConnectionsRequest>
+                    for GetLocationConnectionsSvc<T> {
+                        type Response = super::GetLocationConnectionsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetLocationConnectionsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AnalyticsService>::get_location_connections(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetLocationConnectionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/lastmile.v1.AnalyticsService/GetReachableDestinations" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetReachableDestinationsSvc<T: AnalyticsService>(pub Arc<T>);
+                    impl<
+                        T: AnalyticsService,
+                    > tonic::server::UnaryService<super::GetReachableDestinationsRequest>
+                    for GetReachableDestinationsSvc<T> {
+                        type Response = super::GetReachableDestinationsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::GetReachableDestinationsRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AnalyticsService>::get_reachable_destinations(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetReachableDestinationsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/lastmile.v1.AnalyticsService/TraceShipment" => {
+                    #[allow(non_camel_case_types)]
+                    struct TraceShipmentSvc<T: AnalyticsService>(pub Arc<T>);
+                    impl<
+                        T: AnalyticsService,
+                    > tonic::server::UnaryService<super::TraceShipmentRequest>
+                    for TraceShipmentSvc<T> {
+                        type Response = super::TraceShipmentResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TraceShipmentRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AnalyticsService>::trace_shipment(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = TraceShipmentSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(empty_body());
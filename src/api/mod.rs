@@ -6,6 +6,9 @@ pub mod proto {
     #![allow(clippy::all)]
     #![allow(warnings)]
     include!("lastmile.v1.rs");
+
+    /// Encoded descriptors of `lastmile.v1`, served by gRPC reflection
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("lastmile_descriptor");
 }
 
 pub mod service;
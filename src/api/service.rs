@@ -3,12 +3,23 @@
 //! This service layer is used by both REST and gRPC handlers.
 
 use anyhow::Result;
-use crate::{db, location_names::format_lane_short};
+use chrono::{DateTime, SecondsFormat, Utc};
+use crate::{db, location_names::format_lane_short, migrations};
+use crate::db::DbConn;
+use crate::alerts::{self, AlertQuery, StoredAlert, SyncSummary};
 use crate::carrier_names::get_carrier_name;
 use crate::location_names::get_location_long;
-use serde::Deserialize;
+use crate::compare::{self, CompareRequest, PeriodComparison};
+use crate::delay_model::{self, DelayModel, DelayPrediction, DelayRequest};
+use crate::eta::{self, EtaEstimate, EtaRequest};
+use crate::simulation::{self, SimulationRequest, SimulationResult};
+use crate::stats::wilson_95;
+use crate::clustering::{self, cluster_name, rule_based_cluster, LaneFeatures};
+use crate::playbook::playbooks;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
 use std::collections::HashMap;
 
 // Re-export graph response types
@@ -43,9 +54,17 @@ pub struct LaneMetrics {
     pub volume: i64,
     pub avg_delay: f64,
     pub transit_variance: f64,
+    /// Rates are fractions (0-1); the REST and gRPC responses convert them
+    /// to percent
     pub early_rate: f64,
     pub on_time_rate: f64,
     pub late_rate: f64,
+    /// 95% Wilson bounds on `on_time_rate`, fractions like the rate
+    pub on_time_rate_lower: f64,
+    pub on_time_rate_upper: f64,
+    /// 95% Wilson bounds on `late_rate`, fractions like the rate
+    pub late_rate_lower: f64,
+    pub late_rate_upper: f64,
     pub cluster_id: u8,
     pub cluster_name: String,
 }
@@ -66,7 +85,9 @@ pub struct Playbook {
     pub cluster_id: u8,
     pub cluster_name: String,
     pub description: String,
+    pub strategy: String,
     pub actions: Vec<String>,
+    pub kpis_to_watch: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -74,7 +95,11 @@ pub struct FrictionZone {
     pub dest_zip: String,
     pub location: String,
     pub friction_score: f64,
+    /// Percent, one decimal
     pub late_rate: f64,
+    /// 95% Wilson bounds on `late_rate`, in percent like the rate
+    pub late_rate_lower: f64,
+    pub late_rate_upper: f64,
     pub transit_variance: f64,
     pub volume: i64,
     pub lane_count: i64,
@@ -85,8 +110,15 @@ pub struct TerminalPerformance {
     pub origin_zip: String,
     pub terminal: String,
     pub performance_score: f64,
+    /// Rates are percent, one decimal
     pub on_time_rate: f64,
+    /// 95% Wilson bounds on `on_time_rate`, in percent like the rate
+    pub on_time_rate_lower: f64,
+    pub on_time_rate_upper: f64,
     pub late_rate: f64,
+    /// 95% Wilson bounds on `late_rate`, in percent like the rate
+    pub late_rate_lower: f64,
+    pub late_rate_upper: f64,
     pub early_rate: f64,
     pub volume: i64,
     pub lane_count: i64,
@@ -149,141 +181,253 @@ pub struct Stats {
 }
 
 // ============================================================================
-// Cluster Definitions
+// Analytics Service
 // ============================================================================
 
-struct ClusterDef {
-    id: u8,
-    name: &'static str,
-    description: &'static str,
-    playbook: Vec<&'static str>,
-}
-
-fn get_cluster_definitions() -> Vec<ClusterDef> {
-    vec![
-        ClusterDef {
-            id: 1,
-            name: "Early & Stable",
-            description: "Consistently arrive 0.5-2 days early with low variance",
-            playbook: vec![
-                "Implement hold-until policies at local depot",
-                "Offer tight customer delivery windows",
-                "Consider tightening SLA promises (reduce buffer)",
-                "Use for premium time-slot offerings",
-            ],
-        },
-        ClusterDef {
-            id: 2,
-            name: "On-Time & Reliable",
-            description: "High on-time rate with predictable transit",
-            playbook: vec![
-                "Maintain current operations - these are your best lanes",
-                "Use as benchmark for other lanes",
-                "Suitable for guaranteed delivery promises",
-                "Monitor for degradation, protect capacity",
-            ],
-        },
-        ClusterDef {
-            id: 3,
-            name: "High-Jitter",
-            description: "Average is OK but high variance - unpredictable",
-            playbook: vec![
-                "Add buffer days to customer promises",
-                "Avoid 'guaranteed by noon' commitments",
-                "Route to lockers/pickup points to handle timing uncertainty",
-                "Investigate root cause: carrier issues? weather corridors?",
-            ],
-        },
-        ClusterDef {
-            id: 4,
-            name: "Systematically Late",
-            description: "Consistently miss SLA - structural problem",
-            playbook: vec![
-                "Downgrade promise (next-day to 2-day) for these lanes",
-                "Negotiate with carriers or switch providers",
-                "Consider pre-positioning inventory closer to destination",
-                "Flag for carrier performance review",
-            ],
-        },
-        ClusterDef {
-            id: 5,
-            name: "Low Volume / Mixed",
-            description: "Insufficient data or mixed patterns",
-            playbook: vec![
-                "Apply conservative SLA buffers",
-                "Monitor as volume grows",
-                "Consider consolidating with similar lanes",
-                "Default to standard operating procedures",
-            ],
-        },
-    ]
+/// How long a snapshot is served before it is recomputed
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Lane metrics and the aggregates derived from them, computed together from
+/// one data version. A refresh builds a new snapshot and swaps it in whole,
+/// so readers never see a mix of old and new aggregates.
+#[derive(Debug)]
+pub struct Snapshot {
+    pub lanes: Vec<LaneMetrics>,
+    pub clusters: Vec<Cluster>,
+    /// Every friction zone, highest score first
+    pub friction_zones: Vec<FrictionZone>,
+    /// Every terminal with enough volume, unranked
+    pub terminals: Vec<TerminalPerformance>,
+    /// History behind delay predictions
+    pub delay_rates: delay_model::RateTable,
+    pub data_version: i64,
+    pub computed_at: DateTime<Utc>,
+    pub compute_time: Duration,
 }
 
-fn assign_cluster(avg_delay: f64, transit_variance: f64, early_rate: f64, on_time_rate: f64, late_rate: f64, volume: i64) -> (u8, &'static str) {
-    if volume < 20 {
-        return (5, "Low Volume / Mixed");
-    }
-    if avg_delay < -0.3 && transit_variance < 2.0 && early_rate > 0.3 {
-        return (1, "Early & Stable");
-    }
-    if late_rate > 0.45 {
-        return (4, "Systematically Late");
+impl Snapshot {
+    pub fn age(&self) -> Duration {
+        (Utc::now() - self.computed_at).to_std().unwrap_or_default()
     }
-    if transit_variance > 3.5 {
-        return (3, "High-Jitter");
-    }
-    if on_time_rate > 0.55 && transit_variance < 2.5 {
-        return (2, "On-Time & Reliable");
+
+    pub fn info(&self) -> CacheInfo {
+        CacheInfo {
+            data_version: self.data_version,
+            computed_at: self.computed_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            age_seconds: self.age().as_secs(),
+            compute_ms: self.compute_time.as_millis() as u64,
+            lanes: self.lanes.len(),
+        }
     }
-    (5, "Low Volume / Mixed")
 }
 
-// ============================================================================
-// Analytics Service
-// ============================================================================
+/// Age and origin of the snapshot being served
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheInfo {
+    pub data_version: i64,
+    pub computed_at: String,
+    pub age_seconds: u64,
+    pub compute_ms: u64,
+    pub lanes: usize,
+}
 
+/// Whether the service can answer requests
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub schema_version: u32,
+    pub latest_schema_version: u32,
+    pub data_version: i64,
+    /// Why the service isn't ready
+    pub reason: Option<String>,
+}
+
+/// Shared by the REST and gRPC servers. Holds one database handle, opened at
+/// startup, that every request reuses.
 pub struct AnalyticsService {
-    db_path: String,
-    cached_lanes: Arc<RwLock<Option<Vec<LaneMetrics>>>>,
+    db: DbConn,
+    snapshot: RwLock<Option<Arc<Snapshot>>>,
+    /// Held while a snapshot is computed, so concurrent misses compute it once
+    refresh_lock: Mutex<()>,
+    cache_ttl: Duration,
+    delay_model: Option<Arc<DelayModel>>,
 }
 
 impl AnalyticsService {
-    pub fn new(db_path: &str) -> Self {
+    pub fn new(db: DbConn) -> Self {
         Self {
-            db_path: db_path.to_string(),
-            cached_lanes: Arc::new(RwLock::new(None)),
+            db,
+            snapshot: RwLock::new(None),
+            refresh_lock: Mutex::new(()),
+            cache_ttl: DEFAULT_CACHE_TTL,
+            delay_model: None,
         }
     }
 
-    pub async fn get_lanes(&self) -> Result<Vec<LaneMetrics>> {
-        // Check cache first
-        {
-            let cache = self.cached_lanes.read().await;
-            if let Some(lanes) = cache.as_ref() {
-                return Ok(lanes.clone());
-            }
+    /// Open the database at `db_url` (see [`db::connect`]) once for the
+    /// lifetime of the service
+    pub async fn connect(db_url: &str) -> Result<Self> {
+        Ok(Self::new(db::connect(db_url).await?))
+    }
+
+    /// Ready once the database answers and its schema is fully migrated
+    pub async fn readiness(&self) -> Readiness {
+        let latest_schema_version = migrations::latest_version();
+        let versions = async {
+            let schema_version = migrations::current_version(&self.db).await?;
+            let data_version = db::data_version(&self.db).await?;
+            anyhow::Ok((schema_version, data_version))
+        };
+        match versions.await {
+            Ok((schema_version, data_version)) => Readiness {
+                ready: schema_version >= latest_schema_version,
+                schema_version,
+                latest_schema_version,
+                data_version,
+                reason: (schema_version < latest_schema_version).then(|| {
+                    format!("schema at version {}, run migrate to reach {}", schema_version, latest_schema_version)
+                }),
+            },
+            Err(e) => Readiness {
+                ready: false,
+                schema_version: 0,
+                latest_schema_version,
+                data_version: 0,
+                reason: Some(format!("database unavailable: {}", e)),
+            },
+        }
+    }
+
+    /// Score delay predictions with a trained model instead of historical rates
+    pub fn with_delay_model(mut self, model: DelayModel) -> Self {
+        self.delay_model = Some(Arc::new(model));
+        self
+    }
+
+    /// Serve cached aggregates for at most `ttl` before recomputing them
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Drop the cached snapshot so the next request recomputes it
+    pub async fn invalidate_cache(&self) {
+        *self.snapshot.write().await = None;
+    }
+
+    /// Age and data version of the snapshot being served, if any
+    pub async fn cache_info(&self) -> Option<CacheInfo> {
+        self.snapshot.read().await.as_ref().map(|s| s.info())
+    }
+
+    /// The cached snapshot, recomputed first if it is older than the TTL.
+    /// Reads don't query the data version; [`Self::refresh_cache_if_stale`]
+    /// and [`Self::refresh_cache`] pick up ingests.
+    pub async fn snapshot(&self) -> Result<Arc<Snapshot>> {
+        if let Some(snapshot) = self.unexpired_snapshot().await {
+            return Ok(snapshot);
+        }
+        let _guard = self.refresh_lock.lock().await;
+        // Another request may have refreshed it while we waited
+        if let Some(snapshot) = self.unexpired_snapshot().await {
+            return Ok(snapshot);
         }
+        let version = db::data_version(&self.db).await?;
+        self.recompute(version).await
+    }
 
-        // Query database
-        let db = db::connect(&self.db_path).await?;
+    /// Recompute the snapshot now, whatever its age
+    pub async fn refresh_cache(&self) -> Result<CacheInfo> {
+        let _guard = self.refresh_lock.lock().await;
+        let version = db::data_version(&self.db).await?;
+        Ok(self.recompute(version).await?.info())
+    }
+
+    /// Recompute the snapshot if the data version changed or it expires
+    /// within `margin`; returns whether it did. Requests keep being served
+    /// from the old snapshot until the new one is swapped in.
+    pub async fn refresh_cache_if_stale(&self, margin: Duration) -> Result<bool> {
+        let version = db::data_version(&self.db).await?;
+        if self.fresh_snapshot(version, margin).await.is_some() {
+            return Ok(false);
+        }
+        let _guard = self.refresh_lock.lock().await;
+        if self.fresh_snapshot(version, margin).await.is_some() {
+            return Ok(false);
+        }
+        self.recompute(version).await?;
+        Ok(true)
+    }
+
+    async fn unexpired_snapshot(&self) -> Option<Arc<Snapshot>> {
+        self.snapshot.read().await.as_ref().filter(|s| s.age() < self.cache_ttl).cloned()
+    }
+
+    async fn fresh_snapshot(&self, version: i64, margin: Duration) -> Option<Arc<Snapshot>> {
+        let ttl = self.cache_ttl.saturating_sub(margin);
+        self.snapshot
+            .read()
+            .await
+            .as_ref()
+            .filter(|s| s.data_version == version && s.age() < ttl)
+            .cloned()
+    }
+
+    async fn recompute(&self, version: i64) -> Result<Arc<Snapshot>> {
+        let started = Instant::now();
+        let lanes = self.load_lanes().await?;
+        let delay_rates = delay_model::load_rate_table(&self.db).await?;
+        let snapshot = Arc::new(Snapshot {
+            clusters: clusters_from(&lanes),
+            friction_zones: friction_zones_from(&lanes),
+            terminals: terminals_from(&lanes),
+            delay_rates,
+            lanes,
+            data_version: version,
+            computed_at: Utc::now(),
+            compute_time: started.elapsed(),
+        });
+        *self.snapshot.write().await = Some(snapshot.clone());
+        Ok(snapshot)
+    }
+
+    pub async fn get_lanes(&self) -> Result<Vec<LaneMetrics>> {
+        Ok(self.snapshot().await?.lanes.clone())
+    }
+
+    async fn load_lanes(&self) -> Result<Vec<LaneMetrics>> {
+        let db = &self.db;
+
+        // Query database. Graph paths can't be GROUP BY targets, so group on
+        // the ZIP3 fields each schema flavor stores on the shipment itself
 
         let lanes_raw: Vec<LaneMetricsRaw> = db
             .query(r#"
                 SELECT
-                    ->origin_at->location.zip3 as origin_zip,
-                    ->dest_at->location.zip3 as dest_zip,
+                    origin_zip,
+                    dest_zip,
                     count() as volume,
                     math::mean(actual_transit_days - goal_transit_days) as avg_delay,
                     math::variance(actual_transit_days) as transit_variance,
                     count(IF otd = "Early" THEN 1 END) as early_count,
                     count(IF otd = "OnTime" THEN 1 END) as ontime_count,
                     count(IF otd = "Late" THEN 1 END) as late_count
-                FROM shipment
-                GROUP BY ->origin_at->location.zip3, ->dest_at->location.zip3
+                FROM (
+                    SELECT
+                        (origin_zip ?? origin_zip3) as origin_zip,
+                        (dest_zip ?? dest_zip3) as dest_zip,
+                        otd, actual_transit_days, goal_transit_days
+                    FROM shipment
+                )
+                GROUP BY origin_zip, dest_zip
             "#)
             .await?
             .take(0)?;
 
+        // Clusters from the last `analytics_clustering --save` run; lanes it
+        // hasn't seen (or a never-clustered database) use the fixed rules
+        let stored_clusters = clustering::load_assignments(db).await?;
+
         let lanes: Vec<LaneMetrics> = lanes_raw
             .into_iter()
             .map(|raw| {
@@ -291,9 +435,24 @@ impl AnalyticsService {
                 let early_rate = raw.early_count as f64 / vol;
                 let on_time_rate = raw.ontime_count as f64 / vol;
                 let late_rate = raw.late_count as f64 / vol;
-                let (cluster_id, cluster_name) = assign_cluster(
-                    raw.avg_delay, raw.transit_variance, early_rate, on_time_rate, late_rate, raw.volume
-                );
+                let cluster_id = stored_clusters
+                    .get(&(raw.origin_zip.clone(), raw.dest_zip.clone()))
+                    .copied()
+                    .unwrap_or_else(|| rule_based_cluster(&LaneFeatures {
+                        lane_ref: None,
+                        origin_zip: raw.origin_zip.clone(),
+                        dest_zip: raw.dest_zip.clone(),
+                        volume: raw.volume,
+                        avg_delay: raw.avg_delay,
+                        transit_variance: raw.transit_variance,
+                        avg_transit: 0.0,
+                        early_rate,
+                        on_time_rate,
+                        late_rate,
+                        distance_miles: 0.0,
+                    }, playbooks().rules.min_volume));
+                let on_time_ci = wilson_95(raw.ontime_count, raw.volume);
+                let late_ci = wilson_95(raw.late_count, raw.volume);
                 LaneMetrics {
                     origin_zip: raw.origin_zip.clone(),
                     dest_zip: raw.dest_zip.clone(),
@@ -304,52 +463,21 @@ impl AnalyticsService {
                     early_rate,
                     on_time_rate,
                     late_rate,
+                    on_time_rate_lower: on_time_ci.lower,
+                    on_time_rate_upper: on_time_ci.upper,
+                    late_rate_lower: late_ci.lower,
+                    late_rate_upper: late_ci.upper,
                     cluster_id,
-                    cluster_name: cluster_name.to_string(),
+                    cluster_name: cluster_name(cluster_id).to_string(),
                 }
             })
             .collect();
 
-        // Update cache
-        {
-            let mut cache = self.cached_lanes.write().await;
-            *cache = Some(lanes.clone());
-        }
-
         Ok(lanes)
     }
 
     pub async fn get_clusters(&self) -> Result<Vec<Cluster>> {
-        let lanes = self.get_lanes().await?;
-        let definitions = get_cluster_definitions();
-
-        let clusters: Vec<Cluster> = definitions.iter().map(|def| {
-            let cluster_lanes: Vec<&LaneMetrics> = lanes
-                .iter()
-                .filter(|l| l.cluster_id == def.id)
-                .collect();
-
-            let lane_count = cluster_lanes.len();
-            let total_volume: i64 = cluster_lanes.iter().map(|l| l.volume).sum();
-            let avg_delay = if lane_count > 0 {
-                cluster_lanes.iter().map(|l| l.avg_delay).sum::<f64>() / lane_count as f64
-            } else { 0.0 };
-            let avg_late_rate = if lane_count > 0 {
-                cluster_lanes.iter().map(|l| l.late_rate).sum::<f64>() / lane_count as f64
-            } else { 0.0 };
-
-            Cluster {
-                id: def.id,
-                name: def.name.to_string(),
-                description: def.description.to_string(),
-                lane_count,
-                total_volume,
-                avg_delay: (avg_delay * 100.0).round() / 100.0,
-                avg_late_rate: (avg_late_rate * 1000.0).round() / 10.0,
-            }
-        }).collect();
-
-        Ok(clusters)
+        Ok(self.snapshot().await?.clusters.clone())
     }
 
     pub async fn get_lanes_in_cluster(&self, cluster_id: u8, limit: usize) -> Result<Vec<LaneMetrics>> {
@@ -381,13 +509,14 @@ impl AnalyticsService {
     }
 
     pub fn get_playbook(&self, cluster_id: u8) -> Option<Playbook> {
-        let definitions = get_cluster_definitions();
-        definitions.into_iter().find(|d| d.id == cluster_id).map(|def| {
+        playbooks().cluster(cluster_id).map(|def| {
             Playbook {
                 cluster_id: def.id,
-                cluster_name: def.name.to_string(),
-                description: def.description.to_string(),
-                actions: def.playbook.iter().map(|s| s.to_string()).collect(),
+                cluster_name: def.name.clone(),
+                description: def.description.clone(),
+                strategy: def.strategy.clone(),
+                actions: def.actions.clone(),
+                kpis_to_watch: def.kpis_to_watch.clone(),
             }
         })
     }
@@ -511,26 +640,17 @@ impl AnalyticsService {
         let cluster_breakdown: Vec<ClusterBreakdown> = (1..=5).map(|cid| {
             let count = regional_lanes.iter().filter(|l| l.cluster_id == cid).count();
             let vol: i64 = regional_lanes.iter().filter(|l| l.cluster_id == cid).map(|l| l.volume).sum();
-            let name = match cid {
-                1 => "Early & Stable",
-                2 => "On-Time & Reliable",
-                3 => "High-Jitter",
-                4 => "Systematically Late",
-                _ => "Low Volume / Mixed"
-            };
             ClusterBreakdown {
-                cluster: name.to_string(),
+                cluster: cluster_name(cid).to_string(),
                 lane_count: count,
                 volume: vol,
             }
         }).collect();
 
-        let mut problem_lanes: Vec<&LaneMetrics> = regional_lanes
-            .iter()
-            .filter(|l| l.volume >= 10)
-            .copied()
-            .collect();
-        problem_lanes.sort_by(|a, b| b.late_rate.partial_cmp(&a.late_rate).unwrap_or(std::cmp::Ordering::Equal));
+        // Rank on the lower bound so a 2-of-3 lane doesn't outrank a
+        // consistently late high-volume one
+        let mut problem_lanes = regional_lanes;
+        problem_lanes.sort_by(|a, b| b.late_rate_lower.partial_cmp(&a.late_rate_lower).unwrap_or(std::cmp::Ordering::Equal));
 
         let highest_friction_lanes: Vec<LaneMetrics> = problem_lanes.iter().take(5).map(|l| (*l).clone()).collect();
 
@@ -547,75 +667,13 @@ impl AnalyticsService {
     }
 
     pub async fn get_friction_zones(&self, limit: usize) -> Result<Vec<FrictionZone>> {
-        let lanes = self.get_lanes().await?;
-
-        let mut dest_stats: HashMap<String, (i64, f64, f64, i64)> = HashMap::new();
-        for lane in &lanes {
-            let entry = dest_stats.entry(lane.dest_zip.clone()).or_insert((0, 0.0, 0.0, 0));
-            entry.0 += lane.volume;
-            entry.1 += lane.late_rate * lane.volume as f64;
-            entry.2 += lane.transit_variance * lane.volume as f64;
-            entry.3 += 1;
-        }
-
-        let mut friction_zones: Vec<FrictionZone> = dest_stats
-            .into_iter()
-            .filter(|(_, (vol, _, _, _))| *vol >= 100)
-            .map(|(zip, (vol, late_sum, var_sum, count))| {
-                let avg_late = late_sum / vol as f64;
-                let avg_var = var_sum / vol as f64;
-                let friction_score = (avg_late * 100.0 + avg_var * 10.0).round() / 10.0;
-                FrictionZone {
-                    dest_zip: zip.clone(),
-                    location: format_lane_short("", &zip).trim_start_matches(" → ").to_string(),
-                    friction_score,
-                    late_rate: (avg_late * 1000.0).round() / 10.0,
-                    transit_variance: (avg_var * 100.0).round() / 100.0,
-                    volume: vol,
-                    lane_count: count,
-                }
-            })
-            .collect();
-
-        friction_zones.sort_by(|a, b| b.friction_score.partial_cmp(&a.friction_score).unwrap_or(std::cmp::Ordering::Equal));
-        friction_zones.truncate(limit);
-
-        Ok(friction_zones)
+        let snapshot = self.snapshot().await?;
+        Ok(snapshot.friction_zones.iter().take(limit).cloned().collect())
     }
 
     pub async fn get_terminal_performance(&self, limit: usize) -> Result<(Vec<TerminalPerformance>, Vec<TerminalPerformance>, f64, i64, i64)> {
-        let lanes = self.get_lanes().await?;
-
-        let mut origin_stats: HashMap<String, (i64, f64, f64, f64, i64)> = HashMap::new();
-        for lane in &lanes {
-            let entry = origin_stats.entry(lane.origin_zip.clone()).or_insert((0, 0.0, 0.0, 0.0, 0));
-            entry.0 += lane.volume;
-            entry.1 += lane.late_rate * lane.volume as f64;
-            entry.2 += lane.early_rate * lane.volume as f64;
-            entry.3 += lane.on_time_rate * lane.volume as f64;
-            entry.4 += 1;
-        }
-
-        let terminals: Vec<TerminalPerformance> = origin_stats
-            .into_iter()
-            .filter(|(_, (vol, _, _, _, _))| *vol >= 50)
-            .map(|(zip, (vol, late_sum, early_sum, ontime_sum, count))| {
-                let late_rate = late_sum / vol as f64;
-                let early_rate = early_sum / vol as f64;
-                let ontime_rate = ontime_sum / vol as f64;
-                let score = ((1.0 - late_rate) * 100.0).round();
-                TerminalPerformance {
-                    origin_zip: zip.clone(),
-                    terminal: format_lane_short(&zip, "").trim_end_matches(" → ").to_string(),
-                    performance_score: score,
-                    on_time_rate: (ontime_rate * 1000.0).round() / 10.0,
-                    late_rate: (late_rate * 1000.0).round() / 10.0,
-                    early_rate: (early_rate * 1000.0).round() / 10.0,
-                    volume: vol,
-                    lane_count: count,
-                }
-            })
-            .collect();
+        let snapshot = self.snapshot().await?;
+        let terminals = &snapshot.terminals;
 
         let total_volume: i64 = terminals.iter().map(|t| t.volume).sum();
         let avg_score: f64 = if !terminals.is_empty() {
@@ -623,12 +681,14 @@ impl AnalyticsService {
         } else { 0.0 };
         let total_terminals = terminals.len() as i64;
 
+        // Best terminals are those whose late rate is low even at the upper
+        // bound, worst those whose late rate is high even at the lower bound
         let mut best = terminals.clone();
-        best.sort_by(|a, b| b.performance_score.partial_cmp(&a.performance_score).unwrap_or(std::cmp::Ordering::Equal));
+        best.sort_by(|a, b| a.late_rate_upper.partial_cmp(&b.late_rate_upper).unwrap_or(std::cmp::Ordering::Equal));
         best.truncate(limit);
 
-        let mut worst = terminals;
-        worst.sort_by(|a, b| a.performance_score.partial_cmp(&b.performance_score).unwrap_or(std::cmp::Ordering::Equal));
+        let mut worst = terminals.clone();
+        worst.sort_by(|a, b| b.late_rate_lower.partial_cmp(&a.late_rate_lower).unwrap_or(std::cmp::Ordering::Equal));
         worst.truncate(limit);
 
         Ok((best, worst, (avg_score * 10.0).round() / 10.0, total_volume, total_terminals))
@@ -659,13 +719,61 @@ impl AnalyticsService {
         })
     }
 
+    /// Compare KPIs, carrier OTD and lane late rates between two ship-date
+    /// windows (see [`compare::compare_periods`])
+    pub async fn compare_periods(&self, request: &CompareRequest) -> Result<PeriodComparison> {
+        let db = &self.db;
+        compare::compare_periods(db, request).await
+    }
+
+    /// Late risk of a planned shipment (see [`delay_model::predict_delay`]),
+    /// from the rates cached with the snapshot
+    pub async fn predict_delay(&self, request: &DelayRequest, factors: usize) -> Result<DelayPrediction> {
+        let features = request.features()?;
+        let snapshot = self.snapshot().await?;
+        Ok(delay_model::predict_delay(self.delay_model.as_deref(), &snapshot.delay_rates, &features, factors))
+    }
+
+    /// P50/P80/P95 delivery dates for a planned shipment (see
+    /// [`eta::estimate_eta`]); `None` without any comparable history
+    pub async fn estimate_eta(&self, request: &EtaRequest) -> Result<Option<EtaEstimate>> {
+        let db = &self.db;
+        eta::estimate_eta(db, request).await
+    }
+
+    /// Apply what-if levers to the network and rank the levers that reach
+    /// the target on-time rate (see [`simulation::simulate`])
+    pub async fn simulate(&self, request: &SimulationRequest) -> Result<SimulationResult> {
+        let db = &self.db;
+        let baseline = simulation::load_baseline(db).await?;
+        simulation::simulate(&baseline, request)
+    }
+
+    /// Stored exception alerts (see [`alerts::list`])
+    pub async fn list_alerts(&self, query: &AlertQuery) -> Result<Vec<StoredAlert>> {
+        let db = &self.db;
+        alerts::list(db, query).await
+    }
+
+    /// Acknowledge an open alert; `None` if there is no alert with `id`
+    pub async fn acknowledge_alert(&self, id: &str, by: Option<String>, note: Option<String>) -> Result<Option<StoredAlert>> {
+        let db = &self.db;
+        alerts::acknowledge(db, id, by, note).await
+    }
+
+    /// Re-evaluate the alert rules and update stored alert state
+    pub async fn refresh_alerts(&self) -> Result<SyncSummary> {
+        let db = &self.db;
+        alerts::refresh(db).await
+    }
+
     // ========================================================================
     // Graph-Oriented Methods
     // ========================================================================
 
     /// Get a carrier's operational network - lanes served, volume, and performance
     pub async fn get_carrier_network(&self, carrier_id: &str, limit: usize) -> Result<CarrierNetworkResponse> {
-        let db = db::connect(&self.db_path).await?;
+        let db = &self.db;
         let carrier_id_owned = carrier_id.to_string();
 
         #[derive(Debug, Deserialize)]
@@ -743,7 +851,7 @@ impl AnalyticsService {
 
     /// Get location connections - what ZIP5s are connected inbound/outbound
     pub async fn get_location_connections(&self, zip5: &str, direction: &str, limit: usize) -> Result<LocationConnectionsResponse> {
-        let db = db::connect(&self.db_path).await?;
+        let db = &self.db;
         let zip5_owned = zip5.to_string();
 
         #[derive(Debug, Deserialize)]
@@ -847,7 +955,7 @@ impl AnalyticsService {
 
     /// Get network topology statistics - counts of nodes and edges
     pub async fn get_network_topology(&self) -> Result<NetworkTopologyResponse> {
-        let db = db::connect(&self.db_path).await?;
+        let db = &self.db;
 
         // Node counts
         let shipments: Option<i64> = db.query("SELECT count() FROM shipment GROUP ALL").await?.take("count")?;
@@ -880,25 +988,19 @@ impl AnalyticsService {
             0.0
         };
 
-        // Estimate avg destinations per origin
-        #[derive(Debug, Deserialize)]
-        struct OriginDestCount {
-            origin_count: i64,
-            dest_count: i64,
-        }
-        let origin_dest: Option<OriginDestCount> = db
-            .query(r#"
-                SELECT
-                    array::len(array::distinct(origin_zip5)) as origin_count,
-                    array::len(array::distinct(dest_zip5)) as dest_count
-                FROM shipment
-                GROUP ALL
-            "#)
+        // Estimate avg destinations per origin; aggregates can't be nested
+        // under GROUP ALL, so distinct ZIP5s are counted over a grouped subquery
+        let origin_count: Option<i64> = db
+            .query("SELECT count() FROM (SELECT origin_zip5 FROM shipment WHERE origin_zip5 != NONE GROUP BY origin_zip5) GROUP ALL")
             .await?
-            .take(0)?;
+            .take("count")?;
+        let dest_count: Option<i64> = db
+            .query("SELECT count() FROM (SELECT dest_zip5 FROM shipment WHERE dest_zip5 != NONE GROUP BY dest_zip5) GROUP ALL")
+            .await?
+            .take("count")?;
 
-        let avg_destinations_per_origin = match origin_dest {
-            Some(od) if od.origin_count > 0 => od.dest_count as f64 / od.origin_count as f64,
+        let avg_destinations_per_origin = match (origin_count, dest_count) {
+            (Some(origins), Some(dests)) if origins > 0 => dests as f64 / origins as f64,
             _ => 0.0,
         };
 
@@ -928,7 +1030,7 @@ impl AnalyticsService {
 
     /// Trace a shipment through the graph - carrier, origin, destination, lane
     pub async fn trace_shipment(&self, load_id: &str) -> Result<Option<ShipmentTraceResponse>> {
-        let db = db::connect(&self.db_path).await?;
+        let db = &self.db;
         let load_id_owned = load_id.to_string();
 
         #[derive(Debug, Deserialize)]
@@ -945,8 +1047,6 @@ impl AnalyticsService {
             origin_zip5: String,
             dest_zip5: String,
             lane_zip5_pair: String,
-            origin_zip: String,
-            dest_zip: String,
         }
 
         let shipment: Option<ShipmentData> = db
@@ -954,8 +1054,7 @@ impl AnalyticsService {
                 SELECT
                     load_id, carrier_mode, otd, actual_transit_days, goal_transit_days,
                     actual_ship, actual_delivery, is_synthetic, carrier_ref,
-                    origin_zip5, dest_zip5, lane_zip5_pair,
-                    origin_zip, dest_zip
+                    origin_zip5, dest_zip5, lane_zip5_pair
                 FROM shipment
                 WHERE load_id = $load_id
                 LIMIT 1
@@ -1007,7 +1106,7 @@ impl AnalyticsService {
 
     /// Get reachable destinations from a ZIP5 with carrier and performance info
     pub async fn get_reachable_destinations(&self, zip5: &str, min_volume: i64, limit: usize) -> Result<ReachableDestinationsResponse> {
-        let db = db::connect(&self.db_path).await?;
+        let db = &self.db;
         let zip5_owned = zip5.to_string();
 
         #[derive(Debug, Deserialize)]
@@ -1021,16 +1120,18 @@ impl AnalyticsService {
 
         let destinations: Vec<DestData> = db
             .query(r#"
-                SELECT
-                    dest_zip5,
-                    count() as volume,
-                    array::distinct(carrier_ref) as carriers,
-                    math::mean(actual_transit_days) as avg_transit,
-                    count(IF otd = "OnTime" THEN 1 END) as ontime_count
-                FROM shipment
-                WHERE origin_zip5 = $zip5
-                GROUP BY dest_zip5
-                HAVING volume >= $min_volume
+                SELECT * FROM (
+                    SELECT
+                        dest_zip5,
+                        count() as volume,
+                        array::distinct(carrier_ref) as carriers,
+                        math::mean(actual_transit_days) as avg_transit,
+                        count(IF otd = "OnTime" THEN 1 END) as ontime_count
+                    FROM shipment
+                    WHERE origin_zip5 = $zip5
+                    GROUP BY dest_zip5
+                )
+                WHERE volume >= $min_volume
                 ORDER BY volume DESC
                 LIMIT $limit
             "#)
@@ -1078,3 +1179,166 @@ impl AnalyticsService {
         })
     }
 }
+
+/// Per-cluster lane counts, volume and averages
+fn clusters_from(lanes: &[LaneMetrics]) -> Vec<Cluster> {
+    playbooks().sorted().into_iter().map(|def| {
+        let cluster_lanes: Vec<&LaneMetrics> = lanes
+            .iter()
+            .filter(|l| l.cluster_id == def.id)
+            .collect();
+
+        let lane_count = cluster_lanes.len();
+        let total_volume: i64 = cluster_lanes.iter().map(|l| l.volume).sum();
+        let avg_delay = if lane_count > 0 {
+            cluster_lanes.iter().map(|l| l.avg_delay).sum::<f64>() / lane_count as f64
+        } else { 0.0 };
+        let avg_late_rate = if lane_count > 0 {
+            cluster_lanes.iter().map(|l| l.late_rate).sum::<f64>() / lane_count as f64
+        } else { 0.0 };
+
+        Cluster {
+            id: def.id,
+            name: def.name.clone(),
+            description: def.description.clone(),
+            lane_count,
+            total_volume,
+            avg_delay: (avg_delay * 100.0).round() / 100.0,
+            avg_late_rate: (avg_late_rate * 1000.0).round() / 10.0,
+        }
+    }).collect()
+}
+
+/// Destinations by friction score, highest first
+fn friction_zones_from(lanes: &[LaneMetrics]) -> Vec<FrictionZone> {
+    let mut dest_stats: HashMap<String, (i64, f64, f64, i64)> = HashMap::new();
+    for lane in lanes {
+        let entry = dest_stats.entry(lane.dest_zip.clone()).or_insert((0, 0.0, 0.0, 0));
+        entry.0 += lane.volume;
+        entry.1 += lane.late_rate * lane.volume as f64;
+        entry.2 += lane.transit_variance * lane.volume as f64;
+        entry.3 += 1;
+    }
+
+    let mut friction_zones: Vec<FrictionZone> = dest_stats
+        .into_iter()
+        .filter(|(_, (vol, _, _, _))| *vol >= 100)
+        .map(|(zip, (vol, late_sum, var_sum, count))| {
+            let avg_late = late_sum / vol as f64;
+            let avg_var = var_sum / vol as f64;
+            let friction_score = (avg_late * 100.0 + avg_var * 10.0).round() / 10.0;
+            let (late_lower, late_upper) = wilson_95(late_sum.round() as i64, vol).percent();
+            FrictionZone {
+                dest_zip: zip.clone(),
+                location: format_lane_short("", &zip).trim_start_matches(" → ").to_string(),
+                friction_score,
+                late_rate: (avg_late * 1000.0).round() / 10.0,
+                late_rate_lower: late_lower,
+                late_rate_upper: late_upper,
+                transit_variance: (avg_var * 100.0).round() / 100.0,
+                volume: vol,
+                lane_count: count,
+            }
+        })
+        .collect();
+
+    friction_zones.sort_by(|a, b| b.friction_score.partial_cmp(&a.friction_score).unwrap_or(std::cmp::Ordering::Equal));
+    friction_zones
+}
+
+/// Origin terminals with at least 50 shipments
+fn terminals_from(lanes: &[LaneMetrics]) -> Vec<TerminalPerformance> {
+    let mut origin_stats: HashMap<String, (i64, f64, f64, f64, i64)> = HashMap::new();
+    for lane in lanes {
+        let entry = origin_stats.entry(lane.origin_zip.clone()).or_insert((0, 0.0, 0.0, 0.0, 0));
+        entry.0 += lane.volume;
+        entry.1 += lane.late_rate * lane.volume as f64;
+        entry.2 += lane.early_rate * lane.volume as f64;
+        entry.3 += lane.on_time_rate * lane.volume as f64;
+        entry.4 += 1;
+    }
+
+    origin_stats
+        .into_iter()
+        .filter(|(_, (vol, _, _, _, _))| *vol >= 50)
+        .map(|(zip, (vol, late_sum, early_sum, ontime_sum, count))| {
+            let late_rate = late_sum / vol as f64;
+            let early_rate = early_sum / vol as f64;
+            let ontime_rate = ontime_sum / vol as f64;
+            let score = ((1.0 - late_rate) * 100.0).round();
+            let (ontime_lower, ontime_upper) = wilson_95(ontime_sum.round() as i64, vol).percent();
+            let (late_lower, late_upper) = wilson_95(late_sum.round() as i64, vol).percent();
+            TerminalPerformance {
+                origin_zip: zip.clone(),
+                terminal: format_lane_short(&zip, "").trim_end_matches(" → ").to_string(),
+                performance_score: score,
+                on_time_rate: (ontime_rate * 1000.0).round() / 10.0,
+                on_time_rate_lower: ontime_lower,
+                on_time_rate_upper: ontime_upper,
+                late_rate: (late_rate * 1000.0).round() / 10.0,
+                late_rate_lower: late_lower,
+                late_rate_upper: late_upper,
+                early_rate: (early_rate * 1000.0).round() / 10.0,
+                volume: vol,
+                lane_count: count,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three shipments from 75201: two to 43215 (C1, C2), one to 10001
+    async fn zip5_service() -> AnalyticsService {
+        let db = db::connect("mem://").await.unwrap();
+        db::init_schema(&db).await.unwrap();
+        db.query(r#"
+            CREATE shipment SET load_id = "L1", carrier_ref = "C1", carrier_mode = "LTL", otd = "OnTime",
+                actual_transit_days = 2, goal_transit_days = 3, is_synthetic = false,
+                actual_ship = d"2024-03-04T10:00:00Z", actual_delivery = d"2024-03-06T10:00:00Z",
+                origin_zip5 = "75201", dest_zip5 = "43215", lane_zip5_pair = "75201→43215";
+            CREATE shipment SET load_id = "L2", carrier_ref = "C2", carrier_mode = "LTL", otd = "Late",
+                actual_transit_days = 4, goal_transit_days = 3,
+                actual_ship = d"2024-03-05T10:00:00Z", actual_delivery = d"2024-03-09T10:00:00Z",
+                origin_zip5 = "75201", dest_zip5 = "43215", lane_zip5_pair = "75201→43215";
+            CREATE shipment SET load_id = "L3", carrier_ref = "C1", carrier_mode = "LTL", otd = "OnTime",
+                actual_transit_days = 3, goal_transit_days = 3,
+                actual_ship = d"2024-03-05T10:00:00Z", actual_delivery = d"2024-03-08T10:00:00Z",
+                origin_zip5 = "75201", dest_zip5 = "10001", lane_zip5_pair = "75201→10001";
+        "#).await.unwrap().check().unwrap();
+        AnalyticsService::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_network_topology_counts_distinct_zip5s() {
+        let topology = zip5_service().await.get_network_topology().await.unwrap();
+        assert_eq!(topology.nodes.shipments, 3);
+        // One origin, two destinations
+        assert_eq!(topology.density.avg_destinations_per_origin, 2.0);
+    }
+
+    #[tokio::test]
+    async fn test_trace_zip5_shipment() {
+        let service = zip5_service().await;
+        let trace = service.trace_shipment("L2").await.unwrap().unwrap();
+        assert_eq!(trace.carrier.carrier_id, "C2");
+        assert!(trace.shipment.ship_date.starts_with("2024-03-05T10:00:00"), "{}", trace.shipment.ship_date);
+        assert_eq!(trace.lane.zip3_pair, "752xx→432xx");
+        assert!(service.trace_shipment("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reachable_destinations_min_volume() {
+        let service = zip5_service().await;
+        let reachable = service.get_reachable_destinations("75201", 2, 10).await.unwrap();
+        assert_eq!(reachable.total_destinations, 1);
+        assert_eq!(reachable.destinations[0].zip5, "43215");
+        assert_eq!(reachable.destinations[0].volume, 2);
+        assert_eq!(reachable.total_carriers, 2);
+
+        let all = service.get_reachable_destinations("75201", 1, 10).await.unwrap();
+        assert_eq!(all.destinations.iter().map(|d| d.volume).collect::<Vec<_>>(), [2, 1]);
+    }
+}
@@ -1,39 +1,52 @@
 //! Lane Clustering Analytics
 //! Clusters lanes into behavioral families for standardized playbooks
 //!
-//! Run: ./target/release/analytics_clustering [section]
+//! Run: ./target/release/analytics_clustering [section] [lane] [--format table|json|csv|markdown]
+//! Filters: --from, --to, --mode, --carrier, --origin-zip3, --distance-bucket
+//! Database: --db URL (default: rocksdb://data/lastmile.db)
+//! Clustering: --algorithm kmeans|dbscan|rules, --k, --eps, --min-points,
+//!             --min-volume, --save (store assignments for the API)
+//! Playbooks: --playbooks FILE (default: $LASTMILE_PLAYBOOKS or config/playbooks.toml)
 //! Sections: all, clusters, lanes, playbooks, similar
 
 use anyhow::Result;
-use nyc_last_mile::{db, carrier_names::get_carrier_name, location_names::format_lane_short};
+use clap::Parser;
+use nyc_last_mile::{db, carrier_names::get_carrier_name, location_names::format_lane_short, table_println};
+use nyc_last_mile::clustering::{self, Algorithm, ClusterArgs, ClusteringResult};
+use nyc_last_mile::filter::ShipmentFilter;
+use nyc_last_mile::playbook::{self, Playbooks};
+use nyc_last_mile::report::{OutputArgs, Report};
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::path::PathBuf;
 
-/// Lane metrics for clustering (raw counts from DB)
-#[derive(Debug, Clone, Deserialize)]
-struct LaneMetricsRaw {
-    origin_zip: String,
-    dest_zip: String,
-    volume: i64,
-    avg_delay: f64,        // actual - goal (negative = early)
-    transit_variance: f64,
-    early_count: i64,
-    ontime_count: i64,
-    late_count: i64,
-    avg_transit: f64,
-}
+/// Lane clustering analytics
+#[derive(Parser, Debug)]
+#[command(name = "analytics_clustering")]
+#[command(about = "Cluster lanes into behavioral families with playbooks")]
+struct Args {
+    /// Section to run
+    #[arg(default_value = "all", value_parser = ["all", "clusters", "lanes", "playbooks", "similar"])]
+    section: String,
 
-/// Lane metrics with computed rates
-#[derive(Debug, Clone)]
-struct LaneMetrics {
-    origin_zip: String,
-    dest_zip: String,
-    volume: i64,
-    avg_delay: f64,
-    transit_variance: f64,
-    early_rate: f64,
-    on_time_rate: f64,
-    late_rate: f64,
+    /// Lane, city or ZIP3 to match for `similar`
+    lane: Option<String>,
+
+    #[command(flatten)]
+    filter: ShipmentFilter,
+
+    #[command(flatten)]
+    clustering: ClusterArgs,
+
+    /// Cluster playbook file, .toml or .json
+    /// [default: $LASTMILE_PLAYBOOKS or config/playbooks.toml]
+    #[arg(long)]
+    playbooks: Option<PathBuf>,
+
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
 }
 
 /// Lane with cluster assignment
@@ -49,15 +62,39 @@ struct ClusteredLane {
     late_rate: f64,
     cluster_id: u8,
     cluster_name: String,
+    silhouette: Option<f64>,
 }
 
-/// Cluster definition
-#[derive(Debug, Clone)]
-struct ClusterDef {
-    id: u8,
-    name: &'static str,
-    description: &'static str,
-    playbook: Vec<&'static str>,
+/// Per-cluster totals for the summary table
+#[derive(Debug, Serialize)]
+struct ClusterSummary {
+    cluster_id: u8,
+    cluster_name: String,
+    description: String,
+    lanes: usize,
+    shipments: i64,
+    avg_delay: f64,
+    avg_variance: f64,
+    avg_late_rate: f64,
+    top_lanes: String,
+}
+
+/// Share of lanes and volume per cluster
+#[derive(Debug, Serialize)]
+struct ClusterShare {
+    cluster_name: String,
+    lanes: usize,
+    shipments: i64,
+    pct_volume: f64,
+}
+
+/// One playbook step
+#[derive(Debug, Serialize)]
+struct PlaybookStep {
+    cluster_id: u8,
+    cluster_name: String,
+    step: usize,
+    action: String,
 }
 
 /// Day-of-week pattern for a lane
@@ -81,211 +118,94 @@ struct LaneCarrierMix {
     late_rate: f64,
 }
 
-fn get_cluster_definitions() -> Vec<ClusterDef> {
-    vec![
-        ClusterDef {
-            id: 1,
-            name: "Early & Stable",
-            description: "Consistently arrive 0.5-2 days early with low variance",
-            playbook: vec![
-                "Implement hold-until policies at local depot",
-                "Offer tight customer delivery windows",
-                "Consider tightening SLA promises (reduce buffer)",
-                "Use for premium time-slot offerings",
-            ],
-        },
-        ClusterDef {
-            id: 2,
-            name: "On-Time & Reliable",
-            description: "High on-time rate with predictable transit",
-            playbook: vec![
-                "Maintain current operations - these are your best lanes",
-                "Use as benchmark for other lanes",
-                "Suitable for guaranteed delivery promises",
-                "Monitor for degradation, protect capacity",
-            ],
-        },
-        ClusterDef {
-            id: 3,
-            name: "High-Jitter",
-            description: "Average is OK but high variance - unpredictable",
-            playbook: vec![
-                "Add buffer days to customer promises",
-                "Avoid 'guaranteed by noon' commitments",
-                "Route to lockers/pickup points to handle timing uncertainty",
-                "Investigate root cause: carrier issues? weather corridors?",
-            ],
-        },
-        ClusterDef {
-            id: 4,
-            name: "Systematically Late",
-            description: "Consistently miss SLA - structural problem",
-            playbook: vec![
-                "Downgrade promise (next-day → 2-day) for these lanes",
-                "Negotiate with carriers or switch providers",
-                "Consider pre-positioning inventory closer to destination",
-                "Flag for carrier performance review",
-            ],
-        },
-        ClusterDef {
-            id: 5,
-            name: "Low Volume / Mixed",
-            description: "Insufficient data or mixed patterns",
-            playbook: vec![
-                "Apply conservative SLA buffers",
-                "Monitor as volume grows",
-                "Consider consolidating with similar lanes",
-                "Default to standard operating procedures",
-            ],
-        },
-    ]
-}
-
-/// Assign a lane to a cluster based on its metrics
-fn assign_cluster(lane: &LaneMetrics) -> (u8, &'static str) {
-    // Minimum volume threshold for confident clustering
-    if lane.volume < 20 {
-        return (5, "Low Volume / Mixed");
-    }
-
-    // Cluster 1: Early & Stable
-    // - Average arrival is early (negative delay)
-    // - Low variance (consistent)
-    if lane.avg_delay < -0.3 && lane.transit_variance < 2.0 && lane.early_rate > 0.3 {
-        return (1, "Early & Stable");
-    }
-
-    // Cluster 4: Systematically Late (check before high-jitter)
-    // - High late rate regardless of variance
-    if lane.late_rate > 0.45 {
-        return (4, "Systematically Late");
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let section = args.section.as_str();
+    let playbooks = playbook::init(args.playbooks.as_deref())?;
+
+    let db = args.db.connect().await?;
+    let mut report = args.output.report("Lane Clustering Analytics");
+    report.filter = args.filter.describe();
+    let out = &mut report;
+    let filter = &args.filter;
+
+    table_println!(out, "\n{}", "█".repeat(90));
+    table_println!(out, "{}  LANE CLUSTERING ANALYTICS  {}", "█".repeat(29), "█".repeat(30));
+    table_println!(out, "{}  Behavioral Families for Last-Mile Playbooks  {}", "█".repeat(20), "█".repeat(21));
+    table_println!(out, "{}\n", "█".repeat(90));
+    if !filter.is_empty() {
+        table_println!(out, "  Filter: {}\n", out.filter);
     }
 
-    // Cluster 3: High-Jitter
-    // - High variance regardless of mean
-    if lane.transit_variance > 3.5 {
-        return (3, "High-Jitter");
+    let lanes = clustering::load_lane_features(&db, filter).await?;
+    let result = clustering::cluster_lanes(&lanes, &args.clustering.config());
+
+    let fit = match result.config.algorithm {
+        Algorithm::Kmeans => format!("k-means (k={})", result.config.k),
+        Algorithm::Dbscan => format!("DBSCAN (eps={}, min points={}, {} noise lanes)",
+                                     result.config.eps, result.config.min_points, result.noise),
+        Algorithm::Rules => "fixed thresholds".to_string(),
+    };
+    match result.silhouette {
+        Some(score) => table_println!(out, "  Clustering: {} | Silhouette: {:.3}\n", fit, score),
+        None => table_println!(out, "  Clustering: {}\n", fit),
     }
-
-    // Cluster 2: On-Time & Reliable
-    // - Good on-time rate with reasonable variance
-    if lane.on_time_rate > 0.55 && lane.transit_variance < 2.5 {
-        return (2, "On-Time & Reliable");
+    if args.clustering.save {
+        clustering::save(&db, &result).await?;
+        table_println!(out, "  Saved {} lane assignments to lane_cluster\n", result.assignments.len());
     }
 
-    // Default: Mixed patterns
-    (5, "Low Volume / Mixed")
-}
-
-fn print_section_header(title: &str) {
-    println!("\n{}", "═".repeat(90));
-    println!("  {}", title);
-    println!("{}\n", "═".repeat(90));
-}
-
-fn print_subsection(title: &str) {
-    println!("\n{}", title);
-    println!("{}", "─".repeat(80));
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
-    let section = args.get(1).map(|s| s.as_str()).unwrap_or("all");
-
-    let db = db::connect("data/lastmile.db").await?;
-
-    println!("\n{}", "█".repeat(90));
-    println!("{}  LANE CLUSTERING ANALYTICS  {}", "█".repeat(29), "█".repeat(30));
-    println!("{}  Behavioral Families for Last-Mile Playbooks  {}", "█".repeat(20), "█".repeat(21));
-    println!("{}\n", "█".repeat(90));
-
-    // Fetch all lane metrics (raw counts - avoid SurrealDB integer division bug)
-    let lanes_raw: Vec<LaneMetricsRaw> = db
-        .query(r#"
-            SELECT
-                origin_zip,
-                dest_zip,
-                count() as volume,
-                math::mean(actual_transit_days - goal_transit_days) as avg_delay,
-                math::variance(actual_transit_days) as transit_variance,
-                count(IF otd = "Early" THEN 1 END) as early_count,
-                count(IF otd = "OnTime" THEN 1 END) as ontime_count,
-                count(IF otd = "Late" THEN 1 END) as late_count,
-                math::mean(actual_transit_days) as avg_transit
-            FROM shipment
-            GROUP BY origin_zip, dest_zip
-        "#)
-        .await?
-        .take(0)?;
-
-    // Compute rates in Rust to avoid SurrealDB integer division
-    let lanes: Vec<LaneMetrics> = lanes_raw
-        .into_iter()
-        .map(|raw| {
-            let vol = raw.volume as f64;
-            LaneMetrics {
-                origin_zip: raw.origin_zip,
-                dest_zip: raw.dest_zip,
-                volume: raw.volume,
-                avg_delay: raw.avg_delay,
-                transit_variance: raw.transit_variance,
-                early_rate: raw.early_count as f64 / vol,
-                on_time_rate: raw.ontime_count as f64 / vol,
-                late_rate: raw.late_count as f64 / vol,
-            }
-        })
-        .collect();
-
-    // Cluster all lanes
-    let clustered_lanes: Vec<ClusteredLane> = lanes
+    let clustered_lanes: Vec<ClusteredLane> = result
+        .assignments
         .iter()
-        .map(|lane| {
-            let (cluster_id, cluster_name) = assign_cluster(lane);
-            ClusteredLane {
-                origin_zip: lane.origin_zip.clone(),
-                dest_zip: lane.dest_zip.clone(),
-                volume: lane.volume,
-                avg_delay: lane.avg_delay,
-                variance: lane.transit_variance,
-                early_rate: lane.early_rate,
-                on_time_rate: lane.on_time_rate,
-                late_rate: lane.late_rate,
-                cluster_id,
-                cluster_name: cluster_name.to_string(),
-            }
+        .map(|a| ClusteredLane {
+            origin_zip: a.lane.origin_zip.clone(),
+            dest_zip: a.lane.dest_zip.clone(),
+            volume: a.lane.volume,
+            avg_delay: a.lane.avg_delay,
+            variance: a.lane.transit_variance,
+            early_rate: a.lane.early_rate,
+            on_time_rate: a.lane.on_time_rate,
+            late_rate: a.lane.late_rate,
+            cluster_id: a.cluster_id,
+            cluster_name: a.cluster_name.clone(),
+            silhouette: a.silhouette,
         })
         .collect();
 
     match section {
         "all" => {
-            run_cluster_summary(&clustered_lanes).await?;
-            run_cluster_details(&clustered_lanes).await?;
-            run_playbooks().await?;
+            run_cluster_summary(&clustered_lanes, &result, playbooks, out).await?;
+            run_cluster_details(&clustered_lanes, playbooks, out).await?;
+            run_playbooks(playbooks, out).await?;
         }
-        "clusters" => run_cluster_summary(&clustered_lanes).await?,
-        "lanes" => run_cluster_details(&clustered_lanes).await?,
-        "playbooks" => run_playbooks().await?,
+        "clusters" => run_cluster_summary(&clustered_lanes, &result, playbooks, out).await?,
+        "lanes" => run_cluster_details(&clustered_lanes, playbooks, out).await?,
+        "playbooks" => run_playbooks(playbooks, out).await?,
         "similar" => {
-            let target = args.get(2).cloned().unwrap_or_default();
-            run_similar_lanes(&clustered_lanes, &target).await?;
-        }
-        _ => {
-            println!("Unknown section: {}", section);
-            println!("Available: all, clusters, lanes, playbooks, similar <lane>");
+            let target = args.lane.clone().unwrap_or_default();
+            run_similar_lanes(&clustered_lanes, &target, out).await?;
         }
+        _ => unreachable!("section is validated by clap"),
     }
 
-    println!("\n{}", "█".repeat(90));
-    Ok(())
+    table_println!(out, "\n{}", "█".repeat(90));
+    args.output.write(&report)
 }
 
-async fn run_cluster_summary(lanes: &[ClusteredLane]) -> Result<()> {
-    print_section_header("CLUSTER SUMMARY");
+async fn run_cluster_summary(lanes: &[ClusteredLane], result: &ClusteringResult, playbooks: &Playbooks,
+                             out: &mut Report) -> Result<()> {
+    out.section("clusters", "CLUSTER SUMMARY");
+
+    if !result.clusters.is_empty() {
+        run_learned_clusters(result, out)?;
+    }
 
-    let clusters = get_cluster_definitions();
+    let clusters = playbooks.sorted();
 
     // Calculate stats per cluster
+    let mut summary = Vec::new();
     for cluster in &clusters {
         let cluster_lanes: Vec<&ClusteredLane> = lanes
             .iter()
@@ -310,43 +230,50 @@ async fn run_cluster_summary(lanes: &[ClusteredLane]) -> Result<()> {
             0.0
         };
 
-        let indicator = match cluster.id {
-            1 => "🟢",
-            2 => "🟢",
-            3 => "🟡",
-            4 => "🔴",
-            _ => "⚪",
-        };
-
-        println!("{} Cluster {}: {} ({} lanes, {} shipments)",
-                 indicator, cluster.id, cluster.name, total_lanes, total_volume);
-        println!("   {}", cluster.description);
-        println!("   Avg Delay: {:+.2} days | Variance: {:.2} | Late Rate: {:.1}%",
+        table_println!(out, "{} Cluster {}: {} ({} lanes, {} shipments)",
+                 cluster.indicator, cluster.id, cluster.name, total_lanes, total_volume);
+        table_println!(out, "   {}", cluster.description);
+        table_println!(out, "   Avg Delay: {:+.2} days | Variance: {:.2} | Late Rate: {:.1}%",
                  avg_delay, avg_variance, avg_late_rate * 100.0);
 
         // Show top 5 lanes in this cluster
         let mut sorted_lanes = cluster_lanes.clone();
         sorted_lanes.sort_by(|a, b| b.volume.cmp(&a.volume));
 
+        let top_lanes = sorted_lanes
+            .iter()
+            .take(5)
+            .map(|lane| format_lane_short(&lane.origin_zip, &lane.dest_zip))
+            .collect::<Vec<_>>()
+            .join(", ");
         if !sorted_lanes.is_empty() {
-            print!("   Top lanes: ");
-            for (i, lane) in sorted_lanes.iter().take(5).enumerate() {
-                if i > 0 { print!(", "); }
-                print!("{}", format_lane_short(&lane.origin_zip, &lane.dest_zip));
-            }
-            println!();
+            table_println!(out, "   Top lanes: {}", top_lanes);
         }
-        println!();
+        table_println!(out);
+
+        summary.push(ClusterSummary {
+            cluster_id: cluster.id,
+            cluster_name: cluster.name.clone(),
+            description: cluster.description.clone(),
+            lanes: total_lanes,
+            shipments: total_volume,
+            avg_delay,
+            avg_variance,
+            avg_late_rate,
+            top_lanes,
+        });
     }
+    out.add("summary", &summary)?;
 
     // Overall distribution
-    print_subsection("Cluster Distribution");
-    println!("  {:25} {:>10} {:>12} {:>12}",
+    out.subsection("Cluster Distribution");
+    table_println!(out, "  {:25} {:>10} {:>12} {:>12}",
              "Cluster", "Lanes", "Shipments", "% Volume");
-    println!("  {}", "─".repeat(61));
+    table_println!(out, "  {}", "─".repeat(61));
 
     let total_volume: i64 = lanes.iter().map(|l| l.volume).sum();
 
+    let mut shares = Vec::new();
     for cluster in &clusters {
         let cluster_lanes: Vec<&ClusteredLane> = lanes
             .iter()
@@ -357,17 +284,43 @@ async fn run_cluster_summary(lanes: &[ClusteredLane]) -> Result<()> {
         let volume: i64 = cluster_lanes.iter().map(|l| l.volume).sum();
         let pct = (volume as f64 / total_volume as f64) * 100.0;
 
-        println!("  {:25} {:>10} {:>12} {:>11.1}%",
+        table_println!(out, "  {:25} {:>10} {:>12} {:>11.1}%",
                  cluster.name, lane_count, volume, pct);
+        shares.push(ClusterShare {
+            cluster_name: cluster.name.clone(),
+            lanes: lane_count,
+            shipments: volume,
+            pct_volume: pct,
+        });
+    }
+    out.add("distribution", &shares)?;
+
+    Ok(())
+}
+
+/// Learned clusters and the behavior each centroid was mapped to
+fn run_learned_clusters(result: &ClusteringResult, out: &mut Report) -> Result<()> {
+    out.subsection("Learned Clusters");
+    table_println!(out, "  {:>5} {:22} {:>6} {:>9} {:>10} {:>9} {:>8} {:>11}",
+             "Label", "Mapped To", "Lanes", "Shipments", "Avg Delay", "Variance", "Late%", "Silhouette");
+    table_println!(out, "  {}", "─".repeat(88));
+
+    for c in &result.clusters {
+        let silhouette = c.silhouette.map(|s| format!("{:.3}", s)).unwrap_or_else(|| "-".to_string());
+        table_println!(out, "  {:>5} {:22} {:>6} {:>9} {:>+9.2}d {:>9.2} {:>7.1}% {:>11}",
+                 c.label, c.cluster_name, c.lanes, c.shipments, c.avg_delay,
+                 c.transit_variance, c.late_rate * 100.0, silhouette);
     }
+    table_println!(out);
+    out.add("learned", &result.clusters)?;
 
     Ok(())
 }
 
-async fn run_cluster_details(lanes: &[ClusteredLane]) -> Result<()> {
-    print_section_header("LANES BY CLUSTER");
+async fn run_cluster_details(lanes: &[ClusteredLane], playbooks: &Playbooks, out: &mut Report) -> Result<()> {
+    out.section("lanes", "LANES BY CLUSTER");
 
-    let clusters = get_cluster_definitions();
+    let clusters = playbooks.sorted();
 
     for cluster in &clusters {
         let mut cluster_lanes: Vec<&ClusteredLane> = lanes
@@ -382,57 +335,61 @@ async fn run_cluster_details(lanes: &[ClusteredLane]) -> Result<()> {
         // Sort by volume descending
         cluster_lanes.sort_by(|a, b| b.volume.cmp(&a.volume));
 
-        print_subsection(&format!("Cluster {}: {} ({} lanes)",
+        out.subsection(&format!("Cluster {}: {} ({} lanes)",
                                   cluster.id, cluster.name, cluster_lanes.len()));
 
-        println!("  {:20} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        table_println!(out, "  {:20} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
                  "Lane", "Volume", "Avg Delay", "Variance", "Early%", "OnTime%", "Late%");
-        println!("  {}", "─".repeat(78));
+        table_println!(out, "  {}", "─".repeat(78));
 
         for lane in cluster_lanes.iter().take(15) {
             let route = format_lane_short(&lane.origin_zip, &lane.dest_zip);
-            println!("  {:20} {:>8} {:>+9.2}d {:>10.2} {:>9.1}% {:>9.1}% {:>9.1}%",
+            table_println!(out, "  {:20} {:>8} {:>+9.2}d {:>10.2} {:>9.1}% {:>9.1}% {:>9.1}%",
                      route, lane.volume, lane.avg_delay, lane.variance,
                      lane.early_rate * 100.0, lane.on_time_rate * 100.0, lane.late_rate * 100.0);
         }
 
         if cluster_lanes.len() > 15 {
-            println!("  ... and {} more lanes", cluster_lanes.len() - 15);
+            table_println!(out, "  ... and {} more lanes", cluster_lanes.len() - 15);
         }
+        out.add(&format!("cluster_{}", cluster.id), &cluster_lanes)?;
     }
 
     Ok(())
 }
 
-async fn run_playbooks() -> Result<()> {
-    print_section_header("CLUSTER PLAYBOOKS");
+async fn run_playbooks(playbooks: &Playbooks, out: &mut Report) -> Result<()> {
+    out.section("playbooks", "CLUSTER PLAYBOOKS");
 
-    let clusters = get_cluster_definitions();
+    let clusters = playbooks.sorted();
 
+    let mut steps = Vec::new();
     for cluster in &clusters {
-        let indicator = match cluster.id {
-            1 => "🟢",
-            2 => "🟢",
-            3 => "🟡",
-            4 => "🔴",
-            _ => "⚪",
-        };
-
-        println!("{} CLUSTER {}: {}", indicator, cluster.id, cluster.name.to_uppercase());
-        println!("   {}", cluster.description);
-        println!();
-        println!("   Recommended Actions:");
-        for (i, action) in cluster.playbook.iter().enumerate() {
-            println!("   {}. {}", i + 1, action);
+        table_println!(out, "{} CLUSTER {}: {}", cluster.indicator, cluster.id, cluster.name.to_uppercase());
+        table_println!(out, "   {}", cluster.description);
+        if !cluster.strategy.is_empty() {
+            table_println!(out, "   Strategy: {}", cluster.strategy);
         }
-        println!();
+        table_println!(out);
+        table_println!(out, "   Recommended Actions:");
+        for (i, action) in cluster.actions.iter().enumerate() {
+            table_println!(out, "   {}. {}", i + 1, action);
+            steps.push(PlaybookStep {
+                cluster_id: cluster.id,
+                cluster_name: cluster.name.clone(),
+                step: i + 1,
+                action: action.clone(),
+            });
+        }
+        table_println!(out);
     }
+    out.add("steps", &steps)?;
 
     Ok(())
 }
 
-async fn run_similar_lanes(lanes: &[ClusteredLane], target: &str) -> Result<()> {
-    print_section_header(&format!("LANES SIMILAR TO: {}", target));
+async fn run_similar_lanes(lanes: &[ClusteredLane], target: &str, out: &mut Report) -> Result<()> {
+    out.section("similar", &format!("LANES SIMILAR TO: {}", target));
 
     // Find the target lane
     let target_lane = lanes.iter().find(|l| {
@@ -445,15 +402,16 @@ async fn run_similar_lanes(lanes: &[ClusteredLane], target: &str) -> Result<()>
     match target_lane {
         Some(lane) => {
             let route = format_lane_short(&lane.origin_zip, &lane.dest_zip);
-            println!("Target Lane: {}", route);
-            println!("  Cluster: {} - {}", lane.cluster_id, lane.cluster_name);
-            println!("  Volume: {} | Avg Delay: {:+.2}d | Variance: {:.2}",
+            table_println!(out, "Target Lane: {}", route);
+            table_println!(out, "  Cluster: {} - {}", lane.cluster_id, lane.cluster_name);
+            table_println!(out, "  Volume: {} | Avg Delay: {:+.2}d | Variance: {:.2}",
                      lane.volume, lane.avg_delay, lane.variance);
-            println!("  Early: {:.1}% | On-Time: {:.1}% | Late: {:.1}%",
+            table_println!(out, "  Early: {:.1}% | On-Time: {:.1}% | Late: {:.1}%",
                      lane.early_rate * 100.0, lane.on_time_rate * 100.0, lane.late_rate * 100.0);
+            out.add_one("target", lane)?;
 
             // Find similar lanes (same cluster)
-            print_subsection("Similar Lanes (Same Cluster)");
+            out.subsection("Similar Lanes (Same Cluster)");
 
             let similar: Vec<&ClusteredLane> = lanes
                 .iter()
@@ -462,23 +420,25 @@ async fn run_similar_lanes(lanes: &[ClusteredLane], target: &str) -> Result<()>
                 .take(20)
                 .collect();
 
-            println!("  {:20} {:>8} {:>10} {:>10} {:>10}",
+            table_println!(out, "  {:20} {:>8} {:>10} {:>10} {:>10}",
                      "Lane", "Volume", "Avg Delay", "Variance", "Late%");
-            println!("  {}", "─".repeat(60));
+            table_println!(out, "  {}", "─".repeat(60));
 
             for sim in &similar {
                 let sim_route = format_lane_short(&sim.origin_zip, &sim.dest_zip);
-                println!("  {:20} {:>8} {:>+9.2}d {:>10.2} {:>9.1}%",
+                table_println!(out, "  {:20} {:>8} {:>+9.2}d {:>10.2} {:>9.1}%",
                          sim_route, sim.volume, sim.avg_delay, sim.variance, sim.late_rate * 100.0);
             }
 
-            println!("\n  Total {} lanes share the '{}' playbook",
+            out.add("similar", &similar)?;
+
+            table_println!(out, "\n  Total {} lanes share the '{}' playbook",
                      similar.len() + 1, lane.cluster_name);
         }
         None => {
-            println!("Lane not found matching: {}", target);
-            println!("Try: ./target/release/analytics_clustering similar DFW");
-            println!("  or: ./target/release/analytics_clustering similar 750");
+            table_println!(out, "Lane not found matching: {}", target);
+            table_println!(out, "Try: ./target/release/analytics_clustering similar DFW");
+            table_println!(out, "  or: ./target/release/analytics_clustering similar 750");
         }
     }
 
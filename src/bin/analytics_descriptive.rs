@@ -1,15 +1,44 @@
 //! Descriptive Analytics - What is happening?
 //! Comprehensive KPIs and performance metrics
 //!
-//! Run: ./target/release/analytics_descriptive [section]
+//! Run: ./target/release/analytics_descriptive [section] [--format table|json|csv|markdown]
+//! Filters: --from, --to, --mode, --carrier, --origin-zip3, --distance-bucket
+//! Database: --db URL (default: rocksdb://data/lastmile.db)
+//! Compare: --baseline-from/--baseline-to turn the KPI section into a
+//!          period-over-period comparison against --from/--to
 //! Sections: all, kpi, transit, volume, distribution
 
 use anyhow::Result;
-use nyc_last_mile::{db, carrier_names::get_carrier_name};
-use serde::Deserialize;
-use std::env;
+use clap::Parser;
+use nyc_last_mile::{db, carrier_names::get_carrier_name, table_println};
+use nyc_last_mile::compare::{self, CompareArgs, PeriodComparison};
+use nyc_last_mile::filter::ShipmentFilter;
+use nyc_last_mile::report::{OutputArgs, Report};
+use serde::{Deserialize, Serialize};
+
+/// Descriptive analytics - what is happening?
+#[derive(Parser, Debug)]
+#[command(name = "analytics_descriptive")]
+#[command(about = "KPIs, transit times, volumes and distributions")]
+struct Args {
+    /// Section to run
+    #[arg(default_value = "all", value_parser = ["all", "kpi", "transit", "volume", "distribution"])]
+    section: String,
+
+    #[command(flatten)]
+    filter: ShipmentFilter,
+
+    #[command(flatten)]
+    compare: CompareArgs,
+
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct KpiMetric {
     total: i64,
     on_time: i64,
@@ -19,7 +48,8 @@ struct KpiMetric {
     avg_goal: f64,
 }
 
-#[derive(Debug, Deserialize)]
+/// OTD breakdown for one group; rates are percentages
+#[derive(Debug, Deserialize, Serialize)]
 struct GroupedOtd {
     group: String,
     total: i64,
@@ -29,13 +59,13 @@ struct GroupedOtd {
     avg_transit: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct TransitDistribution {
     transit_days: i64,
     count: i64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct VarianceMetric {
     group: String,
     total: i64,
@@ -43,16 +73,18 @@ struct VarianceMetric {
     min_transit: i64,
     max_transit: i64,
     variance: f64,
+    #[serde(default)]
+    reliability: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct VolumeMetric {
     group: String,
     shipments: i64,
     pct_of_total: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct MonthlyTrend {
     year_month: String,
     shipments: i64,
@@ -60,64 +92,65 @@ struct MonthlyTrend {
     avg_transit: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct VeryLateMetric {
     category: String,
     count: i64,
     pct: f64,
 }
 
-fn print_section_header(title: &str) {
-    println!("\n{}", "═".repeat(80));
-    println!("  {}", title);
-    println!("{}\n", "═".repeat(80));
-}
-
-fn print_subsection(title: &str) {
-    println!("\n{}", title);
-    println!("{}", "─".repeat(70));
-}
-
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
-    let section = args.get(1).map(|s| s.as_str()).unwrap_or("all");
-
-    let db = db::connect("data/lastmile.db").await?;
-
-    println!("\n{}", "█".repeat(80));
-    println!("{}  DESCRIPTIVE ANALYTICS - What is Happening?  {}", "█".repeat(15), "█".repeat(16));
-    println!("{}\n", "█".repeat(80));
+    let args = Args::parse();
+    let section = args.section.as_str();
+
+    let db = args.db.connect().await?;
+    let comparison = match args.compare.request(&args.filter)? {
+        Some(request) => Some(compare::compare_periods(&db, &request).await?),
+        None => None,
+    };
+    let mut report = args.output.report("Descriptive Analytics - What is Happening?");
+    report.filter = args.filter.describe();
+    let out = &mut report;
+    let filter = &args.filter;
+
+    table_println!(out, "\n{}", "█".repeat(80));
+    table_println!(out, "{}  DESCRIPTIVE ANALYTICS - What is Happening?  {}", "█".repeat(15), "█".repeat(16));
+    table_println!(out, "{}\n", "█".repeat(80));
+    if !filter.is_empty() {
+        table_println!(out, "  Filter: {}\n", out.filter);
+    }
 
     match section {
         "all" => {
-            run_kpi_section(&db).await?;
-            run_transit_section(&db).await?;
-            run_volume_section(&db).await?;
-            run_distribution_section(&db).await?;
-        }
-        "kpi" => run_kpi_section(&db).await?,
-        "transit" => run_transit_section(&db).await?,
-        "volume" => run_volume_section(&db).await?,
-        "distribution" => run_distribution_section(&db).await?,
-        _ => {
-            println!("Unknown section: {}", section);
-            println!("Available: all, kpi, transit, volume, distribution");
+            run_kpi_section(&db, out, filter, comparison.as_ref()).await?;
+            run_transit_section(&db, out, filter).await?;
+            run_volume_section(&db, out, filter).await?;
+            run_distribution_section(&db, out, filter).await?;
         }
+        "kpi" => run_kpi_section(&db, out, filter, comparison.as_ref()).await?,
+        "transit" => run_transit_section(&db, out, filter).await?,
+        "volume" => run_volume_section(&db, out, filter).await?,
+        "distribution" => run_distribution_section(&db, out, filter).await?,
+        _ => unreachable!("section is validated by clap"),
     }
 
-    println!("\n{}", "█".repeat(80));
-    Ok(())
+    table_println!(out, "\n{}", "█".repeat(80));
+    args.output.write(&report)
 }
 
-async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("1. BASIC PERFORMANCE KPIs");
+async fn run_kpi_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter, comparison: Option<&PeriodComparison>) -> Result<()> {
+    out.section("kpi", "1. BASIC PERFORMANCE KPIs");
+
+    if let Some(comparison) = comparison {
+        return run_kpi_comparison(out, comparison);
+    }
 
     // Overall KPIs
-    print_subsection("Overall Network Performance");
+    out.subsection("Overall Network Performance");
 
-    let overall: Option<KpiMetric> = db
-        .query(r#"
+    let overall: Option<KpiMetric> = filter
+        .query(db, r#"
             SELECT
                 count() as total,
                 count(IF otd = "OnTime" THEN 1 END) as on_time,
@@ -125,7 +158,7 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
                 count(IF otd = "Early" THEN 1 END) as early,
                 math::mean(actual_transit_days) as avg_transit,
                 math::mean(goal_transit_days) as avg_goal
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP ALL
         "#)
         .await?
@@ -137,17 +170,18 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         let early_rate = (kpi.early as f64 / kpi.total as f64) * 100.0;
         let delta = kpi.avg_transit - kpi.avg_goal;
 
-        println!("  Total Shipments:      {:>12}", kpi.total);
-        println!("  On-Time Rate:         {:>11.1}%", otd_rate);
-        println!("  Late Rate:            {:>11.1}%", late_rate);
-        println!("  Early Rate:           {:>11.1}%", early_rate);
-        println!("  Avg Transit Days:     {:>12.2}", kpi.avg_transit);
-        println!("  Avg Goal Days:        {:>12.2}", kpi.avg_goal);
-        println!("  Avg Delta:            {:>+12.2} days", delta);
+        table_println!(out, "  Total Shipments:      {:>12}", kpi.total);
+        table_println!(out, "  On-Time Rate:         {:>11.1}%", otd_rate);
+        table_println!(out, "  Late Rate:            {:>11.1}%", late_rate);
+        table_println!(out, "  Early Rate:           {:>11.1}%", early_rate);
+        table_println!(out, "  Avg Transit Days:     {:>12.2}", kpi.avg_transit);
+        table_println!(out, "  Avg Goal Days:        {:>12.2}", kpi.avg_goal);
+        table_println!(out, "  Avg Delta:            {:>+12.2} days", delta);
+        out.add_one("overall", &kpi)?;
     }
 
     // OTD by Carrier (Top 15)
-    print_subsection("OTD by Carrier (Top 15 by Volume)");
+    out.subsection("OTD by Carrier (Top 15 by Volume)");
 
     #[derive(Debug, Deserialize)]
     struct CarrierOtd {
@@ -159,8 +193,8 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         avg_transit: f64,
     }
 
-    let by_carrier: Vec<CarrierOtd> = db
-        .query(r#"
+    let by_carrier: Vec<CarrierOtd> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     carrier_ref,
@@ -169,7 +203,7 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
                     count(IF otd = "Late" THEN 1 END) as late,
                     count(IF otd = "Early" THEN 1 END) as early,
                     math::mean(actual_transit_days) as avg_transit
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref
             )
             ORDER BY total DESC
@@ -178,20 +212,30 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         .await?
         .take(0)?;
 
-    println!("  {:22} {:>8} {:>10} {:>10} {:>10} {:>10}",
+    table_println!(out, "  {:22} {:>8} {:>10} {:>10} {:>10} {:>10}",
              "Carrier", "Volume", "OTD%", "Late%", "Early%", "Avg Days");
-    println!("  {}", "─".repeat(72));
+    table_println!(out, "  {}", "─".repeat(72));
+    let mut rows = Vec::new();
     for row in &by_carrier {
         let name = get_carrier_name(&row.carrier_ref);
         let otd_rate = row.on_time as f64 / row.total as f64 * 100.0;
         let late_rate = row.late as f64 / row.total as f64 * 100.0;
         let early_rate = row.early as f64 / row.total as f64 * 100.0;
-        println!("  {:22} {:>8} {:>9.1}% {:>9.1}% {:>9.1}% {:>10.1}",
+        table_println!(out, "  {:22} {:>8} {:>9.1}% {:>9.1}% {:>9.1}% {:>10.1}",
                  name, row.total, otd_rate, late_rate, early_rate, row.avg_transit);
+        rows.push(GroupedOtd {
+            group: name,
+            total: row.total,
+            otd_rate,
+            late_rate,
+            early_rate,
+            avg_transit: row.avg_transit,
+        });
     }
+    out.add("by_carrier", &rows)?;
 
     // OTD by Mode
-    print_subsection("OTD by Carrier Mode");
+    out.subsection("OTD by Carrier Mode");
 
     #[derive(Debug, Deserialize)]
     struct ModeOtd {
@@ -203,8 +247,8 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         avg_transit: f64,
     }
 
-    let by_mode: Vec<ModeOtd> = db
-        .query(r#"
+    let by_mode: Vec<ModeOtd> = filter
+        .query(db, r#"
             SELECT
                 carrier_mode,
                 count() as total,
@@ -212,26 +256,36 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
                 count(IF otd = "Late" THEN 1 END) as late,
                 count(IF otd = "Early" THEN 1 END) as early,
                 math::mean(actual_transit_days) as avg_transit
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY carrier_mode
             ORDER BY total DESC
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:15} {:>10} {:>10} {:>10} {:>10} {:>10}",
+    table_println!(out, "  {:15} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "Mode", "Volume", "OTD%", "Late%", "Early%", "Avg Days");
-    println!("  {}", "─".repeat(65));
+    table_println!(out, "  {}", "─".repeat(65));
+    let mut rows = Vec::new();
     for row in &by_mode {
         let otd_rate = row.on_time as f64 / row.total as f64 * 100.0;
         let late_rate = row.late as f64 / row.total as f64 * 100.0;
         let early_rate = row.early as f64 / row.total as f64 * 100.0;
-        println!("  {:15} {:>10} {:>9.1}% {:>9.1}% {:>9.1}% {:>10.1}",
+        table_println!(out, "  {:15} {:>10} {:>9.1}% {:>9.1}% {:>9.1}% {:>10.1}",
                  row.carrier_mode, row.total, otd_rate, late_rate, early_rate, row.avg_transit);
+        rows.push(GroupedOtd {
+            group: row.carrier_mode.clone(),
+            total: row.total,
+            otd_rate,
+            late_rate,
+            early_rate,
+            avg_transit: row.avg_transit,
+        });
     }
+    out.add("by_mode", &rows)?;
 
     // OTD by Day of Week
-    print_subsection("OTD by Day of Week (Pickup Day)");
+    out.subsection("OTD by Day of Week (Pickup Day)");
 
     #[derive(Debug, Deserialize)]
     struct DowOtd {
@@ -243,8 +297,8 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         avg_transit: f64,
     }
 
-    let by_dow: Vec<DowOtd> = db
-        .query(r#"
+    let by_dow: Vec<DowOtd> = filter
+        .query(db, r#"
             SELECT
                 ship_dow,
                 count() as total,
@@ -252,7 +306,7 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
                 count(IF otd = "Late" THEN 1 END) as late,
                 count(IF otd = "Early" THEN 1 END) as early,
                 math::mean(actual_transit_days) as avg_transit
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY ship_dow
             ORDER BY ship_dow
         "#)
@@ -260,20 +314,30 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         .take(0)?;
 
     let dow_names = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
-    println!("  {:10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+    table_println!(out, "  {:10} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "Day", "Volume", "OTD%", "Late%", "Early%", "Avg Days");
-    println!("  {}", "─".repeat(60));
+    table_println!(out, "  {}", "─".repeat(60));
+    let mut rows = Vec::new();
     for row in &by_dow {
         let day = dow_names.get(row.ship_dow as usize).unwrap_or(&"???");
         let otd_rate = row.on_time as f64 / row.total as f64 * 100.0;
         let late_rate = row.late as f64 / row.total as f64 * 100.0;
         let early_rate = row.early as f64 / row.total as f64 * 100.0;
-        println!("  {:10} {:>10} {:>9.1}% {:>9.1}% {:>9.1}% {:>10.1}",
+        table_println!(out, "  {:10} {:>10} {:>9.1}% {:>9.1}% {:>9.1}% {:>10.1}",
                  day, row.total, otd_rate, late_rate, early_rate, row.avg_transit);
+        rows.push(GroupedOtd {
+            group: day.to_string(),
+            total: row.total,
+            otd_rate,
+            late_rate,
+            early_rate,
+            avg_transit: row.avg_transit,
+        });
     }
+    out.add("by_dow", &rows)?;
 
     // OTD by Distance Bucket
-    print_subsection("OTD by Distance Segment");
+    out.subsection("OTD by Distance Segment");
 
     #[derive(Debug, Deserialize)]
     struct DistOtd {
@@ -285,8 +349,8 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         avg_transit: f64,
     }
 
-    let by_distance: Vec<DistOtd> = db
-        .query(r#"
+    let by_distance: Vec<DistOtd> = filter
+        .query(db, r#"
             SELECT
                 distance_bucket,
                 count() as total,
@@ -294,73 +358,124 @@ async fn run_kpi_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
                 count(IF otd = "Late" THEN 1 END) as late,
                 count(IF otd = "Early" THEN 1 END) as early,
                 math::mean(actual_transit_days) as avg_transit
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY distance_bucket
             ORDER BY distance_bucket
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:12} {:>10} {:>10} {:>10} {:>10} {:>10}",
+    table_println!(out, "  {:12} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "Distance", "Volume", "OTD%", "Late%", "Early%", "Avg Days");
-    println!("  {}", "─".repeat(62));
+    table_println!(out, "  {}", "─".repeat(62));
+    let mut rows = Vec::new();
     for row in &by_distance {
         let otd_rate = row.on_time as f64 / row.total as f64 * 100.0;
         let late_rate = row.late as f64 / row.total as f64 * 100.0;
         let early_rate = row.early as f64 / row.total as f64 * 100.0;
-        println!("  {:12} {:>10} {:>9.1}% {:>9.1}% {:>9.1}% {:>10.1}",
+        table_println!(out, "  {:12} {:>10} {:>9.1}% {:>9.1}% {:>9.1}% {:>10.1}",
                  row.distance_bucket, row.total, otd_rate, late_rate, early_rate, row.avg_transit);
+        rows.push(GroupedOtd {
+            group: row.distance_bucket.clone(),
+            total: row.total,
+            otd_rate,
+            late_rate,
+            early_rate,
+            avg_transit: row.avg_transit,
+        });
     }
+    out.add("by_distance", &rows)?;
 
     Ok(())
 }
 
-async fn run_transit_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("2. TRANSIT TIME PERFORMANCE");
+/// KPI section in compare mode: the same KPIs for both windows
+fn run_kpi_comparison(out: &mut Report, c: &PeriodComparison) -> Result<()> {
+    out.subsection(&format!("Period Comparison: {} (baseline) vs {} (current)",
+                            c.baseline.label(), c.current.label()));
+
+    table_println!(out, "  {:18} {:>12} {:>12} {:>12} {:>8}",
+             "Metric", "Baseline", "Current", "Change", "Signif.");
+    table_println!(out, "  {}", "─".repeat(66));
+    for k in &c.kpis {
+        let sig = if k.significant { "*" } else { "" };
+        match k.metric.as_str() {
+            "shipments" => table_println!(out, "  {:18} {:>12.0} {:>12.0} {:>+12.0} {:>8}",
+                                          k.metric, k.baseline, k.current, k.delta, sig),
+            "avg_transit_days" => table_println!(out, "  {:18} {:>11.2}d {:>11.2}d {:>+11.2}d {:>8}",
+                                                 k.metric, k.baseline, k.current, k.delta, sig),
+            _ => table_println!(out, "  {:18} {:>11.1}% {:>11.1}% {:>+10.1}pp {:>8}",
+                                k.metric, k.baseline * 100.0, k.current * 100.0, k.delta * 100.0, sig),
+        }
+    }
+    out.add("comparison", &c.kpis)?;
+
+    out.subsection("OTD Rate Change by Carrier");
+    table_println!(out, "  {:20} {:>9} {:>9} {:>10} {:>10} {:>10} {:>8}",
+             "Carrier", "Base Vol", "Cur Vol", "Base OTD%", "Cur OTD%", "Change", "Signif.");
+    table_println!(out, "  {}", "─".repeat(82));
+    for row in &c.carriers {
+        table_println!(out, "  {:20} {:>9} {:>9} {:>9.1}% {:>9.1}% {:>+8.1}pp {:>8}",
+                 row.carrier_name, row.baseline_volume, row.current_volume,
+                 row.baseline_otd_rate * 100.0, row.current_otd_rate * 100.0,
+                 row.otd_delta * 100.0, if row.significant { "*" } else { "" });
+    }
+    table_println!(out, "\n  * significant at 95% (two-proportion z-test)");
+    out.add("carrier_otd_change", &c.carriers)?;
+
+    Ok(())
+}
+
+async fn run_transit_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("transit", "2. TRANSIT TIME PERFORMANCE");
 
     // Actual vs Goal by Distance
-    print_subsection("Actual vs Goal Transit Days by Distance");
+    out.subsection("Actual vs Goal Transit Days by Distance");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct TransitComparison {
         distance_bucket: String,
         total: i64,
         avg_actual: f64,
         avg_goal: f64,
         delta: f64,
+        #[serde(default)]
+        status: String,
     }
 
-    let transit_comp: Vec<TransitComparison> = db
-        .query(r#"
+    let mut transit_comp: Vec<TransitComparison> = filter
+        .query(db, r#"
             SELECT
                 distance_bucket,
                 count() as total,
                 math::mean(actual_transit_days) as avg_actual,
                 math::mean(goal_transit_days) as avg_goal,
                 math::mean(actual_transit_days - goal_transit_days) as delta
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY distance_bucket
             ORDER BY distance_bucket
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:12} {:>8} {:>12} {:>12} {:>12} {:>10}",
+    table_println!(out, "  {:12} {:>8} {:>12} {:>12} {:>12} {:>10}",
              "Distance", "Volume", "Avg Actual", "Avg Goal", "Delta", "Status");
-    println!("  {}", "─".repeat(66));
-    for row in &transit_comp {
+    table_println!(out, "  {}", "─".repeat(66));
+    for row in &mut transit_comp {
         let status = if row.delta > 0.5 { "⚠ SLOW" }
                     else if row.delta < -0.5 { "✓ FAST" }
                     else { "→ OK" };
-        println!("  {:12} {:>8} {:>11.2}d {:>11.2}d {:>+11.2}d {:>10}",
+        table_println!(out, "  {:12} {:>8} {:>11.2}d {:>11.2}d {:>+11.2}d {:>10}",
                  row.distance_bucket, row.total, row.avg_actual, row.avg_goal, row.delta, status);
+        row.status = status.to_string();
     }
+    out.add("actual_vs_goal", &transit_comp)?;
 
     // Transit Time Variance by Carrier
-    print_subsection("Transit Time Variance by Carrier (Top 10 by Volume)");
+    out.subsection("Transit Time Variance by Carrier (Top 10 by Volume)");
 
-    let variance: Vec<VarianceMetric> = db
-        .query(r#"
+    let mut variance: Vec<VarianceMetric> = filter
+        .query(db, r#"
             SELECT
                 carrier_ref as group,
                 count() as total,
@@ -368,7 +483,7 @@ async fn run_transit_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
                 math::min(actual_transit_days) as min_transit,
                 math::max(actual_transit_days) as max_transit,
                 math::variance(actual_transit_days) as variance
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY carrier_ref
             ORDER BY total DESC
             LIMIT 10
@@ -376,79 +491,84 @@ async fn run_transit_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
         .await?
         .take(0)?;
 
-    println!("  {:20} {:>8} {:>8} {:>6} {:>6} {:>10} {:>10}",
+    table_println!(out, "  {:20} {:>8} {:>8} {:>6} {:>6} {:>10} {:>10}",
              "Carrier", "Volume", "Avg", "Min", "Max", "Variance", "Reliability");
-    println!("  {}", "─".repeat(70));
-    for row in &variance {
+    table_println!(out, "  {}", "─".repeat(70));
+    for row in &mut variance {
         let reliability = if row.variance < 2.0 { "High" }
                          else if row.variance < 5.0 { "Medium" }
                          else { "Low" };
-        println!("  {:20} {:>8} {:>7.1}d {:>5}d {:>5}d {:>10.2} {:>10}",
-                 get_carrier_name(&row.group), row.total, row.avg_transit, row.min_transit,
+        row.group = get_carrier_name(&row.group);
+        table_println!(out, "  {:20} {:>8} {:>7.1}d {:>5}d {:>5}d {:>10.2} {:>10}",
+                 row.group, row.total, row.avg_transit, row.min_transit,
                  row.max_transit, row.variance, reliability);
+        row.reliability = reliability.to_string();
     }
+    out.add("carrier_variance", &variance)?;
 
     // Very Late Shipments Analysis
-    print_subsection("Very Late Shipments (>2 days late)");
+    out.subsection("Very Late Shipments (>2 days late)");
 
-    let very_late: Vec<VeryLateMetric> = db
-        .query(r#"
+    let very_late: Vec<VeryLateMetric> = filter
+        .query(db, r#"
             SELECT
                 "Very Late (>2d)" as category,
                 count(IF (actual_transit_days - goal_transit_days) > 2 THEN 1 END) as count,
                 (count(IF (actual_transit_days - goal_transit_days) > 2 THEN 1 END) / count()) as pct
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP ALL
         "#)
         .await?
         .take(0)?;
 
     if let Some(vl) = very_late.first() {
-        println!("  Very Late (>2 days): {:>8} shipments ({:.1}%)", vl.count, vl.pct * 100.0);
+        table_println!(out, "  Very Late (>2 days): {:>8} shipments ({:.1}%)", vl.count, vl.pct * 100.0);
     }
 
     // Extremely late
-    let extreme_late: Vec<VeryLateMetric> = db
-        .query(r#"
+    let extreme_late: Vec<VeryLateMetric> = filter
+        .query(db, r#"
             SELECT
                 "Extreme Late (>5d)" as category,
                 count(IF (actual_transit_days - goal_transit_days) > 5 THEN 1 END) as count,
                 (count(IF (actual_transit_days - goal_transit_days) > 5 THEN 1 END) / count()) as pct
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP ALL
         "#)
         .await?
         .take(0)?;
 
     if let Some(el) = extreme_late.first() {
-        println!("  Extreme Late (>5 days): {:>5} shipments ({:.1}%)", el.count, el.pct * 100.0);
+        table_println!(out, "  Extreme Late (>5 days): {:>5} shipments ({:.1}%)", el.count, el.pct * 100.0);
     }
+    let late_rows: Vec<&VeryLateMetric> = very_late.iter().chain(extreme_late.iter()).collect();
+    out.add("very_late", &late_rows)?;
 
     Ok(())
 }
 
-async fn run_volume_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("3. VOLUME ANALYTICS");
+async fn run_volume_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("volume", "3. VOLUME ANALYTICS");
 
     // Get total for percentage calculation
     #[derive(Debug, Deserialize)]
     struct TotalCount { total: i64 }
-    let total_result: Option<TotalCount> = db
-        .query("SELECT count() as total FROM shipment GROUP ALL")
+    let total_result: Option<TotalCount> = filter
+        .query(db, "SELECT count() as total FROM shipment WHERE {filter} GROUP ALL")
         .await?
         .take(0)?;
     let grand_total = total_result.map(|t| t.total).unwrap_or(1) as f64;
 
     // Shipments by Carrier
-    print_subsection("Shipments by Carrier (Top 10)");
+    out.subsection("Shipments by Carrier (Top 10)");
 
-    let by_carrier: Vec<VolumeMetric> = db
-        .query(r#"
+    let mut by_carrier: Vec<VolumeMetric> = filter
+        .query(db, r#"
             SELECT
                 carrier_ref as group,
                 count() as shipments,
                 0.0 as pct_of_total
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY carrier_ref
             ORDER BY shipments DESC
             LIMIT 10
@@ -456,26 +576,29 @@ async fn run_volume_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db
         .await?
         .take(0)?;
 
-    println!("  {:20} {:>12} {:>12} {:>20}",
+    table_println!(out, "  {:20} {:>12} {:>12} {:>20}",
              "Carrier", "Shipments", "% of Total", "Volume Bar");
-    println!("  {}", "─".repeat(66));
-    for row in &by_carrier {
+    table_println!(out, "  {}", "─".repeat(66));
+    for row in &mut by_carrier {
         let pct = (row.shipments as f64 / grand_total) * 100.0;
         let bar_len = (pct / 2.0).min(30.0) as usize;
         let bar: String = "█".repeat(bar_len);
-        println!("  {:20} {:>12} {:>11.1}% {}", get_carrier_name(&row.group), row.shipments, pct, bar);
+        row.group = get_carrier_name(&row.group);
+        row.pct_of_total = pct;
+        table_println!(out, "  {:20} {:>12} {:>11.1}% {}", row.group, row.shipments, pct, bar);
     }
+    out.add("by_carrier", &by_carrier)?;
 
     // Top Origin DCs
-    print_subsection("Top 10 Origin DCs (Distribution Centers)");
+    out.subsection("Top 10 Origin DCs (Distribution Centers)");
 
-    let origins: Vec<VolumeMetric> = db
-        .query(r#"
+    let mut origins: Vec<VolumeMetric> = filter
+        .query(db, r#"
             SELECT
                 origin_zip as group,
                 count() as shipments,
                 0.0 as pct_of_total
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY origin_zip
             ORDER BY shipments DESC
             LIMIT 10
@@ -483,23 +606,25 @@ async fn run_volume_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db
         .await?
         .take(0)?;
 
-    println!("  {:15} {:>12} {:>12}", "Origin DC", "Shipments", "% of Total");
-    println!("  {}", "─".repeat(41));
-    for row in &origins {
+    table_println!(out, "  {:15} {:>12} {:>12}", "Origin DC", "Shipments", "% of Total");
+    table_println!(out, "  {}", "─".repeat(41));
+    for row in &mut origins {
         let pct = (row.shipments as f64 / grand_total) * 100.0;
-        println!("  {:15} {:>12} {:>11.1}%", row.group, row.shipments, pct);
+        row.pct_of_total = pct;
+        table_println!(out, "  {:15} {:>12} {:>11.1}%", row.group, row.shipments, pct);
     }
+    out.add("top_origins", &origins)?;
 
     // Top Delivery Regions
-    print_subsection("Top 10 Delivery Regions");
+    out.subsection("Top 10 Delivery Regions");
 
-    let dests: Vec<VolumeMetric> = db
-        .query(r#"
+    let mut dests: Vec<VolumeMetric> = filter
+        .query(db, r#"
             SELECT
                 dest_zip as group,
                 count() as shipments,
                 0.0 as pct_of_total
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY dest_zip
             ORDER BY shipments DESC
             LIMIT 10
@@ -507,15 +632,17 @@ async fn run_volume_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db
         .await?
         .take(0)?;
 
-    println!("  {:15} {:>12} {:>12}", "Region", "Shipments", "% of Total");
-    println!("  {}", "─".repeat(41));
-    for row in &dests {
+    table_println!(out, "  {:15} {:>12} {:>12}", "Region", "Shipments", "% of Total");
+    table_println!(out, "  {}", "─".repeat(41));
+    for row in &mut dests {
         let pct = (row.shipments as f64 / grand_total) * 100.0;
-        println!("  {:15} {:>12} {:>11.1}%", row.group, row.shipments, pct);
+        row.pct_of_total = pct;
+        table_println!(out, "  {:15} {:>12} {:>11.1}%", row.group, row.shipments, pct);
     }
+    out.add("top_destinations", &dests)?;
 
     // Monthly Trends
-    print_subsection("Monthly Volume & Performance Trends");
+    out.subsection("Monthly Volume & Performance Trends");
 
     #[derive(Debug, Deserialize)]
     struct MonthlyData {
@@ -526,47 +653,55 @@ async fn run_volume_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db
         avg_transit: f64,
     }
 
-    let monthly: Vec<MonthlyData> = db
-        .query(r#"
+    let monthly: Vec<MonthlyData> = filter
+        .query(db, r#"
             SELECT
                 ship_year,
                 ship_month,
                 count() as shipments,
                 (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
                 math::mean(actual_transit_days) as avg_transit
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY ship_year, ship_month
             ORDER BY ship_year, ship_month
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:10} {:>10} {:>10} {:>10} {:>25}",
+    table_println!(out, "  {:10} {:>10} {:>10} {:>10} {:>25}",
              "Month", "Shipments", "OTD%", "Avg Days", "Volume Trend");
-    println!("  {}", "─".repeat(67));
+    table_println!(out, "  {}", "─".repeat(67));
+    let mut trends = Vec::new();
     for row in &monthly {
         let year_month = format!("{}-{:02}", row.ship_year, row.ship_month);
         let bar_len = (row.shipments as f64 / 200.0).min(20.0) as usize;
         let bar: String = "▓".repeat(bar_len);
-        println!("  {:10} {:>10} {:>9.1}% {:>9.1}d {}",
+        table_println!(out, "  {:10} {:>10} {:>9.1}% {:>9.1}d {}",
                  year_month, row.shipments, row.otd_rate * 100.0, row.avg_transit, bar);
+        trends.push(MonthlyTrend {
+            year_month,
+            shipments: row.shipments,
+            otd_rate: row.otd_rate * 100.0,
+            avg_transit: row.avg_transit,
+        });
     }
+    out.add("monthly", &trends)?;
 
     Ok(())
 }
 
-async fn run_distribution_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("4. DISTRIBUTION ANALYSIS");
+async fn run_distribution_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("distribution", "4. DISTRIBUTION ANALYSIS");
 
     // Transit Time Distribution (Histogram)
-    print_subsection("Transit Time Distribution (Histogram)");
+    out.subsection("Transit Time Distribution (Histogram)");
 
-    let transit_dist: Vec<TransitDistribution> = db
-        .query(r#"
+    let transit_dist: Vec<TransitDistribution> = filter
+        .query(db, r#"
             SELECT
                 actual_transit_days as transit_days,
                 count() as count
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY actual_transit_days
             ORDER BY actual_transit_days
             LIMIT 20
@@ -576,25 +711,28 @@ async fn run_distribution_section(db: &surrealdb::Surreal<surrealdb::engine::loc
 
     let max_count = transit_dist.iter().map(|r| r.count).max().unwrap_or(1) as f64;
 
-    println!("  {:>6}  {:>10}  {}", "Days", "Count", "Distribution");
-    println!("  {}", "─".repeat(60));
+    table_println!(out, "  {:>6}  {:>10}  {}", "Days", "Count", "Distribution");
+    table_println!(out, "  {}", "─".repeat(60));
     for row in &transit_dist {
         let bar_len = ((row.count as f64 / max_count) * 40.0) as usize;
         let bar: String = "█".repeat(bar_len);
-        println!("  {:>5}d  {:>10}  {}", row.transit_days, row.count, bar);
+        table_println!(out, "  {:>5}d  {:>10}  {}", row.transit_days, row.count, bar);
     }
+    out.add("transit_histogram", &transit_dist)?;
 
     // Delay Distribution
-    print_subsection("Delay Distribution (Actual - Goal)");
+    out.subsection("Delay Distribution (Actual - Goal)");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct DelayDist {
         delay_bucket: String,
         count: i64,
+        #[serde(default)]
+        pct: f64,
     }
 
-    let delay_dist: Vec<DelayDist> = db
-        .query(r#"
+    let mut delay_dist: Vec<DelayDist> = filter
+        .query(db, r#"
             SELECT
                 IF (actual_transit_days - goal_transit_days) < (0 - 2) THEN "Early >2d"
                 ELSE IF (actual_transit_days - goal_transit_days) < 0 THEN "Early 1-2d"
@@ -603,7 +741,7 @@ async fn run_distribution_section(db: &surrealdb::Surreal<surrealdb::engine::loc
                 ELSE "Late >2d"
                 END as delay_bucket,
                 count() as count
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY delay_bucket
             ORDER BY delay_bucket
         "#)
@@ -611,14 +749,16 @@ async fn run_distribution_section(db: &surrealdb::Surreal<surrealdb::engine::loc
         .take(0)?;
 
     let total: i64 = delay_dist.iter().map(|r| r.count).sum();
-    println!("  {:15} {:>10} {:>10} {:>30}", "Category", "Count", "Percent", "");
-    println!("  {}", "─".repeat(67));
-    for row in &delay_dist {
+    table_println!(out, "  {:15} {:>10} {:>10} {:>30}", "Category", "Count", "Percent", "");
+    table_println!(out, "  {}", "─".repeat(67));
+    for row in &mut delay_dist {
         let pct = (row.count as f64 / total as f64) * 100.0;
         let bar_len = (pct / 2.0) as usize;
         let bar: String = "█".repeat(bar_len);
-        println!("  {:15} {:>10} {:>9.1}% {}", row.delay_bucket, row.count, pct, bar);
+        table_println!(out, "  {:15} {:>10} {:>9.1}% {}", row.delay_bucket, row.count, pct, bar);
+        row.pct = pct;
     }
+    out.add("delay_distribution", &delay_dist)?;
 
     Ok(())
 }
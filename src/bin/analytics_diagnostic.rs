@@ -1,39 +1,95 @@
 //! Diagnostic Analytics - Why is it happening?
 //! Root cause analysis, carrier benchmarking, lane diagnostics
 //!
-//! Run: ./target/release/analytics_diagnostic [section]
+//! Run: ./target/release/analytics_diagnostic [section] [--format table|json|csv|markdown]
+//! Filters: --from, --to, --mode, --carrier, --origin-zip3, --distance-bucket
+//! Database: --db URL (default: rocksdb://data/lastmile.db)
+//! Compare: --baseline-from/--baseline-to turn the carrier and lane sections
+//!          into a period-over-period comparison against --from/--to
 //! Sections: all, carriers, lanes, hotspots, modes
 
 use anyhow::Result;
-use nyc_last_mile::{db, carrier_names::get_carrier_name, location_names::{format_lane_short, get_location_short, get_location_long}};
-use serde::Deserialize;
-use std::env;
+use clap::Parser;
+use nyc_last_mile::{db, carrier_names::get_carrier_name, location_names::{format_lane_short, get_location_short, get_location_long}, table_println};
+use nyc_last_mile::compare::{self, CompareArgs, PeriodComparison};
+use nyc_last_mile::filter::ShipmentFilter;
+use nyc_last_mile::stats::wilson_95;
+use nyc_last_mile::report::{OutputArgs, Report};
+use serde::{Deserialize, Serialize};
+
+/// Diagnostic analytics - why is it happening?
+#[derive(Parser, Debug)]
+#[command(name = "analytics_diagnostic")]
+#[command(about = "Carrier benchmarking, lane diagnostics, hotspots and mode efficiency")]
+struct Args {
+    /// Section to run
+    #[arg(default_value = "all", value_parser = ["all", "carriers", "lanes", "hotspots", "modes"])]
+    section: String,
+
+    #[command(flatten)]
+    filter: ShipmentFilter,
+
+    #[command(flatten)]
+    compare: CompareArgs,
+
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct CarrierBenchmark {
     carrier_ref: String,
     total: i64,
     otd_rate: f64,
+    late: i64,
+    #[serde(default)]
     late_rate: f64,
     early_rate: f64,
     avg_transit: f64,
     avg_delay: f64,
     variance: f64,
+    #[serde(default)]
+    late_rate_lower: f64,
+    #[serde(default)]
+    late_rate_upper: f64,
+    #[serde(default)]
+    late_vs_network: f64,
+    #[serde(default)]
+    rating: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct LaneDiagnostic {
     lane_ref: String,
     origin_zip: String,
     dest_zip: String,
     total: i64,
+    late: i64,
     otd_rate: f64,
+    #[serde(default)]
     late_rate: f64,
+    #[serde(default)]
+    late_rate_lower: f64,
+    #[serde(default)]
+    late_rate_upper: f64,
     avg_delay: f64,
     network_delta: f64,
 }
 
-#[derive(Debug, Deserialize)]
+impl LaneDiagnostic {
+    fn with_interval(mut self) -> Self {
+        self.late_rate = if self.total > 0 { self.late as f64 / self.total as f64 } else { 0.0 };
+        let ci = wilson_95(self.late, self.total);
+        self.late_rate_lower = ci.lower;
+        self.late_rate_upper = ci.upper;
+        self
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 struct ZipHotspot {
     zip: String,
     total: i64,
@@ -42,7 +98,7 @@ struct ZipHotspot {
     severity: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct ModeComparison {
     carrier_mode: String,
     distance_bucket: String,
@@ -52,7 +108,7 @@ struct ModeComparison {
     variance: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct CarrierLanePerf {
     carrier_ref: String,
     distance_bucket: String,
@@ -68,37 +124,38 @@ struct FailurePattern {
     late_rate: f64,
 }
 
-fn print_section_header(title: &str) {
-    println!("\n{}", "═".repeat(85));
-    println!("  {}", title);
-    println!("{}\n", "═".repeat(85));
-}
-
-fn print_subsection(title: &str) {
-    println!("\n{}", title);
-    println!("{}", "─".repeat(75));
-}
-
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
-    let section = args.get(1).map(|s| s.as_str()).unwrap_or("all");
-
-    let db = db::connect("data/lastmile.db").await?;
-
-    println!("\n{}", "█".repeat(85));
-    println!("{}  DIAGNOSTIC ANALYTICS - Why is it Happening?  {}", "█".repeat(17), "█".repeat(18));
-    println!("{}\n", "█".repeat(85));
+    let args = Args::parse();
+    let section = args.section.as_str();
+
+    let db = args.db.connect().await?;
+    let comparison = match args.compare.request(&args.filter)? {
+        Some(request) => Some(compare::compare_periods(&db, &request).await?),
+        None => None,
+    };
+    let comparison = comparison.as_ref();
+    let mut report = args.output.report("Diagnostic Analytics - Why is it Happening?");
+    report.filter = args.filter.describe();
+    let out = &mut report;
+    let filter = &args.filter;
+
+    table_println!(out, "\n{}", "█".repeat(85));
+    table_println!(out, "{}  DIAGNOSTIC ANALYTICS - Why is it Happening?  {}", "█".repeat(17), "█".repeat(18));
+    table_println!(out, "{}\n", "█".repeat(85));
+    if !filter.is_empty() {
+        table_println!(out, "  Filter: {}\n", out.filter);
+    }
 
     // Calculate network average for benchmarking
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct NetworkAvg { avg_late: f64, avg_delay: f64 }
-    let network: Option<NetworkAvg> = db
-        .query(r#"
+    let network: Option<NetworkAvg> = filter
+        .query(&db, r#"
             SELECT
                 (count(IF otd = "Late" THEN 1 END) / count()) as avg_late,
                 math::mean(actual_transit_days - goal_transit_days) as avg_delay
-            FROM shipment GROUP ALL
+            FROM shipment WHERE {filter} GROUP ALL
         "#)
         .await?
         .take(0)?;
@@ -106,61 +163,73 @@ async fn main() -> Result<()> {
     let network_late = network.as_ref().map(|n| n.avg_late).unwrap_or(0.0);
     let network_delay = network.as_ref().map(|n| n.avg_delay).unwrap_or(0.0);
 
-    println!("  Network Baseline: Late Rate = {:.1}%, Avg Delay = {:.2} days\n",
+    table_println!(out, "  Network Baseline: Late Rate = {:.1}%, Avg Delay = {:.2} days\n",
              network_late * 100.0, network_delay);
+    if let Some(network) = &network {
+        out.add_one("network_baseline", network)?;
+    }
 
     match section {
         "all" => {
-            run_carrier_section(&db, network_late).await?;
-            run_lane_section(&db, network_late, network_delay).await?;
-            run_hotspot_section(&db).await?;
-            run_mode_section(&db).await?;
-        }
-        "carriers" => run_carrier_section(&db, network_late).await?,
-        "lanes" => run_lane_section(&db, network_late, network_delay).await?,
-        "hotspots" => run_hotspot_section(&db).await?,
-        "modes" => run_mode_section(&db).await?,
-        _ => {
-            println!("Unknown section: {}", section);
-            println!("Available: all, carriers, lanes, hotspots, modes");
+            run_carrier_section(&db, out, filter, network_late, comparison).await?;
+            run_lane_section(&db, out, filter, network_late, network_delay, comparison).await?;
+            run_hotspot_section(&db, out, filter).await?;
+            run_mode_section(&db, out, filter).await?;
         }
+        "carriers" => run_carrier_section(&db, out, filter, network_late, comparison).await?,
+        "lanes" => run_lane_section(&db, out, filter, network_late, network_delay, comparison).await?,
+        "hotspots" => run_hotspot_section(&db, out, filter).await?,
+        "modes" => run_mode_section(&db, out, filter).await?,
+        _ => unreachable!("section is validated by clap"),
     }
 
-    println!("\n{}", "█".repeat(85));
-    Ok(())
+    table_println!(out, "\n{}", "█".repeat(85));
+    args.output.write(&report)
 }
 
-async fn run_carrier_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>, network_late: f64) -> Result<()> {
-    print_section_header("1. CARRIER PERFORMANCE BENCHMARKING");
+async fn run_carrier_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter, network_late: f64, comparison: Option<&PeriodComparison>) -> Result<()> {
+    out.section("carriers", "1. CARRIER PERFORMANCE BENCHMARKING");
+
+    if let Some(comparison) = comparison {
+        return run_carrier_comparison(out, comparison);
+    }
 
     // Full carrier benchmark
-    print_subsection("Carrier Performance Matrix (min 50 shipments)");
+    out.subsection("Carrier Performance Matrix (min 50 shipments)");
 
-    let carriers: Vec<CarrierBenchmark> = db
-        .query(r#"
+    let mut carriers: Vec<CarrierBenchmark> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     carrier_ref,
                     count() as total,
                     (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
-                    (count(IF otd = "Late" THEN 1 END) / count()) as late_rate,
+                    count(IF otd = "Late" THEN 1 END) as late,
                     (count(IF otd = "Early" THEN 1 END) / count()) as early_rate,
                     math::mean(actual_transit_days) as avg_transit,
                     math::mean(actual_transit_days - goal_transit_days) as avg_delay,
                     math::variance(actual_transit_days) as variance
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref
             ) WHERE total >= 50
-            ORDER BY late_rate DESC
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:20} {:>7} {:>8} {:>8} {:>8} {:>9} {:>8} {:>10}",
-             "Carrier", "Volume", "OTD%", "Late%", "Early%", "Avg Delay", "Var", "Rating");
-    println!("  {}", "─".repeat(81));
+    // Rank worst-first on the lower bound of the late rate
+    for row in &mut carriers {
+        row.late_rate = row.late as f64 / row.total as f64;
+        let ci = wilson_95(row.late, row.total);
+        row.late_rate_lower = ci.lower;
+        row.late_rate_upper = ci.upper;
+    }
+    carriers.sort_by(|a, b| b.late_rate_lower.partial_cmp(&a.late_rate_lower).unwrap_or(std::cmp::Ordering::Equal));
+
+    table_println!(out, "  {:20} {:>7} {:>8} {:>8} {:>13} {:>8} {:>9} {:>8} {:>10}",
+             "Carrier", "Volume", "OTD%", "Late%", "Late 95% CI", "Early%", "Avg Delay", "Var", "Rating");
+    table_println!(out, "  {}", "─".repeat(95));
 
-    for row in &carriers {
+    for row in &mut carriers {
         let delta_vs_network = row.late_rate - network_late;
         let rating = if row.late_rate < 0.10 { "★★★ Best" }
                     else if row.late_rate < 0.20 { "★★ Good" }
@@ -173,16 +242,20 @@ async fn run_carrier_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
             format!("{:.1}%", delta_vs_network * 100.0)
         };
 
-        println!("  {:20} {:>7} {:>7.1}% {:>7.1}% {:>7.1}% {:>8.2}d {:>7.1} {:>10}",
+        table_println!(out, "  {:20} {:>7} {:>7.1}% {:>7.1}% {:>13} {:>7.1}% {:>8.2}d {:>7.1} {:>10}",
                  get_carrier_name(&row.carrier_ref), row.total, row.otd_rate * 100.0, row.late_rate * 100.0,
+                 format!("{:.1}-{:.1}%", row.late_rate_lower * 100.0, row.late_rate_upper * 100.0),
                  row.early_rate * 100.0, row.avg_delay, row.variance, rating);
+        row.late_vs_network = delta_vs_network;
+        row.rating = rating.to_string();
     }
+    out.add("benchmark", &carriers)?;
 
     // Carrier performance by distance
-    print_subsection("Carrier Performance: Long-Haul vs Short-Haul");
+    out.subsection("Carrier Performance: Long-Haul vs Short-Haul");
 
-    let carrier_by_dist: Vec<CarrierLanePerf> = db
-        .query(r#"
+    let carrier_by_dist: Vec<CarrierLanePerf> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     carrier_ref,
@@ -193,7 +266,7 @@ async fn run_carrier_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
                     count() as total,
                     (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
                     (count(IF otd = "Late" THEN 1 END) / count()) as late_rate
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref, distance_bucket
             ) WHERE total >= 20
             ORDER BY carrier_ref, distance_bucket
@@ -201,9 +274,9 @@ async fn run_carrier_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
         .await?
         .take(0)?;
 
-    println!("  {:20} {:>18} {:>8} {:>10} {:>10}",
+    table_println!(out, "  {:20} {:>18} {:>8} {:>10} {:>10}",
              "Carrier", "Distance", "Volume", "OTD%", "Late%");
-    println!("  {}", "─".repeat(68));
+    table_println!(out, "  {}", "─".repeat(68));
 
     let mut current_carrier = String::new();
     let mut current_carrier_name = String::new();
@@ -215,15 +288,16 @@ async fn run_carrier_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
         } else {
             ""
         };
-        println!("  {:20} {:>18} {:>8} {:>9.1}% {:>9.1}%",
+        table_println!(out, "  {:20} {:>18} {:>8} {:>9.1}% {:>9.1}%",
                  carrier_display, row.distance_bucket, row.total,
                  row.otd_rate * 100.0, row.late_rate * 100.0);
     }
+    out.add("by_distance", &carrier_by_dist)?;
 
     // Early/Late distribution shape
-    print_subsection("Delivery Timing Profile by Carrier (Top 5 by Volume)");
+    out.subsection("Delivery Timing Profile by Carrier (Top 5 by Volume)");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct TimingProfile {
         carrier_ref: String,
         very_early: i64,
@@ -234,8 +308,8 @@ async fn run_carrier_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
         total: i64,
     }
 
-    let profiles: Vec<TimingProfile> = db
-        .query(r#"
+    let profiles: Vec<TimingProfile> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     carrier_ref,
@@ -245,7 +319,7 @@ async fn run_carrier_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
                     count(IF (actual_transit_days - goal_transit_days) > 0 AND (actual_transit_days - goal_transit_days) <= 2 THEN 1 END) as late,
                     count(IF (actual_transit_days - goal_transit_days) > 2 THEN 1 END) as very_late,
                     count() as total
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref
             )
             ORDER BY total DESC
@@ -254,9 +328,9 @@ async fn run_carrier_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
         .await?
         .take(0)?;
 
-    println!("  {:20} {:>10} {:>10} {:>10} {:>10} {:>10}",
+    table_println!(out, "  {:20} {:>10} {:>10} {:>10} {:>10} {:>10}",
              "Carrier", "V.Early", "Early", "On-Time", "Late", "V.Late");
-    println!("  {}", "─".repeat(70));
+    table_println!(out, "  {}", "─".repeat(70));
 
     for p in &profiles {
         let ve_pct = (p.very_early as f64 / p.total as f64) * 100.0;
@@ -265,45 +339,113 @@ async fn run_carrier_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
         let l_pct = (p.late as f64 / p.total as f64) * 100.0;
         let vl_pct = (p.very_late as f64 / p.total as f64) * 100.0;
 
-        println!("  {:20} {:>9.1}% {:>9.1}% {:>9.1}% {:>9.1}% {:>9.1}%",
+        table_println!(out, "  {:20} {:>9.1}% {:>9.1}% {:>9.1}% {:>9.1}% {:>9.1}%",
                  get_carrier_name(&p.carrier_ref), ve_pct, e_pct, ot_pct, l_pct, vl_pct);
     }
+    out.add("timing_profile", &profiles)?;
 
     Ok(())
 }
 
-async fn run_lane_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>, network_late: f64, network_delay: f64) -> Result<()> {
-    print_section_header("2. LANE DIAGNOSTICS");
+/// Carrier section in compare mode: benchmark deltas between the windows
+fn run_carrier_comparison(out: &mut Report, c: &PeriodComparison) -> Result<()> {
+    out.subsection(&format!("Carrier Changes: {} (baseline) vs {} (current)",
+                            c.baseline.label(), c.current.label()));
+
+    table_println!(out, "  {:20} {:>9} {:>9} {:>10} {:>10} {:>10} {:>10} {:>8}",
+             "Carrier", "Base Vol", "Cur Vol", "Base Late%", "Cur Late%", "Late Chg", "OTD Chg", "Signif.");
+    table_println!(out, "  {}", "─".repeat(93));
+
+    for row in &c.carriers {
+        table_println!(out, "  {:20} {:>9} {:>9} {:>9.1}% {:>9.1}% {:>+8.1}pp {:>+8.1}pp {:>8}",
+                 row.carrier_name, row.baseline_volume, row.current_volume,
+                 row.baseline_late_rate * 100.0, row.current_late_rate * 100.0,
+                 row.late_delta * 100.0, row.otd_delta * 100.0,
+                 if row.significant { "*" } else { "" });
+    }
+    table_println!(out, "\n  * OTD change significant at 95% (two-proportion z-test)");
+    out.add("comparison", &c.carriers)?;
 
-    // Worst performing lanes
-    print_subsection("Worst Performing Lanes (vs Network Average)");
+    Ok(())
+}
 
-    let worst_lanes: Vec<LaneDiagnostic> = db
-        .query(r#"
-            SELECT * FROM (
-                SELECT
-                    lane_ref,
-                    origin_zip,
-                    dest_zip,
-                    count() as total,
-                    (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
-                    (count(IF otd = "Late" THEN 1 END) / count()) as late_rate,
-                    math::mean(actual_transit_days - goal_transit_days) as avg_delay,
-                    0.0 as network_delta
-                FROM shipment
-                GROUP BY lane_ref, origin_zip, dest_zip
-            ) WHERE total >= 30
-            ORDER BY late_rate DESC
-            LIMIT 15
+/// Lane section in compare mode: movers, new and lost lanes
+fn run_lane_comparison(out: &mut Report, c: &PeriodComparison) -> Result<()> {
+    out.subsection(&format!("Lanes Whose Late Rate Moved: {} (baseline) vs {} (current)",
+                            c.baseline.label(), c.current.label()));
+
+    table_println!(out, "  {:25} {:>9} {:>9} {:>10} {:>10} {:>10} {:>8}",
+             "Lane", "Base Vol", "Cur Vol", "Base Late%", "Cur Late%", "Change", "Signif.");
+    table_println!(out, "  {}", "─".repeat(87));
+    for row in &c.lane_movers {
+        table_println!(out, "  {:25} {:>9} {:>9} {:>9.1}% {:>9.1}% {:>+8.1}pp {:>8}",
+                 row.route, row.baseline_volume, row.current_volume,
+                 row.baseline_late_rate * 100.0, row.current_late_rate * 100.0,
+                 row.late_delta * 100.0, if row.significant { "*" } else { "" });
+    }
+    if c.lane_movers.is_empty() {
+        table_println!(out, "  No lanes moved past the threshold");
+    }
+    out.add("late_rate_movers", &c.lane_movers)?;
+
+    out.subsection("New Lanes (current window only)");
+    table_println!(out, "  {:25} {:>8} {:>10}", "Lane", "Volume", "Late%");
+    table_println!(out, "  {}", "─".repeat(45));
+    for row in &c.new_lanes {
+        table_println!(out, "  {:25} {:>8} {:>9.1}%", row.route, row.volume, row.late_rate * 100.0);
+    }
+    out.add("new_lanes", &c.new_lanes)?;
+
+    out.subsection("Lost Lanes (baseline window only)");
+    table_println!(out, "  {:25} {:>8} {:>10}", "Lane", "Volume", "Late%");
+    table_println!(out, "  {}", "─".repeat(45));
+    for row in &c.lost_lanes {
+        table_println!(out, "  {:25} {:>8} {:>9.1}%", row.route, row.volume, row.late_rate * 100.0);
+    }
+    out.add("lost_lanes", &c.lost_lanes)?;
+
+    Ok(())
+}
+
+async fn run_lane_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter, network_late: f64, network_delay: f64, comparison: Option<&PeriodComparison>) -> Result<()> {
+    out.section("lanes", "2. LANE DIAGNOSTICS");
+
+    if let Some(comparison) = comparison {
+        return run_lane_comparison(out, comparison);
+    }
+
+    // Lanes are ranked on the 95% Wilson bounds of their late rate rather
+    // than the raw rate, so a handful of shipments can't top either list
+    let lanes: Vec<LaneDiagnostic> = filter
+        .query(db, r#"
+            SELECT
+                lane_ref,
+                origin_zip,
+                dest_zip,
+                count() as total,
+                count(IF otd = "Late" THEN 1 END) as late,
+                (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
+                math::mean(actual_transit_days - goal_transit_days) as avg_delay,
+                0.0 as network_delta
+            FROM shipment WHERE {filter}
+            GROUP BY lane_ref, origin_zip, dest_zip
         "#)
         .await?
         .take(0)?;
+    let lanes: Vec<LaneDiagnostic> = lanes.into_iter().map(LaneDiagnostic::with_interval).collect();
+
+    // Worst performing lanes
+    out.subsection("Worst Performing Lanes (vs Network Average, by 95% lower bound)");
 
-    println!("  {:25} {:>7} {:>8} {:>10} {:>12} {:>12}",
-             "Lane (Origin->Dest)", "Volume", "Late%", "Avg Delay", "vs Network", "Action");
-    println!("  {}", "─".repeat(78));
+    let mut worst_lanes = lanes.clone();
+    worst_lanes.sort_by(|a, b| b.late_rate_lower.partial_cmp(&a.late_rate_lower).unwrap_or(std::cmp::Ordering::Equal));
+    worst_lanes.truncate(15);
 
-    for row in &worst_lanes {
+    table_println!(out, "  {:25} {:>7} {:>8} {:>13} {:>10} {:>12} {:>12}",
+             "Lane (Origin->Dest)", "Volume", "Late%", "Late 95% CI", "Avg Delay", "vs Network", "Action");
+    table_println!(out, "  {}", "─".repeat(92));
+
+    for row in &mut worst_lanes {
         let route = format_lane_short(&row.origin_zip, &row.dest_zip);
         let delta = row.late_rate - network_late;
         let delta_str = format!("{:+.1}%", delta * 100.0);
@@ -311,52 +453,43 @@ async fn run_lane_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
                     else if delta > 0.05 { "MONITOR" }
                     else { "OK" };
 
-        println!("  {:25} {:>7} {:>7.1}% {:>9.2}d {:>12} {:>12}",
-                 route, row.total, row.late_rate * 100.0, row.avg_delay, delta_str, action);
+        table_println!(out, "  {:25} {:>7} {:>7.1}% {:>13} {:>9.2}d {:>12} {:>12}",
+                 route, row.total, row.late_rate * 100.0,
+                 format!("{:.1}-{:.1}%", row.late_rate_lower * 100.0, row.late_rate_upper * 100.0),
+                 row.avg_delay, delta_str, action);
+        row.network_delta = delta;
     }
+    out.add("worst", &worst_lanes)?;
 
     // Best performing lanes
-    print_subsection("Best Performing Lanes (Learn from Success)");
+    out.subsection("Best Performing Lanes (Learn from Success, by 95% upper bound)");
 
-    let best_lanes: Vec<LaneDiagnostic> = db
-        .query(r#"
-            SELECT * FROM (
-                SELECT
-                    lane_ref,
-                    origin_zip,
-                    dest_zip,
-                    count() as total,
-                    (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
-                    (count(IF otd = "Late" THEN 1 END) / count()) as late_rate,
-                    math::mean(actual_transit_days - goal_transit_days) as avg_delay,
-                    0.0 as network_delta
-                FROM shipment
-                GROUP BY lane_ref, origin_zip, dest_zip
-            ) WHERE total >= 30
-            ORDER BY late_rate ASC
-            LIMIT 10
-        "#)
-        .await?
-        .take(0)?;
+    let mut best_lanes = lanes;
+    best_lanes.sort_by(|a, b| a.late_rate_upper.partial_cmp(&b.late_rate_upper).unwrap_or(std::cmp::Ordering::Equal));
+    best_lanes.truncate(10);
 
-    println!("  {:25} {:>7} {:>8} {:>10} {:>15}",
-             "Lane (Origin->Dest)", "Volume", "Late%", "Avg Delay", "Performance");
-    println!("  {}", "─".repeat(67));
+    table_println!(out, "  {:25} {:>7} {:>8} {:>13} {:>10} {:>15}",
+             "Lane (Origin->Dest)", "Volume", "Late%", "Late 95% CI", "Avg Delay", "Performance");
+    table_println!(out, "  {}", "─".repeat(81));
 
-    for row in &best_lanes {
+    for row in &mut best_lanes {
         let route = format_lane_short(&row.origin_zip, &row.dest_zip);
         let perf = if row.late_rate < 0.05 { "★★★ Excellent" }
                   else if row.late_rate < 0.10 { "★★ Very Good" }
                   else { "★ Good" };
 
-        println!("  {:25} {:>7} {:>7.1}% {:>9.2}d {:>15}",
-                 route, row.total, row.late_rate * 100.0, row.avg_delay, perf);
+        table_println!(out, "  {:25} {:>7} {:>7.1}% {:>13} {:>9.2}d {:>15}",
+                 route, row.total, row.late_rate * 100.0,
+                 format!("{:.1}-{:.1}%", row.late_rate_lower * 100.0, row.late_rate_upper * 100.0),
+                 row.avg_delay, perf);
+        row.network_delta = row.late_rate - network_late;
     }
+    out.add("best", &best_lanes)?;
 
     // Lane clustering by delay pattern
-    print_subsection("Lane Delay Clustering");
+    out.subsection("Lane Delay Clustering");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct DelayCluster {
         cluster: String,
         lane_count: i64,
@@ -364,8 +497,8 @@ async fn run_lane_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
         avg_late_rate: f64,
     }
 
-    let clusters: Vec<DelayCluster> = db
-        .query(r#"
+    let clusters: Vec<DelayCluster> = filter
+        .query(db, r#"
             SELECT
                 IF avg_delay < (0 - 1) THEN "Consistently Early"
                 ELSE IF avg_delay < 0 THEN "Slightly Early"
@@ -382,7 +515,7 @@ async fn run_lane_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
                     count() as total,
                     (count(IF otd = "Late" THEN 1 END) / count()) as late_rate,
                     math::mean(actual_transit_days - goal_transit_days) as avg_delay
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY lane_ref
             )
             GROUP BY cluster
@@ -391,26 +524,27 @@ async fn run_lane_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
         .await?
         .take(0)?;
 
-    println!("  {:25} {:>12} {:>15} {:>15}",
+    table_println!(out, "  {:25} {:>12} {:>15} {:>15}",
              "Delay Cluster", "# Lanes", "Total Ships", "Avg Late Rate");
-    println!("  {}", "─".repeat(69));
+    table_println!(out, "  {}", "─".repeat(69));
 
     for c in &clusters {
-        println!("  {:25} {:>12} {:>15} {:>14.1}%",
+        table_println!(out, "  {:25} {:>12} {:>15} {:>14.1}%",
                  c.cluster, c.lane_count, c.total_shipments, c.avg_late_rate * 100.0);
     }
+    out.add("delay_clusters", &clusters)?;
 
     Ok(())
 }
 
-async fn run_hotspot_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("3. PROBLEM HOTSPOTS");
+async fn run_hotspot_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("hotspots", "3. PROBLEM HOTSPOTS");
 
     // Origin DC hotspots
-    print_subsection("Problem Origin DCs (High Late Rate)");
+    out.subsection("Problem Origin DCs (High Late Rate)");
 
-    let origin_hotspots: Vec<ZipHotspot> = db
-        .query(r#"
+    let mut origin_hotspots: Vec<ZipHotspot> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     origin_zip as zip,
@@ -418,7 +552,7 @@ async fn run_hotspot_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
                     (count(IF otd = "Late" THEN 1 END) / count()) as late_rate,
                     math::mean(actual_transit_days - goal_transit_days) as avg_delay,
                     "" as severity
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY origin_zip
             ) WHERE total >= 50 AND late_rate > 0.20
             ORDER BY late_rate DESC
@@ -427,24 +561,26 @@ async fn run_hotspot_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
         .await?
         .take(0)?;
 
-    println!("  {:6} {:22} {:>10} {:>10} {:>10} {:>12}",
+    table_println!(out, "  {:6} {:22} {:>10} {:>10} {:>10} {:>12}",
              "DC", "Location", "Shipments", "Late%", "Delay", "Severity");
-    println!("  {}", "─".repeat(74));
+    table_println!(out, "  {}", "─".repeat(74));
 
-    for h in &origin_hotspots {
+    for h in &mut origin_hotspots {
         let severity = if h.late_rate > 0.40 { "🔴 CRITICAL" }
                       else if h.late_rate > 0.30 { "🟠 HIGH" }
                       else { "🟡 MODERATE" };
-        println!("  {:6} {:22} {:>10} {:>9.1}% {:>9.2}d {:>12}",
+        table_println!(out, "  {:6} {:22} {:>10} {:>9.1}% {:>9.2}d {:>12}",
                  get_location_short(&h.zip), get_location_long(&h.zip),
                  h.total, h.late_rate * 100.0, h.avg_delay, severity);
+        h.severity = severity.to_string();
     }
+    out.add("origins", &origin_hotspots)?;
 
     // Delivery Region hotspots
-    print_subsection("Problem Delivery Regions (High Late Rate)");
+    out.subsection("Problem Delivery Regions (High Late Rate)");
 
-    let dest_hotspots: Vec<ZipHotspot> = db
-        .query(r#"
+    let mut dest_hotspots: Vec<ZipHotspot> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     dest_zip as zip,
@@ -452,7 +588,7 @@ async fn run_hotspot_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
                     (count(IF otd = "Late" THEN 1 END) / count()) as late_rate,
                     math::mean(actual_transit_days - goal_transit_days) as avg_delay,
                     "" as severity
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY dest_zip
             ) WHERE total >= 50 AND late_rate > 0.20
             ORDER BY late_rate DESC
@@ -461,23 +597,25 @@ async fn run_hotspot_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
         .await?
         .take(0)?;
 
-    println!("  {:6} {:22} {:>10} {:>10} {:>10} {:>12}",
+    table_println!(out, "  {:6} {:22} {:>10} {:>10} {:>10} {:>12}",
              "Region", "Location", "Shipments", "Late%", "Delay", "Severity");
-    println!("  {}", "─".repeat(74));
+    table_println!(out, "  {}", "─".repeat(74));
 
-    for h in &dest_hotspots {
+    for h in &mut dest_hotspots {
         let severity = if h.late_rate > 0.40 { "🔴 CRITICAL" }
                       else if h.late_rate > 0.30 { "🟠 HIGH" }
                       else { "🟡 MODERATE" };
-        println!("  {:6} {:22} {:>10} {:>9.1}% {:>9.2}d {:>12}",
+        table_println!(out, "  {:6} {:22} {:>10} {:>9.1}% {:>9.2}d {:>12}",
                  get_location_short(&h.zip), get_location_long(&h.zip),
                  h.total, h.late_rate * 100.0, h.avg_delay, severity);
+        h.severity = severity.to_string();
     }
+    out.add("destinations", &dest_hotspots)?;
 
     // Metro vs Rural analysis
-    print_subsection("Delivery Pattern: High vs Low Volume Destinations");
+    out.subsection("Delivery Pattern: High vs Low Volume Destinations");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct VolumePattern {
         category: String,
         dest_count: i64,
@@ -486,8 +624,8 @@ async fn run_hotspot_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
         late_rate: f64,
     }
 
-    let patterns: Vec<VolumePattern> = db
-        .query(r#"
+    let patterns: Vec<VolumePattern> = filter
+        .query(db, r#"
             SELECT
                 IF total >= 500 THEN "High Volume (≥500)"
                 ELSE IF total >= 100 THEN "Medium Volume (100-499)"
@@ -503,7 +641,7 @@ async fn run_hotspot_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
                     count() as total,
                     (count(IF otd = "Early" THEN 1 END) / count()) as early_rate,
                     (count(IF otd = "Late" THEN 1 END) / count()) as late_rate
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY dest_zip
             )
             GROUP BY category
@@ -512,26 +650,27 @@ async fn run_hotspot_section(db: &surrealdb::Surreal<surrealdb::engine::local::D
         .await?
         .take(0)?;
 
-    println!("  {:25} {:>12} {:>12} {:>12} {:>12}",
+    table_println!(out, "  {:25} {:>12} {:>12} {:>12} {:>12}",
              "Volume Category", "# Locations", "Shipments", "Early%", "Late%");
-    println!("  {}", "─".repeat(75));
+    table_println!(out, "  {}", "─".repeat(75));
 
     for p in &patterns {
-        println!("  {:25} {:>12} {:>12} {:>11.1}% {:>11.1}%",
+        table_println!(out, "  {:25} {:>12} {:>12} {:>11.1}% {:>11.1}%",
                  p.category, p.dest_count, p.shipments, p.early_rate * 100.0, p.late_rate * 100.0);
     }
+    out.add("volume_patterns", &patterns)?;
 
     Ok(())
 }
 
-async fn run_mode_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("4. MODE EFFICIENCY ANALYSIS");
+async fn run_mode_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("modes", "4. MODE EFFICIENCY ANALYSIS");
 
     // Mode comparison by distance
-    print_subsection("LTL vs Truckload Performance by Distance");
+    out.subsection("LTL vs Truckload Performance by Distance");
 
-    let mode_by_distance: Vec<ModeComparison> = db
-        .query(r#"
+    let mode_by_distance: Vec<ModeComparison> = filter
+        .query(db, r#"
             SELECT
                 carrier_mode,
                 distance_bucket,
@@ -539,73 +678,78 @@ async fn run_mode_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
                 (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
                 math::mean(actual_transit_days) as avg_transit,
                 math::variance(actual_transit_days) as variance
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY carrier_mode, distance_bucket
             ORDER BY carrier_mode, distance_bucket
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:12} {:>12} {:>8} {:>10} {:>10} {:>12}",
+    table_println!(out, "  {:12} {:>12} {:>8} {:>10} {:>10} {:>12}",
              "Mode", "Distance", "Volume", "OTD%", "Avg Days", "Variance");
-    println!("  {}", "─".repeat(66));
+    table_println!(out, "  {}", "─".repeat(66));
 
     let mut current_mode = String::new();
     for row in &mode_by_distance {
         let mode_display = if row.carrier_mode != current_mode {
             current_mode = row.carrier_mode.clone();
-            println!("  {}", "-".repeat(66));
+            table_println!(out, "  {}", "-".repeat(66));
             row.carrier_mode.as_str()
         } else {
             ""
         };
-        println!("  {:12} {:>12} {:>8} {:>9.1}% {:>9.1}d {:>12.2}",
+        table_println!(out, "  {:12} {:>12} {:>8} {:>9.1}% {:>9.1}d {:>12.2}",
                  mode_display, row.distance_bucket, row.total,
                  row.otd_rate * 100.0, row.avg_transit, row.variance);
     }
+    out.add("by_distance", &mode_by_distance)?;
 
     // Mode variability comparison
-    print_subsection("Mode Reliability Comparison");
+    out.subsection("Mode Reliability Comparison");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct ModeReliability {
         carrier_mode: String,
         total: i64,
         otd_rate: f64,
         variance: f64,
         max_delay: i64,
+        #[serde(default)]
+        reliability: String,
     }
 
-    let reliability: Vec<ModeReliability> = db
-        .query(r#"
+    let mut reliability: Vec<ModeReliability> = filter
+        .query(db, r#"
             SELECT
                 carrier_mode,
                 count() as total,
                 (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
                 math::variance(actual_transit_days) as variance,
                 math::max(actual_transit_days - goal_transit_days) as max_delay
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY carrier_mode
             ORDER BY otd_rate DESC
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:15} {:>10} {:>10} {:>12} {:>12} {:>15}",
+    table_println!(out, "  {:15} {:>10} {:>10} {:>12} {:>12} {:>15}",
              "Mode", "Volume", "OTD%", "Variance", "Max Delay", "Reliability");
-    println!("  {}", "─".repeat(76));
+    table_println!(out, "  {}", "─".repeat(76));
 
-    for r in &reliability {
+    for r in &mut reliability {
         let reliability_rating = if r.variance < 2.0 && r.otd_rate > 0.7 { "★★★ High" }
                                 else if r.variance < 5.0 && r.otd_rate > 0.5 { "★★ Medium" }
                                 else { "★ Low" };
-        println!("  {:15} {:>10} {:>9.1}% {:>12.2} {:>11}d {:>15}",
+        table_println!(out, "  {:15} {:>10} {:>9.1}% {:>12.2} {:>11}d {:>15}",
                  r.carrier_mode, r.total, r.otd_rate * 100.0, r.variance, r.max_delay, reliability_rating);
+        r.reliability = reliability_rating.to_string();
     }
+    out.add("reliability", &reliability)?;
 
     // Recommendation
-    print_subsection("Mode Selection Insights");
-    println!("
+    out.subsection("Mode Selection Insights");
+    table_println!(out, "
   Key Findings:
   • LTL dominates volume but check variance on long-haul routes
   • Truckload may reduce variability for high-value/time-critical lanes
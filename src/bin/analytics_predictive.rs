@@ -1,15 +1,39 @@
 //! Predictive Analytics - What will happen?
 //! Delay probability scoring, ETA prediction factors, volume forecasting
 //!
-//! Run: ./target/release/analytics_predictive [section]
+//! Run: ./target/release/analytics_predictive [section] [--format table|json|csv|markdown]
+//! Filters: --from, --to, --mode, --carrier, --origin-zip3, --distance-bucket
+//! Database: --db URL (default: rocksdb://data/lastmile.db)
 //! Sections: all, delay, eta, forecast, risk
 
 use anyhow::Result;
-use nyc_last_mile::{db, carrier_names::get_carrier_name, location_names::format_lane_short};
-use serde::Deserialize;
-use std::env;
+use clap::Parser;
+use nyc_last_mile::{db, carrier_names::get_carrier_name, location_names::format_lane_short, table_println};
+use nyc_last_mile::filter::ShipmentFilter;
+use nyc_last_mile::forecast::{self, Level, Method};
+use nyc_last_mile::report::{OutputArgs, Report};
+use serde::{Deserialize, Serialize};
+
+/// Predictive analytics - what will happen?
+#[derive(Parser, Debug)]
+#[command(name = "analytics_predictive")]
+#[command(about = "Delay likelihood, ETA factors, volume forecasts and risk scores")]
+struct Args {
+    /// Section to run
+    #[arg(default_value = "all", value_parser = ["all", "delay", "eta", "forecast", "risk"])]
+    section: String,
+
+    #[command(flatten)]
+    filter: ShipmentFilter,
+
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct DelayProbability {
     factor: String,
     value: String,
@@ -27,14 +51,14 @@ struct EtaFactor {
     reliability_score: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct VolumeProjection {
     period: String,
     historical_avg: f64,
     trend: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct RiskScore {
     lane_ref: String,
     origin_zip: String,
@@ -45,7 +69,7 @@ struct RiskScore {
     combined_risk: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct CarrierRisk {
     carrier_ref: String,
     volume: i64,
@@ -54,56 +78,49 @@ struct CarrierRisk {
     risk_tier: String,
 }
 
-fn print_section_header(title: &str) {
-    println!("\n{}", "═".repeat(85));
-    println!("  {}", title);
-    println!("{}\n", "═".repeat(85));
-}
-
-fn print_subsection(title: &str) {
-    println!("\n{}", title);
-    println!("{}", "─".repeat(75));
-}
-
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
-    let section = args.get(1).map(|s| s.as_str()).unwrap_or("all");
-
-    let db = db::connect("data/lastmile.db").await?;
-
-    println!("\n{}", "█".repeat(85));
-    println!("{}  PREDICTIVE ANALYTICS - What Will Happen?  {}", "█".repeat(18), "█".repeat(19));
-    println!("{}\n", "█".repeat(85));
+    let args = Args::parse();
+    let section = args.section.as_str();
+
+    let db = args.db.connect().await?;
+    let mut report = args.output.report("Predictive Analytics - What Will Happen?");
+    report.filter = args.filter.describe();
+    let out = &mut report;
+    let filter = &args.filter;
+
+    table_println!(out, "\n{}", "█".repeat(85));
+    table_println!(out, "{}  PREDICTIVE ANALYTICS - What Will Happen?  {}", "█".repeat(18), "█".repeat(19));
+    table_println!(out, "{}\n", "█".repeat(85));
+    if !filter.is_empty() {
+        table_println!(out, "  Filter: {}\n", out.filter);
+    }
 
     match section {
         "all" => {
-            run_delay_section(&db).await?;
-            run_eta_section(&db).await?;
-            run_forecast_section(&db).await?;
-            run_risk_section(&db).await?;
-        }
-        "delay" => run_delay_section(&db).await?,
-        "eta" => run_eta_section(&db).await?,
-        "forecast" => run_forecast_section(&db).await?,
-        "risk" => run_risk_section(&db).await?,
-        _ => {
-            println!("Unknown section: {}", section);
-            println!("Available: all, delay, eta, forecast, risk");
+            run_delay_section(&db, out, filter).await?;
+            run_eta_section(&db, out, filter).await?;
+            run_forecast_section(&db, out, filter).await?;
+            run_risk_section(&db, out, filter).await?;
         }
+        "delay" => run_delay_section(&db, out, filter).await?,
+        "eta" => run_eta_section(&db, out, filter).await?,
+        "forecast" => run_forecast_section(&db, out, filter).await?,
+        "risk" => run_risk_section(&db, out, filter).await?,
+        _ => unreachable!("section is validated by clap"),
     }
 
-    println!("\n{}", "█".repeat(85));
-    Ok(())
+    table_println!(out, "\n{}", "█".repeat(85));
+    args.output.write(&report)
 }
 
-async fn run_delay_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("1. DELAY LIKELIHOOD SCORING");
+async fn run_delay_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("delay", "1. DELAY LIKELIHOOD SCORING");
 
     // Delay probability by Day of Week
-    print_subsection("Delay Probability by Day of Week");
+    out.subsection("Delay Probability by Day of Week");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct DowDelayProb {
         ship_dow: i32,
         total: i64,
@@ -111,14 +128,14 @@ async fn run_delay_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>
         avg_delay_when_late: f64,
     }
 
-    let by_dow: Vec<DowDelayProb> = db
-        .query(r#"
+    let by_dow: Vec<DowDelayProb> = filter
+        .query(db, r#"
             SELECT
                 ship_dow,
                 count() as total,
                 (count(IF otd = "Late" THEN 1 END) / count()) as late_probability,
                 math::mean(IF otd = "Late" THEN actual_transit_days - goal_transit_days END) as avg_delay_when_late
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY ship_dow
             ORDER BY late_probability DESC
         "#)
@@ -126,9 +143,9 @@ async fn run_delay_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>
         .take(0)?;
 
     let dow_names = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
-    println!("  {:12} {:>10} {:>15} {:>18} {:>15}",
+    table_println!(out, "  {:12} {:>10} {:>15} {:>18} {:>15}",
              "Ship Day", "Volume", "P(Late)", "Avg Delay if Late", "Risk Level");
-    println!("  {}", "─".repeat(72));
+    table_println!(out, "  {}", "─".repeat(72));
 
     for row in &by_dow {
         let day = dow_names.get(row.ship_dow as usize).unwrap_or(&"???");
@@ -137,54 +154,56 @@ async fn run_delay_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>
                   else { "🟢 LOW" };
         let delay = row.avg_delay_when_late;
 
-        println!("  {:12} {:>10} {:>14.1}% {:>17.1}d {:>15}",
+        table_println!(out, "  {:12} {:>10} {:>14.1}% {:>17.1}d {:>15}",
                  day, row.total, row.late_probability * 100.0, delay, risk);
     }
+    out.add("by_dow", &by_dow)?;
 
     // Delay probability by Distance
-    print_subsection("Delay Probability by Distance");
+    out.subsection("Delay Probability by Distance");
 
-    let by_distance: Vec<DelayProbability> = db
-        .query(r#"
+    let by_distance: Vec<DelayProbability> = filter
+        .query(db, r#"
             SELECT
                 "Distance" as factor,
                 distance_bucket as value,
                 count() as total,
                 (count(IF otd = "Late" THEN 1 END) / count()) as late_probability,
                 math::mean(IF otd = "Late" THEN actual_transit_days - goal_transit_days END) as avg_delay_when_late
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY distance_bucket
             ORDER BY late_probability DESC
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:12} {:>10} {:>15} {:>18} {:>15}",
+    table_println!(out, "  {:12} {:>10} {:>15} {:>18} {:>15}",
              "Distance", "Volume", "P(Late)", "Avg Delay if Late", "Risk Level");
-    println!("  {}", "─".repeat(72));
+    table_println!(out, "  {}", "─".repeat(72));
 
     for row in &by_distance {
         let risk = if row.late_probability > 0.25 { "🔴 HIGH" }
                   else if row.late_probability > 0.18 { "🟠 MEDIUM" }
                   else { "🟢 LOW" };
 
-        println!("  {:12} {:>10} {:>14.1}% {:>17.1}d {:>15}",
+        table_println!(out, "  {:12} {:>10} {:>14.1}% {:>17.1}d {:>15}",
                  row.value, row.total, row.late_probability * 100.0,
                  row.avg_delay_when_late, risk);
     }
+    out.add("by_distance", &by_distance)?;
 
     // Delay probability by Carrier (Top 10)
-    print_subsection("Delay Probability by Carrier (Top 10 by Volume)");
+    out.subsection("Delay Probability by Carrier (Top 10 by Volume)");
 
-    let by_carrier: Vec<DelayProbability> = db
-        .query(r#"
+    let by_carrier: Vec<DelayProbability> = filter
+        .query(db, r#"
             SELECT
                 "Carrier" as factor,
                 carrier_ref as value,
                 count() as total,
                 (count(IF otd = "Late" THEN 1 END) / count()) as late_probability,
                 math::mean(IF otd = "Late" THEN actual_transit_days - goal_transit_days END) as avg_delay_when_late
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY carrier_ref
             ORDER BY total DESC
             LIMIT 10
@@ -192,24 +211,25 @@ async fn run_delay_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>
         .await?
         .take(0)?;
 
-    println!("  {:20} {:>10} {:>15} {:>18} {:>12}",
+    table_println!(out, "  {:20} {:>10} {:>15} {:>18} {:>12}",
              "Carrier", "Volume", "P(Late)", "Avg Delay if Late", "Prediction");
-    println!("  {}", "─".repeat(77));
+    table_println!(out, "  {}", "─".repeat(77));
 
     for row in &by_carrier {
         let prediction = if row.late_probability > 0.25 { "Likely Late" }
                         else if row.late_probability > 0.15 { "Possible Late" }
                         else { "Likely On-Time" };
 
-        println!("  {:20} {:>10} {:>14.1}% {:>17.1}d {:>12}",
+        table_println!(out, "  {:20} {:>10} {:>14.1}% {:>17.1}d {:>12}",
                  get_carrier_name(&row.value), row.total, row.late_probability * 100.0,
                  row.avg_delay_when_late, prediction);
     }
+    out.add("by_carrier", &by_carrier)?;
 
     // Combined risk matrix
-    print_subsection("Combined Delay Risk Matrix (Carrier x Distance)");
+    out.subsection("Combined Delay Risk Matrix (Carrier x Distance)");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct CombinedRisk {
         carrier_ref: String,
         distance_bucket: String,
@@ -217,15 +237,15 @@ async fn run_delay_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>
         late_prob: f64,
     }
 
-    let combined: Vec<CombinedRisk> = db
-        .query(r#"
+    let combined: Vec<CombinedRisk> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     carrier_ref,
                     distance_bucket,
                     count() as total,
                     (count(IF otd = "Late" THEN 1 END) / count()) as late_prob
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref, distance_bucket
             ) WHERE total >= 20
             ORDER BY late_prob DESC
@@ -234,9 +254,9 @@ async fn run_delay_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>
         .await?
         .take(0)?;
 
-    println!("  {:20} {:>12} {:>10} {:>12} {:>15}",
+    table_println!(out, "  {:20} {:>12} {:>10} {:>12} {:>15}",
              "Carrier", "Distance", "Volume", "P(Late)", "Risk");
-    println!("  {}", "─".repeat(71));
+    table_println!(out, "  {}", "─".repeat(71));
 
     for row in &combined {
         let risk = if row.late_prob > 0.35 { "⚠ VERY HIGH" }
@@ -244,20 +264,21 @@ async fn run_delay_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>
                   else if row.late_prob > 0.15 { "🟠 MEDIUM" }
                   else { "🟢 LOW" };
 
-        println!("  {:20} {:>12} {:>10} {:>11.1}% {:>15}",
+        table_println!(out, "  {:20} {:>12} {:>10} {:>11.1}% {:>15}",
                  get_carrier_name(&row.carrier_ref), row.distance_bucket, row.total, row.late_prob * 100.0, risk);
     }
+    out.add("carrier_x_distance", &combined)?;
 
     Ok(())
 }
 
-async fn run_eta_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("2. ETA PREDICTION FACTORS");
+async fn run_eta_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("eta", "2. ETA PREDICTION FACTORS");
 
     // Historical transit time by carrier and distance
-    print_subsection("Historical Transit Benchmarks (for ETA Calculation)");
+    out.subsection("Historical Transit Benchmarks (for ETA Calculation)");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct TransitBenchmark {
         carrier_ref: String,
         distance_bucket: String,
@@ -268,8 +289,8 @@ async fn run_eta_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         variance: f64,
     }
 
-    let benchmarks: Vec<TransitBenchmark> = db
-        .query(r#"
+    let benchmarks: Vec<TransitBenchmark> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     carrier_ref,
@@ -279,7 +300,7 @@ async fn run_eta_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
                     math::min(actual_transit_days) as min_transit,
                     math::max(actual_transit_days) as max_transit,
                     math::variance(actual_transit_days) as variance
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref, distance_bucket
             ) WHERE total >= 50
             ORDER BY carrier_ref, distance_bucket
@@ -288,60 +309,68 @@ async fn run_eta_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         .await?
         .take(0)?;
 
-    println!("  {:18} {:>10} {:>7} {:>8} {:>6} {:>6} {:>8} {:>12}",
+    table_println!(out, "  {:18} {:>10} {:>7} {:>8} {:>6} {:>6} {:>8} {:>12}",
              "Carrier", "Distance", "Volume", "Avg", "Min", "Max", "Var", "ETA Conf.");
-    println!("  {}", "─".repeat(79));
+    table_println!(out, "  {}", "─".repeat(79));
 
     for row in &benchmarks {
         let confidence = if row.variance < 1.5 { "High" }
                         else if row.variance < 4.0 { "Medium" }
                         else { "Low" };
-        println!("  {:18} {:>10} {:>7} {:>7.1}d {:>5}d {:>5}d {:>7.1} {:>12}",
+        table_println!(out, "  {:18} {:>10} {:>7} {:>7.1}d {:>5}d {:>5}d {:>7.1} {:>12}",
                  get_carrier_name(&row.carrier_ref), row.distance_bucket, row.total,
                  row.avg_transit, row.min_transit, row.max_transit, row.variance, confidence);
     }
+    out.add("transit_benchmarks", &benchmarks)?;
 
     // P90 Transit Times (for conservative ETA)
-    print_subsection("P90 Transit Times (Conservative ETA Buffer)");
+    out.subsection("P90 Transit Times (Conservative ETA Buffer)");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct P90Transit {
         distance_bucket: String,
         total: i64,
         avg_transit: f64,
         goal_transit: f64,
+        #[serde(default)]
+        suggested_eta: f64,
+        #[serde(default)]
+        buffer: f64,
     }
 
-    let p90: Vec<P90Transit> = db
-        .query(r#"
+    let mut p90: Vec<P90Transit> = filter
+        .query(db, r#"
             SELECT
                 distance_bucket,
                 count() as total,
                 math::mean(actual_transit_days) as avg_transit,
                 math::mean(goal_transit_days) as goal_transit
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY distance_bucket
             ORDER BY distance_bucket
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:12} {:>10} {:>12} {:>12} {:>15} {:>12}",
+    table_println!(out, "  {:12} {:>10} {:>12} {:>12} {:>15} {:>12}",
              "Distance", "Volume", "Avg Transit", "Goal", "Suggested ETA", "Buffer");
-    println!("  {}", "─".repeat(75));
+    table_println!(out, "  {}", "─".repeat(75));
 
-    for row in &p90 {
+    for row in &mut p90 {
         let suggested_buffer = row.avg_transit * 1.5;
         let buffer = suggested_buffer - row.goal_transit;
-        println!("  {:12} {:>10} {:>11.1}d {:>11.1}d {:>14.1}d {:>+11.1}d",
+        table_println!(out, "  {:12} {:>10} {:>11.1}d {:>11.1}d {:>14.1}d {:>+11.1}d",
                  row.distance_bucket, row.total, row.avg_transit,
                  row.goal_transit, suggested_buffer, buffer);
+        row.suggested_eta = suggested_buffer;
+        row.buffer = buffer;
     }
+    out.add("eta_buffers", &p90)?;
 
     // ETA Adjustment Recommendations
-    print_subsection("ETA Adjustment Recommendations");
+    out.subsection("ETA Adjustment Recommendations");
 
-    println!("
+    table_println!(out, "
   PREDICTIVE ETA MODEL FACTORS:
 
   Base ETA = Goal Transit Days (from SLA)
@@ -372,13 +401,13 @@ async fn run_eta_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
     Ok(())
 }
 
-async fn run_forecast_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("3. CAPACITY & VOLUME FORECASTING");
+async fn run_forecast_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("forecast", "3. CAPACITY & VOLUME FORECASTING");
 
     // Monthly volume patterns
-    print_subsection("Historical Monthly Volume Patterns");
+    out.subsection("Historical Monthly Volume Patterns");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct MonthlyVolume {
         month: i32,
         avg_shipments: f64,
@@ -386,8 +415,8 @@ async fn run_forecast_section(db: &surrealdb::Surreal<surrealdb::engine::local::
         max_shipments: i64,
     }
 
-    let monthly: Vec<MonthlyVolume> = db
-        .query(r#"
+    let monthly: Vec<MonthlyVolume> = filter
+        .query(db, r#"
             SELECT
                 ship_month as month,
                 math::mean(cnt) as avg_shipments,
@@ -395,7 +424,7 @@ async fn run_forecast_section(db: &surrealdb::Surreal<surrealdb::engine::local::
                 math::max(cnt) as max_shipments
             FROM (
                 SELECT ship_month, ship_year, count() as cnt
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY ship_year, ship_month
             )
             GROUP BY ship_month
@@ -407,12 +436,13 @@ async fn run_forecast_section(db: &surrealdb::Surreal<surrealdb::engine::local::
     let month_names = ["", "Jan", "Feb", "Mar", "Apr", "May", "Jun",
                        "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
 
-    println!("  {:8} {:>12} {:>12} {:>12} {:>20}",
+    table_println!(out, "  {:8} {:>12} {:>12} {:>12} {:>20}",
              "Month", "Avg Volume", "Min", "Max", "Seasonality");
-    println!("  {}", "─".repeat(66));
+    table_println!(out, "  {}", "─".repeat(66));
 
     let overall_avg: f64 = monthly.iter().map(|m| m.avg_shipments).sum::<f64>() / monthly.len() as f64;
 
+    let mut projections = Vec::new();
     for row in &monthly {
         let month_name = month_names.get(row.month as usize).unwrap_or(&"???");
         let seasonality = if row.avg_shipments > overall_avg * 1.1 { "📈 Peak" }
@@ -422,30 +452,57 @@ async fn run_forecast_section(db: &surrealdb::Surreal<surrealdb::engine::local::
         let bar_len = ((row.avg_shipments / 100.0).min(20.0)) as usize;
         let bar: String = "▓".repeat(bar_len);
 
-        println!("  {:8} {:>12.0} {:>12} {:>12} {:>10} {}",
+        table_println!(out, "  {:8} {:>12.0} {:>12} {:>12} {:>10} {}",
                  month_name, row.avg_shipments, row.min_shipments,
                  row.max_shipments, seasonality, bar);
+        projections.push(VolumeProjection {
+            period: month_name.to_string(),
+            historical_avg: row.avg_shipments,
+            trend: seasonality.to_string(),
+        });
+    }
+    out.add("monthly", &monthly)?;
+    out.add("seasonality", &projections)?;
+
+    // Weekly forecast for the whole (filtered) network
+    out.subsection("Weekly Volume Forecast (next 8 weeks)");
+
+    let network = forecast::load_weekly(db, filter, Level::Network).await?;
+    match network.first().map(|s| forecast::forecast(s, 8, Method::Auto)) {
+        Some(Ok(weekly)) => {
+            table_println!(out, "  {} from {} weeks of history (last week {:.0}); per terminal/lane: volume_forecast",
+                     weekly.method.as_str(), weekly.history_weeks, weekly.last_week);
+            table_println!(out, "  {:12} {:>10} {:>17}", "Week of", "Forecast", "95% Interval");
+            table_println!(out, "  {}", "─".repeat(42));
+            for p in &weekly.points {
+                let interval = format!("{:.0}-{:.0}", p.lower_95, p.upper_95);
+                table_println!(out, "  {:12} {:>10.0} {:>17}", p.week.to_string(), p.forecast, interval);
+            }
+            out.add("weekly_forecast", &weekly.points)?;
+        }
+        Some(Err(e)) => table_println!(out, "  Not enough history to forecast: {}", e),
+        None => table_println!(out, "  No complete weeks of shipments to forecast from"),
     }
 
     // Day of week patterns
-    print_subsection("Day of Week Volume Distribution");
+    out.subsection("Day of Week Volume Distribution");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct DowVolume {
         dow: i32,
         avg_shipments: f64,
         pct_of_week: f64,
     }
 
-    let dow_vol: Vec<DowVolume> = db
-        .query(r#"
+    let mut dow_vol: Vec<DowVolume> = filter
+        .query(db, r#"
             SELECT
                 ship_dow as dow,
                 math::mean(cnt) as avg_shipments,
                 0.0 as pct_of_week
             FROM (
                 SELECT ship_dow, ship_week, ship_year, count() as cnt
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY ship_year, ship_week, ship_dow
             )
             GROUP BY ship_dow
@@ -457,11 +514,11 @@ async fn run_forecast_section(db: &surrealdb::Surreal<surrealdb::engine::local::
     let dow_names = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
     let total_avg: f64 = dow_vol.iter().map(|d| d.avg_shipments).sum();
 
-    println!("  {:12} {:>15} {:>15} {:>20}",
+    table_println!(out, "  {:12} {:>15} {:>15} {:>20}",
              "Day", "Avg/Week", "% of Week", "Capacity Need");
-    println!("  {}", "─".repeat(64));
+    table_println!(out, "  {}", "─".repeat(64));
 
-    for row in &dow_vol {
+    for row in &mut dow_vol {
         let day = dow_names.get(row.dow as usize).unwrap_or(&"???");
         let pct = (row.avg_shipments / total_avg) * 100.0;
         let capacity = if pct > 20.0 { "🔴 HIGH" }
@@ -469,14 +526,16 @@ async fn run_forecast_section(db: &surrealdb::Surreal<surrealdb::engine::local::
                       else if pct > 5.0 { "🟢 NORMAL" }
                       else { "⚪ LOW" };
 
-        println!("  {:12} {:>15.0} {:>14.1}% {:>20}",
+        table_println!(out, "  {:12} {:>15.0} {:>14.1}% {:>20}",
                  day, row.avg_shipments, pct, capacity);
+        row.pct_of_week = pct;
     }
+    out.add("by_dow", &dow_vol)?;
 
     // Carrier capacity utilization
-    print_subsection("Carrier Capacity Analysis");
+    out.subsection("Carrier Capacity Analysis");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct CarrierCapacity {
         carrier_ref: String,
         total_shipments: i64,
@@ -484,8 +543,8 @@ async fn run_forecast_section(db: &surrealdb::Surreal<surrealdb::engine::local::
         peak_week: i64,
     }
 
-    let carrier_cap: Vec<CarrierCapacity> = db
-        .query(r#"
+    let carrier_cap: Vec<CarrierCapacity> = filter
+        .query(db, r#"
             SELECT
                 carrier_ref,
                 math::sum(cnt) as total_shipments,
@@ -493,7 +552,7 @@ async fn run_forecast_section(db: &surrealdb::Surreal<surrealdb::engine::local::
                 math::max(cnt) as peak_week
             FROM (
                 SELECT carrier_ref, ship_week, ship_year, count() as cnt
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref, ship_year, ship_week
             )
             GROUP BY carrier_ref
@@ -503,27 +562,28 @@ async fn run_forecast_section(db: &surrealdb::Surreal<surrealdb::engine::local::
         .await?
         .take(0)?;
 
-    println!("  {:20} {:>12} {:>12} {:>12} {:>15}",
+    table_println!(out, "  {:20} {:>12} {:>12} {:>12} {:>15}",
              "Carrier", "Total", "Weekly Avg", "Peak Week", "Headroom");
-    println!("  {}", "─".repeat(73));
+    table_println!(out, "  {}", "─".repeat(73));
 
     for row in &carrier_cap {
         let headroom = ((row.peak_week as f64 / row.weekly_avg) - 1.0) * 100.0;
         let headroom_str = format!("{:.0}% above avg", headroom);
 
-        println!("  {:20} {:>12} {:>12.0} {:>12} {:>15}",
+        table_println!(out, "  {:20} {:>12} {:>12.0} {:>12} {:>15}",
                  get_carrier_name(&row.carrier_ref), row.total_shipments, row.weekly_avg,
                  row.peak_week, headroom_str);
     }
+    out.add("carrier_capacity", &carrier_cap)?;
 
     Ok(())
 }
 
-async fn run_risk_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("4. RISK SCORING & ALERTS");
+async fn run_risk_section(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("risk", "4. RISK SCORING & ALERTS");
 
     // Lane risk scores
-    print_subsection("High-Risk Lanes (Composite Risk Score)");
+    out.subsection("High-Risk Lanes (Composite Risk Score)");
 
     #[derive(Debug, Deserialize)]
     struct LaneRiskData {
@@ -534,8 +594,8 @@ async fn run_risk_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         variance: f64,
     }
 
-    let lane_risks: Vec<LaneRiskData> = db
-        .query(r#"
+    let lane_risks: Vec<LaneRiskData> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     origin_zip,
@@ -543,7 +603,7 @@ async fn run_risk_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
                     count() as volume,
                     (count(IF otd = "Late" THEN 1 END) / count()) as delay_risk,
                     math::variance(actual_transit_days) as variance
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY origin_zip, dest_zip
             ) WHERE volume >= 30
             ORDER BY delay_risk DESC
@@ -552,10 +612,11 @@ async fn run_risk_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         .await?
         .take(0)?;
 
-    println!("  {:25} {:>8} {:>12} {:>12} {:>12} {:>10}",
+    table_println!(out, "  {:25} {:>8} {:>12} {:>12} {:>12} {:>10}",
              "Lane", "Volume", "Delay Risk", "Var Risk", "Combined", "Alert");
-    println!("  {}", "─".repeat(81));
+    table_println!(out, "  {}", "─".repeat(81));
 
+    let mut scores = Vec::new();
     for row in &lane_risks {
         let route = format_lane_short(&row.origin_zip, &row.dest_zip);
         let variability_risk = row.variance / 10.0;
@@ -565,16 +626,26 @@ async fn run_risk_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
                    else if combined_risk > 0.15 { "📋 WATCH" }
                    else { "✓ OK" };
 
-        println!("  {:25} {:>8} {:>11.1}% {:>11.2} {:>11.2} {:>10}",
+        table_println!(out, "  {:25} {:>8} {:>11.1}% {:>11.2} {:>11.2} {:>10}",
                  route, row.volume, row.delay_risk * 100.0,
                  variability_risk, combined_risk, alert);
+        scores.push(RiskScore {
+            lane_ref: format!("{}{}", row.origin_zip, row.dest_zip),
+            origin_zip: row.origin_zip.clone(),
+            dest_zip: row.dest_zip.clone(),
+            volume: row.volume,
+            delay_risk: row.delay_risk,
+            variability_risk,
+            combined_risk,
+        });
     }
+    out.add("lanes", &scores)?;
 
     // Carrier risk tiers
-    print_subsection("Carrier Risk Tiers");
+    out.subsection("Carrier Risk Tiers");
 
-    let carrier_risks: Vec<CarrierRisk> = db
-        .query(r#"
+    let mut carrier_risks: Vec<CarrierRisk> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     carrier_ref,
@@ -582,7 +653,7 @@ async fn run_risk_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
                     (count(IF otd = "Late" THEN 1 END) / count()) as late_prob,
                     math::variance(actual_transit_days) as variance,
                     "" as risk_tier
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref
             ) WHERE volume >= 50
             ORDER BY late_prob DESC
@@ -590,11 +661,11 @@ async fn run_risk_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
         .await?
         .take(0)?;
 
-    println!("  {:20} {:>10} {:>12} {:>10} {:>15} {:>12}",
+    table_println!(out, "  {:20} {:>10} {:>12} {:>10} {:>15} {:>12}",
              "Carrier", "Volume", "Late Prob", "Variance", "Risk Tier", "Action");
-    println!("  {}", "─".repeat(81));
+    table_println!(out, "  {}", "─".repeat(81));
 
-    for row in &carrier_risks {
+    for row in &mut carrier_risks {
         let (tier, action) = if row.late_prob > 0.30 && row.variance > 5.0 {
             ("🔴 CRITICAL", "Reduce load")
         } else if row.late_prob > 0.25 || row.variance > 8.0 {
@@ -605,15 +676,17 @@ async fn run_risk_section(db: &surrealdb::Surreal<surrealdb::engine::local::Db>)
             ("🟢 LOW", "Maintain")
         };
 
-        println!("  {:20} {:>10} {:>11.1}% {:>10.1} {:>15} {:>12}",
+        table_println!(out, "  {:20} {:>10} {:>11.1}% {:>10.1} {:>15} {:>12}",
                  get_carrier_name(&row.carrier_ref), row.volume, row.late_prob * 100.0,
                  row.variance, tier, action);
+        row.risk_tier = tier.to_string();
     }
+    out.add("carriers", &carrier_risks)?;
 
     // Alert thresholds summary
-    print_subsection("Alert Threshold Configuration");
+    out.subsection("Alert Threshold Configuration");
 
-    println!("
+    table_println!(out, "
   RECOMMENDED ALERT THRESHOLDS:
 
   ┌──────────────────────────────────────────────────────────────────────────┐
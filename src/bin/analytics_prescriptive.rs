@@ -1,13 +1,50 @@
 //! Prescriptive Analytics - What should we do?
 //! Carrier optimization, mode recommendations, SLA tuning, exception management
 //!
-//! Run: ./target/release/analytics_prescriptive [section]
+//! Run: ./target/release/analytics_prescriptive [section] [--format table|json|csv|markdown]
+//! Filters: --from, --to, --mode, --carrier, --origin-zip3, --distance-bucket
+//! Database: --db URL (default: rocksdb://data/lastmile.db)
 //! Sections: all, carriers, modes, sla, exceptions
-
-use anyhow::Result;
-use nyc_last_mile::{db, carrier_names::get_carrier_name, location_names::format_lane_short};
-use serde::Deserialize;
-use std::env;
+//! Carrier capacity: --capacity FILE (weekly shipments per carrier), --headroom 0.1
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use nyc_last_mile::{db, carrier_names::get_carrier_name, location_names::format_lane_short, table_println};
+use nyc_last_mile::alerts::{self, Severity};
+use nyc_last_mile::filter::ShipmentFilter;
+use nyc_last_mile::reallocation::{self, ReallocationConfig};
+use nyc_last_mile::report::{OutputArgs, Report};
+use nyc_last_mile::sla::{self, SlaConfig, SlaProposal, SlaScope};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Prescriptive analytics - what should we do?
+#[derive(Parser, Debug)]
+#[command(name = "analytics_prescriptive")]
+#[command(about = "Carrier, mode and SLA recommendations and exception alerts")]
+struct Args {
+    /// Section to run
+    #[arg(default_value = "all", value_parser = ["all", "carriers", "modes", "sla", "exceptions"])]
+    section: String,
+
+    /// Weekly carrier capacity file (.toml or .json) for the reallocation plan
+    #[arg(long)]
+    capacity: Option<PathBuf>,
+
+    /// Capacity above each carrier's busiest week when none is given in the file
+    #[arg(long, default_value_t = 0.0)]
+    headroom: f64,
+
+    #[command(flatten)]
+    filter: ShipmentFilter,
+
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
 
 #[derive(Debug, Deserialize)]
 struct CarrierLanePerformance {
@@ -21,7 +58,7 @@ struct CarrierLanePerformance {
     avg_delay: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct CarrierRecommendation {
     lane_ref: String,
     origin_zip: String,
@@ -31,9 +68,10 @@ struct CarrierRecommendation {
     best_carrier: String,
     best_late_rate: f64,
     potential_improvement: f64,
+    recommendation: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct ModeConversion {
     lane_ref: String,
     origin_zip: String,
@@ -46,7 +84,7 @@ struct ModeConversion {
     improvement: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct SlaSuggestion {
     distance_bucket: String,
     current_goal: f64,
@@ -56,7 +94,7 @@ struct SlaSuggestion {
     adjustment: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct CarrierSlaSuggestion {
     carrier_ref: String,
     distance_bucket: String,
@@ -66,74 +104,76 @@ struct CarrierSlaSuggestion {
     suggested_sla: i64,
 }
 
-#[derive(Debug, Deserialize)]
-struct ExceptionAlert {
-    entity_type: String,
-    entity_id: String,
-    metric: String,
-    current_value: f64,
-    baseline: f64,
-    deviation: f64,
-    severity: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct VolumeShiftRecommendation {
-    from_carrier: String,
-    to_carrier: String,
-    lane_ref: String,
-    volume_to_shift: i64,
-    expected_improvement: f64,
-}
-
-fn print_section_header(title: &str) {
-    println!("\n{}", "═".repeat(90));
-    println!("  {}", title);
-    println!("{}\n", "═".repeat(90));
+#[derive(Debug, Serialize)]
+struct ReallocationSummary {
+    weeks: usize,
+    shipments: i64,
+    current_otd: f64,
+    planned_otd: f64,
+    current_late_rate: f64,
+    planned_late_rate: f64,
+    late_avoided: f64,
+    unplaced: i64,
 }
 
-fn print_subsection(title: &str) {
-    println!("\n{}", title);
-    println!("{}", "─".repeat(80));
+/// Capacity inputs of the reallocation plan
+struct CapacityOptions {
+    overrides: HashMap<String, f64>,
+    config: ReallocationConfig,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
-    let section = args.get(1).map(|s| s.as_str()).unwrap_or("all");
-
-    let db = db::connect("data/lastmile.db").await?;
-
-    println!("\n{}", "█".repeat(90));
-    println!("{}  PRESCRIPTIVE ANALYTICS - What Should We Do?  {}", "█".repeat(20), "█".repeat(21));
-    println!("{}\n", "█".repeat(90));
+    let args = Args::parse();
+    let section = args.section.as_str();
+    if args.headroom < 0.0 {
+        bail!("--headroom must not be negative");
+    }
+    let capacity = CapacityOptions {
+        overrides: match &args.capacity {
+            Some(path) => reallocation::load_capacity(path)?,
+            None => HashMap::new(),
+        },
+        config: ReallocationConfig { headroom: args.headroom, ..Default::default() },
+    };
+
+    let db = args.db.connect().await?;
+    let mut report = args.output.report("Prescriptive Analytics - What Should We Do?");
+    report.filter = args.filter.describe();
+    let out = &mut report;
+    let filter = &args.filter;
+
+    table_println!(out, "\n{}", "█".repeat(90));
+    table_println!(out, "{}  PRESCRIPTIVE ANALYTICS - What Should We Do?  {}", "█".repeat(20), "█".repeat(21));
+    table_println!(out, "{}\n", "█".repeat(90));
+    if !filter.is_empty() {
+        table_println!(out, "  Filter: {}\n", out.filter);
+    }
 
     match section {
         "all" => {
-            run_carrier_optimization(&db).await?;
-            run_mode_optimization(&db).await?;
-            run_sla_optimization(&db).await?;
-            run_exception_management(&db).await?;
-        }
-        "carriers" => run_carrier_optimization(&db).await?,
-        "modes" => run_mode_optimization(&db).await?,
-        "sla" => run_sla_optimization(&db).await?,
-        "exceptions" => run_exception_management(&db).await?,
-        _ => {
-            println!("Unknown section: {}", section);
-            println!("Available: all, carriers, modes, sla, exceptions");
+            run_carrier_optimization(&db, out, filter, &capacity).await?;
+            run_mode_optimization(&db, out, filter).await?;
+            run_sla_optimization(&db, out, filter).await?;
+            run_exception_management(&db, out, filter).await?;
         }
+        "carriers" => run_carrier_optimization(&db, out, filter, &capacity).await?,
+        "modes" => run_mode_optimization(&db, out, filter).await?,
+        "sla" => run_sla_optimization(&db, out, filter).await?,
+        "exceptions" => run_exception_management(&db, out, filter).await?,
+        _ => unreachable!("section is validated by clap"),
     }
 
-    println!("\n{}", "█".repeat(90));
-    Ok(())
+    table_println!(out, "\n{}", "█".repeat(90));
+    args.output.write(&report)
 }
 
-async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("1. CARRIER OPTIMIZATION RECOMMENDATIONS");
+async fn run_carrier_optimization(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter,
+                                  capacity: &CapacityOptions) -> Result<()> {
+    out.section("carriers", "1. CARRIER OPTIMIZATION RECOMMENDATIONS");
 
     // Find worst lanes and identify best carriers for them
-    print_subsection("Lanes with Poor Performance - Carrier Switch Recommendations");
+    out.subsection("Lanes with Poor Performance - Carrier Switch Recommendations");
 
     // First, get the best carrier per lane
     #[derive(Debug, Clone, Deserialize)]
@@ -146,8 +186,8 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
         late_rate: f64,
     }
 
-    let lane_carrier_perf: Vec<LaneCarrierPerf> = db
-        .query(r#"
+    let lane_carrier_perf: Vec<LaneCarrierPerf> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     lane_ref,
@@ -156,7 +196,7 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
                     carrier_ref,
                     count() as volume,
                     (count(IF otd = "Late" THEN 1 END) / count()) as late_rate
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY lane_ref, origin_zip, dest_zip, carrier_ref
             ) WHERE volume >= 10
             ORDER BY lane_ref, late_rate ASC
@@ -174,8 +214,8 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
         lane_late_rate: f64,
     }
 
-    let worst_lanes: Vec<WorstLane> = db
-        .query(r#"
+    let worst_lanes: Vec<WorstLane> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     lane_ref,
@@ -183,7 +223,7 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
                     dest_zip,
                     count() as total_volume,
                     (count(IF otd = "Late" THEN 1 END) / count()) as lane_late_rate
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY lane_ref, origin_zip, dest_zip
             ) WHERE total_volume >= 30 AND lane_late_rate > 0.25
             ORDER BY lane_late_rate DESC
@@ -192,10 +232,11 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
         .await?
         .take(0)?;
 
-    println!("  {:25} {:>8} {:>10} {:>20} {:>15}",
+    table_println!(out, "  {:25} {:>8} {:>10} {:>20} {:>15}",
              "Lane", "Volume", "Late%", "Primary Carrier", "Recommendation");
-    println!("  {}", "─".repeat(80));
+    table_println!(out, "  {}", "─".repeat(80));
 
+    let mut recommendations = Vec::new();
     for lane in &worst_lanes {
         // Find primary carrier for this lane
         let primary: Option<LaneCarrierPerf> = lane_carrier_perf.iter()
@@ -219,16 +260,28 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
                 "Monitor".to_string()
             };
 
-            println!("  {:25} {:>8} {:>9.1}% {:>20} {:>15}",
+            table_println!(out, "  {:25} {:>8} {:>9.1}% {:>20} {:>15}",
                      route, lane.total_volume, lane.lane_late_rate * 100.0,
                      get_carrier_name(&pri.carrier_ref), recommendation);
+            recommendations.push(CarrierRecommendation {
+                lane_ref: lane.lane_ref.clone(),
+                origin_zip: lane.origin_zip.clone(),
+                dest_zip: lane.dest_zip.clone(),
+                current_carrier: pri.carrier_ref.clone(),
+                current_late_rate: pri.late_rate,
+                best_carrier: b.carrier_ref.clone(),
+                best_late_rate: b.late_rate,
+                potential_improvement: pri.late_rate - b.late_rate,
+                recommendation,
+            });
         }
     }
+    out.add("switch_recommendations", &recommendations)?;
 
     // Carrier volume reallocation recommendations
-    print_subsection("Volume Reallocation Opportunities");
+    out.subsection("Volume Reallocation Opportunities");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct CarrierPerf {
         carrier_ref: String,
         total_volume: i64,
@@ -236,15 +289,15 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
         avg_delay: f64,
     }
 
-    let carrier_perf: Vec<CarrierPerf> = db
-        .query(r#"
+    let carrier_perf: Vec<CarrierPerf> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     carrier_ref,
                     count() as total_volume,
                     (count(IF otd = "Late" THEN 1 END) / count()) as late_rate,
                     math::mean(actual_transit_days - goal_transit_days) as avg_delay
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref
             ) WHERE total_volume >= 50
             ORDER BY late_rate ASC
@@ -261,35 +314,41 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
         .filter(|c| c.late_rate > 0.30)
         .collect();
 
-    println!("\n  TOP PERFORMERS (Consider increasing volume):");
-    println!("  {:20} {:>10} {:>10} {:>12} {:>20}",
+    table_println!(out, "\n  TOP PERFORMERS (Consider increasing volume):");
+    table_println!(out, "  {:20} {:>10} {:>10} {:>12} {:>20}",
              "Carrier", "Volume", "Late%", "Avg Delay", "Action");
-    println!("  {}", "─".repeat(74));
+    table_println!(out, "  {}", "─".repeat(74));
 
-    for c in best_carriers.iter().take(5) {
-        println!("  {:20} {:>10} {:>9.1}% {:>11.2}d {:>20}",
+    let top: Vec<&CarrierPerf> = best_carriers.iter().take(5).copied().collect();
+    for c in &top {
+        table_println!(out, "  {:20} {:>10} {:>9.1}% {:>11.2}d {:>20}",
                  get_carrier_name(&c.carrier_ref), c.total_volume, c.late_rate * 100.0,
                  c.avg_delay, "Increase allocation");
     }
+    out.add("top_performers", &top)?;
 
-    println!("\n  UNDERPERFORMERS (Consider reducing volume):");
-    println!("  {:20} {:>10} {:>10} {:>12} {:>20}",
+    table_println!(out, "\n  UNDERPERFORMERS (Consider reducing volume):");
+    table_println!(out, "  {:20} {:>10} {:>10} {:>12} {:>20}",
              "Carrier", "Volume", "Late%", "Avg Delay", "Action");
-    println!("  {}", "─".repeat(74));
+    table_println!(out, "  {}", "─".repeat(74));
 
-    for c in worst_carriers.iter().take(5) {
+    let under: Vec<&CarrierPerf> = worst_carriers.iter().take(5).copied().collect();
+    for c in &under {
         let action = if c.late_rate > 0.40 { "Urgent: Reduce 50%" }
                     else if c.late_rate > 0.35 { "Reduce 30%" }
                     else { "Reduce 15%" };
-        println!("  {:20} {:>10} {:>9.1}% {:>11.2}d {:>20}",
+        table_println!(out, "  {:20} {:>10} {:>9.1}% {:>11.2}d {:>20}",
                  get_carrier_name(&c.carrier_ref), c.total_volume, c.late_rate * 100.0,
                  c.avg_delay, action);
     }
+    out.add("underperformers", &under)?;
+
+    run_reallocation_plan(db, out, filter, capacity).await?;
 
     // Optimal carrier blend recommendation
-    print_subsection("Optimal Carrier Mix by Distance Segment");
+    out.subsection("Optimal Carrier Mix by Distance Segment");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct CarrierDistPerf {
         carrier_ref: String,
         distance_bucket: String,
@@ -298,8 +357,8 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
         variance: f64,
     }
 
-    let carrier_dist: Vec<CarrierDistPerf> = db
-        .query(r#"
+    let carrier_dist: Vec<CarrierDistPerf> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     carrier_ref,
@@ -307,7 +366,7 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
                     count() as volume,
                     (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
                     math::variance(actual_transit_days) as variance
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref, distance_bucket
             ) WHERE volume >= 20
             ORDER BY distance_bucket, otd_rate DESC
@@ -315,11 +374,12 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
         .await?
         .take(0)?;
 
-    println!("  {:12} {:20} {:>10} {:>10} {:>10} {:>15}",
+    table_println!(out, "  {:12} {:20} {:>10} {:>10} {:>10} {:>15}",
              "Distance", "Best Carrier", "Volume", "OTD%", "Variance", "Recommendation");
-    println!("  {}", "─".repeat(79));
+    table_println!(out, "  {}", "─".repeat(79));
 
     let distance_buckets = ["0-100", "100-250", "250-500", "500-1k", "1k-2k", "2k+"];
+    let mut best_mix = Vec::new();
     for bucket in distance_buckets {
         let best_for_bucket: Option<&CarrierDistPerf> = carrier_dist.iter()
             .filter(|c| c.distance_bucket == bucket)
@@ -330,20 +390,95 @@ async fn run_carrier_optimization(db: &surrealdb::Surreal<surrealdb::engine::loc
                      else if best.otd_rate > 0.60 { "Use with backup" }
                      else { "Seek alternatives" };
 
-            println!("  {:12} {:20} {:>10} {:>9.1}% {:>10.1} {:>15}",
+            table_println!(out, "  {:12} {:20} {:>10} {:>9.1}% {:>10.1} {:>15}",
                      bucket, get_carrier_name(&best.carrier_ref), best.volume,
                      best.otd_rate * 100.0, best.variance, rec);
+            best_mix.push(best);
         }
     }
+    out.add("best_by_distance", &best_mix)?;
 
     Ok(())
 }
 
-async fn run_mode_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("2. MODE & ROUTING OPTIMIZATION");
+/// Volume shifts that cut expected late shipments within carrier capacity
+async fn run_reallocation_plan(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter,
+                               capacity: &CapacityOptions) -> Result<()> {
+    out.subsection("Capacity-Constrained Reallocation Plan");
+
+    let input = reallocation::load_input(db, filter).await?;
+    if input.lanes.is_empty() {
+        table_println!(out, "  No shipments to reallocate.");
+        return Ok(());
+    }
+    let plan = reallocation::optimize(&input, &capacity.overrides, &capacity.config);
+
+    let source = if capacity.overrides.is_empty() {
+        format!("busiest historical week +{:.0}%", capacity.config.headroom * 100.0)
+    } else {
+        format!("{} carriers from file, others busiest week +{:.0}%",
+                capacity.overrides.len(), capacity.config.headroom * 100.0)
+    };
+    table_println!(out, "  {} shipments over {} weeks; capacity: {}", plan.shipments, plan.weeks, source);
+    table_println!(out, "  Network OTD: {:.1}% -> {:.1}% projected  (late {:.1}% -> {:.1}%, {:.0} late shipments avoided)",
+             plan.current_otd * 100.0, plan.planned_otd * 100.0,
+             plan.current_late_rate * 100.0, plan.planned_late_rate * 100.0, plan.late_avoided);
+    if plan.unplaced > 0 {
+        table_println!(out, "  Warning: {} shipments exceed the capacity given in their week and have no carrier in the plan", plan.unplaced);
+    }
+    out.add_one("reallocation_summary", &ReallocationSummary {
+        weeks: plan.weeks,
+        shipments: plan.shipments,
+        current_otd: plan.current_otd,
+        planned_otd: plan.planned_otd,
+        current_late_rate: plan.current_late_rate,
+        planned_late_rate: plan.planned_late_rate,
+        late_avoided: plan.late_avoided,
+        unplaced: plan.unplaced,
+    })?;
+
+    table_println!(out, "\n  VOLUME SHIFTS (largest saving first):");
+    table_println!(out, "  {:25} {:18} {:18} {:>9} {:>8} {:>8} {:>9}",
+             "Lane", "From", "To", "Per Week", "Late%", "Late%", "Avoided");
+    table_println!(out, "  {}", "─".repeat(101));
+    if plan.shifts.is_empty() {
+        table_println!(out, "  No shift beats the current mix by {:.0} points within capacity.",
+                 capacity.config.min_improvement * 100.0);
+    }
+    for s in plan.shifts.iter().take(15) {
+        table_println!(out, "  {:25} {:18} {:18} {:>9.1} {:>7.1}% {:>7.1}% {:>9.0}",
+                 format_lane_short(&s.origin_zip, &s.dest_zip),
+                 get_carrier_name(&s.from_carrier), get_carrier_name(&s.to_carrier),
+                 s.weekly_shipments, s.from_late_rate * 100.0, s.to_late_rate * 100.0, s.late_avoided);
+    }
+    if plan.shifts.len() > 15 {
+        table_println!(out, "  ... {} more shifts", plan.shifts.len() - 15);
+    }
+    out.add("volume_shifts", &plan.shifts)?;
+
+    table_println!(out, "\n  CARRIER LOAD (shipments per week):");
+    table_println!(out, "  {:20} {:>10} {:>10} {:>10} {:>10} {:>7} {:>9}",
+             "Carrier", "Current", "Planned", "Unplaced", "Capacity", "Util%", "Source");
+    table_println!(out, "  {}", "─".repeat(82));
+    let shifted: Vec<_> = plan.carriers.iter()
+        .filter(|c| c.unplaced_weekly > 0.0
+            || plan.shifts.iter().any(|s| s.from_carrier == c.carrier_ref || s.to_carrier == c.carrier_ref))
+        .collect();
+    for c in &shifted {
+        table_println!(out, "  {:20} {:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>6.0}% {:>9}",
+                 get_carrier_name(&c.carrier_ref), c.current_weekly, c.planned_weekly, c.unplaced_weekly,
+                 c.capacity_weekly, c.utilization() * 100.0, c.capacity_source.as_str());
+    }
+    out.add("carrier_load", &plan.carriers)?;
+
+    Ok(())
+}
+
+async fn run_mode_optimization(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("modes", "2. MODE & ROUTING OPTIMIZATION");
 
     // Compare LTL vs TL performance on same lanes
-    print_subsection("Mode Conversion Opportunities (LTL -> Truckload)");
+    out.subsection("Mode Conversion Opportunities (LTL -> Truckload)");
 
     #[derive(Debug, Deserialize)]
     struct ModePerLane {
@@ -356,8 +491,8 @@ async fn run_mode_optimization(db: &surrealdb::Surreal<surrealdb::engine::local:
         variance: f64,
     }
 
-    let mode_perf: Vec<ModePerLane> = db
-        .query(r#"
+    let mode_perf: Vec<ModePerLane> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     lane_ref,
@@ -367,7 +502,7 @@ async fn run_mode_optimization(db: &surrealdb::Surreal<surrealdb::engine::local:
                     count() as volume,
                     (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
                     math::variance(actual_transit_days) as variance
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY lane_ref, origin_zip, dest_zip, carrier_mode
             ) WHERE volume >= 10
             ORDER BY lane_ref, carrier_mode
@@ -376,9 +511,9 @@ async fn run_mode_optimization(db: &surrealdb::Surreal<surrealdb::engine::local:
         .take(0)?;
 
     // Find lanes where LTL underperforms and TL is available
-    println!("  {:25} {:>8} {:>10} {:>10} {:>10} {:>15}",
+    table_println!(out, "  {:25} {:>8} {:>10} {:>10} {:>10} {:>15}",
              "Lane", "LTL Vol", "LTL OTD%", "TL OTD%", "Diff", "Recommendation");
-    println!("  {}", "─".repeat(80));
+    table_println!(out, "  {}", "─".repeat(80));
 
     let mut processed_lanes = std::collections::HashSet::new();
     let mut conversion_opportunities = Vec::new();
@@ -401,23 +536,36 @@ async fn run_mode_optimization(db: &surrealdb::Surreal<surrealdb::engine::local:
                          else if diff > 10.0 { "Consider TL" }
                          else { "Monitor" };
 
-                conversion_opportunities.push((route.clone(), ltl_lane.volume, ltl_lane.otd_rate, tl.otd_rate, diff, rec));
+                conversion_opportunities.push((route, rec, ModeConversion {
+                    lane_ref: ltl_lane.lane_ref.clone(),
+                    origin_zip: ltl_lane.origin_zip.clone(),
+                    dest_zip: ltl_lane.dest_zip.clone(),
+                    current_mode: ltl_lane.carrier_mode.clone(),
+                    volume: ltl_lane.volume,
+                    current_otd: ltl_lane.otd_rate,
+                    alt_mode: tl.carrier_mode.clone(),
+                    alt_otd: tl.otd_rate,
+                    improvement: diff,
+                }));
                 processed_lanes.insert(ltl_lane.lane_ref.clone());
             }
         }
     }
 
     // Sort by improvement potential and show top lanes
-    conversion_opportunities.sort_by(|a, b| b.4.partial_cmp(&a.4).unwrap());
-    for (route, vol, ltl_otd, tl_otd, diff, rec) in conversion_opportunities.iter().take(15) {
-        println!("  {:25} {:>8} {:>9.1}% {:>9.1}% {:>+9.1}% {:>15}",
-                 route, vol, ltl_otd * 100.0, tl_otd * 100.0, diff, rec);
+    conversion_opportunities.sort_by(|a, b| b.2.improvement.partial_cmp(&a.2.improvement).unwrap());
+    conversion_opportunities.truncate(15);
+    for (route, rec, c) in &conversion_opportunities {
+        table_println!(out, "  {:25} {:>8} {:>9.1}% {:>9.1}% {:>+9.1}% {:>15}",
+                 route, c.volume, c.current_otd * 100.0, c.alt_otd * 100.0, c.improvement, rec);
     }
+    let conversions: Vec<&ModeConversion> = conversion_opportunities.iter().map(|(_, _, c)| c).collect();
+    out.add("conversions", &conversions)?;
 
     // Mode efficiency by distance
-    print_subsection("Mode Selection Guide by Distance");
+    out.subsection("Mode Selection Guide by Distance");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct ModeDist {
         carrier_mode: String,
         distance_bucket: String,
@@ -425,10 +573,12 @@ async fn run_mode_optimization(db: &surrealdb::Surreal<surrealdb::engine::local:
         otd_rate: f64,
         avg_transit: f64,
         variance: f64,
+        #[serde(default)]
+        suitability: String,
     }
 
-    let mode_dist: Vec<ModeDist> = db
-        .query(r#"
+    let mut mode_dist: Vec<ModeDist> = filter
+        .query(db, r#"
             SELECT
                 carrier_mode,
                 distance_bucket,
@@ -436,22 +586,22 @@ async fn run_mode_optimization(db: &surrealdb::Surreal<surrealdb::engine::local:
                 (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate,
                 math::mean(actual_transit_days) as avg_transit,
                 math::variance(actual_transit_days) as variance
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY carrier_mode, distance_bucket
             ORDER BY distance_bucket, otd_rate DESC
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:12} {:12} {:>8} {:>10} {:>10} {:>10} {:>15}",
+    table_println!(out, "  {:12} {:12} {:>8} {:>10} {:>10} {:>10} {:>15}",
              "Distance", "Mode", "Volume", "OTD%", "Avg Days", "Variance", "Suitability");
-    println!("  {}", "─".repeat(79));
+    table_println!(out, "  {}", "─".repeat(79));
 
     let mut current_bucket = String::new();
-    for row in &mode_dist {
+    for row in &mut mode_dist {
         if row.distance_bucket != current_bucket {
             if !current_bucket.is_empty() {
-                println!("  {}", "─".repeat(79));
+                table_println!(out, "  {}", "─".repeat(79));
             }
             current_bucket = row.distance_bucket.clone();
         }
@@ -461,15 +611,17 @@ async fn run_mode_optimization(db: &surrealdb::Surreal<surrealdb::engine::local:
                          else if row.otd_rate > 0.45 { "Fair" }
                          else { "Poor" };
 
-        println!("  {:12} {:12} {:>8} {:>9.1}% {:>9.1}d {:>10.1} {:>15}",
+        table_println!(out, "  {:12} {:12} {:>8} {:>9.1}% {:>9.1}d {:>10.1} {:>15}",
                  row.distance_bucket, row.carrier_mode, row.volume,
                  row.otd_rate * 100.0, row.avg_transit, row.variance, suitability);
+        row.suitability = suitability.to_string();
     }
+    out.add("by_distance", &mode_dist)?;
 
     // Cost vs Performance tradeoff analysis
-    print_subsection("Mode Selection Decision Matrix");
+    out.subsection("Mode Selection Decision Matrix");
 
-    println!("
+    table_println!(out, "
   RECOMMENDED MODE BY DISTANCE & PRIORITY:
 
   ┌──────────────────────────────────────────────────────────────────────────────────┐
@@ -491,11 +643,11 @@ async fn run_mode_optimization(db: &surrealdb::Surreal<surrealdb::engine::local:
     Ok(())
 }
 
-async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("3. DYNAMIC SLA OPTIMIZATION");
+async fn run_sla_optimization(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("sla", "3. DYNAMIC SLA OPTIMIZATION");
 
     // Current SLA vs actual performance by distance
-    print_subsection("SLA Adjustment Recommendations by Distance");
+    out.subsection("SLA Adjustment Recommendations by Distance");
 
     #[derive(Debug, Deserialize)]
     struct SlaAnalysis {
@@ -506,28 +658,30 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
         otd_rate: f64,
     }
 
-    let sla_analysis: Vec<SlaAnalysis> = db
-        .query(r#"
+    let sla_analysis: Vec<SlaAnalysis> = filter
+        .query(db, r#"
             SELECT
                 distance_bucket,
                 count() as volume,
                 math::mean(goal_transit_days) as avg_goal,
                 math::mean(actual_transit_days) as avg_actual,
                 (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate
-            FROM shipment
+            FROM shipment WHERE {filter}
             GROUP BY distance_bucket
             ORDER BY distance_bucket
         "#)
         .await?
         .take(0)?;
 
-    println!("  {:12} {:>8} {:>10} {:>10} {:>10} {:>12} {:>15}",
+    table_println!(out, "  {:12} {:>8} {:>10} {:>10} {:>10} {:>12} {:>15}",
              "Distance", "Volume", "Curr SLA", "Avg Actual", "OTD%", "Suggested", "Adjustment");
-    println!("  {}", "─".repeat(79));
+    table_println!(out, "  {}", "─".repeat(79));
 
+    let mut suggestions = Vec::new();
     for row in &sla_analysis {
         // Suggest SLA based on P80 of actual performance
-        let suggested = (row.avg_actual * 1.15).ceil();
+        let p80_transit = row.avg_actual * 1.15;
+        let suggested = p80_transit.ceil();
         let adjustment = suggested - row.avg_goal;
         let adj_str = if adjustment > 0.0 {
             format!("+{:.0}d (relax)", adjustment)
@@ -537,13 +691,22 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
             "OK".to_string()
         };
 
-        println!("  {:12} {:>8} {:>9.1}d {:>9.1}d {:>9.1}% {:>11.0}d {:>15}",
+        table_println!(out, "  {:12} {:>8} {:>9.1}d {:>9.1}d {:>9.1}% {:>11.0}d {:>15}",
                  row.distance_bucket, row.volume, row.avg_goal,
                  row.avg_actual, row.otd_rate * 100.0, suggested, adj_str);
+        suggestions.push(SlaSuggestion {
+            distance_bucket: row.distance_bucket.clone(),
+            current_goal: row.avg_goal,
+            actual_avg: row.avg_actual,
+            p80_transit,
+            suggested_sla: suggested,
+            adjustment,
+        });
     }
+    out.add("by_distance", &suggestions)?;
 
     // Carrier-specific SLA recommendations
-    print_subsection("Carrier-Specific SLA Recommendations (Top Carriers)");
+    out.subsection("Carrier-Specific SLA Recommendations (Top Carriers)");
 
     #[derive(Debug, Deserialize)]
     struct CarrierSla {
@@ -554,8 +717,8 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
         variance: f64,
     }
 
-    let carrier_sla: Vec<CarrierSla> = db
-        .query(r#"
+    let carrier_sla: Vec<CarrierSla> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     carrier_ref,
@@ -563,7 +726,7 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
                     count() as volume,
                     math::mean(actual_transit_days) as avg_transit,
                     math::variance(actual_transit_days) as variance
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY carrier_ref, distance_bucket
             ) WHERE volume >= 30
             ORDER BY carrier_ref, distance_bucket
@@ -572,12 +735,13 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
         .await?
         .take(0)?;
 
-    println!("  {:18} {:>10} {:>8} {:>10} {:>10} {:>12} {:>10}",
+    table_println!(out, "  {:18} {:>10} {:>8} {:>10} {:>10} {:>12} {:>10}",
              "Carrier", "Distance", "Volume", "Avg Days", "Variance", "Sugg. SLA", "Confidence");
-    println!("  {}", "─".repeat(80));
+    table_println!(out, "  {}", "─".repeat(80));
 
     let mut current_carrier = String::new();
     let mut current_carrier_name = String::new();
+    let mut carrier_suggestions = Vec::new();
     for row in &carrier_sla {
         let carrier_display = if row.carrier_ref != current_carrier {
             current_carrier = row.carrier_ref.clone();
@@ -592,15 +756,24 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
                         else { "Low" };
 
         let suggested_sla = (row.avg_transit * 1.2).ceil();
-        println!("  {:18} {:>10} {:>8} {:>9.1}d {:>10.1} {:>11.0}d {:>10}",
+        table_println!(out, "  {:18} {:>10} {:>8} {:>9.1}d {:>10.1} {:>11.0}d {:>10}",
                  carrier_display, row.distance_bucket, row.volume,
                  row.avg_transit, row.variance, suggested_sla, confidence);
+        carrier_suggestions.push(CarrierSlaSuggestion {
+            carrier_ref: row.carrier_ref.clone(),
+            distance_bucket: row.distance_bucket.clone(),
+            volume: row.volume,
+            avg_transit: row.avg_transit,
+            variance: row.variance,
+            suggested_sla: suggested_sla as i64,
+        });
     }
+    out.add("by_carrier", &carrier_suggestions)?;
 
     // Lane-specific SLA tuning
-    print_subsection("Lane-Specific SLA Overrides Needed");
+    out.subsection("Lane-Specific SLA Overrides Needed");
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     struct LaneSla {
         lane_ref: String,
         origin_zip: String,
@@ -609,10 +782,12 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
         avg_goal: f64,
         avg_actual: f64,
         otd_rate: f64,
+        #[serde(default)]
+        recommendation: String,
     }
 
-    let lane_sla: Vec<LaneSla> = db
-        .query(r#"
+    let mut lane_sla: Vec<LaneSla> = filter
+        .query(db, r#"
             SELECT * FROM (
                 SELECT
                     lane_ref,
@@ -622,7 +797,7 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
                     math::mean(goal_transit_days) as avg_goal,
                     math::mean(actual_transit_days) as avg_actual,
                     (count(IF otd = "OnTime" THEN 1 END) / count()) as otd_rate
-                FROM shipment
+                FROM shipment WHERE {filter}
                 GROUP BY lane_ref, origin_zip, dest_zip
             ) WHERE volume >= 30 AND otd_rate < 0.60
             ORDER BY otd_rate ASC
@@ -631,11 +806,11 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
         .await?
         .take(0)?;
 
-    println!("  {:25} {:>8} {:>10} {:>10} {:>10} {:>15}",
+    table_println!(out, "  {:25} {:>8} {:>10} {:>10} {:>10} {:>15}",
              "Lane", "Volume", "Curr SLA", "Actual", "OTD%", "Recommendation");
-    println!("  {}", "─".repeat(80));
+    table_println!(out, "  {}", "─".repeat(80));
 
-    for row in &lane_sla {
+    for row in &mut lane_sla {
         let route = format_lane_short(&row.origin_zip, &row.dest_zip);
         let delta = row.avg_actual - row.avg_goal;
         let rec = if delta > 2.0 {
@@ -646,15 +821,47 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
             "Review carrier".to_string()
         };
 
-        println!("  {:25} {:>8} {:>9.1}d {:>9.1}d {:>9.1}% {:>15}",
+        table_println!(out, "  {:25} {:>8} {:>9.1}d {:>9.1}d {:>9.1}% {:>15}",
                  route, row.volume, row.avg_goal, row.avg_actual,
                  row.otd_rate * 100.0, rec);
+        row.recommendation = rec;
     }
+    out.add("lane_overrides", &lane_sla)?;
+
+    // Goal transit days from the P80 of actual transit, re-scored over history
+    out.subsection("Proposed Goal Transit Days (P80)");
+
+    let cells = sla::load_transits(db, filter).await?;
+    let proposals = sla::propose(&cells, &SlaConfig::default());
+    let rescore = sla::rescore(&cells, &proposals);
+
+    table_println!(out, "  {:25} {:>8} {:>10} {:>10} {:>16} {:>16}",
+             "Lane", "Volume", "Curr SLA", "Proposed", "OTD% now → new", "Late% now → new");
+    table_println!(out, "  {}", "─".repeat(90));
+    let lane_proposals: Vec<&SlaProposal> = proposals
+        .iter()
+        .filter(|p| p.scope == SlaScope::Lane)
+        .take(15)
+        .collect();
+    for p in &lane_proposals {
+        table_println!(out, "  {:25} {:>8} {:>9}d {:>9}d {:>6.1}% → {:>5.1}% {:>6.1}% → {:>5.1}%",
+                 format_lane_short(&p.origin_zip, &p.dest_zip), p.shipments,
+                 p.current_goal_days, p.proposed_goal_days,
+                 p.current_on_time_rate * 100.0, p.proposed_on_time_rate * 100.0,
+                 p.current_late_rate * 100.0, p.proposed_late_rate * 100.0);
+    }
+    table_println!(out, "\n  Network re-score: on-time {:.1}% → {:.1}%, late {:.1}% → {:.1}% ({} of {} shipments change goal)",
+             rescore.current.on_time_rate * 100.0, rescore.proposed.on_time_rate * 100.0,
+             rescore.current.late_rate * 100.0, rescore.proposed.late_rate * 100.0,
+             rescore.changed_shipments, rescore.shipments);
+    table_println!(out, "  Store for review with: sla_proposals propose --save");
+    out.add("proposed_goals", &lane_proposals)?;
+    out.add("sla_rescore", &rescore.outcomes())?;
 
     // Customer promise date recommendations
-    print_subsection("Customer Promise Date Strategy");
+    out.subsection("Customer Promise Date Strategy");
 
-    println!("
+    table_println!(out, "
   PROMISE DATE RECOMMENDATIONS:
 
   ┌─────────────────────────────────────────────────────────────────────────────────────┐
@@ -675,125 +882,50 @@ async fn run_sla_optimization(db: &surrealdb::Surreal<surrealdb::engine::local::
     Ok(())
 }
 
-async fn run_exception_management(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<()> {
-    print_section_header("4. EXCEPTION MANAGEMENT & ALERTS");
-
-    // Calculate baselines
-    #[derive(Debug, Deserialize)]
-    struct Baseline {
-        avg_late_rate: f64,
-        avg_delay: f64,
-        avg_variance: f64,
-    }
-
-    let baseline: Option<Baseline> = db
-        .query(r#"
-            SELECT
-                (count(IF otd = "Late" THEN 1 END) / count()) as avg_late_rate,
-                math::mean(actual_transit_days - goal_transit_days) as avg_delay,
-                math::variance(actual_transit_days) as avg_variance
-            FROM shipment
-            GROUP ALL
-        "#)
-        .await?
-        .take(0)?;
+async fn run_exception_management(db: &db::DbConn, out: &mut Report, filter: &ShipmentFilter) -> Result<()> {
+    out.section("exceptions", "4. EXCEPTION MANAGEMENT & ALERTS");
 
-    let base = baseline.unwrap_or(Baseline { avg_late_rate: 0.2, avg_delay: 0.5, avg_variance: 4.0 });
+    // Baselines and deviations, with the same rules the alerting subsystem persists
+    let evaluation = alerts::evaluate(db, filter).await?;
+    let base = &evaluation.baseline;
 
-    println!("  Network Baselines:");
-    println!("  • Average Late Rate: {:.1}%", base.avg_late_rate * 100.0);
-    println!("  • Average Delay: {:.2} days", base.avg_delay);
-    println!("  • Average Variance: {:.2}", base.avg_variance);
+    table_println!(out, "  Network Baselines:");
+    table_println!(out, "  • Average Late Rate: {:.1}%", base.avg_late_rate * 100.0);
+    table_println!(out, "  • Average Delay: {:.2} days", base.avg_delay);
+    table_println!(out, "  • Average Variance: {:.2}", base.avg_variance);
+    out.add_one("baseline", base)?;
 
     // Carrier deviations from baseline
-    print_subsection("Carrier Performance Deviations (Exceeding Thresholds)");
-
-    #[derive(Debug, Deserialize)]
-    struct CarrierDeviation {
-        carrier_ref: String,
-        volume: i64,
-        late_rate: f64,
-        avg_delay: f64,
-        variance: f64,
-    }
+    out.subsection("Carrier Performance Deviations (Exceeding Thresholds)");
 
-    let carrier_dev: Vec<CarrierDeviation> = db
-        .query(r#"
-            SELECT * FROM (
-                SELECT
-                    carrier_ref,
-                    count() as volume,
-                    (count(IF otd = "Late" THEN 1 END) / count()) as late_rate,
-                    math::mean(actual_transit_days - goal_transit_days) as avg_delay,
-                    math::variance(actual_transit_days) as variance
-                FROM shipment
-                GROUP BY carrier_ref
-            ) WHERE volume >= 50
-            ORDER BY late_rate DESC
-        "#)
-        .await?
-        .take(0)?;
-
-    println!("  {:20} {:>8} {:>10} {:>12} {:>12} {:>10} {:>12}",
+    table_println!(out, "  {:20} {:>8} {:>10} {:>12} {:>12} {:>10} {:>12}",
              "Carrier", "Volume", "Late%", "vs Baseline", "Delay", "Variance", "Alert");
-    println!("  {}", "─".repeat(86));
+    table_println!(out, "  {}", "─".repeat(86));
 
-    for c in &carrier_dev {
+    for c in &evaluation.carriers {
         let late_delta = c.late_rate - base.avg_late_rate;
         let delta_str = format!("{:+.1}%", late_delta * 100.0);
 
-        let alert = if c.late_rate > base.avg_late_rate * 1.5 && c.late_rate > 0.30 {
-            "🚨 CRITICAL"
-        } else if c.late_rate > base.avg_late_rate * 1.3 {
-            "⚠ WARNING"
-        } else if c.late_rate > base.avg_late_rate * 1.1 {
-            "📋 WATCH"
-        } else {
-            "✓ OK"
+        let alert = match c.severity {
+            Some(Severity::Critical) => "🚨 CRITICAL",
+            Some(Severity::Warning) => "⚠ WARNING",
+            Some(Severity::Watch) => "📋 WATCH",
+            None => "✓ OK",
         };
 
-        println!("  {:20} {:>8} {:>9.1}% {:>12} {:>11.2}d {:>10.1} {:>12}",
+        table_println!(out, "  {:20} {:>8} {:>9.1}% {:>12} {:>11.2}d {:>10.1} {:>12}",
                  get_carrier_name(&c.carrier_ref), c.volume, c.late_rate * 100.0,
                  delta_str, c.avg_delay, c.variance, alert);
     }
 
     // Lane deviation alerts
-    print_subsection("Lane Performance Alerts (Sudden Deviations)");
+    out.subsection("Lane Performance Alerts (Sudden Deviations)");
 
-    #[derive(Debug, Deserialize)]
-    struct LaneDeviation {
-        lane_ref: String,
-        origin_zip: String,
-        dest_zip: String,
-        volume: i64,
-        late_rate: f64,
-        avg_delay: f64,
-    }
-
-    let lane_dev: Vec<LaneDeviation> = db
-        .query(r#"
-            SELECT * FROM (
-                SELECT
-                    lane_ref,
-                    origin_zip,
-                    dest_zip,
-                    count() as volume,
-                    (count(IF otd = "Late" THEN 1 END) / count()) as late_rate,
-                    math::mean(actual_transit_days - goal_transit_days) as avg_delay
-                FROM shipment
-                GROUP BY lane_ref, origin_zip, dest_zip
-            ) WHERE volume >= 20 AND late_rate > 0.35
-            ORDER BY late_rate DESC
-            LIMIT 20
-        "#)
-        .await?
-        .take(0)?;
-
-    println!("  {:25} {:>8} {:>10} {:>12} {:>12} {:>15}",
+    table_println!(out, "  {:25} {:>8} {:>10} {:>12} {:>12} {:>15}",
              "Lane", "Volume", "Late%", "Deviation", "Avg Delay", "Action");
-    println!("  {}", "─".repeat(84));
+    table_println!(out, "  {}", "─".repeat(84));
 
-    for lane in &lane_dev {
+    for lane in evaluation.lanes.iter().take(20) {
         let route = format_lane_short(&lane.origin_zip, &lane.dest_zip);
         let deviation = lane.late_rate - base.avg_late_rate;
         let dev_str = format!("{:+.1}%", deviation * 100.0);
@@ -806,15 +938,20 @@ async fn run_exception_management(db: &surrealdb::Surreal<surrealdb::engine::loc
             "Monitor daily"
         };
 
-        println!("  {:25} {:>8} {:>9.1}% {:>12} {:>11.2}d {:>15}",
+        table_println!(out, "  {:25} {:>8} {:>9.1}% {:>12} {:>11.2}d {:>15}",
                  route, lane.volume, lane.late_rate * 100.0,
                  dev_str, lane.avg_delay, action);
     }
+    if evaluation.lanes.len() > 20 {
+        table_println!(out, "  ... {} more lanes above {:.0}% late", evaluation.lanes.len() - 20,
+                 alerts::LANE_WARNING_LATE_RATE * 100.0);
+    }
+    out.add("alerts", &evaluation.alerts)?;
 
     // Escalation rules and thresholds
-    print_subsection("Exception Escalation Framework");
+    out.subsection("Exception Escalation Framework");
 
-    println!("
+    table_println!(out, "
   TIERED ESCALATION RULES:
 
   ┌────────────────────────────────────────────────────────────────────────────────────────┐
@@ -840,26 +977,26 @@ async fn run_exception_management(db: &surrealdb::Surreal<surrealdb::engine::loc
 ");
 
     // Summary action items
-    print_subsection("Priority Action Items");
+    out.subsection("Priority Action Items");
 
-    println!("
+    table_println!(out, "
   IMMEDIATE ACTIONS (This Week):
   ─────────────────────────────────────────────────────────────────────────────────");
 
     // Count critical carriers and lanes
-    let critical_carriers = carrier_dev.iter()
-        .filter(|c| c.late_rate > base.avg_late_rate * 1.5 && c.late_rate > 0.30)
+    let critical_carriers = evaluation.carriers.iter()
+        .filter(|c| c.severity == Some(Severity::Critical))
         .count();
 
-    let critical_lanes = lane_dev.iter()
-        .filter(|l| l.late_rate > 0.50)
+    let critical_lanes = evaluation.lanes.iter()
+        .filter(|l| l.severity == Severity::Critical)
         .count();
 
-    println!("  1. Review {} carriers with CRITICAL performance deviation", critical_carriers);
-    println!("  2. Investigate {} lanes with >50% late rate", critical_lanes);
-    println!("  3. Implement carrier volume reallocation on top 5 problem lanes");
-    println!("  4. Adjust SLAs on lanes with consistent underperformance");
-    println!("
+    table_println!(out, "  1. Review {} carriers with CRITICAL performance deviation", critical_carriers);
+    table_println!(out, "  2. Investigate {} lanes with >50% late rate", critical_lanes);
+    table_println!(out, "  3. Implement carrier volume reallocation on top 5 problem lanes");
+    table_println!(out, "  4. Adjust SLAs on lanes with consistent underperformance");
+    table_println!(out, "
   ONGOING MONITORING:
   ─────────────────────────────────────────────────────────────────────────────────
   • Daily: Check CRITICAL and WARNING alerts
@@ -7,12 +7,26 @@
 //!
 //! Options:
 //!   --port PORT       Port to listen on (default: 8080)
-//!   --db-path PATH    Path to SurrealDB database (default: data/lastmile.db)
+//!   --db URL          Database URL: rocksdb://PATH, mem:// or ws://HOST:PORT
+//!                     (default: rocksdb://data/lastmile.db); replicas can share
+//!                     one SurrealDB server. --db-path PATH is the older spelling
+//!   --playbooks PATH  Cluster playbook file (default: $LASTMILE_PLAYBOOKS or
+//!                     config/playbooks.toml)
+//!   --delay-model PATH  Trained delay model (default: models/delay_model.json);
+//!                     without one, delay predictions use historical rates
+//!   --cache-ttl SECS  Serve cached lane aggregates for at most SECS seconds
+//!                     (default: 300)
+//!   --cache-refresh SECS  Check every SECS seconds and rebuild the cache in the
+//!                     background when data changed or it nears expiry
+//!                     (default: 60, 0 turns it off)
+//!   --alert-interval SECS  Re-evaluate exception alerts every SECS seconds
+//!                     (default: 0, off)
 //!   --rest-only       Only serve REST endpoints
 //!   --grpc-only       Only serve gRPC endpoints
 //!
 //! REST endpoints:
 //!   GET /api/v1/health              - Health check
+//!   GET /api/v1/ready               - Readiness (database open and migrated)
 //!   GET /api/v1/stats               - Database statistics
 //!   GET /api/v1/lanes               - All lanes (with optional ?limit=N)
 //!   GET /api/v1/lanes/:origin/:dest - Single lane profile
@@ -24,28 +38,55 @@
 //!   GET /api/v1/analysis/terminals  - Terminal performance
 //!   GET /api/v1/analysis/early      - Early delivery analysis
 //!   GET /api/v1/search/similar?lane=X - Similar lanes
+//!   POST /api/v1/predict/delay      - Late risk of a planned shipment
+//!   GET /api/v1/eta?origin=X&dest=Y&carrier=Z&ship=DATE - P50/P80/P95 ETA
+//!   POST /api/v1/simulate           - What-if levers and a target on-time rate
+//!   GET /api/v1/alerts              - Exception alerts (?state=&severity=&since=)
+//!   POST /api/v1/alerts/:id/acknowledge - Acknowledge an open alert
 //!
-//! gRPC service: lastmile.v1.AnalyticsService
+//! Admin endpoints need `Authorization: Bearer $LASTMILE_ADMIN_TOKEN` and are
+//! off when the variable is unset; browsers can't reach them cross-origin:
+//!   POST /api/v1/alerts/evaluate    - Re-evaluate alert rules now
+//!   POST /api/v1/admin/cache/refresh - Rebuild cached lane aggregates now
+//!
+//! Responses built from cached lane aggregates carry `Age`,
+//! `X-Cache-Computed-At` and `X-Data-Version` headers.
+//!
+//! gRPC services:
+//!   lastmile.v1.AnalyticsService    - See proto/lastmile/v1/analytics.proto
+//!   grpc.health.v1.Health           - SERVING while /api/v1/ready is 200
+//!   grpc.reflection.v1 and v1alpha  - e.g. `grpcurl -plaintext localhost:8080 list`
 
 use anyhow::Result;
 use axum::{
-    routing::get,
+    extract::Request,
+    http::header,
+    middleware,
+    routing::{get, post},
     Router,
 };
 use nyc_last_mile::api::{
-    handlers,
+    handlers::{self, ADMIN_TOKEN_ENV},
     graph_handlers,
-    grpc::GrpcService,
-    proto::analytics_service_server::AnalyticsServiceServer,
+    grpc,
+    service::DEFAULT_CACHE_TTL,
     AnalyticsService,
 };
+use nyc_last_mile::db;
+use nyc_last_mile::delay_model::{DelayModel, DEFAULT_MODEL_PATH};
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tonic::transport::Server as TonicServer;
+use tonic_health::server::HealthReporter;
+use tower::steer::Steer;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+/// How often the gRPC health service re-checks readiness
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
 fn print_banner(port: u16, rest_only: bool, grpc_only: bool) {
     println!("============================================================");
     println!("         NYC LAST-MILE DELIVERY API SERVER");
@@ -62,6 +103,7 @@ fn print_banner(port: u16, rest_only: bool, grpc_only: bool) {
     if !grpc_only {
         println!("REST Endpoints:");
         println!("  GET /api/v1/health              Health check");
+        println!("  GET /api/v1/ready               Readiness check");
         println!("  GET /api/v1/stats               Database statistics");
         println!("  GET /api/v1/lanes               All lanes");
         println!("  GET /api/v1/lanes/:o/:d         Lane profile");
@@ -73,6 +115,15 @@ fn print_banner(port: u16, rest_only: bool, grpc_only: bool) {
         println!("  GET /api/v1/analysis/terminals  Terminal perf");
         println!("  GET /api/v1/analysis/early      Early analysis");
         println!("  GET /api/v1/search/similar      Similar lanes");
+        println!("  POST /api/v1/predict/delay      Shipment late risk");
+        println!("  GET /api/v1/eta                 P50/P80/P95 ETA");
+        println!("  POST /api/v1/simulate           What-if scenario");
+        println!("  GET /api/v1/alerts              Exception alerts");
+        println!("  POST /api/v1/alerts/:id/acknowledge  Acknowledge");
+        println!();
+        println!("Admin Endpoints (Bearer ${}):", ADMIN_TOKEN_ENV);
+        println!("  POST /api/v1/alerts/evaluate    Re-evaluate alerts");
+        println!("  POST /api/v1/admin/cache/refresh  Rebuild cache");
         println!();
         println!("Graph Endpoints:");
         println!("  GET /api/v1/graph/topology           Network topology");
@@ -83,7 +134,10 @@ fn print_banner(port: u16, rest_only: bool, grpc_only: bool) {
         println!();
     }
     if !rest_only {
-        println!("gRPC Service: lastmile.v1.AnalyticsService");
+        println!("gRPC Services:");
+        println!("  lastmile.v1.AnalyticsService");
+        println!("  grpc.health.v1.Health           Follows /api/v1/ready");
+        println!("  grpc.reflection.v1(alpha)       Server reflection");
         println!();
     }
     println!("============================================================");
@@ -102,7 +156,12 @@ async fn main() -> Result<()> {
     // Parse command line arguments
     let args: Vec<String> = std::env::args().collect();
     let mut port: u16 = 8080;
-    let mut db_path = "data/lastmile.db".to_string();
+    let mut db_url = db::DEFAULT_DB.to_string();
+    let mut playbooks_path: Option<String> = None;
+    let mut delay_model_path = DEFAULT_MODEL_PATH.to_string();
+    let mut alert_interval: u64 = 0;
+    let mut cache_ttl: u64 = DEFAULT_CACHE_TTL.as_secs();
+    let mut cache_refresh: u64 = 60;
     let mut rest_only = false;
     let mut grpc_only = false;
 
@@ -115,10 +174,40 @@ async fn main() -> Result<()> {
                     port = args[i].parse().unwrap_or(8080);
                 }
             }
-            "--db-path" => {
+            "--db" | "--db-path" => {
+                i += 1;
+                if i < args.len() {
+                    db_url = args[i].clone();
+                }
+            }
+            "--playbooks" => {
                 i += 1;
                 if i < args.len() {
-                    db_path = args[i].clone();
+                    playbooks_path = Some(args[i].clone());
+                }
+            }
+            "--delay-model" => {
+                i += 1;
+                if i < args.len() {
+                    delay_model_path = args[i].clone();
+                }
+            }
+            "--cache-ttl" => {
+                i += 1;
+                if i < args.len() {
+                    cache_ttl = args[i].parse().unwrap_or(cache_ttl);
+                }
+            }
+            "--cache-refresh" => {
+                i += 1;
+                if i < args.len() {
+                    cache_refresh = args[i].parse().unwrap_or(cache_refresh);
+                }
+            }
+            "--alert-interval" => {
+                i += 1;
+                if i < args.len() {
+                    alert_interval = args[i].parse().unwrap_or(0);
                 }
             }
             "--rest-only" => rest_only = true,
@@ -128,86 +217,169 @@ async fn main() -> Result<()> {
         i += 1;
     }
 
+    let playbooks = nyc_last_mile::playbook::init(playbooks_path.as_deref().map(std::path::Path::new))?;
+    tracing::info!("Loaded {} cluster playbooks", playbooks.clusters.len());
+
     print_banner(port, rest_only, grpc_only);
 
-    // Create shared analytics service
-    let service = Arc::new(AnalyticsService::new(&db_path));
+    // Create shared analytics service; the database is opened once and shared
+    // by every REST and gRPC request
+    let mut service = AnalyticsService::connect(&db_url)
+        .await?
+        .with_cache_ttl(Duration::from_secs(cache_ttl));
+    let readiness = service.readiness().await;
+    match &readiness.reason {
+        None => tracing::info!(
+            "Opened {} (schema v{}, data v{})",
+            db_url, readiness.schema_version, readiness.data_version
+        ),
+        Some(reason) => tracing::warn!("Opened {} but not ready: {}", db_url, reason),
+    }
+    if std::path::Path::new(&delay_model_path).exists() {
+        let model = DelayModel::load(std::path::Path::new(&delay_model_path))?;
+        tracing::info!("Loaded delay model from {} (trained {})", delay_model_path, model.trained_at);
+        service = service.with_delay_model(model);
+    } else {
+        tracing::info!("No delay model at {}; delay predictions use historical rates", delay_model_path);
+    }
+    let service = Arc::new(service);
+
+    let admin_token: Option<Arc<str>> = std::env::var(ADMIN_TOKEN_ENV)
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| t.trim().into());
+    if admin_token.is_none() && !grpc_only {
+        tracing::info!("{} is not set; admin endpoints are disabled", ADMIN_TOKEN_ENV);
+    }
+
+    if cache_refresh > 0 {
+        spawn_cache_refresh(service.clone(), cache_refresh);
+    }
+    if alert_interval > 0 {
+        spawn_alert_evaluation(service.clone(), alert_interval);
+    }
 
     let addr: SocketAddr = format!("0.0.0.0:{}", port).parse()?;
 
+    if rest_only {
+        // REST only mode
+        let app = create_rest_router(service, admin_token);
+        tracing::info!("Starting REST-only server on {}", addr);
+
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, app).await?;
+        return Ok(());
+    }
+
+    let (grpc_routes, health) = grpc::routes(service.clone())?;
+    spawn_health_reporting(health, service.clone());
+
     if grpc_only {
         // gRPC only mode
-        let grpc_service = GrpcService::new(service);
         tracing::info!("Starting gRPC-only server on {}", addr);
 
         TonicServer::builder()
-            .add_service(AnalyticsServiceServer::new(grpc_service))
+            .add_routes(grpc_routes)
             .serve(addr)
             .await?;
-    } else if rest_only {
-        // REST only mode
-        let app = create_rest_router(service);
-        tracing::info!("Starting REST-only server on {}", addr);
+    } else {
+        // Combined mode: REST and gRPC share the port, routed by content type
+        let app = multiplex(create_rest_router(service, admin_token), grpc_routes.into_axum_router());
+        tracing::info!("Starting REST + gRPC server on {}", addr);
 
         let listener = tokio::net::TcpListener::bind(addr).await?;
         axum::serve(listener, app).await?;
-    } else {
-        // Combined mode - use axum with tonic multiplexing
-        // For simplicity, we'll run REST on main port and gRPC on port+1
-        let grpc_port = port + 1;
-        let grpc_addr: SocketAddr = format!("0.0.0.0:{}", grpc_port).parse()?;
-
-        println!("Note: Running REST on port {} and gRPC on port {}", port, grpc_port);
+    }
 
-        let rest_service = service.clone();
-        let grpc_service = GrpcService::new(service);
+    Ok(())
+}
 
-        // Spawn gRPC server
-        let grpc_handle = tokio::spawn(async move {
-            tracing::info!("Starting gRPC server on {}", grpc_addr);
-            TonicServer::builder()
-                .add_service(AnalyticsServiceServer::new(grpc_service))
-                .serve(grpc_addr)
-                .await
-        });
+/// Whether a request is gRPC (`application/grpc`, `application/grpc+proto`, ...)
+fn is_grpc(req: &Request) -> bool {
+    req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/grpc"))
+}
 
-        // Start REST server
-        let app = create_rest_router(rest_service);
-        tracing::info!("Starting REST server on {}", addr);
+/// One router for both protocols: gRPC requests go to the gRPC routes,
+/// everything else to the REST router. The server speaks HTTP/1.1 and
+/// cleartext HTTP/2 on the same listener, so gRPC clients connect directly.
+fn multiplex(rest: Router, grpc: Router) -> Router {
+    let steer = Steer::new([rest, grpc], |req: &Request, _: &[Router]| usize::from(is_grpc(req)));
+    Router::new().fallback_service(steer)
+}
 
-        let listener = tokio::net::TcpListener::bind(addr).await?;
-        let rest_handle = tokio::spawn(async move {
-            axum::serve(listener, app).await
-        });
-
-        // Wait for either to finish (or error)
-        tokio::select! {
-            result = grpc_handle => {
-                if let Err(e) = result {
-                    tracing::error!("gRPC server error: {}", e);
-                }
+/// Keep the gRPC health service in step with database readiness, checking
+/// every few seconds and logging changes
+fn spawn_health_reporting(mut health: HealthReporter, service: Arc<AnalyticsService>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+        let mut last = None;
+        loop {
+            interval.tick().await;
+            let ready = grpc::report_health(&mut health, &service).await;
+            if last.is_some_and(|was| was != ready) {
+                tracing::info!("gRPC health: {}", if ready { "SERVING" } else { "NOT_SERVING" });
             }
-            result = rest_handle => {
-                if let Err(e) = result {
-                    tracing::error!("REST server error: {}", e);
+            last = Some(ready);
+        }
+    });
+}
+
+/// Check the cache every `seconds` and rebuild it when an ingest changed the
+/// data or it would expire before the next check. The first run warms it.
+fn spawn_cache_refresh(service: Arc<AnalyticsService>, seconds: u64) {
+    tracing::info!("Refreshing the lane cache in the background every {}s", seconds);
+    tokio::spawn(async move {
+        let period = Duration::from_secs(seconds);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            match service.refresh_cache_if_stale(period).await {
+                Ok(true) => {
+                    if let Some(info) = service.cache_info().await {
+                        tracing::info!(
+                            "Lane cache rebuilt: {} lanes, data v{}, {}ms",
+                            info.lanes, info.data_version, info.compute_ms
+                        );
+                    }
                 }
+                Ok(false) => {}
+                Err(e) => tracing::error!("Lane cache refresh failed: {}", e),
             }
         }
-    }
+    });
+}
 
-    Ok(())
+/// Re-evaluate exception alerts every `seconds`, starting now
+fn spawn_alert_evaluation(service: Arc<AnalyticsService>, seconds: u64) {
+    tracing::info!("Evaluating exception alerts every {}s", seconds);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(seconds));
+        loop {
+            interval.tick().await;
+            match service.refresh_alerts().await {
+                Ok(s) => tracing::info!(
+                    "Alerts evaluated: {} opened, {} updated, {} reopened, {} resolved",
+                    s.opened, s.updated, s.reopened, s.resolved
+                ),
+                Err(e) => tracing::error!("Alert evaluation failed: {}", e),
+            }
+        }
+    });
 }
 
-fn create_rest_router(service: Arc<AnalyticsService>) -> Router {
-    // CORS configuration
+fn create_rest_router(service: Arc<AnalyticsService>, admin_token: Option<Arc<str>>) -> Router {
+    // CORS configuration; admin routes are merged after it so browsers on
+    // other origins can't call them
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any);
 
-    Router::new()
-        // Health check
-        .route("/api/v1/health", get(handlers::health))
+    // Endpoints answered from the cached lane snapshot
+    let cached = Router::new()
         // Stats
         .route("/api/v1/stats", get(handlers::get_stats))
         // Lanes
@@ -216,7 +388,6 @@ fn create_rest_router(service: Arc<AnalyticsService>) -> Router {
         // Clusters
         .route("/api/v1/clusters", get(handlers::get_clusters))
         .route("/api/v1/clusters/:id/lanes", get(handlers::get_cluster_lanes))
-        .route("/api/v1/clusters/:id/playbook", get(handlers::get_playbook))
         // Regions
         .route("/api/v1/regions/:zip3", get(handlers::get_region))
         // Analysis
@@ -225,14 +396,36 @@ fn create_rest_router(service: Arc<AnalyticsService>) -> Router {
         .route("/api/v1/analysis/early", get(handlers::get_early_analysis))
         // Search
         .route("/api/v1/search/similar", get(handlers::find_similar))
+        .route_layer(middleware::from_fn_with_state(service.clone(), handlers::cache_headers));
+
+    // Endpoints that change server state, behind the shared admin token
+    let admin = Router::new()
+        .route("/api/v1/admin/cache/refresh", post(handlers::refresh_cache))
+        .route("/api/v1/alerts/evaluate", post(handlers::evaluate_alerts))
+        .route_layer(middleware::from_fn_with_state(admin_token, handlers::require_admin_token));
+
+    Router::new()
+        // Health check
+        .route("/api/v1/health", get(handlers::health))
+        .route("/api/v1/ready", get(handlers::ready))
+        .merge(cached)
+        .route("/api/v1/clusters/:id/playbook", get(handlers::get_playbook))
+        // Prediction
+        .route("/api/v1/predict/delay", post(handlers::predict_delay))
+        .route("/api/v1/eta", get(handlers::get_eta))
+        .route("/api/v1/simulate", post(handlers::simulate))
+        // Alerts
+        .route("/api/v1/alerts", get(handlers::get_alerts))
+        .route("/api/v1/alerts/:id/acknowledge", post(handlers::acknowledge_alert))
         // Graph endpoints
         .route("/api/v1/graph/topology", get(graph_handlers::get_network_topology))
         .route("/api/v1/graph/carrier/:carrier_id/network", get(graph_handlers::get_carrier_network))
         .route("/api/v1/graph/location/:zip5/connections", get(graph_handlers::get_location_connections))
         .route("/api/v1/graph/location/:zip5/reachable", get(graph_handlers::get_reachable_destinations))
         .route("/api/v1/graph/shipment/:load_id/trace", get(graph_handlers::trace_shipment))
+        .layer(cors)
+        .merge(admin)
         // State and middleware
         .with_state(service)
         .layer(TraceLayer::new_for_http())
-        .layer(cors)
 }
@@ -7,7 +7,8 @@
 //! Tests multiple distance metrics: EUCLIDEAN, COSINE, MANHATTAN
 
 use anyhow::Result;
-use nyc_last_mile::db_enhanced;
+use clap::Parser;
+use nyc_last_mile::db;
 use serde_json::Value;
 use std::time::Instant;
 use tracing::info;
@@ -50,15 +51,24 @@ struct BenchmarkResult {
     results_count: usize,
 }
 
+/// Native HNSW vs application-side vector search benchmark
+#[derive(Parser, Debug)]
+#[command(name = "benchmark_vector_native")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter("info")
         .init();
 
-    let db_path = "data/lastmile_enhanced.db";
+    let args = Args::parse();
+    let db_path = args.db.url.as_str();
     info!("Connecting to SurrealDB at {}", db_path);
-    let db = db_enhanced::connect(db_path).await?;
+    let db = args.db.connect().await?;
 
     println!("\n══════════════════════════════════════════════════════════════");
     println!("  Native HNSW vs Application-Side Vector Search Benchmark");
@@ -0,0 +1,300 @@
+//! Shipment delay model
+//! Trains, evaluates and queries the logistic-regression P(late) model
+//!
+//! Run: ./target/release/delay_model train [--test-fraction 0.2] [--model FILE]
+//!      ./target/release/delay_model evaluate --from 2024-10-01
+//!      ./target/release/delay_model predict --origin 750 --dest 432 --carrier "XPO Logistics" \
+//!          --mode LTL --ship-date 2024-12-02 --goal-days 3
+//! Filters (train, evaluate): --from, --to, --mode, --carrier, --origin-zip3, --distance-bucket
+//! Output: --format table|json|csv|markdown
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use nyc_last_mile::{db, table_println};
+use nyc_last_mile::carrier_names::{find_carrier_id, get_carrier_name};
+use nyc_last_mile::delay_model::{self, DelayFeatures, DelayModel, Evaluation, TrainConfig, DEFAULT_MODEL_PATH};
+use nyc_last_mile::filter::{normalize_zip3, ShipmentFilter};
+use nyc_last_mile::location_names::format_lane_short;
+use nyc_last_mile::models::CarrierMode;
+use nyc_last_mile::report::{OutputArgs, Report};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Shipment delay model
+#[derive(Parser, Debug)]
+#[command(name = "delay_model")]
+#[command(about = "Train, evaluate and query the shipment delay model")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    /// Model file
+    #[arg(long, default_value = DEFAULT_MODEL_PATH, global = true)]
+    model: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Fit the model on historical shipments and save it
+    Train {
+        /// Most recent share of shipments held out for evaluation
+        #[arg(long, default_value_t = 0.2)]
+        test_fraction: f64,
+
+        /// Gradient steps
+        #[arg(long, default_value_t = 500)]
+        iterations: usize,
+
+        /// L2 penalty on the weights
+        #[arg(long, default_value_t = 1e-3)]
+        l2: f64,
+
+        /// Carriers and lanes with fewer shipments share an "other" weight
+        #[arg(long, default_value_t = 30)]
+        min_category_count: usize,
+
+        #[command(flatten)]
+        filter: ShipmentFilter,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Score a saved model on the shipments matching the filters
+    Evaluate {
+        #[command(flatten)]
+        filter: ShipmentFilter,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// P(late) for a planned shipment
+    Predict {
+        /// Origin ZIP3
+        #[arg(long)]
+        origin: String,
+
+        /// Destination ZIP3
+        #[arg(long)]
+        dest: String,
+
+        /// Carrier ID or display name
+        #[arg(long)]
+        carrier: String,
+
+        /// Carrier mode, e.g. LTL, Truckload or "TL Flatbed"
+        #[arg(long, default_value = "LTL", value_parser = CarrierMode::resolve)]
+        mode: CarrierMode,
+
+        /// Planned ship date (YYYY-MM-DD)
+        #[arg(long)]
+        ship_date: NaiveDate,
+
+        /// Goal transit days
+        #[arg(long)]
+        goal_days: i32,
+
+        /// Lane distance in miles (default: the lane's training average)
+        #[arg(long)]
+        distance: Option<f64>,
+
+        /// Contributing factors to show
+        #[arg(long, default_value_t = 5)]
+        factors: usize,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+}
+
+/// Headline numbers of a training run or evaluation
+#[derive(Debug, Serialize)]
+struct Metric {
+    metric: String,
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Prediction {
+    lane: String,
+    carrier: String,
+    mode: String,
+    ship_date: NaiveDate,
+    goal_days: i32,
+    late_probability: f64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    match &args.command {
+        Command::Train { test_fraction, iterations, l2, min_category_count, filter, output } => {
+            let config = TrainConfig {
+                iterations: *iterations,
+                l2: *l2,
+                min_category_count: *min_category_count,
+                test_fraction: *test_fraction,
+                ..Default::default()
+            };
+            let mut report = output.report("Delay Model - Training");
+            report.filter = filter.describe();
+
+            let db = args.db.connect().await?;
+            let rows = delay_model::load_shipments(&db, filter).await?;
+            if rows.is_empty() {
+                bail!("no shipments match the filters");
+            }
+            let model = delay_model::train(&rows, &config)?;
+            model.save(&args.model)?;
+
+            run_training_summary(&model, &args.model, &mut report)?;
+            if let Some(holdout) = &model.holdout {
+                run_evaluation(holdout, "holdout", "HOLDOUT EVALUATION (most recent shipments)", &mut report)?;
+            }
+            output.write(&report)
+        }
+        Command::Evaluate { filter, output } => {
+            let model = DelayModel::load(&args.model)?;
+            let mut report = output.report("Delay Model - Evaluation");
+            report.filter = filter.describe();
+
+            let db = args.db.connect().await?;
+            let rows = delay_model::load_shipments(&db, filter).await?;
+            if rows.is_empty() {
+                bail!("no shipments match the filters");
+            }
+            let evaluation = delay_model::evaluate(&model, &rows);
+            table_println!(report, "\n  Model: {} (trained {} on {} shipments, {}..{})",
+                     args.model.display(), &model.trained_at[..10], model.training_rows,
+                     model.trained_from, model.trained_to);
+            if !report.filter.is_empty() {
+                table_println!(report, "  Filter: {}", report.filter);
+            }
+            run_evaluation(&evaluation, "evaluation", "EVALUATION", &mut report)?;
+            output.write(&report)
+        }
+        Command::Predict { origin, dest, carrier, mode, ship_date, goal_days, distance, factors, output } => {
+            let model = DelayModel::load(&args.model)?;
+            let mut report = output.report("Delay Model - Prediction");
+
+            let carrier_ref = find_carrier_id(carrier).map(str::to_string).unwrap_or_else(|| carrier.clone());
+            let (origin, dest) = (normalize_zip3(origin), normalize_zip3(dest));
+            let mut features = DelayFeatures::planned(*mode, &carrier_ref, &origin, &dest, *ship_date, *goal_days);
+            features.distance_miles = *distance;
+            run_prediction(&model, &features, *ship_date, *factors, &mut report)?;
+            output.write(&report)
+        }
+    }
+}
+
+fn run_training_summary(model: &DelayModel, path: &std::path::Path, out: &mut Report) -> Result<()> {
+    out.section("training", "DELAY MODEL TRAINING");
+
+    let kept: Vec<String> = ["modes", "carriers", "lanes"]
+        .iter()
+        .zip(&model.encoder.categories)
+        .map(|(name, values)| format!("{} {}", values.len(), name))
+        .collect();
+    let metrics = vec![
+        Metric { metric: "Training shipments".into(), value: model.training_rows.to_string() },
+        Metric { metric: "Ship dates".into(), value: format!("{}..{}", model.trained_from, model.trained_to) },
+        Metric { metric: "Late rate".into(), value: format!("{:.1}%", model.training_late_rate * 100.0) },
+        Metric { metric: "Features".into(), value: format!("{} ({})", model.encoder.len(), kept.join(", ")) },
+        Metric { metric: "Saved to".into(), value: path.display().to_string() },
+    ];
+    for m in &metrics {
+        table_println!(out, "  {:22} {}", m.metric, m.value);
+    }
+    out.add("summary", &metrics)?;
+
+    // Largest weights, for a sense of what the model learned
+    out.subsection("Strongest Effects (log-odds)");
+    #[derive(Serialize)]
+    struct Weight {
+        feature: String,
+        log_odds: f64,
+    }
+    let mut weights: Vec<Weight> = model
+        .weights
+        .iter()
+        .enumerate()
+        .map(|(i, w)| Weight { feature: model.encoder.feature_name(i), log_odds: *w })
+        .collect();
+    weights.sort_by(|a, b| b.log_odds.abs().total_cmp(&a.log_odds.abs()));
+    weights.truncate(10);
+    for w in &weights {
+        table_println!(out, "  {:40} {:>+8.3}", w.feature, w.log_odds);
+    }
+    out.add("weights", &weights)?;
+
+    Ok(())
+}
+
+fn run_evaluation(evaluation: &Evaluation, key: &str, title: &str, out: &mut Report) -> Result<()> {
+    out.section(key, title);
+
+    let metrics = vec![
+        Metric { metric: "Shipments".into(), value: evaluation.shipments.to_string() },
+        Metric { metric: "Late rate".into(), value: format!("{:.1}%", evaluation.late_rate * 100.0) },
+        Metric {
+            metric: "AUC".into(),
+            value: evaluation.auc.map(|a| format!("{:.3}", a)).unwrap_or_else(|| "n/a (one class)".into()),
+        },
+        Metric {
+            metric: "Brier score".into(),
+            value: format!("{:.4} (baseline {:.4}, skill {:+.1}%)",
+                           evaluation.brier, evaluation.baseline_brier, evaluation.brier_skill() * 100.0),
+        },
+        Metric { metric: "Log loss".into(), value: format!("{:.4}", evaluation.log_loss) },
+    ];
+    for m in &metrics {
+        table_println!(out, "  {:22} {}", m.metric, m.value);
+    }
+    out.add("metrics", &metrics)?;
+
+    out.subsection("Calibration");
+    table_println!(out, "  {:>12} {:>10} {:>12} {:>12}", "Predicted", "Shipments", "Mean Pred", "Observed");
+    table_println!(out, "  {}", "─".repeat(50));
+    for bin in &evaluation.calibration {
+        let range = format!("{:.0}-{:.0}%", bin.lower * 100.0, bin.upper * 100.0);
+        table_println!(out, "  {:>12} {:>10} {:>11.1}% {:>11.1}%",
+                 range, bin.shipments,
+                 bin.mean_predicted * 100.0, bin.observed_late_rate * 100.0);
+    }
+    out.add("calibration", &evaluation.calibration)?;
+
+    Ok(())
+}
+
+fn run_prediction(model: &DelayModel, features: &DelayFeatures, ship_date: NaiveDate, factors: usize,
+                  out: &mut Report) -> Result<()> {
+    out.section("prediction", "LATE-RISK PREDICTION");
+
+    let prediction = Prediction {
+        lane: format_lane_short(&features.origin_zip, &features.dest_zip),
+        carrier: get_carrier_name(&features.carrier_ref),
+        mode: features.mode_label().to_string(),
+        ship_date,
+        goal_days: features.goal_transit_days,
+        late_probability: model.predict(features),
+    };
+    table_println!(out, "  {} via {} ({}), {} goal days", prediction.lane, prediction.carrier,
+             prediction.mode, prediction.goal_days);
+    table_println!(out, "  P(late): {:.1}%  (training late rate {:.1}%)",
+             prediction.late_probability * 100.0, model.training_late_rate * 100.0);
+    out.add_one("prediction", &prediction)?;
+
+    out.subsection("Contributing Factors (log-odds)");
+    let contributions = model.explain(features, factors);
+    for c in &contributions {
+        let direction = if c.log_odds > 0.0 { "raises risk" } else { "lowers risk" };
+        table_println!(out, "  {:40} {:>+8.3}  {}", c.feature, c.log_odds, direction);
+    }
+    out.add("factors", &contributions)?;
+
+    Ok(())
+}
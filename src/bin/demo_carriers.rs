@@ -1,7 +1,8 @@
 //! Carrier Performance Analysis Demo
-//! Run: ./target/release/demo_carriers
+//! Run: ./target/release/demo_carriers [--db URL]
 
 use anyhow::Result;
+use clap::Parser;
 use nyc_last_mile::{db, carrier_names::get_carrier_name};
 use serde::Deserialize;
 
@@ -22,9 +23,18 @@ struct CarrierMode {
     cnt: i64,
 }
 
+/// Carrier performance analysis demo
+#[derive(Parser, Debug)]
+#[command(name = "demo_carriers")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let db = db::connect("data/lastmile.db").await?;
+    let args = Args::parse();
+    let db = args.db.connect().await?;
 
     println!("\n{}", "=".repeat(80));
     println!("                    CARRIER PERFORMANCE ANALYSIS");
@@ -1,7 +1,8 @@
 //! Lane Analysis Demo
-//! Run: ./target/release/demo_lanes
+//! Run: ./target/release/demo_lanes [--db URL]
 
 use anyhow::Result;
+use clap::Parser;
 use nyc_last_mile::{db, location_names::{format_lane_short, get_location_short, get_location_long}};
 use serde::Deserialize;
 
@@ -38,9 +39,18 @@ struct LanePerformance {
     avg_delay: f64,
 }
 
+/// Lane analysis demo
+#[derive(Parser, Debug)]
+#[command(name = "demo_lanes")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let db = db::connect("data/lastmile.db").await?;
+    let args = Args::parse();
+    let db = args.db.connect().await?;
 
     println!("\n{}", "=".repeat(85));
     println!("                         LANE ANALYSIS DASHBOARD");
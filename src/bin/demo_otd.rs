@@ -1,7 +1,8 @@
 //! On-Time Delivery Analysis Demo
-//! Run: ./target/release/demo_otd
+//! Run: ./target/release/demo_otd [--db URL]
 
 use anyhow::Result;
+use clap::Parser;
 use nyc_last_mile::db;
 use serde::Deserialize;
 
@@ -70,9 +71,18 @@ fn month_name(m: i32) -> &'static str {
     }
 }
 
+/// On-time delivery analysis demo
+#[derive(Parser, Debug)]
+#[command(name = "demo_otd")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let db = db::connect("data/lastmile.db").await?;
+    let args = Args::parse();
+    let db = args.db.connect().await?;
 
     println!("\n{}", "=".repeat(75));
     println!("                ON-TIME DELIVERY ANALYSIS");
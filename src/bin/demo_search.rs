@@ -6,11 +6,26 @@
 //!   ./target/release/demo_search origin 100
 //!   ./target/release/demo_search late
 //!   ./target/release/demo_search stats
+//! Database: --db URL (default: rocksdb://data/lastmile.db)
 
 use anyhow::Result;
+use clap::Parser;
 use nyc_last_mile::{db, carrier_names::get_carrier_name, location_names::format_lane_short};
 use serde::Deserialize;
-use std::env;
+
+/// Search shipments, lanes and carriers
+#[derive(Parser, Debug)]
+#[command(name = "demo_search")]
+struct Args {
+    /// carrier, lane, origin, dest, late, early, long or stats
+    command: Option<String>,
+
+    /// Search term for carrier, lane, origin and dest
+    term: Option<String>,
+
+    #[command(flatten)]
+    db: db::DbArgs,
+}
 
 #[derive(Debug, Deserialize)]
 struct ShipmentResult {
@@ -63,19 +78,18 @@ fn print_usage() {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
+    let args = Args::parse();
 
-    if args.len() < 2 {
+    let Some(command) = args.command.as_deref() else {
         print_usage();
         return Ok(());
-    }
+    };
 
-    let db = db::connect("data/lastmile.db").await?;
-    let command = args[1].as_str();
+    let db = args.db.connect().await?;
 
     match command {
         "carrier" => {
-            let pattern = args.get(2).cloned().unwrap_or_default();
+            let pattern = args.term.clone().unwrap_or_default();
             if pattern.is_empty() {
                 println!("Please provide a carrier ID pattern");
                 return Ok(());
@@ -116,7 +130,7 @@ async fn main() -> Result<()> {
         }
 
         "lane" => {
-            let pattern = args.get(2).cloned().unwrap_or_default();
+            let pattern = args.term.clone().unwrap_or_default();
             if pattern.is_empty() {
                 println!("Please provide a lane pattern (e.g., '100' or '100->200')");
                 return Ok(());
@@ -161,7 +175,7 @@ async fn main() -> Result<()> {
         }
 
         "origin" => {
-            let zip = args.get(2).cloned().unwrap_or_default();
+            let zip = args.term.clone().unwrap_or_default();
             if zip.is_empty() {
                 println!("Please provide an origin DC ZIP3");
                 return Ok(());
@@ -186,7 +200,7 @@ async fn main() -> Result<()> {
         }
 
         "dest" => {
-            let zip = args.get(2).cloned().unwrap_or_default();
+            let zip = args.term.clone().unwrap_or_default();
             if zip.is_empty() {
                 println!("Please provide a delivery region ZIP3");
                 return Ok(());
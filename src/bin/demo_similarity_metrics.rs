@@ -3,7 +3,8 @@
 //! Shows different distance/similarity metrics and when to use each
 
 use anyhow::Result;
-use nyc_last_mile::db_enhanced;
+use clap::Parser;
+use nyc_last_mile::db;
 use serde_json::Value;
 use tracing::info;
 
@@ -121,15 +122,24 @@ impl CarrierPerformance {
     }
 }
 
+/// Similarity metrics comparison demo
+#[derive(Parser, Debug)]
+#[command(name = "demo_similarity_metrics")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter("info")
         .init();
 
-    let db_path = "data/lastmile_enhanced.db";
+    let args = Args::parse();
+    let db_path = args.db.url.as_str();
     info!("Connecting to SurrealDB at {}", db_path);
-    let db = db_enhanced::connect(db_path).await?;
+    let db = args.db.connect().await?;
 
     println!("\n========================================");
     println!("  Similarity Metrics Comparison");
@@ -1,7 +1,8 @@
 //! Database Statistics Demo
-//! Run: ./target/release/demo_stats
+//! Run: ./target/release/demo_stats [--db URL]
 
 use anyhow::Result;
+use clap::Parser;
 use nyc_last_mile::db;
 use serde::Deserialize;
 
@@ -36,9 +37,18 @@ struct DateRange {
     max_date: Option<String>,
 }
 
+/// Database statistics demo
+#[derive(Parser, Debug)]
+#[command(name = "demo_stats")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let db = db::connect("data/lastmile.db").await?;
+    let args = Args::parse();
+    let db = args.db.connect().await?;
 
     println!("\n{}", "=".repeat(60));
     println!("         NYC LAST-MILE DELIVERY DATABASE STATISTICS");
@@ -10,7 +10,8 @@
 //! - "Find lanes with similar characteristics"
 
 use anyhow::Result;
-use nyc_last_mile::db_enhanced;
+use clap::Parser;
+use nyc_last_mile::db;
 use serde_json::Value;
 use tracing::info;
 
@@ -46,15 +47,24 @@ impl CarrierPerformance {
     }
 }
 
+/// Vector similarity search demo
+#[derive(Parser, Debug)]
+#[command(name = "demo_vector_search")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter("info")
         .init();
 
-    let db_path = "data/lastmile_enhanced.db";
+    let args = Args::parse();
+    let db_path = args.db.url.as_str();
     info!("Connecting to SurrealDB (enhanced) at {}", db_path);
-    let db = db_enhanced::connect(db_path).await?;
+    let db = args.db.connect().await?;
 
     info!("\n========================================");
     info!("  Vector Similarity Search Demo");
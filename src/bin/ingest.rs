@@ -1,9 +1,115 @@
-use anyhow::Result;
-use csv::ReaderBuilder;
-use nyc_last_mile::{db, models::CsvRecord};
-use std::collections::HashSet;
+//! Ingest last-mile shipment data (CSV, Parquet or NDJSON) into SurrealDB
+//!
+//! One command for every schema flavor:
+//! - `basic`: shipments with string references to carriers, locations, lanes
+//! - `enhanced`: adds record links and (with `--vectors`) performance vectors
+//! - `zip5`: the synthetic data format with ZIP5 locations and lanes
+//!
+//! Parquet and NDJSON exports are mapped onto the CSV columns; source fields
+//! with other names (e.g. `carrier_id` for `carrier_pseudo`) are renamed by
+//! built-in aliases or a `--column-map` JSON file.
+//!
+//! Rows are streamed and written in batched transactions. A checkpoint is
+//! committed with every batch, so re-running after an interruption resumes
+//! from the last committed row.
+//!
+//! Usage:
+//!   cargo run --release --bin ingest -- [OPTIONS]
+//!
+//! Options:
+//!   --input <PATH>       Input file (default: raw-data/last-mile-data.csv)
+//!   --format <FORMAT>    csv, parquet or ndjson (default: from file extension)
+//!   --column-map <PATH>  JSON object mapping source fields to CSV column names
+//!   --db <URL>           Database URL (default: rocksdb://data/lastmile.db)
+//!   --schema <FLAVOR>    basic, enhanced or zip5 (default: basic)
+//!   --graph              Relate shipments to carriers, locations and lanes
+//!   --vectors            Compute carrier/lane performance vectors (enhanced)
+//!   --link-only          Add graph edges to an existing database, read no CSV
+//!   --incremental        Upsert by load_id into an existing database
+//!   --clear              Delete the database directory before ingesting
+//!   --batch-size <N>     Rows per transaction (default: 1000)
+//!   --no-resume          Ignore any stored checkpoint and start from row 0
+//!   --rejects <PATH>     Reject file (default: <input>.rejects.csv)
+//!   --report-json <PATH> Write the validation summary as JSON
+//!   --no-alerts          Skip re-evaluating exception alerts afterwards
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use nyc_last_mile::{
+    db, db_enhanced,
+    alerts,
+    ingest::{self, IngestOptions, SchemaFlavor},
+    models::{CsvRecord, SyntheticCsvRecord},
+    sources::{ColumnMapping, InputFormat},
+    validation,
+};
 use std::path::PathBuf;
-use tracing::{info, warn};
+use tracing::info;
+
+/// Ingest last-mile shipping data into SurrealDB
+#[derive(Parser, Debug)]
+#[command(name = "ingest")]
+#[command(about = "Ingest last-mile shipping data from CSV, Parquet or NDJSON")]
+struct Args {
+    /// Input file path
+    #[arg(long, default_value = "raw-data/last-mile-data.csv")]
+    input: PathBuf,
+
+    /// Input format (default: guessed from the file extension)
+    #[arg(long, value_enum)]
+    format: Option<InputFormat>,
+
+    /// JSON file mapping source field names to CSV column names
+    #[arg(long)]
+    column_map: Option<PathBuf>,
+
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    /// Schema flavor to create and load
+    #[arg(long, value_enum, default_value_t = SchemaFlavor::Basic)]
+    schema: SchemaFlavor,
+
+    /// Create graph edges (shipped_by, origin_at, dest_at, on_lane, connects) while ingesting
+    #[arg(long)]
+    graph: bool,
+
+    /// Compute carrier and lane performance vectors after ingesting (enhanced schema)
+    #[arg(long)]
+    vectors: bool,
+
+    /// Only add graph edges to shipments and lanes that have none; no CSV is read
+    #[arg(long)]
+    link_only: bool,
+
+    /// Upsert shipments by load_id, adding only missing carriers, locations and lanes
+    #[arg(long)]
+    incremental: bool,
+
+    /// Delete the existing database before ingesting
+    #[arg(long)]
+    clear: bool,
+
+    /// Rows per transaction
+    #[arg(long, default_value = "1000")]
+    batch_size: usize,
+
+    /// Ignore any stored checkpoint and start from the first row
+    #[arg(long)]
+    no_resume: bool,
+
+    /// CSV file for rejected rows (default: <input>.rejects.csv)
+    #[arg(long)]
+    rejects: Option<PathBuf>,
+
+    /// Write the validation summary as JSON to this path
+    #[arg(long)]
+    report_json: Option<PathBuf>,
+
+    /// Don't re-evaluate exception alerts after ingesting
+    #[arg(long)]
+    no_alerts: bool,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -11,211 +117,135 @@ async fn main() -> Result<()> {
         .with_env_filter("info")
         .init();
 
-    let csv_path = PathBuf::from("raw-data/last-mile-data.csv");
-    let db_path = "data/lastmile.db";
-
-    info!("Connecting to SurrealDB at {}", db_path);
-    let db = db::connect(db_path).await?;
+    let args = Args::parse();
 
-    info!("Initializing schema...");
-    db::init_schema(&db).await?;
+    if args.vectors && !args.schema.record_links() {
+        bail!("--vectors requires --schema enhanced");
+    }
+    if args.clear && args.incremental {
+        bail!("--clear and --incremental are mutually exclusive");
+    }
 
-    info!("Reading CSV from {:?}", csv_path);
-    let mut reader = ReaderBuilder::new()
-        .has_headers(true)
-        .from_path(&csv_path)?;
+    match args.db.local_path() {
+        Some(path) => {
+            if args.clear && path.exists() {
+                info!("Clearing existing database at {:?}", path);
+                std::fs::remove_dir_all(path)?;
+            }
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        None if args.clear && db::is_remote(&args.db.url) => {
+            bail!("--clear deletes a local database directory; remove the database on the server instead");
+        }
+        None => {}
+    }
 
-    // Track unique entities
-    let mut carriers: HashSet<String> = HashSet::new();
-    let mut locations: HashSet<String> = HashSet::new();
-    let mut lanes: HashSet<(String, String)> = HashSet::new();
+    info!("Connecting to SurrealDB at {} ({}/{})", args.db.url, db::NAMESPACE, db::DATABASE);
+    let db = args.db.connect().await?;
 
-    let mut shipment_count = 0;
-    let mut error_count = 0;
+    info!("Initializing {:?} schema...", args.schema);
+    args.schema.init_schema(&db).await?;
 
-    // First pass: collect unique entities and shipments
-    let records: Vec<CsvRecord> = reader
-        .deserialize()
-        .filter_map(|r| r.ok())
-        .collect();
+    if args.link_only {
+        info!("Creating graph edges for unlinked shipments and lanes...");
+        ingest::link_existing(&db).await?;
+        db::bump_data_version(&db).await?;
+        print_totals(&db).await?;
+        return Ok(());
+    }
 
-    info!("Parsed {} records from CSV", records.len());
+    let format = args.format.unwrap_or_else(|| InputFormat::from_path(&args.input));
+    let columns = match &args.column_map {
+        Some(path) => ColumnMapping::load(path)?,
+        None => ColumnMapping::default(),
+    };
+
+    info!("Streaming {:?} from {:?}", format, args.input);
+    let options = IngestOptions {
+        schema: args.schema,
+        batch_size: args.batch_size,
+        resume: !args.no_resume,
+        rejects_path: Some(
+            args.rejects
+                .clone()
+                .unwrap_or_else(|| validation::default_rejects_path(&args.input)),
+        ),
+        incremental: args.incremental,
+        graph: args.graph,
+        format: Some(format),
+        columns,
+    };
+    let stats = match args.schema {
+        SchemaFlavor::Zip5 => {
+            ingest::ingest_file::<SyntheticCsvRecord>(&db, &args.input, &options).await?
+        }
+        SchemaFlavor::Basic | SchemaFlavor::Enhanced => {
+            ingest::ingest_file::<CsvRecord>(&db, &args.input, &options).await?
+        }
+    };
 
-    // Collect unique entities
-    for record in &records {
-        carriers.insert(record.carrier_pseudo.clone());
-        locations.insert(record.origin_zip_3d.clone());
-        locations.insert(record.dest_zip_3d.clone());
-        lanes.insert((record.lane_id.clone(), record.lane_zip3_pair.clone()));
+    if stats.resumed_rows > 0 {
+        info!("Resumed after {} previously committed rows", stats.resumed_rows);
     }
-
     info!(
-        "Found {} carriers, {} locations, {} lanes",
-        carriers.len(),
-        locations.len(),
-        lanes.len()
+        "Ingestion complete: {} inserted, {} updated ({} re-linked), {} rejected, {} errors",
+        stats.shipments, stats.updated, stats.relinked, stats.report.rejected, stats.errors
     );
-
-    // Insert carriers
-    info!("Inserting carriers...");
-    for carrier_id in &carriers {
-        db.query("CREATE carrier SET carrier_id = $carrier_id")
-            .bind(("carrier_id", carrier_id.clone()))
-            .await?
-            .check()?;
+    info!(
+        "New entities: {} carriers, {} locations, {} lanes",
+        stats.carriers, stats.locations, stats.lanes
+    );
+    if args.schema == SchemaFlavor::Zip5 {
+        info!(
+            "ZIP5: {} new locations, {} new lanes, {} synthetic rows",
+            stats.locations_zip5, stats.lanes_zip5, stats.synthetic
+        );
     }
 
-    // Insert locations
-    info!("Inserting locations...");
-    for zip3 in &locations {
-        // Extract state if present (e.g., "PA" from "PA→TX" or just use zip)
-        let state = extract_state(zip3);
-        db.query("CREATE location SET zip3 = $zip3, state = $state")
-            .bind(("zip3", zip3.clone()))
-            .bind(("state", state))
-            .await?
-            .check()?;
+    if args.vectors {
+        info!("Computing performance vectors...");
+        db_enhanced::compute_vectors(&db).await?;
     }
 
-    // Insert lanes
-    info!("Inserting lanes...");
-    for (lane_id, zip3_pair) in &lanes {
-        db.query("CREATE lane SET lane_id = $lane_id, zip3_pair = $zip3_pair")
-            .bind(("lane_id", lane_id.clone()))
-            .bind(("zip3_pair", zip3_pair.clone()))
-            .await?
-            .check()?;
+    stats.report.print_summary();
+    if let Some(path) = &args.report_json {
+        stats.report.write_json(path)?;
+        info!("Validation report written to {:?}", path);
     }
 
-    // Insert shipments and relationships
-    info!("Inserting shipments and relationships...");
-    for (i, record) in records.iter().enumerate() {
-        match record.to_shipment() {
-            Ok(shipment) => {
-                // Insert shipment
-                let result = db
-                    .query(
-                        r#"
-                        CREATE shipment CONTENT {
-                            load_id: $load_id,
-                            carrier_mode: $carrier_mode,
-                            actual_ship: <datetime>$actual_ship,
-                            actual_delivery: <datetime>$actual_delivery,
-                            carrier_posted_service_days: $carrier_posted_service_days,
-                            customer_distance: $customer_distance,
-                            truckload_service_days: $truckload_service_days,
-                            goal_transit_days: $goal_transit_days,
-                            actual_transit_days: $actual_transit_days,
-                            otd: $otd,
-                            ship_dow: $ship_dow,
-                            ship_week: $ship_week,
-                            ship_month: $ship_month,
-                            ship_year: $ship_year,
-                            distance_bucket: $distance_bucket,
-                            carrier_ref: $carrier_id,
-                            origin_zip: $origin_zip,
-                            dest_zip: $dest_zip,
-                            lane_ref: $lane_id
-                        };
-                        "#,
-                    )
-                    .bind(("load_id", shipment.load_id.clone()))
-                    .bind(("carrier_mode", format!("{:?}", shipment.carrier_mode)))
-                    .bind(("actual_ship", shipment.actual_ship.format("%Y-%m-%dT%H:%M:%SZ").to_string()))
-                    .bind(("actual_delivery", shipment.actual_delivery.format("%Y-%m-%dT%H:%M:%SZ").to_string()))
-                    .bind(("carrier_posted_service_days", shipment.carrier_posted_service_days))
-                    .bind(("customer_distance", shipment.customer_distance))
-                    .bind(("truckload_service_days", shipment.truckload_service_days))
-                    .bind(("goal_transit_days", shipment.goal_transit_days))
-                    .bind(("actual_transit_days", shipment.actual_transit_days))
-                    .bind(("otd", format!("{:?}", shipment.otd)))
-                    .bind(("ship_dow", shipment.ship_dow))
-                    .bind(("ship_week", shipment.ship_week))
-                    .bind(("ship_month", shipment.ship_month))
-                    .bind(("ship_year", shipment.ship_year))
-                    .bind(("distance_bucket", shipment.distance_bucket.clone()))
-                    .bind(("carrier_id", record.carrier_pseudo.clone()))
-                    .bind(("origin_zip", record.origin_zip_3d.clone()))
-                    .bind(("dest_zip", record.dest_zip_3d.clone()))
-                    .bind(("lane_id", record.lane_id.clone()))
-                    .await;
-
-                match result {
-                    Ok(mut response) => {
-                        match response.check() {
-                            Ok(_) => shipment_count += 1,
-                            Err(e) => {
-                                if error_count < 5 {
-                                    warn!("Query check failed for record {}: {}", i, e);
-                                }
-                                error_count += 1;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        if error_count < 5 {
-                            warn!("Query error for record {}: {}", i, e);
-                        }
-                        error_count += 1;
-                    }
-                }
-            }
-            Err(e) => {
-                if error_count < 5 {
-                    warn!("Failed to parse record {}: {}", i, e);
-                }
-                error_count += 1;
-            }
-        }
-
-        if (i + 1) % 5000 == 0 {
-            info!("Processed {}/{} records...", i + 1, records.len());
-        }
+    if !args.no_alerts {
+        let summary = alerts::refresh(&db).await?;
+        info!(
+            "Exception alerts: {} opened, {} updated, {} reopened, {} resolved",
+            summary.opened, summary.updated, summary.reopened, summary.resolved
+        );
     }
 
-    info!(
-        "Ingestion complete: {} shipments, {} errors",
-        shipment_count, error_count
-    );
+    print_totals(&db).await
+}
 
-    // Verify counts
-    let shipment_total: Option<i64> = db
+async fn print_totals(db: &db::DbConn) -> Result<()> {
+    let mut response = db
         .query("SELECT count() FROM shipment GROUP ALL")
-        .await?
-        .take("count")?;
-    let carrier_total: Option<i64> = db
         .query("SELECT count() FROM carrier GROUP ALL")
-        .await?
-        .take("count")?;
-    let location_total: Option<i64> = db
         .query("SELECT count() FROM location GROUP ALL")
-        .await?
-        .take("count")?;
-    let lane_total: Option<i64> = db
         .query("SELECT count() FROM lane GROUP ALL")
-        .await?
-        .take("count")?;
+        .query("SELECT count() FROM shipped_by GROUP ALL")
+        .await?;
+    let shipment_total: Option<i64> = response.take((0, "count"))?;
+    let carrier_total: Option<i64> = response.take((1, "count"))?;
+    let location_total: Option<i64> = response.take((2, "count"))?;
+    let lane_total: Option<i64> = response.take((3, "count"))?;
+    let edge_total: Option<i64> = response.take((4, "count"))?;
 
     info!("Database totals:");
-    info!("  Shipments: {:?}", shipment_total);
-    info!("  Carriers: {:?}", carrier_total);
-    info!("  Locations: {:?}", location_total);
-    info!("  Lanes: {:?}", lane_total);
+    info!("  Shipments: {}", shipment_total.unwrap_or(0));
+    info!("  Carriers: {}", carrier_total.unwrap_or(0));
+    info!("  Locations: {}", location_total.unwrap_or(0));
+    info!("  Lanes: {}", lane_total.unwrap_or(0));
+    info!("  Linked shipments (shipped_by edges): {}", edge_total.unwrap_or(0));
 
     Ok(())
 }
-
-fn extract_state(zip3: &str) -> Option<String> {
-    // If it's a 2-letter state code, return it
-    if zip3.len() == 2 && zip3.chars().all(|c| c.is_ascii_uppercase()) {
-        return Some(zip3.to_string());
-    }
-    // Check if it contains state info like "PA→TX"
-    if zip3.contains('→') {
-        let parts: Vec<&str> = zip3.split('→').collect();
-        if parts.len() == 2 && parts[0].len() == 2 {
-            return Some(parts[0].to_string());
-        }
-    }
-    None
-}
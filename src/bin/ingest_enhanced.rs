@@ -1,314 +0,0 @@
-//! Enhanced ingest with record links and graph relationships
-//!
-//! This version:
-//! 1. Creates entities with deterministic IDs (carrier:abc123)
-//! 2. Uses record links instead of string references
-//! 3. Creates graph edges with RELATE statements
-//! 4. Computes performance vectors after ingestion
-
-use anyhow::Result;
-use csv::ReaderBuilder;
-use nyc_last_mile::{db_enhanced, models::CsvRecord};
-use std::collections::HashSet;
-use std::path::PathBuf;
-use surrealdb::sql::Thing;
-use tracing::{info, warn};
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter("info")
-        .init();
-
-    let csv_path = PathBuf::from("raw-data/last-mile-data.csv");
-    let db_path = "data/lastmile_enhanced.db";
-
-    info!("Connecting to SurrealDB (enhanced) at {}", db_path);
-    let db = db_enhanced::connect(db_path).await?;
-
-    info!("Initializing enhanced schema...");
-    db_enhanced::init_schema(&db).await?;
-
-    info!("Reading CSV from {:?}", csv_path);
-    let mut reader = ReaderBuilder::new()
-        .has_headers(true)
-        .from_path(&csv_path)?;
-
-    // Track unique entities
-    let mut carriers: HashSet<String> = HashSet::new();
-    let mut locations: HashSet<String> = HashSet::new();
-    let mut lanes: HashSet<(String, String)> = HashSet::new();
-
-    let mut shipment_count = 0;
-    let mut error_count = 0;
-
-    // First pass: collect unique entities and shipments
-    let records: Vec<CsvRecord> = reader
-        .deserialize()
-        .filter_map(|r| r.ok())
-        .collect();
-
-    info!("Parsed {} records from CSV", records.len());
-
-    // Collect unique entities
-    for record in &records {
-        carriers.insert(record.carrier_pseudo.clone());
-        locations.insert(record.origin_zip_3d.clone());
-        locations.insert(record.dest_zip_3d.clone());
-        lanes.insert((record.lane_id.clone(), record.lane_zip3_pair.clone()));
-    }
-
-    info!(
-        "Found {} carriers, {} locations, {} lanes",
-        carriers.len(),
-        locations.len(),
-        lanes.len()
-    );
-
-    // =========================================================================
-    // Insert carriers with deterministic IDs
-    // =========================================================================
-    info!("Inserting carriers with deterministic IDs...");
-    for carrier_id in &carriers {
-        // Create carrier with ID like carrier:abc123
-        let thing_id = format!("carrier:{}", carrier_id);
-        db.query("CREATE type::thing($id) SET carrier_id = $carrier_id")
-            .bind(("id", thing_id))
-            .bind(("carrier_id", carrier_id.clone()))
-            .await?
-            .check()?;
-    }
-
-    // =========================================================================
-    // Insert locations with deterministic IDs
-    // =========================================================================
-    info!("Inserting locations with deterministic IDs...");
-    for zip3 in &locations {
-        let state = extract_state(zip3);
-        // Sanitize zip3 for use as ID (replace non-alphanumeric)
-        let safe_id: String = zip3.chars()
-            .map(|c| if c.is_alphanumeric() { c } else { '_' })
-            .collect();
-        let thing_id = format!("location:{}", safe_id);
-        db.query("CREATE type::thing($id) SET zip3 = $zip3, state = $state")
-            .bind(("id", thing_id))
-            .bind(("zip3", zip3.clone()))
-            .bind(("state", state))
-            .await?
-            .check()?;
-    }
-
-    // =========================================================================
-    // Insert lanes with deterministic IDs and record links to locations
-    // =========================================================================
-    info!("Inserting lanes with record links...");
-    for (lane_id, zip3_pair) in &lanes {
-        // Parse zip3_pair to get origin and destination
-        let (origin_zip, dest_zip) = parse_lane_zips(zip3_pair);
-
-        let safe_lane_id: String = lane_id.chars()
-            .map(|c| if c.is_alphanumeric() { c } else { '_' })
-            .collect();
-        let thing_id = format!("lane:{}", safe_lane_id);
-
-        // Create origin and dest location references
-        let origin_safe: String = origin_zip.chars()
-            .map(|c| if c.is_alphanumeric() { c } else { '_' })
-            .collect();
-        let dest_safe: String = dest_zip.chars()
-            .map(|c| if c.is_alphanumeric() { c } else { '_' })
-            .collect();
-
-        db.query(r#"
-            CREATE type::thing($id) SET
-                lane_id = $lane_id,
-                zip3_pair = $zip3_pair,
-                origin = type::thing($origin_ref),
-                destination = type::thing($dest_ref)
-        "#)
-            .bind(("id", thing_id))
-            .bind(("lane_id", lane_id.clone()))
-            .bind(("zip3_pair", zip3_pair.clone()))
-            .bind(("origin_ref", format!("location:{}", origin_safe)))
-            .bind(("dest_ref", format!("location:{}", dest_safe)))
-            .await?
-            .check()?;
-    }
-
-    // =========================================================================
-    // Insert shipments with record links and graph edges
-    // =========================================================================
-    info!("Inserting shipments with record links and graph edges...");
-    for (i, record) in records.iter().enumerate() {
-        match record.to_shipment() {
-            Ok(shipment) => {
-                // Build record link references
-                let carrier_ref = format!("carrier:{}", record.carrier_pseudo);
-
-                let safe_lane_id: String = record.lane_id.chars()
-                    .map(|c| if c.is_alphanumeric() { c } else { '_' })
-                    .collect();
-                let lane_ref = format!("lane:{}", safe_lane_id);
-
-                let origin_safe: String = record.origin_zip_3d.chars()
-                    .map(|c| if c.is_alphanumeric() { c } else { '_' })
-                    .collect();
-                let origin_ref = format!("location:{}", origin_safe);
-
-                let dest_safe: String = record.dest_zip_3d.chars()
-                    .map(|c| if c.is_alphanumeric() { c } else { '_' })
-                    .collect();
-                let dest_ref = format!("location:{}", dest_safe);
-
-                // Insert shipment with record links
-                let result = db
-                    .query(
-                        r#"
-                        CREATE shipment CONTENT {
-                            load_id: $load_id,
-                            carrier_mode: $carrier_mode,
-                            actual_ship: <datetime>$actual_ship,
-                            actual_delivery: <datetime>$actual_delivery,
-                            carrier_posted_service_days: $carrier_posted_service_days,
-                            customer_distance: $customer_distance,
-                            truckload_service_days: $truckload_service_days,
-                            goal_transit_days: $goal_transit_days,
-                            actual_transit_days: $actual_transit_days,
-                            otd: $otd,
-                            ship_dow: $ship_dow,
-                            ship_week: $ship_week,
-                            ship_month: $ship_month,
-                            ship_year: $ship_year,
-                            distance_bucket: $distance_bucket,
-                            carrier: type::thing($carrier_ref),
-                            lane: type::thing($lane_ref),
-                            origin: type::thing($origin_ref),
-                            destination: type::thing($dest_ref)
-                        };
-                        "#,
-                    )
-                    .bind(("load_id", shipment.load_id.clone()))
-                    .bind(("carrier_mode", format!("{:?}", shipment.carrier_mode)))
-                    .bind(("actual_ship", shipment.actual_ship.format("%Y-%m-%dT%H:%M:%SZ").to_string()))
-                    .bind(("actual_delivery", shipment.actual_delivery.format("%Y-%m-%dT%H:%M:%SZ").to_string()))
-                    .bind(("carrier_posted_service_days", shipment.carrier_posted_service_days))
-                    .bind(("customer_distance", shipment.customer_distance))
-                    .bind(("truckload_service_days", shipment.truckload_service_days))
-                    .bind(("goal_transit_days", shipment.goal_transit_days))
-                    .bind(("actual_transit_days", shipment.actual_transit_days))
-                    .bind(("otd", format!("{:?}", shipment.otd)))
-                    .bind(("ship_dow", shipment.ship_dow))
-                    .bind(("ship_week", shipment.ship_week))
-                    .bind(("ship_month", shipment.ship_month))
-                    .bind(("ship_year", shipment.ship_year))
-                    .bind(("distance_bucket", shipment.distance_bucket.clone()))
-                    .bind(("carrier_ref", carrier_ref))
-                    .bind(("lane_ref", lane_ref))
-                    .bind(("origin_ref", origin_ref))
-                    .bind(("dest_ref", dest_ref))
-                    .await;
-
-                match result {
-                    Ok(mut response) => {
-                        match response.check() {
-                            Ok(_) => shipment_count += 1,
-                            Err(e) => {
-                                if error_count < 5 {
-                                    warn!("Query check failed for record {}: {}", i, e);
-                                }
-                                error_count += 1;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        if error_count < 5 {
-                            warn!("Query error for record {}: {}", i, e);
-                        }
-                        error_count += 1;
-                    }
-                }
-            }
-            Err(e) => {
-                if error_count < 5 {
-                    warn!("Failed to parse record {}: {}", i, e);
-                }
-                error_count += 1;
-            }
-        }
-
-        if (i + 1) % 5000 == 0 {
-            info!("Processed {}/{} records...", i + 1, records.len());
-        }
-    }
-
-    info!(
-        "Ingestion complete: {} shipments, {} errors",
-        shipment_count, error_count
-    );
-
-    // =========================================================================
-    // Note: Graph edges (RELATE) are defined but populated through record links
-    // The record links (carrier, lane, origin, destination) already provide
-    // the traversal capability. Full graph edges can be created post-hoc if needed.
-    // =========================================================================
-    info!("Record links created. Graph traversal available via record link fields.");
-
-    // =========================================================================
-    // Verify counts
-    // =========================================================================
-    let shipment_total: Option<i64> = db
-        .query("SELECT count() FROM shipment GROUP ALL")
-        .await?
-        .take("count")?;
-    let carrier_total: Option<i64> = db
-        .query("SELECT count() FROM carrier GROUP ALL")
-        .await?
-        .take("count")?;
-    let location_total: Option<i64> = db
-        .query("SELECT count() FROM location GROUP ALL")
-        .await?
-        .take("count")?;
-    let lane_total: Option<i64> = db
-        .query("SELECT count() FROM lane GROUP ALL")
-        .await?
-        .take("count")?;
-
-    info!("Database totals:");
-    info!("  Shipments: {:?}", shipment_total);
-    info!("  Carriers: {:?}", carrier_total);
-    info!("  Locations: {:?}", location_total);
-    info!("  Lanes: {:?}", lane_total);
-
-    Ok(())
-}
-
-fn extract_state(zip3: &str) -> Option<String> {
-    if zip3.len() == 2 && zip3.chars().all(|c| c.is_ascii_uppercase()) {
-        return Some(zip3.to_string());
-    }
-    if zip3.contains('→') {
-        let parts: Vec<&str> = zip3.split('→').collect();
-        if parts.len() == 2 && parts[0].len() == 2 {
-            return Some(parts[0].to_string());
-        }
-    }
-    None
-}
-
-fn parse_lane_zips(zip3_pair: &str) -> (String, String) {
-    // zip3_pair format: "123→456" or "123-456"
-    if zip3_pair.contains('→') {
-        let parts: Vec<&str> = zip3_pair.split('→').collect();
-        if parts.len() == 2 {
-            return (parts[0].to_string(), parts[1].to_string());
-        }
-    }
-    if zip3_pair.contains('-') {
-        let parts: Vec<&str> = zip3_pair.split('-').collect();
-        if parts.len() == 2 {
-            return (parts[0].to_string(), parts[1].to_string());
-        }
-    }
-    // Fallback
-    (zip3_pair.to_string(), zip3_pair.to_string())
-}
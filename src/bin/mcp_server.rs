@@ -22,6 +22,7 @@
 //! - get_regional_performance: Get performance for a specific region
 //! - get_friction_zones: Identify high-friction problem destinations
 //! - get_terminal_performance: Score terminals/DCs on outbound performance
+//! - estimate_eta: P50/P80/P95 delivery dates for a planned shipment
 //!
 //! Configure in Claude Desktop's settings as a stdio MCP server.
 
@@ -76,9 +77,22 @@ impl ApiClient {
     }
 
     async fn get(&self, path: &str) -> Result<Value> {
+        self.get_with_query(path, &[]).await
+    }
+
+    async fn get_with_query(&self, path: &str, query: &[(&str, String)]) -> Result<Value> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self.client.get(&url).query(query).send().await?;
+        Self::json(response).await
+    }
+
+    async fn post(&self, path: &str, body: &Value) -> Result<Value> {
         let url = format!("{}{}", self.base_url, path);
-        let response = self.client.get(&url).send().await?;
+        let response = self.client.post(&url).json(body).send().await?;
+        Self::json(response).await
+    }
 
+    async fn json(response: reqwest::Response) -> Result<Value> {
         if response.status().is_success() {
             let json: Value = response.json().await?;
             Ok(json)
@@ -124,6 +138,23 @@ impl ApiClient {
     async fn get_terminal_performance(&self, limit: usize) -> Result<Value> {
         self.get(&format!("/api/v1/analysis/terminals?limit={}", limit)).await
     }
+
+    async fn estimate_eta(&self, args: &Value) -> Result<Value> {
+        let mut query: Vec<(&str, String)> = Vec::new();
+        for (arg, param) in [("origin", "origin"), ("dest", "dest"), ("carrier", "carrier"), ("mode", "mode"), ("ship", "ship")] {
+            if let Some(value) = args.get(arg).and_then(|v| v.as_str()) {
+                query.push((param, value.to_string()));
+            }
+        }
+        if let Some(distance) = args.get("distance_miles").and_then(|v| v.as_f64()) {
+            query.push(("distance", distance.to_string()));
+        }
+        self.get_with_query("/api/v1/eta", &query).await
+    }
+
+    async fn simulate(&self, args: &Value) -> Result<Value> {
+        self.post("/api/v1/simulate", args).await
+    }
 }
 
 // ============================================================================
@@ -273,6 +304,63 @@ fn get_tools_list() -> Value {
                     },
                     "required": []
                 }
+            },
+            {
+                "name": "estimate_eta",
+                "description": "Estimate P50/P80/P95 delivery dates for a planned shipment from historical transit times of the same lane and carrier, falling back to the lane and then the lane's distance bucket. Skips weekends when the history shows no weekend deliveries.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "origin": {
+                            "type": "string",
+                            "description": "Origin ZIP3 code (e.g., '750')"
+                        },
+                        "dest": {
+                            "type": "string",
+                            "description": "Destination ZIP3 code (e.g., '432')"
+                        },
+                        "carrier": {
+                            "type": "string",
+                            "description": "Carrier ID or name"
+                        },
+                        "mode": {
+                            "type": "string",
+                            "description": "Carrier mode, e.g. 'LTL' or 'Truckload' (default: all modes)"
+                        },
+                        "ship": {
+                            "type": "string",
+                            "description": "Ship date or date-time (e.g., '2024-12-02' or '2024-12-02T14:30'); from 17:00 the shipment leaves the next day"
+                        },
+                        "distance_miles": {
+                            "type": "number",
+                            "description": "Lane distance; only needed for lanes without history"
+                        }
+                    },
+                    "required": ["origin", "dest", "carrier", "ship"]
+                }
+            },
+            {
+                "name": "simulate_scenario",
+                "description": "What-if simulation of the network on-time rate. Applies levers in order (switch carrier, convert mode, add buffer days, drop lanes) and returns the projected on-time rate, late shipments and cluster mix, plus a ranked list of single-lane levers that reach a target on-time rate. Rates are percentages.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "levers": {
+                            "type": "array",
+                            "description": "Levers applied in order. Each has a 'type' of switch_carrier ('to' carrier, optional 'from'), convert_mode ('to' mode, optional 'from'), add_buffer_days ('days') or drop_lanes, and 'lanes' such as ['750-432'] (empty means all lanes)",
+                            "items": { "type": "object" }
+                        },
+                        "target_on_time_rate": {
+                            "type": "number",
+                            "description": "Target on-time rate in percent (e.g., 75)"
+                        },
+                        "max_recommendations": {
+                            "type": "integer",
+                            "description": "Ranked levers to return (default 20)"
+                        }
+                    },
+                    "required": []
+                }
             }
         ]
     })
@@ -313,6 +401,8 @@ async fn handle_tool_call(client: &ApiClient, name: &str, args: &Value) -> Resul
             let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(5) as usize;
             client.get_terminal_performance(limit).await
         }
+        "estimate_eta" => client.estimate_eta(args).await,
+        "simulate_scenario" => client.simulate(args).await,
         _ => Ok(json!({"error": format!("Unknown tool: {}", name)}))
     }
 }
@@ -6,10 +6,10 @@
 //! This version can be deployed to cloud platforms (Cloud Run, etc.)
 //! and accessed remotely by MCP clients.
 //!
-//! Run: ./target/release/mcp_server_http --port 8080
+//! Run: ./target/release/mcp_server_http --port 8080 [--db URL]
 //!
 //! Environment variables:
-//!   LASTMILE_DB_PATH - Path to SurrealDB database
+//!   LASTMILE_PLAYBOOKS - Cluster playbook file (default: config/playbooks.toml)
 //!
 //! Endpoints:
 //!   POST /mcp    - JSON-RPC requests
@@ -30,7 +30,12 @@ use axum::{
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::{collections::HashMap, convert::Infallible, sync::Arc, time::Duration};
+use nyc_last_mile::api::handlers::{EtaResponse, SimulationResponse};
+use nyc_last_mile::db;
+use nyc_last_mile::eta::{self, parse_ship_datetime, EtaRequest};
+use nyc_last_mile::playbook::{self, playbooks, CLUSTER_COUNT};
+use nyc_last_mile::simulation::{self, Lever, SimulationRequest};
+use std::{convert::Infallible, path::PathBuf, sync::Arc, time::Duration};
 use tokio::sync::broadcast;
 use tokio_stream::{wrappers::BroadcastStream, StreamExt};
 use tower_http::cors::{Any, CorsLayer};
@@ -49,9 +54,13 @@ struct Args {
     #[arg(long, default_value = "8080")]
     port: u16,
 
-    /// Database path
-    #[arg(long, default_value = "data/synthetic.db")]
-    db: String,
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    /// Cluster playbook file, .toml or .json
+    /// [default: $LASTMILE_PLAYBOOKS or config/playbooks.toml]
+    #[arg(long)]
+    playbooks: Option<PathBuf>,
 }
 
 // ============================================================================
@@ -88,28 +97,15 @@ struct JsonRpcError {
 // ============================================================================
 
 struct AppState {
-    db: surrealdb::Surreal<surrealdb::engine::local::Db>,
+    db: db::DbConn,
     sse_tx: broadcast::Sender<String>,
 }
 
-// ============================================================================
-// Database Operations
-// ============================================================================
-
-async fn init_db(path: &str) -> Result<surrealdb::Surreal<surrealdb::engine::local::Db>> {
-    use surrealdb::engine::local::RocksDb;
-    use surrealdb::Surreal;
-
-    let db = Surreal::new::<RocksDb>(path).await?;
-    db.use_ns("lastmile").use_db("analytics").await?;
-    Ok(db)
-}
-
 // ============================================================================
 // MCP Tool Implementations
 // ============================================================================
 
-async fn get_clusters(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) -> Result<Value> {
+async fn get_clusters(db: &db::DbConn) -> Result<Value> {
     #[derive(Debug, Deserialize)]
     struct ClusterStats {
         cluster_id: i64,
@@ -134,20 +130,13 @@ async fn get_clusters(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) ->
     let mut result = db.query(query).await?;
     let stats: Vec<ClusterStats> = result.take(0)?;
 
-    let cluster_names = [
-        "Early & Stable",
-        "On-Time & Reliable",
-        "High-Jitter",
-        "Systematically Late",
-        "Low Volume/Mixed",
-    ];
-
     let clusters: Vec<Value> = stats
         .iter()
         .map(|s| {
-            let name = cluster_names
-                .get((s.cluster_id - 1) as usize)
-                .unwrap_or(&"Unknown");
+            let name = u8::try_from(s.cluster_id)
+                .ok()
+                .and_then(|id| playbooks().cluster(id))
+                .map_or("Unknown", |c| c.name.as_str());
             json!({
                 "cluster_id": s.cluster_id,
                 "name": name,
@@ -163,7 +152,7 @@ async fn get_clusters(db: &surrealdb::Surreal<surrealdb::engine::local::Db>) ->
 }
 
 async fn get_lanes_in_cluster(
-    db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
+    db: &db::DbConn,
     cluster_id: u8,
     limit: usize,
 ) -> Result<Value> {
@@ -197,7 +186,7 @@ async fn get_lanes_in_cluster(
 }
 
 async fn get_lane_profile(
-    db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
+    db: &db::DbConn,
     origin: &str,
     dest: &str,
 ) -> Result<Value> {
@@ -245,76 +234,22 @@ async fn get_lane_profile(
 }
 
 async fn get_playbook(cluster_id: u8) -> Value {
-    let playbooks: HashMap<u8, Value> = [
-        (1, json!({
-            "cluster": "Early & Stable",
-            "description": "Lanes consistently delivering 0.5-2 days early with low variance",
-            "strategy": "Hold-Until Policy",
-            "actions": [
-                "Implement hold-until-date at destination DC",
-                "Consider negotiating lower rates for non-urgent shipments",
-                "Use as overflow capacity for time-sensitive lanes",
-                "Monitor for over-provisioned transit time SLAs"
-            ],
-            "kpis_to_watch": ["Early delivery rate", "Storage costs", "SLA efficiency"]
-        })),
-        (2, json!({
-            "cluster": "On-Time & Reliable",
-            "description": "High OTD (>95%), low variance - benchmark performers",
-            "strategy": "Protect & Replicate",
-            "actions": [
-                "Document carrier/route combinations for replication",
-                "Prioritize these lanes for premium customers",
-                "Use as baseline for SLA negotiations",
-                "Avoid changes - if it works, don't fix it"
-            ],
-            "kpis_to_watch": ["OTD rate stability", "Volume trends", "Carrier capacity"]
-        })),
-        (3, json!({
-            "cluster": "High-Jitter",
-            "description": "Acceptable average transit but unpredictable variance",
-            "strategy": "Buffer & Monitor",
-            "actions": [
-                "Add 1-2 buffer days to promised delivery dates",
-                "Implement proactive tracking alerts",
-                "Consider carrier diversification",
-                "Root cause analysis on variance drivers"
-            ],
-            "kpis_to_watch": ["Transit variance", "Exception rate", "Customer complaints"]
-        })),
-        (4, json!({
-            "cluster": "Systematically Late",
-            "description": "Consistent SLA misses - structural issues",
-            "strategy": "Carrier Switch or SLA Reset",
-            "actions": [
-                "Evaluate alternative carriers immediately",
-                "If no alternatives, reset customer SLA expectations",
-                "Implement penalty clauses with current carrier",
-                "Consider mode change (LTL → Truckload)"
-            ],
-            "kpis_to_watch": ["Late rate", "Carrier response", "Customer churn risk"]
-        })),
-        (5, json!({
-            "cluster": "Low Volume/Mixed",
-            "description": "Insufficient data for reliable patterns",
-            "strategy": "Conservative Approach",
-            "actions": [
-                "Use conservative SLA estimates",
-                "Consolidate with similar lanes if possible",
-                "Monitor until volume threshold reached",
-                "Default to most reliable carrier in region"
-            ],
-            "kpis_to_watch": ["Volume growth", "Pattern emergence", "Consolidation opportunities"]
-        })),
-    ].into_iter().collect();
-
-    playbooks.get(&cluster_id).cloned().unwrap_or(json!({
-        "error": "Invalid cluster ID. Use 1-5."
-    }))
+    match playbooks().cluster(cluster_id) {
+        Some(playbook) => json!({
+            "cluster": playbook.name,
+            "description": playbook.description,
+            "strategy": playbook.strategy,
+            "actions": playbook.actions,
+            "kpis_to_watch": playbook.kpis_to_watch
+        }),
+        None => json!({
+            "error": format!("Invalid cluster ID. Use 1-{}.", CLUSTER_COUNT)
+        }),
+    }
 }
 
 async fn find_similar_lanes(
-    db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
+    db: &db::DbConn,
     pattern: &str,
     limit: usize,
 ) -> Result<Value> {
@@ -361,7 +296,7 @@ async fn find_similar_lanes(
 }
 
 async fn get_early_analysis(
-    db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
+    db: &db::DbConn,
 ) -> Result<Value> {
     #[derive(Debug, Deserialize, Serialize)]
     struct EarlyStats {
@@ -391,7 +326,7 @@ async fn get_early_analysis(
 }
 
 async fn get_regional_performance(
-    db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
+    db: &db::DbConn,
     zip3: &str,
 ) -> Result<Value> {
     #[derive(Debug, Deserialize, Serialize)]
@@ -434,7 +369,7 @@ async fn get_regional_performance(
 }
 
 async fn get_friction_zones(
-    db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
+    db: &db::DbConn,
     limit: usize,
 ) -> Result<Value> {
     #[derive(Debug, Deserialize, Serialize)]
@@ -466,7 +401,7 @@ async fn get_friction_zones(
 }
 
 async fn get_terminal_performance(
-    db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
+    db: &db::DbConn,
     limit: usize,
 ) -> Result<Value> {
     #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -505,6 +440,51 @@ async fn get_terminal_performance(
     }))
 }
 
+async fn estimate_eta(
+    db: &db::DbConn,
+    args: &Value,
+) -> Result<Value> {
+    let arg = |name: &str| args.get(name).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let request = EtaRequest {
+        origin_zip3: arg("origin"),
+        dest_zip3: arg("dest"),
+        carrier: arg("carrier"),
+        mode: args.get("mode").and_then(|v| v.as_str()).map(str::to_string),
+        ship: parse_ship_datetime(&arg("ship"))?,
+        distance_miles: args.get("distance_miles").and_then(|v| v.as_f64()),
+    };
+
+    match eta::estimate_eta(db, &request).await? {
+        Some(estimate) => Ok(json!(EtaResponse::from(estimate))),
+        None => Ok(json!({
+            "error": format!("No shipment history for {} -> {}. Pass distance_miles to use the distance bucket.",
+                             request.origin_zip3, request.dest_zip3)
+        })),
+    }
+}
+
+async fn simulate_scenario(
+    db: &db::DbConn,
+    args: &Value,
+) -> Result<Value> {
+    let levers: Vec<Lever> = match args.get("levers") {
+        Some(levers) => serde_json::from_value(levers.clone())?,
+        None => Vec::new(),
+    };
+    let request = SimulationRequest {
+        levers,
+        target_on_time_rate: args.get("target_on_time_rate").and_then(|v| v.as_f64()).map(|t| t / 100.0),
+        max_recommendations: args.get("max_recommendations").and_then(|v| v.as_u64()).map(|n| n as usize),
+    };
+    if let Err(e) = simulation::validate(&request) {
+        return Ok(json!({"error": e.to_string()}));
+    }
+
+    let baseline = simulation::load_baseline(db).await?;
+    let result = simulation::simulate(&baseline, &request)?;
+    Ok(json!(SimulationResponse::from(result)))
+}
+
 // ============================================================================
 // MCP Protocol Handlers
 // ============================================================================
@@ -652,13 +632,70 @@ fn get_tools_list() -> Value {
                     },
                     "required": []
                 }
+            },
+            {
+                "name": "estimate_eta",
+                "description": "Estimate P50/P80/P95 delivery dates for a planned shipment from historical transit times of the same lane and carrier, falling back to the lane and then the lane's distance bucket. Skips weekends when the history shows no weekend deliveries.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "origin": {
+                            "type": "string",
+                            "description": "Origin ZIP3 code (e.g., '750')"
+                        },
+                        "dest": {
+                            "type": "string",
+                            "description": "Destination ZIP3 code (e.g., '432')"
+                        },
+                        "carrier": {
+                            "type": "string",
+                            "description": "Carrier ID or name"
+                        },
+                        "mode": {
+                            "type": "string",
+                            "description": "Carrier mode, e.g. 'LTL' or 'Truckload' (default: all modes)"
+                        },
+                        "ship": {
+                            "type": "string",
+                            "description": "Ship date or date-time (e.g., '2024-12-02' or '2024-12-02T14:30'); from 17:00 the shipment leaves the next day"
+                        },
+                        "distance_miles": {
+                            "type": "number",
+                            "description": "Lane distance; only needed for lanes without history"
+                        }
+                    },
+                    "required": ["origin", "dest", "carrier", "ship"]
+                }
+            },
+            {
+                "name": "simulate_scenario",
+                "description": "What-if simulation of the network on-time rate. Applies levers in order (switch carrier, convert mode, add buffer days, drop lanes) and returns the projected on-time rate, late shipments and cluster mix, plus a ranked list of single-lane levers that reach a target on-time rate. Rates are percentages.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "levers": {
+                            "type": "array",
+                            "description": "Levers applied in order. Each has a 'type' of switch_carrier ('to' carrier, optional 'from'), convert_mode ('to' mode, optional 'from'), add_buffer_days ('days') or drop_lanes, and 'lanes' such as ['750-432'] (empty means all lanes)",
+                            "items": { "type": "object" }
+                        },
+                        "target_on_time_rate": {
+                            "type": "number",
+                            "description": "Target on-time rate in percent (e.g., 75)"
+                        },
+                        "max_recommendations": {
+                            "type": "integer",
+                            "description": "Ranked levers to return (default 20)"
+                        }
+                    },
+                    "required": []
+                }
             }
         ]
     })
 }
 
 async fn handle_tool_call(
-    db: &surrealdb::Surreal<surrealdb::engine::local::Db>,
+    db: &db::DbConn,
     name: &str,
     args: &Value,
 ) -> Result<Value> {
@@ -696,6 +733,8 @@ async fn handle_tool_call(
             let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(5) as usize;
             get_terminal_performance(db, limit).await
         }
+        "estimate_eta" => estimate_eta(db, args).await,
+        "simulate_scenario" => simulate_scenario(db, args).await,
         _ => Ok(json!({"error": format!("Unknown tool: {}", name)})),
     }
 }
@@ -800,11 +839,14 @@ async fn main() -> Result<()> {
     let args = Args::parse();
 
     info!("Last-Mile Analytics MCP Server (HTTP)");
-    info!("Database: {}", args.db);
+    info!("Database: {}", args.db.url);
     info!("Port: {}", args.port);
 
+    let playbooks = playbook::init(args.playbooks.as_deref())?;
+    info!("Playbooks: {} clusters", playbooks.clusters.len());
+
     // Initialize database
-    let db = init_db(&args.db).await?;
+    let db = args.db.connect().await?;
     info!("Database connected");
 
     // Create SSE broadcast channel
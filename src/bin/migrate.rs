@@ -0,0 +1,136 @@
+//! Upgrade an existing database to the latest schema
+//!
+//! Applies the pending migrations from `nyc_last_mile::migrations` to a
+//! database in place. Databases created before schema versioning
+//! start at version 0; a new or empty database just gets the schema.
+//!
+//! Stores written by the old binaries keep their data under another
+//! namespace (`lastmile/shipping`, `lastmile_enhanced/nyc`,
+//! `lastmile/analytics`). When `lastmile/nyc` has no shipments, their records
+//! are copied into it after the schema is migrated to the latest version.
+//!
+//! Usage:
+//!   cargo run --release --bin migrate -- [OPTIONS]
+//!
+//! Options:
+//!   --db <URL>      Database URL (default: rocksdb://data/lastmile.db)
+//!   --to <VERSION>  Stop at this version instead of the latest
+//!   --status        Show the current version and pending migrations, change nothing
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use nyc_last_mile::{db, migrations};
+use tracing::{info, warn};
+
+/// Apply schema migrations to a SurrealDB database
+#[derive(Parser, Debug)]
+#[command(name = "migrate")]
+#[command(about = "Bring an existing database to the latest schema version")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    /// Target schema version (default: latest)
+    #[arg(long)]
+    to: Option<u32>,
+
+    /// Only report the current version and pending migrations
+    #[arg(long)]
+    status: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter("info")
+        .init();
+
+    let args = Args::parse();
+
+    info!("Connecting to SurrealDB at {}", args.db.url);
+    let db = args.db.connect().await?;
+
+    let current = migrations::current_version(&db).await?;
+    let target = args.to.unwrap_or_else(migrations::latest_version);
+    let shipments = migrations::shipment_count(&db).await?;
+    let legacy = migrations::find_legacy(&db).await?;
+
+    if args.status {
+        println!("Schema version: {} (latest {})", current, migrations::latest_version());
+        println!("Shipments in {}/{}: {}", db::NAMESPACE, db::DATABASE, shipments);
+        for (database, count) in &legacy {
+            println!("  legacy   {} holds {} shipments", database, count);
+        }
+        for applied in migrations::history(&db).await? {
+            println!(
+                "  applied  {:>3}  {:<28} {}",
+                applied.version,
+                applied.name,
+                applied.applied_at.0.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+        }
+        for migration in migrations::pending(current, target) {
+            println!("  pending  {:>3}  {}", migration.version, migration.name);
+        }
+        return Ok(());
+    }
+
+    if target < current {
+        bail!("database is at version {}; down-migrations are not supported", current);
+    }
+
+    let import = if shipments > 0 {
+        for (database, count) in &legacy {
+            warn!(
+                "Legacy namespace {} still holds {} shipments; not copied because {}/{} already has data",
+                database, count, db::NAMESPACE, db::DATABASE
+            );
+        }
+        None
+    } else {
+        match legacy.as_slice() {
+            [] => None,
+            [(database, _)] => Some(*database),
+            _ => bail!(
+                "shipments found in several legacy namespaces ({}); re-ingest the data with `ingest`",
+                legacy.iter().map(|(d, _)| d.to_string()).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    };
+
+    // Legacy records are copied into the latest schema only
+    if let Some(database) = &import {
+        if target != migrations::latest_version() {
+            bail!(
+                "legacy namespace {} can only be imported when migrating to the latest version ({}); drop --to",
+                database,
+                migrations::latest_version()
+            );
+        }
+    }
+
+    let applied = migrations::migrate_to(&db, Some(target)).await?;
+
+    if let Some(database) = import {
+        info!("Copying legacy namespace {} into {}/{}", database, db::NAMESPACE, db::DATABASE);
+        let copied = migrations::import_legacy(&db, &database).await?;
+        let shipments = migrations::shipment_count(&db).await?;
+        if shipments == 0 {
+            bail!("copied {} records from {} but no shipments arrived", copied, database);
+        }
+        info!("Copied {} records ({} shipments)", copied, shipments);
+    }
+
+    if applied.is_empty() {
+        info!("Schema is up to date (version {})", current);
+    } else {
+        info!(
+            "Migrated from version {} to {} ({} migrations)",
+            current,
+            migrations::current_version(&db).await?,
+            applied.len()
+        );
+    }
+
+    Ok(())
+}
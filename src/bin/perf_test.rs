@@ -2,6 +2,7 @@
 //! Tests baseline vs enhanced schema performance
 
 use anyhow::Result;
+use clap::Parser;
 use nyc_last_mile::db;
 use serde::Deserialize;
 use std::time::Instant;
@@ -50,13 +51,22 @@ struct LaneStats {
     volume: i64,
 }
 
+/// Performance test suite for SurrealDB query patterns
+#[derive(Parser, Debug)]
+#[command(name = "perf_test")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter("info")
         .init();
 
-    let db_path = "data/lastmile.db";
+    let args = Args::parse();
+    let db_path = args.db.url.as_str();
     info!("Connecting to SurrealDB at {}", db_path);
     let db = db::connect(db_path).await?;
 
@@ -2,7 +2,8 @@
 //! Tests record links, graph traversal, and vector search patterns
 
 use anyhow::Result;
-use nyc_last_mile::db_enhanced;
+use clap::Parser;
+use nyc_last_mile::db;
 use std::time::Instant;
 use tracing::info;
 
@@ -16,15 +17,24 @@ struct BenchmarkResult {
 
 // Use serde_json::Value for all queries to handle record links
 
+/// Performance test for the enhanced SurrealDB schema
+#[derive(Parser, Debug)]
+#[command(name = "perf_test_enhanced")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter("info")
         .init();
 
-    let db_path = "data/lastmile_enhanced.db";
+    let args = Args::parse();
+    let db_path = args.db.url.as_str();
     info!("Connecting to SurrealDB (enhanced) at {}", db_path);
-    let db = db_enhanced::connect(db_path).await?;
+    let db = args.db.connect().await?;
 
     let mut results: Vec<BenchmarkResult> = Vec::new();
 
@@ -373,7 +383,7 @@ async fn main() -> Result<()> {
 
     output.push_str("## Schema Enhancements\n\n");
     output.push_str("1. **Record Links**: Shipments use `carrier: carrier:abc123` instead of `carrier_ref: \"abc123\"`\n");
-    output.push_str("2. **Graph Edges**: `shipped_by`, `from_origin`, `to_destination`, `on_lane` relations\n");
+    output.push_str("2. **Graph Edges**: `shipped_by`, `origin_at`, `dest_at`, `on_lane` relations\n");
     output.push_str("3. **Deterministic IDs**: Entities have predictable IDs for direct lookup\n");
     output.push_str("4. **Indexed Record Links**: carrier and lane fields are indexed\n");
 
@@ -0,0 +1,266 @@
+//! SLA proposals
+//! Derives goal transit days per lane and carrier from a target on-time
+//! percentile, stores them for review and re-scores history under them
+//!
+//! Run: ./target/release/sla_proposals propose [--target 0.8] [--min-shipments 30] [--save]
+//!      ./target/release/sla_proposals list [--status proposed|approved|rejected]
+//!      ./target/release/sla_proposals rescore [--status approved]
+//!      ./target/release/sla_proposals approve --lane 750-432 [--carrier "XPO Logistics"]
+//!      ./target/release/sla_proposals reject --all
+//! Filters (propose, rescore): --from, --to, --mode, --carrier, --origin-zip3, --distance-bucket
+//! Output: --format table|json|csv|markdown
+
+use anyhow::{bail, Result};
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use nyc_last_mile::carrier_names::{find_carrier_id, get_carrier_name};
+use nyc_last_mile::filter::ShipmentFilter;
+use nyc_last_mile::location_names::format_lane_short;
+use nyc_last_mile::report::{OutputArgs, Report};
+use nyc_last_mile::simulation::parse_lane;
+use nyc_last_mile::sla::{self, ProposalSelector, ProposalStatus, Rescore, SlaConfig, SlaProposal, StoredProposal};
+use nyc_last_mile::{db, table_println};
+use serde::Serialize;
+
+/// SLA proposals
+#[derive(Parser, Debug)]
+#[command(name = "sla_proposals")]
+#[command(about = "Propose, review and re-score goal transit days per lane and carrier")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Derive proposals from historical transit times
+    Propose {
+        /// Share of shipments to deliver within the proposed goal
+        #[arg(long, default_value_t = sla::DEFAULT_TARGET_PERCENTILE)]
+        target: f64,
+
+        /// Shipments a lane, or a carrier on a lane, needs for a proposal
+        #[arg(long, default_value_t = sla::DEFAULT_MIN_SHIPMENTS)]
+        min_shipments: i64,
+
+        /// Store the proposals for review, replacing those not yet reviewed
+        #[arg(long)]
+        save: bool,
+
+        #[command(flatten)]
+        filter: ShipmentFilter,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Show stored proposals
+    List {
+        /// Only proposals with this status
+        #[arg(long, value_enum)]
+        status: Option<ProposalStatus>,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Re-score history under the stored proposals
+    Rescore {
+        /// Only proposals with this status (default: all but rejected)
+        #[arg(long, value_enum)]
+        status: Option<ProposalStatus>,
+
+        #[command(flatten)]
+        filter: ShipmentFilter,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Approve proposals awaiting review
+    Approve(Selection),
+    /// Reject proposals awaiting review
+    Reject(Selection),
+}
+
+#[derive(ClapArgs, Debug)]
+struct Selection {
+    /// Lane as ORIGIN-DEST ZIP3s, e.g. 750-432
+    #[arg(long, required_unless_present = "all")]
+    lane: Option<String>,
+
+    /// Only this carrier's proposal on the lane (ID or display name)
+    #[arg(long, requires = "lane")]
+    carrier: Option<String>,
+
+    /// Every proposal awaiting review
+    #[arg(long, conflicts_with = "lane")]
+    all: bool,
+}
+
+/// How much of the history the re-scored proposals touch
+#[derive(Debug, Serialize)]
+struct Coverage {
+    shipments: i64,
+    covered_shipments: i64,
+    changed_shipments: i64,
+}
+
+/// One proposal as shown in the report
+#[derive(Debug, Serialize)]
+struct ProposalRow {
+    lane: String,
+    carrier: String,
+    status: String,
+    shipments: i64,
+    current_goal_days: i64,
+    proposed_goal_days: i64,
+    change_days: i64,
+    current_on_time_rate: f64,
+    proposed_on_time_rate: f64,
+    current_late_rate: f64,
+    proposed_late_rate: f64,
+}
+
+impl ProposalRow {
+    fn new(p: &SlaProposal, status: &str) -> Self {
+        Self {
+            lane: format_lane_short(&p.origin_zip, &p.dest_zip),
+            carrier: p.carrier_ref.as_deref().map(get_carrier_name).unwrap_or_else(|| "(all)".into()),
+            status: status.to_string(),
+            shipments: p.shipments,
+            current_goal_days: p.current_goal_days,
+            proposed_goal_days: p.proposed_goal_days,
+            change_days: p.change_days(),
+            current_on_time_rate: p.current_on_time_rate,
+            proposed_on_time_rate: p.proposed_on_time_rate,
+            current_late_rate: p.current_late_rate,
+            proposed_late_rate: p.proposed_late_rate,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let db = args.db.connect().await?;
+
+    match &args.command {
+        Command::Propose { target, min_shipments, save, filter, output } => {
+            if !(*target > 0.0 && *target <= 1.0) {
+                bail!("--target must be in (0, 1], e.g. 0.8 for the 80th percentile");
+            }
+            let config = SlaConfig { target_percentile: *target, min_shipments: *min_shipments };
+            let mut report = output.report("SLA Proposals");
+            report.filter = filter.describe();
+
+            let cells = sla::load_transits(&db, filter).await?;
+            if cells.is_empty() {
+                bail!("no shipments match the filters");
+            }
+            let proposals = sla::propose(&cells, &config);
+            let rows: Vec<ProposalRow> = proposals.iter().map(|p| ProposalRow::new(p, "new")).collect();
+            run_proposals(&rows, &format!("PROPOSED GOAL TRANSIT DAYS (P{:.0})", target * 100.0), &mut report)?;
+            run_rescore(&sla::rescore(&cells, &proposals), &mut report)?;
+
+            if *save {
+                sla::save(&db, &proposals).await?;
+                table_println!(report, "\n  Saved {} proposals for review (sla_proposals list / approve / reject)",
+                         proposals.len());
+            }
+            output.write(&report)
+        }
+        Command::List { status, output } => {
+            let mut report = output.report("SLA Proposals - Review");
+            let stored = sla::load(&db, *status).await?;
+            let rows: Vec<ProposalRow> = stored.iter().map(|s| ProposalRow::new(&s.proposal, s.status.as_str())).collect();
+            let title = match status {
+                Some(status) => format!("STORED PROPOSALS ({})", status.as_str()),
+                None => "STORED PROPOSALS".to_string(),
+            };
+            run_proposals(&rows, &title, &mut report)?;
+            output.write(&report)
+        }
+        Command::Rescore { status, filter, output } => {
+            let mut report = output.report("SLA Proposals - Re-score");
+            report.filter = filter.describe();
+
+            let stored: Vec<StoredProposal> = sla::load(&db, *status)
+                .await?
+                .into_iter()
+                .filter(|s| status.is_some() || s.status != ProposalStatus::Rejected)
+                .collect();
+            if stored.is_empty() {
+                bail!("no stored proposals to re-score; run `sla_proposals propose --save` first");
+            }
+            let proposals: Vec<SlaProposal> = stored.into_iter().map(|s| s.proposal).collect();
+            let cells = sla::load_transits(&db, filter).await?;
+            table_println!(report, "\n  {} stored proposals", proposals.len());
+            if !report.filter.is_empty() {
+                table_println!(report, "  Filter: {}", report.filter);
+            }
+            run_rescore(&sla::rescore(&cells, &proposals), &mut report)?;
+            output.write(&report)
+        }
+        Command::Approve(selection) => review(&db, selection, ProposalStatus::Approved).await,
+        Command::Reject(selection) => review(&db, selection, ProposalStatus::Rejected).await,
+    }
+}
+
+async fn review(db: &db::DbConn, selection: &Selection, status: ProposalStatus) -> Result<()> {
+    let selector = ProposalSelector {
+        lane: selection.lane.as_deref().map(parse_lane).transpose()?,
+        carrier_ref: selection
+            .carrier
+            .as_deref()
+            .map(|c| find_carrier_id(c).map(str::to_string).unwrap_or_else(|| c.to_string())),
+    };
+    let updated = sla::review(db, &selector, status).await?;
+    println!("{} {} proposal{}", status.as_str(), updated, if updated == 1 { "" } else { "s" });
+    Ok(())
+}
+
+fn run_proposals(rows: &[ProposalRow], title: &str, out: &mut Report) -> Result<()> {
+    out.section("proposals", title);
+
+    table_println!(out, "  {:25} {:20} {:>9} {:>5} {:>9} {:>14} {:>14} {:>9}",
+             "Lane", "Carrier", "Shipments", "Goal", "Proposed", "On-Time%", "Late%", "Status");
+    table_println!(out, "  {}", "─".repeat(114));
+    for r in rows.iter().take(40) {
+        let change = match r.change_days {
+            0 => String::new(),
+            d => format!(" ({:+})", d),
+        };
+        table_println!(out, "  {:25} {:20} {:>9} {:>4}d {:>9} {:>5.1}% → {:>5.1}% {:>5.1}% → {:>5.1}% {:>9}",
+                 r.lane, r.carrier, r.shipments, r.current_goal_days,
+                 format!("{}d{}", r.proposed_goal_days, change),
+                 r.current_on_time_rate * 100.0, r.proposed_on_time_rate * 100.0,
+                 r.current_late_rate * 100.0, r.proposed_late_rate * 100.0, r.status);
+    }
+    if rows.len() > 40 {
+        table_println!(out, "  ... {} more", rows.len() - 40);
+    }
+    let changes = rows.iter().filter(|r| r.change_days != 0).count();
+    table_println!(out, "\n  {} proposals, {} change a goal", rows.len(), changes);
+    out.add("proposals", rows)?;
+    Ok(())
+}
+
+fn run_rescore(rescore: &Rescore, out: &mut Report) -> Result<()> {
+    out.section("rescore", "RE-SCORE UNDER PROPOSED SLAs");
+
+    table_println!(out, "  {} shipments, {} covered by a proposal, {} with a changed goal",
+             rescore.shipments, rescore.covered_shipments, rescore.changed_shipments);
+    table_println!(out, "  {:12} {:>10} {:>10}", "", "Recorded", "Proposed");
+    table_println!(out, "  {}", "─".repeat(34));
+    let outcomes = rescore.outcomes();
+    for o in &outcomes {
+        table_println!(out, "  {:12} {:>9.1}% {:>9.1}%", o.outcome, o.recorded_rate * 100.0, o.proposed_rate * 100.0);
+    }
+    out.add("rescore", &outcomes)?;
+    out.add_one("coverage", &Coverage {
+        shipments: rescore.shipments,
+        covered_shipments: rescore.covered_shipments,
+        changed_shipments: rescore.changed_shipments,
+    })?;
+    Ok(())
+}
@@ -0,0 +1,214 @@
+//! Weekly volume forecast
+//! Projects shipments per week for the network, origin terminals or lanes
+//!
+//! Run: ./target/release/volume_forecast [--level network|terminal|lane] [--weeks 12] [--top 10]
+//!      ./target/release/volume_forecast --level terminal --origin-zip3 750 --weeks 16
+//!      ./target/release/volume_forecast --level lane --backtest --holdout 8
+//! Methods: --method auto|holt-winters|seasonal-naive|holt
+//! Filters: --from, --to, --mode, --carrier, --origin-zip3, --distance-bucket
+//! Output: --format table|json|csv|markdown
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use clap::Parser;
+use nyc_last_mile::forecast::{self, Level, Method, SeriesForecast, WeeklySeries};
+use nyc_last_mile::filter::ShipmentFilter;
+use nyc_last_mile::report::{OutputArgs, Report};
+use nyc_last_mile::{db, table_println};
+use serde::Serialize;
+
+/// Weekly volume forecast
+#[derive(Parser, Debug)]
+#[command(name = "volume_forecast")]
+#[command(about = "Forecast weekly shipment volume per terminal or lane, with a MAPE backtest")]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+
+    /// Series to forecast
+    #[arg(long, value_enum, default_value_t = Level::Terminal)]
+    level: Level,
+
+    /// Weeks to forecast past the last complete week
+    #[arg(long, default_value_t = 12)]
+    weeks: usize,
+
+    /// Largest series to forecast
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+
+    /// Forecasting method
+    #[arg(long, value_enum, default_value_t = Method::Auto)]
+    method: Method,
+
+    /// Score each series on its most recent weeks instead of forecasting
+    #[arg(long)]
+    backtest: bool,
+
+    /// Weeks held out by --backtest
+    #[arg(long, default_value_t = 8)]
+    holdout: usize,
+
+    #[command(flatten)]
+    filter: ShipmentFilter,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// One forecast week of one series
+#[derive(Debug, Serialize)]
+struct ForecastRow {
+    series: String,
+    week: NaiveDate,
+    forecast: f64,
+    lower_80: f64,
+    upper_80: f64,
+    lower_95: f64,
+    upper_95: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct MonthlyRow {
+    series: String,
+    month: String,
+    weeks: usize,
+    forecast: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct BacktestRow {
+    series: String,
+    method: String,
+    weeks: usize,
+    mape: Option<f64>,
+    coverage_95: f64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.weeks == 0 {
+        bail!("--weeks must be at least 1");
+    }
+
+    let title = if args.backtest { "Volume Forecast - Backtest" } else { "Volume Forecast" };
+    let mut report = args.output.report(title);
+    report.filter = args.filter.describe();
+
+    let db = args.db.connect().await?;
+    let mut series = forecast::load_weekly(&db, &args.filter, args.level).await?;
+    if series.is_empty() {
+        bail!("no complete weeks of shipments match the filters");
+    }
+    series.truncate(args.top);
+
+    let first = &series[0];
+    table_println!(report, "\n  History: {} complete weeks from {} ({} series shown)",
+             first.counts.len(), first.start, series.len());
+    if !report.filter.is_empty() {
+        table_println!(report, "  Filter: {}", report.filter);
+    }
+
+    if args.backtest {
+        run_backtest(&series, args.holdout, args.method, &mut report)?;
+    } else {
+        run_forecast(&series, args.weeks, args.method, &mut report)?;
+    }
+    args.output.write(&report)
+}
+
+fn run_forecast(series: &[WeeklySeries], weeks: usize, method: Method, out: &mut Report) -> Result<()> {
+    out.section("forecast", "WEEKLY VOLUME FORECAST");
+
+    let mut rows = Vec::new();
+    let mut monthly = Vec::new();
+    let mut skipped = Vec::new();
+    for s in series {
+        let forecast: SeriesForecast = match forecast::forecast(s, weeks, method) {
+            Ok(f) => f,
+            Err(e) => {
+                skipped.push(format!("{}: {}", s.label, e));
+                continue;
+            }
+        };
+
+        out.subsection(&format!("{} - {}, {} weeks of history, last week {:.0}",
+                                forecast.label, forecast.method.as_str(),
+                                forecast.history_weeks, forecast.last_week));
+        table_println!(out, "  {:12} {:>10} {:>17} {:>17}", "Week of", "Forecast", "80% Interval", "95% Interval");
+        table_println!(out, "  {}", "─".repeat(60));
+        for p in &forecast.points {
+            let i80 = format!("{:.0}-{:.0}", p.lower_80, p.upper_80);
+            let i95 = format!("{:.0}-{:.0}", p.lower_95, p.upper_95);
+            table_println!(out, "  {:12} {:>10.0} {:>17} {:>17}", p.week.to_string(), p.forecast, i80, i95);
+            rows.push(ForecastRow {
+                series: forecast.label.clone(),
+                week: p.week,
+                forecast: p.forecast,
+                lower_80: p.lower_80,
+                upper_80: p.upper_80,
+                lower_95: p.lower_95,
+                upper_95: p.upper_95,
+            });
+        }
+
+        let months = forecast::monthly_totals(&forecast.points);
+        let line: Vec<String> = months
+            .iter()
+            .map(|m| format!("{} {:.0} ({}w)", m.month, m.forecast, m.weeks))
+            .collect();
+        table_println!(out, "  By month: {}", line.join(", "));
+        monthly.extend(months.into_iter().map(|m| MonthlyRow {
+            series: forecast.label.clone(),
+            month: m.month,
+            weeks: m.weeks,
+            forecast: m.forecast,
+        }));
+    }
+    out.add("weekly", &rows)?;
+    out.add("monthly", &monthly)?;
+
+    if !skipped.is_empty() {
+        out.subsection("Not Forecast");
+        for line in &skipped {
+            table_println!(out, "  {}", line);
+        }
+    }
+    Ok(())
+}
+
+fn run_backtest(series: &[WeeklySeries], holdout: usize, method: Method, out: &mut Report) -> Result<()> {
+    out.section("backtest", &format!("BACKTEST (last {} weeks held out)", holdout));
+
+    table_println!(out, "  {:32} {:>15} {:>8} {:>14}", "Series", "Method", "MAPE", "95% Coverage");
+    table_println!(out, "  {}", "─".repeat(72));
+
+    let mut rows = Vec::new();
+    for s in series {
+        match forecast::backtest(s, holdout, method) {
+            Ok(b) => {
+                let mape = b.mape.map(|m| format!("{:.1}%", m * 100.0)).unwrap_or_else(|| "n/a".into());
+                table_println!(out, "  {:32} {:>15} {:>8} {:>13.0}%",
+                         b.label, b.method.as_str(), mape, b.coverage_95 * 100.0);
+                rows.push(BacktestRow {
+                    series: b.label,
+                    method: b.method.as_str().to_string(),
+                    weeks: b.weeks,
+                    mape: b.mape,
+                    coverage_95: b.coverage_95,
+                });
+            }
+            Err(e) => table_println!(out, "  {:32} skipped: {}", s.label, e),
+        }
+    }
+
+    let scored: Vec<f64> = rows.iter().filter_map(|r| r.mape).collect();
+    if !scored.is_empty() {
+        table_println!(out, "  {}", "─".repeat(72));
+        table_println!(out, "  {:32} {:>15} {:>7.1}%", "Mean", "",
+                 scored.iter().sum::<f64>() / scored.len() as f64 * 100.0);
+    }
+    out.add("backtest", &rows)?;
+    Ok(())
+}
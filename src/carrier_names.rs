@@ -80,6 +80,14 @@ pub fn get_carrier_name(carrier_id: &str) -> String {
         })
 }
 
+/// Look up a carrier ID by display name (case-insensitive)
+pub fn find_carrier_id(name: &str) -> Option<&'static str> {
+    CARRIER_NAMES
+        .iter()
+        .find(|(_, display)| display.eq_ignore_ascii_case(name.trim()))
+        .map(|(id, _)| *id)
+}
+
 /// Get carrier display name with max length (for table formatting)
 pub fn get_carrier_name_short(carrier_id: &str, max_len: usize) -> String {
     let name = get_carrier_name(carrier_id);
@@ -0,0 +1,954 @@
+//! Data-driven lane clustering
+//!
+//! Lanes are described by a feature vector (average delay, transit variance,
+//! early/on-time/late rates, log volume and distance) which is standardized
+//! and clustered with k-means or DBSCAN. Fit quality is reported as a
+//! silhouette score.
+//!
+//! The learned clusters are then named: each centroid is matched to the
+//! closest of the behavior prototypes in the playbook file (see
+//! [`crate::playbook`]), so cluster IDs and names stay the same from run to
+//! run no matter how the algorithm numbered its clusters. Lanes below the
+//! volume floor, and DBSCAN noise, go to "Low Volume / Mixed".
+//!
+//! [`save`] writes one `lane_cluster` row per lane plus a `cluster_run`
+//! summary. The API serves those assignments when they exist and falls back
+//! to [`rule_based_cluster`] on a database that has never been clustered.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::Utc;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Datetime;
+
+use crate::db::{self, DbConn};
+use crate::filter::ShipmentFilter;
+use crate::playbook::playbooks;
+
+pub use crate::playbook::MIXED_CLUSTER;
+
+/// Display name of a behavior cluster, from the active playbooks
+pub fn cluster_name(id: u8) -> &'static str {
+    playbooks().name(id)
+}
+
+// ============================================================================
+// Features
+// ============================================================================
+
+/// Per-lane inputs to the clustering
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaneFeatures {
+    /// The lane's `lane_ref`, the busiest one if its ZIP3s have several;
+    /// `None` for lanes not read from shipments
+    #[serde(default)]
+    pub lane_ref: Option<String>,
+    pub origin_zip: String,
+    pub dest_zip: String,
+    pub volume: i64,
+    pub avg_delay: f64,
+    pub transit_variance: f64,
+    pub avg_transit: f64,
+    pub early_rate: f64,
+    pub on_time_rate: f64,
+    pub late_rate: f64,
+    /// Approximate lane distance in miles, from the distance bucket
+    pub distance_miles: f64,
+}
+
+/// Number of features in [`LaneFeatures::vector`]
+const DIMS: usize = 7;
+
+impl LaneFeatures {
+    fn vector(&self) -> [f64; DIMS] {
+        [
+            self.avg_delay,
+            self.transit_variance,
+            self.early_rate,
+            self.on_time_rate,
+            self.late_rate,
+            (1.0 + self.volume as f64).ln(),
+            self.distance_miles,
+        ]
+    }
+
+    fn behavior(&self) -> [f64; 5] {
+        [self.avg_delay, self.transit_variance, self.early_rate, self.on_time_rate, self.late_rate]
+    }
+}
+
+/// `distance_bucket` label for a distance in miles
+pub fn distance_bucket_for_miles(miles: f64) -> &'static str {
+    match miles {
+        d if d < 100.0 => "0-100",
+        d if d < 250.0 => "100-250",
+        d if d < 500.0 => "250-500",
+        d if d < 1000.0 => "500-1k",
+        d if d < 2000.0 => "1k-2k",
+        _ => "2k+",
+    }
+}
+
+/// Midpoint in miles of a `distance_bucket` label
+pub fn distance_bucket_miles(bucket: &str) -> Option<f64> {
+    match bucket {
+        "0-100" => Some(50.0),
+        "100-250" => Some(175.0),
+        "250-500" => Some(375.0),
+        "500-1k" => Some(750.0),
+        "1k-2k" => Some(1500.0),
+        "2k+" => Some(2500.0),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LaneFeaturesRaw {
+    origin_zip: String,
+    dest_zip: String,
+    volume: i64,
+    avg_delay: Option<f64>,
+    transit_variance: Option<f64>,
+    avg_transit: Option<f64>,
+    early_count: i64,
+    ontime_count: i64,
+    late_count: i64,
+    distance_buckets: Vec<Option<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LaneRefRaw {
+    origin_zip: String,
+    dest_zip: String,
+    lane_ref: String,
+    shipments: i64,
+}
+
+/// Aggregate lane features from the shipment table
+pub async fn load_lane_features(db: &DbConn, filter: &ShipmentFilter) -> Result<Vec<LaneFeatures>> {
+    // Counts rather than rates: SurrealDB divides integers
+    let mut response = filter
+        .query(db, r#"
+            SELECT
+                origin_zip,
+                dest_zip,
+                count() as volume,
+                math::mean(actual_transit_days - goal_transit_days) as avg_delay,
+                math::variance(actual_transit_days) as transit_variance,
+                math::mean(actual_transit_days) as avg_transit,
+                count(IF otd = "Early" THEN 1 END) as early_count,
+                count(IF otd = "OnTime" THEN 1 END) as ontime_count,
+                count(IF otd = "Late" THEN 1 END) as late_count,
+                array::distinct(distance_bucket) as distance_buckets
+            FROM (
+                SELECT
+                    (origin_zip ?? origin_zip3) as origin_zip,
+                    (dest_zip ?? dest_zip3) as dest_zip,
+                    otd, actual_transit_days, goal_transit_days, distance_bucket
+                FROM shipment WHERE {filter}
+            )
+            GROUP BY origin_zip, dest_zip;
+
+            SELECT origin_zip, dest_zip, lane_ref, count() as shipments
+            FROM (
+                SELECT
+                    lane_ref,
+                    (origin_zip ?? origin_zip3) as origin_zip,
+                    (dest_zip ?? dest_zip3) as dest_zip
+                FROM shipment WHERE {filter} AND lane_ref != NONE
+            )
+            GROUP BY origin_zip, dest_zip, lane_ref;
+        "#)
+        .await?;
+    let raw: Vec<LaneFeaturesRaw> = response.take(0)?;
+    let lane_ref_rows: Vec<LaneRefRaw> = response.take(1)?;
+
+    let mut lane_refs: HashMap<(String, String), (i64, String)> = HashMap::new();
+    for r in lane_ref_rows {
+        let busiest = lane_refs.entry((r.origin_zip, r.dest_zip)).or_insert((r.shipments, r.lane_ref.clone()));
+        if (r.shipments, std::cmp::Reverse(&r.lane_ref)) > (busiest.0, std::cmp::Reverse(&busiest.1)) {
+            *busiest = (r.shipments, r.lane_ref);
+        }
+    }
+
+    let mut lanes: Vec<LaneFeatures> = raw
+        .into_iter()
+        .filter(|r| r.volume > 0)
+        .map(|r| {
+            let vol = r.volume as f64;
+            let miles: Vec<f64> = r
+                .distance_buckets
+                .iter()
+                .flatten()
+                .filter_map(|b| distance_bucket_miles(b))
+                .collect();
+            LaneFeatures {
+                lane_ref: lane_refs.remove(&(r.origin_zip.clone(), r.dest_zip.clone())).map(|(_, lane_ref)| lane_ref),
+                origin_zip: r.origin_zip,
+                dest_zip: r.dest_zip,
+                volume: r.volume,
+                avg_delay: r.avg_delay.unwrap_or_default(),
+                transit_variance: r.transit_variance.unwrap_or_default(),
+                avg_transit: r.avg_transit.unwrap_or_default(),
+                early_rate: r.early_count as f64 / vol,
+                on_time_rate: r.ontime_count as f64 / vol,
+                late_rate: r.late_count as f64 / vol,
+                distance_miles: if miles.is_empty() { 0.0 } else { miles.iter().sum::<f64>() / miles.len() as f64 },
+            }
+        })
+        .collect();
+
+    // Fixed input order keeps the seeded k-means reproducible
+    lanes.sort_by(|a, b| (&a.origin_zip, &a.dest_zip).cmp(&(&b.origin_zip, &b.dest_zip)));
+    Ok(lanes)
+}
+
+// ============================================================================
+// Rule-based fallback
+// ============================================================================
+
+/// The original fixed-threshold classification, kept as `--algorithm rules`
+/// and for databases without a stored clustering run. Thresholds come from
+/// the playbook file's `[rules]`.
+pub fn rule_based_cluster(lane: &LaneFeatures, min_volume: i64) -> u8 {
+    let rules = &playbooks().rules;
+    if lane.volume < min_volume {
+        return MIXED_CLUSTER;
+    }
+    if lane.avg_delay < rules.early_max_delay
+        && lane.transit_variance < rules.early_max_variance
+        && lane.early_rate > rules.early_min_rate
+    {
+        return 1;
+    }
+    // Systematically late is checked before high-jitter
+    if lane.late_rate > rules.late_min_rate {
+        return 4;
+    }
+    if lane.transit_variance > rules.jitter_min_variance {
+        return 3;
+    }
+    if lane.on_time_rate > rules.reliable_min_on_time && lane.transit_variance < rules.reliable_max_variance {
+        return 2;
+    }
+    MIXED_CLUSTER
+}
+
+// ============================================================================
+// Clustering
+// ============================================================================
+
+/// Clustering algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    /// Fixed thresholds (the pre-clustering behavior)
+    Rules,
+    #[default]
+    Kmeans,
+    Dbscan,
+}
+
+/// Clustering parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterConfig {
+    pub algorithm: Algorithm,
+    /// Number of k-means clusters
+    pub k: usize,
+    /// DBSCAN neighborhood radius, in standard deviations
+    pub eps: f64,
+    /// DBSCAN core-point neighbor count (including the point itself)
+    pub min_points: usize,
+    /// Lanes with fewer shipments are not clustered
+    pub min_volume: i64,
+    pub max_iterations: usize,
+    pub seed: u64,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::Kmeans,
+            k: 5,
+            eps: 1.5,
+            min_points: 4,
+            min_volume: playbooks().rules.min_volume,
+            max_iterations: 100,
+            seed: 42,
+        }
+    }
+}
+
+/// `--algorithm/--k/--eps/--min-points/--min-volume/--save` flags
+#[derive(Debug, Clone, clap::Args)]
+pub struct ClusterArgs {
+    /// Clustering algorithm
+    #[arg(long, value_enum, default_value_t = Algorithm::Kmeans)]
+    pub algorithm: Algorithm,
+
+    /// Number of k-means clusters
+    #[arg(long, default_value_t = 5)]
+    pub k: usize,
+
+    /// DBSCAN neighborhood radius, in standard deviations
+    #[arg(long, default_value_t = 1.5)]
+    pub eps: f64,
+
+    /// DBSCAN minimum neighbors for a core lane
+    #[arg(long = "min-points", default_value_t = 4)]
+    pub min_points: usize,
+
+    /// Lanes with fewer shipments go to "Low Volume / Mixed"
+    /// [default: the playbook's rules.min_volume, 20]
+    #[arg(long = "min-volume")]
+    pub min_volume: Option<i64>,
+
+    /// Store the assignments in `lane_cluster` for the API
+    #[arg(long)]
+    pub save: bool,
+}
+
+impl ClusterArgs {
+    pub fn config(&self) -> ClusterConfig {
+        ClusterConfig {
+            algorithm: self.algorithm,
+            k: self.k,
+            eps: self.eps,
+            min_points: self.min_points,
+            min_volume: self.min_volume.unwrap_or(playbooks().rules.min_volume),
+            ..Default::default()
+        }
+    }
+}
+
+/// One lane's cluster
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaneAssignment {
+    pub lane: LaneFeatures,
+    pub cluster_id: u8,
+    pub cluster_name: String,
+    /// Label the algorithm gave the lane (`None` for noise or unclustered)
+    pub label: Option<usize>,
+    pub silhouette: Option<f64>,
+}
+
+/// A learned cluster and the behavior it was mapped to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearnedCluster {
+    pub label: usize,
+    pub cluster_id: u8,
+    pub cluster_name: String,
+    pub lanes: usize,
+    pub shipments: i64,
+    pub avg_delay: f64,
+    pub transit_variance: f64,
+    pub early_rate: f64,
+    pub on_time_rate: f64,
+    pub late_rate: f64,
+    pub silhouette: Option<f64>,
+}
+
+/// Output of [`cluster_lanes`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusteringResult {
+    pub config: ClusterConfig,
+    /// Mean silhouette over clustered lanes (needs two or more clusters)
+    pub silhouette: Option<f64>,
+    pub clusters: Vec<LearnedCluster>,
+    pub assignments: Vec<LaneAssignment>,
+    /// Lanes DBSCAN left as noise
+    pub noise: usize,
+}
+
+/// Cluster lanes and name the clusters
+pub fn cluster_lanes(lanes: &[LaneFeatures], config: &ClusterConfig) -> ClusteringResult {
+    let eligible: Vec<usize> = (0..lanes.len()).filter(|&i| lanes[i].volume >= config.min_volume).collect();
+
+    if config.algorithm == Algorithm::Rules {
+        let assignments = lanes
+            .iter()
+            .map(|lane| {
+                let id = rule_based_cluster(lane, config.min_volume);
+                LaneAssignment {
+                    lane: lane.clone(),
+                    cluster_id: id,
+                    cluster_name: cluster_name(id).to_string(),
+                    label: None,
+                    silhouette: None,
+                }
+            })
+            .collect();
+        return ClusteringResult { config: config.clone(), silhouette: None, clusters: vec![], assignments, noise: 0 };
+    }
+
+    let scaler = Scaler::fit(eligible.iter().map(|&i| lanes[i].vector()));
+    let points: Vec<[f64; DIMS]> = eligible.iter().map(|&i| scaler.transform(&lanes[i].vector())).collect();
+
+    let labels: Vec<Option<usize>> = match config.algorithm {
+        Algorithm::Kmeans => kmeans(&points, config.k, config.max_iterations, config.seed)
+            .into_iter()
+            .map(Some)
+            .collect(),
+        Algorithm::Dbscan => dbscan(&points, config.eps, config.min_points),
+        Algorithm::Rules => unreachable!(),
+    };
+    let point_silhouettes = silhouettes(&points, &labels);
+
+    // Centroids in raw feature space, so they read as lane metrics
+    let label_count = labels.iter().flatten().max().map(|m| m + 1).unwrap_or(0);
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); label_count];
+    for (p, label) in labels.iter().enumerate() {
+        if let Some(l) = label {
+            members[*l].push(p);
+        }
+    }
+    let mut network = [0.0; 5];
+    for &i in &eligible {
+        for (d, v) in lanes[i].behavior().iter().enumerate() {
+            network[d] += v / eligible.len() as f64;
+        }
+    }
+    let centroids: Vec<[f64; 5]> = members
+        .iter()
+        .map(|m| {
+            let mut c = [0.0; 5];
+            for &p in m {
+                for (d, v) in lanes[eligible[p]].behavior().iter().enumerate() {
+                    c[d] += v / m.len() as f64;
+                }
+            }
+            c
+        })
+        .collect();
+    let names = match_behaviors(&centroids, &network);
+
+    let clusters: Vec<LearnedCluster> = members
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| !m.is_empty())
+        .map(|(label, m)| {
+            let lanes_in: Vec<&LaneFeatures> = m.iter().map(|&p| &lanes[eligible[p]]).collect();
+            let n = lanes_in.len() as f64;
+            let mean = |f: fn(&LaneFeatures) -> f64| lanes_in.iter().map(|l| f(l)).sum::<f64>() / n;
+            let sil: Vec<f64> = m.iter().filter_map(|&p| point_silhouettes[p]).collect();
+            LearnedCluster {
+                label,
+                cluster_id: names[label],
+                cluster_name: cluster_name(names[label]).to_string(),
+                lanes: lanes_in.len(),
+                shipments: lanes_in.iter().map(|l| l.volume).sum(),
+                avg_delay: mean(|l| l.avg_delay),
+                transit_variance: mean(|l| l.transit_variance),
+                early_rate: mean(|l| l.early_rate),
+                on_time_rate: mean(|l| l.on_time_rate),
+                late_rate: mean(|l| l.late_rate),
+                silhouette: if sil.is_empty() { None } else { Some(sil.iter().sum::<f64>() / sil.len() as f64) },
+            }
+        })
+        .collect();
+
+    let mut by_lane: HashMap<usize, (Option<usize>, Option<f64>)> = HashMap::new();
+    for (p, &i) in eligible.iter().enumerate() {
+        by_lane.insert(i, (labels[p], point_silhouettes[p]));
+    }
+    let assignments = lanes
+        .iter()
+        .enumerate()
+        .map(|(i, lane)| {
+            let (label, silhouette) = by_lane.get(&i).copied().unwrap_or((None, None));
+            let id = label.map(|l| names[l]).unwrap_or(MIXED_CLUSTER);
+            LaneAssignment {
+                lane: lane.clone(),
+                cluster_id: id,
+                cluster_name: cluster_name(id).to_string(),
+                label,
+                silhouette,
+            }
+        })
+        .collect();
+
+    let clustered: Vec<f64> = point_silhouettes.iter().flatten().copied().collect();
+    let silhouette = if clusters.len() >= 2 && !clustered.is_empty() {
+        Some(clustered.iter().sum::<f64>() / clustered.len() as f64)
+    } else {
+        None
+    };
+
+    ClusteringResult {
+        config: config.clone(),
+        silhouette,
+        clusters,
+        assignments,
+        noise: labels.iter().filter(|l| l.is_none()).count(),
+    }
+}
+
+/// Per-dimension z-score scaling
+struct Scaler<const N: usize> {
+    mean: [f64; N],
+    std: [f64; N],
+}
+
+impl<const N: usize> Scaler<N> {
+    fn fit(rows: impl Iterator<Item = [f64; N]>) -> Self {
+        let rows: Vec<[f64; N]> = rows.collect();
+        let n = rows.len().max(1) as f64;
+        let mut mean = [0.0; N];
+        let mut std = [0.0; N];
+        for row in &rows {
+            for d in 0..N {
+                mean[d] += row[d] / n;
+            }
+        }
+        for row in &rows {
+            for d in 0..N {
+                std[d] += (row[d] - mean[d]).powi(2) / n;
+            }
+        }
+        // A constant feature carries no information; leave it unscaled
+        for s in &mut std {
+            *s = if *s > 1e-12 { s.sqrt() } else { 1.0 };
+        }
+        Self { mean, std }
+    }
+
+    fn transform(&self, row: &[f64; N]) -> [f64; N] {
+        let mut out = [0.0; N];
+        for d in 0..N {
+            out[d] = (row[d] - self.mean[d]) / self.std[d];
+        }
+        out
+    }
+}
+
+fn distance<const N: usize>(a: &[f64; N], b: &[f64; N]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum::<f64>().sqrt()
+}
+
+/// Lloyd's k-means with k-means++ seeding. Returns a label per point.
+fn kmeans(points: &[[f64; DIMS]], k: usize, max_iterations: usize, seed: u64) -> Vec<usize> {
+    let k = k.clamp(1, points.len().max(1));
+    if points.is_empty() {
+        return vec![];
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut centroids: Vec<[f64; DIMS]> = vec![points[rng.gen_range(0..points.len())]];
+    while centroids.len() < k {
+        let weights: Vec<f64> = points
+            .iter()
+            .map(|p| centroids.iter().map(|c| distance(p, c).powi(2)).fold(f64::MAX, f64::min))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            // Fewer distinct points than k
+            break;
+        }
+        let mut target = rng.gen_range(0.0..total);
+        let mut chosen = points.len() - 1;
+        for (i, w) in weights.iter().enumerate() {
+            if target < *w {
+                chosen = i;
+                break;
+            }
+            target -= w;
+        }
+        centroids.push(points[chosen]);
+    }
+
+    let nearest = |p: &[f64; DIMS], centroids: &[[f64; DIMS]]| {
+        centroids
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i, distance(p, c)))
+            .fold((0, f64::MAX), |best, cur| if cur.1 < best.1 { cur } else { best })
+            .0
+    };
+
+    let mut labels: Vec<usize> = points.iter().map(|p| nearest(p, &centroids)).collect();
+    for _ in 0..max_iterations {
+        let mut sums = vec![[0.0; DIMS]; centroids.len()];
+        let mut counts = vec![0usize; centroids.len()];
+        for (p, &l) in points.iter().zip(&labels) {
+            counts[l] += 1;
+            for d in 0..DIMS {
+                sums[l][d] += p[d];
+            }
+        }
+        for (c, (sum, count)) in centroids.iter_mut().zip(sums.iter().zip(&counts)) {
+            if *count > 0 {
+                for d in 0..DIMS {
+                    c[d] = sum[d] / *count as f64;
+                }
+            }
+        }
+        let next: Vec<usize> = points.iter().map(|p| nearest(p, &centroids)).collect();
+        if next == labels {
+            break;
+        }
+        labels = next;
+    }
+
+    // Renumber so labels are contiguous even if a centroid lost all points
+    let mut renumber: HashMap<usize, usize> = HashMap::new();
+    labels
+        .iter()
+        .map(|l| {
+            let next = renumber.len();
+            *renumber.entry(*l).or_insert(next)
+        })
+        .collect()
+}
+
+/// DBSCAN. Returns a label per point, `None` for noise.
+fn dbscan(points: &[[f64; DIMS]], eps: f64, min_points: usize) -> Vec<Option<usize>> {
+    let neighbors = |i: usize| -> Vec<usize> {
+        (0..points.len()).filter(|&j| distance(&points[i], &points[j]) <= eps).collect()
+    };
+
+    let mut labels: Vec<Option<usize>> = vec![None; points.len()];
+    let mut visited = vec![false; points.len()];
+    let mut next_label = 0;
+
+    for i in 0..points.len() {
+        if visited[i] {
+            continue;
+        }
+        visited[i] = true;
+        let seeds = neighbors(i);
+        if seeds.len() < min_points {
+            continue;
+        }
+
+        let label = next_label;
+        next_label += 1;
+        labels[i] = Some(label);
+        let mut queue = seeds;
+        while let Some(j) = queue.pop() {
+            if labels[j].is_none() {
+                labels[j] = Some(label);
+            }
+            if visited[j] {
+                continue;
+            }
+            visited[j] = true;
+            let more = neighbors(j);
+            if more.len() >= min_points {
+                queue.extend(more);
+            }
+        }
+    }
+    labels
+}
+
+/// Silhouette of each labeled point (`None` for noise)
+fn silhouettes(points: &[[f64; DIMS]], labels: &[Option<usize>]) -> Vec<Option<f64>> {
+    let label_count = labels.iter().flatten().max().map(|m| m + 1).unwrap_or(0);
+    if label_count < 2 {
+        return vec![None; points.len()];
+    }
+    points
+        .iter()
+        .zip(labels)
+        .enumerate()
+        .map(|(i, (p, label))| {
+            let own = (*label)?;
+            let mut sums = vec![0.0; label_count];
+            let mut counts = vec![0usize; label_count];
+            for (j, (q, other)) in points.iter().zip(labels).enumerate() {
+                match other {
+                    Some(l) if j != i => {
+                        sums[*l] += distance(p, q);
+                        counts[*l] += 1;
+                    }
+                    _ => {}
+                }
+            }
+            if counts[own] == 0 {
+                // Singleton cluster
+                return Some(0.0);
+            }
+            let a = sums[own] / counts[own] as f64;
+            let b = (0..label_count)
+                .filter(|&l| l != own && counts[l] > 0)
+                .map(|l| sums[l] / counts[l] as f64)
+                .fold(f64::MAX, f64::min);
+            if b == f64::MAX {
+                return Some(0.0);
+            }
+            let denom = a.max(b);
+            Some(if denom > 0.0 { (b - a) / denom } else { 0.0 })
+        })
+        .collect()
+}
+
+/// What counts as one unit of difference when matching a centroid to a
+/// prototype: a day of delay, 2 days² of variance, 15 points of a rate.
+/// Fixed rather than fitted, so a feature that barely varies across lanes
+/// doesn't dominate the match.
+const BEHAVIOR_SCALE: [f64; 5] = [1.0, 2.0, 0.15, 0.15, 0.15];
+
+/// Map centroids (`[avg_delay, variance, early, on_time, late]`) to behavior
+/// IDs. Clusters without a prototype ("Low Volume / Mixed") sit at the
+/// network average. With at most five clusters each behavior is used once
+/// (cheapest total distance); beyond that each centroid takes its nearest
+/// behavior.
+fn match_behaviors(centroids: &[[f64; 5]], network: &[f64; 5]) -> Vec<u8> {
+    let scaled = |v: &[f64; 5]| {
+        let mut out = [0.0; 5];
+        for d in 0..5 {
+            out[d] = v[d] / BEHAVIOR_SCALE[d];
+        }
+        out
+    };
+    let prototypes: Vec<(u8, [f64; 5])> = playbooks()
+        .sorted()
+        .into_iter()
+        .map(|c| (c.id, scaled(&c.prototype.map(|p| p.to_array()).unwrap_or(*network))))
+        .collect();
+    let cost: Vec<Vec<f64>> = centroids
+        .iter()
+        .map(|c| prototypes.iter().map(|(_, p)| distance(&scaled(c), p)).collect())
+        .collect();
+
+    if centroids.len() > prototypes.len() {
+        return cost
+            .iter()
+            .map(|row| {
+                let best = (0..row.len()).fold(0, |b, j| if row[j] < row[b] { j } else { b });
+                prototypes[best].0
+            })
+            .collect();
+    }
+
+    fn search(cost: &[Vec<f64>], row: usize, used: &mut Vec<bool>, current: &mut Vec<usize>, best: &mut (f64, Vec<usize>)) {
+        if row == cost.len() {
+            let total: f64 = current.iter().enumerate().map(|(r, &c)| cost[r][c]).sum();
+            if total < best.0 {
+                *best = (total, current.clone());
+            }
+            return;
+        }
+        for col in 0..used.len() {
+            if !used[col] {
+                used[col] = true;
+                current.push(col);
+                search(cost, row + 1, used, current, best);
+                current.pop();
+                used[col] = false;
+            }
+        }
+    }
+
+    let mut best = (f64::MAX, Vec::new());
+    search(&cost, 0, &mut vec![false; prototypes.len()], &mut Vec::new(), &mut best);
+    best.1.into_iter().map(|col| prototypes[col].0).collect()
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+/// Row stored in `lane_cluster`. Rates are percentages, as the MCP tools
+/// expect.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaneClusterRow {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lane_ref: Option<String>,
+    pub origin_zip: String,
+    pub dest_zip: String,
+    pub cluster_id: u8,
+    pub cluster_name: String,
+    pub shipments: i64,
+    pub otd_rate: f64,
+    pub late_rate: f64,
+    pub early_rate: f64,
+    pub avg_delay: f64,
+    pub avg_transit: f64,
+    pub transit_variance: f64,
+    pub silhouette: Option<f64>,
+}
+
+/// Replace the stored assignments with `result` and log the run
+pub async fn save(db: &DbConn, result: &ClusteringResult) -> Result<()> {
+    let rows: Vec<LaneClusterRow> = result
+        .assignments
+        .iter()
+        .map(|a| LaneClusterRow {
+            lane_ref: a.lane.lane_ref.clone(),
+            origin_zip: a.lane.origin_zip.clone(),
+            dest_zip: a.lane.dest_zip.clone(),
+            cluster_id: a.cluster_id,
+            cluster_name: a.cluster_name.clone(),
+            shipments: a.lane.volume,
+            otd_rate: a.lane.on_time_rate * 100.0,
+            late_rate: a.lane.late_rate * 100.0,
+            early_rate: a.lane.early_rate * 100.0,
+            avg_delay: a.lane.avg_delay,
+            avg_transit: a.lane.avg_transit,
+            transit_variance: a.lane.transit_variance,
+            silhouette: a.silhouette,
+        })
+        .collect();
+
+    #[derive(Serialize)]
+    struct Run {
+        config: ClusterConfig,
+        silhouette: Option<f64>,
+        clusters: Vec<LearnedCluster>,
+        lanes: usize,
+        noise: usize,
+        created_at: Datetime,
+    }
+    let run = Run {
+        config: result.config.clone(),
+        silhouette: result.silhouette,
+        clusters: result.clusters.clone(),
+        lanes: rows.len(),
+        noise: result.noise,
+        created_at: Datetime::from(Utc::now()),
+    };
+
+    db.query(r#"
+        BEGIN TRANSACTION;
+        DELETE lane_cluster;
+        INSERT INTO lane_cluster $rows;
+        CREATE cluster_run CONTENT $run;
+        COMMIT TRANSACTION;
+    "#)
+    .bind(("rows", rows))
+    .bind(("run", run))
+    .await?
+    .check()?;
+
+    // Cached lane metrics carry cluster IDs
+    db::bump_data_version(db).await?;
+    Ok(())
+}
+
+/// Stored cluster ID per lane, keyed by `(origin_zip, dest_zip)`
+pub async fn load_assignments(db: &DbConn) -> Result<HashMap<(String, String), u8>> {
+    #[derive(Deserialize)]
+    struct Row {
+        origin_zip: String,
+        dest_zip: String,
+        cluster_id: u8,
+    }
+    let rows: Vec<Row> = db
+        .query("SELECT origin_zip, dest_zip, cluster_id FROM lane_cluster")
+        .await?
+        .take(0)?;
+    Ok(rows.into_iter().map(|r| ((r.origin_zip, r.dest_zip), r.cluster_id)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lane(name: &str, avg_delay: f64, variance: f64, rates: [f64; 3], volume: i64) -> LaneFeatures {
+        LaneFeatures {
+            lane_ref: None,
+            origin_zip: name.to_string(),
+            dest_zip: "100xx".to_string(),
+            volume,
+            avg_delay,
+            transit_variance: variance,
+            avg_transit: 3.0,
+            early_rate: rates[0],
+            on_time_rate: rates[1],
+            late_rate: rates[2],
+            distance_miles: 375.0,
+        }
+    }
+
+    /// Two tight groups: clearly late lanes and clearly early ones
+    fn two_groups() -> Vec<LaneFeatures> {
+        let mut lanes = Vec::new();
+        for i in 0..6 {
+            let jitter = i as f64 * 0.01;
+            lanes.push(lane(&format!("l{}", i), 1.6 + jitter, 2.4, [0.05, 0.33 - jitter, 0.62 + jitter], 200));
+            lanes.push(lane(&format!("e{}", i), -1.1 - jitter, 0.9, [0.56 + jitter, 0.34, 0.10 - jitter], 200));
+        }
+        lanes
+    }
+
+    #[test]
+    fn test_kmeans_names_clusters_by_behavior() {
+        let config = ClusterConfig { k: 2, ..Default::default() };
+        let result = cluster_lanes(&two_groups(), &config);
+
+        assert_eq!(result.clusters.len(), 2);
+        for a in &result.assignments {
+            let expected = if a.lane.origin_zip.starts_with('l') { 4 } else { 1 };
+            assert_eq!(a.cluster_id, expected, "{}", a.lane.origin_zip);
+        }
+        assert!(result.silhouette.unwrap() > 0.8, "{:?}", result.silhouette);
+    }
+
+    #[test]
+    fn test_labels_are_stable_across_seeds() {
+        let lanes = two_groups();
+        let ids = |seed| {
+            let config = ClusterConfig { k: 2, seed, ..Default::default() };
+            cluster_lanes(&lanes, &config).assignments.iter().map(|a| a.cluster_id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(1), ids(7));
+    }
+
+    #[test]
+    fn test_dbscan_noise_and_low_volume_are_mixed() {
+        let mut lanes = two_groups();
+        lanes.push(lane("outlier", 0.3, 9.0, [0.3, 0.3, 0.4], 200));
+        lanes.push(lane("tiny", 3.0, 0.0, [0.0, 0.0, 1.0], 2));
+        let config = ClusterConfig { algorithm: Algorithm::Dbscan, eps: 1.0, min_points: 3, ..Default::default() };
+        let result = cluster_lanes(&lanes, &config);
+
+        assert_eq!(result.clusters.len(), 2);
+        assert_eq!(result.noise, 1);
+        let by_name: HashMap<&str, u8> = result
+            .assignments
+            .iter()
+            .map(|a| (a.lane.origin_zip.as_str(), a.cluster_id))
+            .collect();
+        assert_eq!(by_name["outlier"], MIXED_CLUSTER);
+        assert_eq!(by_name["tiny"], MIXED_CLUSTER);
+        assert_eq!(by_name["l0"], 4);
+        assert_eq!(by_name["e0"], 1);
+    }
+
+    #[test]
+    fn test_rule_based_cluster() {
+        assert_eq!(rule_based_cluster(&lane("a", 0.5, 1.0, [0.1, 0.4, 0.5], 100), 20), 4);
+        assert_eq!(rule_based_cluster(&lane("a", 0.0, 1.0, [0.1, 0.8, 0.1], 100), 20), 2);
+        assert_eq!(rule_based_cluster(&lane("a", 0.0, 1.0, [0.1, 0.8, 0.1], 5), 20), MIXED_CLUSTER);
+    }
+
+    #[tokio::test]
+    async fn test_load_lane_features_lane_ref() {
+        let db = crate::db::connect("mem://").await.unwrap();
+        crate::db::init_schema(&db).await.unwrap();
+        db.query(r#"
+            CREATE shipment SET lane_ref = "109c918ef6db", origin_zip = "750xx", dest_zip = "432xx",
+                otd = "OnTime", actual_transit_days = 3, goal_transit_days = 3;
+            CREATE shipment SET lane_ref = "109c918ef6db", origin_zip = "750xx", dest_zip = "432xx",
+                otd = "Late", actual_transit_days = 5, goal_transit_days = 3;
+            CREATE shipment SET lane_ref = "5f2a7d01c3b4", origin_zip = "750xx", dest_zip = "432xx",
+                otd = "OnTime", actual_transit_days = 3, goal_transit_days = 3;
+            CREATE shipment SET lane_ref = "e81b44c09a7d", origin_zip3 = "152xx", dest_zip3 = "100xx",
+                origin_zip5 = "15201", otd = "Early", actual_transit_days = 2, goal_transit_days = 3;
+        "#)
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let lanes = load_lane_features(&db, &ShipmentFilter::default()).await.unwrap();
+        let refs: Vec<(&str, Option<&str>)> =
+            lanes.iter().map(|l| (l.origin_zip.as_str(), l.lane_ref.as_deref())).collect();
+        assert_eq!(refs, [("152xx", Some("e81b44c09a7d")), ("750xx", Some("109c918ef6db"))]);
+        assert_eq!(lanes[1].volume, 3);
+    }
+}
@@ -0,0 +1,449 @@
+//! Period-over-period comparison
+//!
+//! Computes the same KPIs for a baseline and a current ship-date window and
+//! reports the deltas: overall KPIs, OTD rate per carrier, lanes whose late
+//! rate moved, and lanes that only shipped in one of the windows. Rate deltas
+//! carry a two-proportion z-test so small-sample noise is not read as a trend.
+//!
+//! Segment filters (mode, carrier, origin, distance) apply to both windows.
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::carrier_names::get_carrier_name;
+use crate::db::DbConn;
+use crate::filter::ShipmentFilter;
+use crate::location_names::format_lane_short;
+use crate::stats::{is_significant, mean_difference_z, two_proportion_z};
+
+/// Inclusive ship-date window; an open end is unbounded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeriodWindow {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl PeriodWindow {
+    pub fn new(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Self {
+        Self { from, to }
+    }
+
+    /// `2024-01-01..2024-03-31`, with `..` for an open end
+    pub fn label(&self) -> String {
+        let date = |d: Option<NaiveDate>| d.map(|d| d.to_string()).unwrap_or_default();
+        format!("{}..{}", date(self.from), date(self.to))
+    }
+
+    fn apply(&self, segment: &ShipmentFilter) -> ShipmentFilter {
+        ShipmentFilter {
+            from: self.from,
+            to: self.to,
+            ..segment.clone()
+        }
+    }
+}
+
+/// What to compare
+#[derive(Debug, Clone)]
+pub struct CompareRequest {
+    pub baseline: PeriodWindow,
+    pub current: PeriodWindow,
+    /// Segment filters; its date fields are ignored
+    pub segment: ShipmentFilter,
+    /// Shipments a carrier or lane needs in each window to be compared
+    pub min_volume: i64,
+    /// Late-rate move (as a fraction) for a lane to be listed
+    pub min_change: f64,
+}
+
+impl Default for CompareRequest {
+    fn default() -> Self {
+        Self {
+            baseline: PeriodWindow::default(),
+            current: PeriodWindow::default(),
+            segment: ShipmentFilter::default(),
+            min_volume: 20,
+            min_change: 0.10,
+        }
+    }
+}
+
+/// `--baseline-from/--baseline-to/--min-change/--min-volume` flags. The
+/// current window is the `--from/--to` of the shipment filter.
+#[derive(Debug, Clone, clap::Args)]
+pub struct CompareArgs {
+    /// Compare against a baseline window starting on this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub baseline_from: Option<NaiveDate>,
+
+    /// Last ship date of the baseline window (YYYY-MM-DD)
+    #[arg(long)]
+    pub baseline_to: Option<NaiveDate>,
+
+    /// Late-rate move, in percentage points, for a lane to be listed
+    #[arg(long, default_value_t = 10.0)]
+    pub min_change: f64,
+
+    /// Shipments a carrier or lane needs in each window to be compared
+    #[arg(long, default_value_t = 20)]
+    pub min_volume: i64,
+}
+
+impl CompareArgs {
+    /// The comparison to run, or `None` when no baseline window was given
+    pub fn request(&self, filter: &ShipmentFilter) -> Result<Option<CompareRequest>> {
+        if self.baseline_from.is_none() && self.baseline_to.is_none() {
+            return Ok(None);
+        }
+        if filter.from.is_none() && filter.to.is_none() {
+            bail!("--baseline-from/--baseline-to need a current window: pass --from and/or --to");
+        }
+        Ok(Some(CompareRequest {
+            baseline: PeriodWindow::new(self.baseline_from, self.baseline_to),
+            current: PeriodWindow::new(filter.from, filter.to),
+            segment: filter.clone(),
+            min_volume: self.min_volume,
+            min_change: self.min_change / 100.0,
+        }))
+    }
+}
+
+/// One KPI in both windows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub baseline: f64,
+    pub current: f64,
+    pub delta: f64,
+    pub z_score: f64,
+    pub significant: bool,
+}
+
+/// Carrier OTD and late rate in both windows; z-test on the OTD rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarrierDelta {
+    pub carrier_ref: String,
+    pub carrier_name: String,
+    pub baseline_volume: i64,
+    pub current_volume: i64,
+    pub baseline_otd_rate: f64,
+    pub current_otd_rate: f64,
+    pub otd_delta: f64,
+    pub baseline_late_rate: f64,
+    pub current_late_rate: f64,
+    pub late_delta: f64,
+    pub z_score: f64,
+    pub significant: bool,
+}
+
+/// Lane late rate in both windows; z-test on the late rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaneDelta {
+    pub lane_ref: String,
+    pub route: String,
+    pub baseline_volume: i64,
+    pub current_volume: i64,
+    pub baseline_late_rate: f64,
+    pub current_late_rate: f64,
+    pub late_delta: f64,
+    pub z_score: f64,
+    pub significant: bool,
+}
+
+/// A lane that shipped in only one window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaneChange {
+    pub lane_ref: String,
+    pub route: String,
+    pub volume: i64,
+    pub late_rate: f64,
+}
+
+/// Result of [`compare_periods`]; rates and deltas are fractions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodComparison {
+    pub baseline: PeriodWindow,
+    pub current: PeriodWindow,
+    pub kpis: Vec<MetricDelta>,
+    pub carriers: Vec<CarrierDelta>,
+    pub lane_movers: Vec<LaneDelta>,
+    pub new_lanes: Vec<LaneChange>,
+    pub lost_lanes: Vec<LaneChange>,
+}
+
+/// OTD counts for one group in one window
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Counts {
+    #[serde(default)]
+    carrier_ref: String,
+    #[serde(default)]
+    lane_ref: String,
+    #[serde(default)]
+    origin_zip: Option<String>,
+    #[serde(default)]
+    dest_zip: Option<String>,
+    total: i64,
+    on_time: i64,
+    late: i64,
+    early: i64,
+    avg_transit: Option<f64>,
+    transit_variance: Option<f64>,
+}
+
+impl Counts {
+    fn rate(&self, count: i64) -> f64 {
+        if self.total > 0 { count as f64 / self.total as f64 } else { 0.0 }
+    }
+
+    fn route(&self) -> String {
+        match (&self.origin_zip, &self.dest_zip) {
+            (Some(origin), Some(dest)) => format_lane_short(origin, dest),
+            _ => self.lane_ref.clone(),
+        }
+    }
+}
+
+/// Counts per group; rates are computed in Rust to avoid integer division
+const COUNTS_SQL: &str = r#"
+    SELECT
+        {group_fields}
+        count() as total,
+        count(IF otd = "OnTime" THEN 1 END) as on_time,
+        count(IF otd = "Late" THEN 1 END) as late,
+        count(IF otd = "Early" THEN 1 END) as early,
+        math::mean(actual_transit_days) as avg_transit,
+        math::variance(actual_transit_days) as transit_variance
+    FROM shipment WHERE {filter}
+    GROUP {group_by}
+"#;
+
+async fn counts(db: &DbConn, filter: &ShipmentFilter, group: &[&str]) -> Result<Vec<Counts>> {
+    let (fields, group_by) = if group.is_empty() {
+        (String::new(), "ALL".to_string())
+    } else {
+        (format!("{},", group.join(", ")), format!("BY {}", group.join(", ")))
+    };
+    let sql = COUNTS_SQL
+        .replace("{group_fields}", &fields)
+        .replace("{group_by}", &group_by);
+    let rows: Vec<Counts> = filter.query(db, &sql).await?.take(0)?;
+    Ok(rows)
+}
+
+fn kpi_deltas(base: &Counts, cur: &Counts) -> Vec<MetricDelta> {
+    let rate = |metric: &str, x1: i64, x2: i64| {
+        let z = two_proportion_z(x1, base.total, x2, cur.total);
+        MetricDelta {
+            metric: metric.to_string(),
+            baseline: base.rate(x1),
+            current: cur.rate(x2),
+            delta: cur.rate(x2) - base.rate(x1),
+            z_score: z,
+            significant: is_significant(z),
+        }
+    };
+    let (m1, m2) = (base.avg_transit.unwrap_or(0.0), cur.avg_transit.unwrap_or(0.0));
+    let z = mean_difference_z(
+        m1, base.transit_variance.unwrap_or(0.0), base.total,
+        m2, cur.transit_variance.unwrap_or(0.0), cur.total,
+    );
+    vec![
+        MetricDelta {
+            metric: "shipments".to_string(),
+            baseline: base.total as f64,
+            current: cur.total as f64,
+            delta: (cur.total - base.total) as f64,
+            z_score: 0.0,
+            significant: false,
+        },
+        rate("otd_rate", base.on_time, cur.on_time),
+        rate("late_rate", base.late, cur.late),
+        rate("early_rate", base.early, cur.early),
+        MetricDelta {
+            metric: "avg_transit_days".to_string(),
+            baseline: m1,
+            current: m2,
+            delta: m2 - m1,
+            z_score: z,
+            significant: is_significant(z),
+        },
+    ]
+}
+
+fn carrier_deltas(base: Vec<Counts>, cur: Vec<Counts>, min_volume: i64) -> Vec<CarrierDelta> {
+    let base: BTreeMap<String, Counts> = base.into_iter().map(|c| (c.carrier_ref.clone(), c)).collect();
+    let mut deltas: Vec<CarrierDelta> = cur
+        .into_iter()
+        .filter_map(|c| {
+            let b = base.get(&c.carrier_ref)?;
+            if b.total < min_volume || c.total < min_volume {
+                return None;
+            }
+            let z = two_proportion_z(b.on_time, b.total, c.on_time, c.total);
+            Some(CarrierDelta {
+                carrier_name: get_carrier_name(&c.carrier_ref),
+                baseline_volume: b.total,
+                current_volume: c.total,
+                baseline_otd_rate: b.rate(b.on_time),
+                current_otd_rate: c.rate(c.on_time),
+                otd_delta: c.rate(c.on_time) - b.rate(b.on_time),
+                baseline_late_rate: b.rate(b.late),
+                current_late_rate: c.rate(c.late),
+                late_delta: c.rate(c.late) - b.rate(b.late),
+                z_score: z,
+                significant: is_significant(z),
+                carrier_ref: c.carrier_ref,
+            })
+        })
+        .collect();
+    deltas.sort_by(|a, b| a.otd_delta.partial_cmp(&b.otd_delta).unwrap_or(std::cmp::Ordering::Equal));
+    deltas
+}
+
+fn lane_deltas(
+    base: Vec<Counts>,
+    cur: Vec<Counts>,
+    min_volume: i64,
+    min_change: f64,
+) -> (Vec<LaneDelta>, Vec<LaneChange>, Vec<LaneChange>) {
+    let mut base: BTreeMap<String, Counts> = base.into_iter().map(|c| (c.lane_ref.clone(), c)).collect();
+    let change = |c: &Counts| LaneChange {
+        lane_ref: c.lane_ref.clone(),
+        route: c.route(),
+        volume: c.total,
+        late_rate: c.rate(c.late),
+    };
+
+    let mut movers = Vec::new();
+    let mut new_lanes = Vec::new();
+    for c in &cur {
+        let Some(b) = base.remove(&c.lane_ref) else {
+            new_lanes.push(change(c));
+            continue;
+        };
+        if b.total < min_volume || c.total < min_volume {
+            continue;
+        }
+        let delta = c.rate(c.late) - b.rate(b.late);
+        if delta.abs() < min_change {
+            continue;
+        }
+        let z = two_proportion_z(b.late, b.total, c.late, c.total);
+        movers.push(LaneDelta {
+            lane_ref: c.lane_ref.clone(),
+            route: c.route(),
+            baseline_volume: b.total,
+            current_volume: c.total,
+            baseline_late_rate: b.rate(b.late),
+            current_late_rate: c.rate(c.late),
+            late_delta: delta,
+            z_score: z,
+            significant: is_significant(z),
+        });
+    }
+    let mut lost_lanes: Vec<LaneChange> = base.values().map(change).collect();
+
+    movers.sort_by(|a, b| b.late_delta.abs().partial_cmp(&a.late_delta.abs()).unwrap_or(std::cmp::Ordering::Equal));
+    new_lanes.sort_by_key(|l| std::cmp::Reverse(l.volume));
+    lost_lanes.sort_by_key(|l| std::cmp::Reverse(l.volume));
+    (movers, new_lanes, lost_lanes)
+}
+
+/// Compare the baseline and current windows of `request`
+pub async fn compare_periods(db: &DbConn, request: &CompareRequest) -> Result<PeriodComparison> {
+    let base_filter = request.baseline.apply(&request.segment);
+    let cur_filter = request.current.apply(&request.segment);
+
+    let overall = |rows: Vec<Counts>| rows.into_iter().next().unwrap_or_default();
+    let base_kpi = overall(counts(db, &base_filter, &[]).await?);
+    let cur_kpi = overall(counts(db, &cur_filter, &[]).await?);
+
+    let carriers = carrier_deltas(
+        counts(db, &base_filter, &["carrier_ref"]).await?,
+        counts(db, &cur_filter, &["carrier_ref"]).await?,
+        request.min_volume,
+    );
+
+    let lane_group = ["lane_ref", "origin_zip", "dest_zip"];
+    let (lane_movers, new_lanes, lost_lanes) = lane_deltas(
+        counts(db, &base_filter, &lane_group).await?,
+        counts(db, &cur_filter, &lane_group).await?,
+        request.min_volume,
+        request.min_change,
+    );
+
+    Ok(PeriodComparison {
+        baseline: request.baseline,
+        current: request.current,
+        kpis: kpi_deltas(&base_kpi, &cur_kpi),
+        carriers,
+        lane_movers,
+        new_lanes,
+        lost_lanes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lane(lane_ref: &str, total: i64, late: i64) -> Counts {
+        Counts {
+            lane_ref: lane_ref.to_string(),
+            total,
+            late,
+            on_time: total - late,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lane_deltas() {
+        let base = vec![lane("a", 100, 10), lane("b", 100, 30), lane("lost", 40, 4)];
+        let cur = vec![lane("a", 100, 40), lane("b", 100, 32), lane("new", 25, 5)];
+        let (movers, new_lanes, lost_lanes) = lane_deltas(base, cur, 20, 0.10);
+
+        assert_eq!(movers.len(), 1);
+        assert_eq!(movers[0].lane_ref, "a");
+        assert!((movers[0].late_delta - 0.30).abs() < 1e-9);
+        assert!(movers[0].significant);
+        assert_eq!(new_lanes[0].lane_ref, "new");
+        assert_eq!(lost_lanes[0].lane_ref, "lost");
+    }
+
+    #[test]
+    fn test_carrier_deltas_need_volume_in_both_windows() {
+        let carrier = |id: &str, total, on_time| Counts {
+            carrier_ref: id.to_string(),
+            total,
+            on_time,
+            ..Default::default()
+        };
+        let base = vec![carrier("x", 200, 150), carrier("y", 5, 5)];
+        let cur = vec![carrier("x", 200, 120), carrier("y", 200, 100)];
+        let deltas = carrier_deltas(base, cur, 20);
+        assert_eq!(deltas.len(), 1);
+        assert!((deltas[0].otd_delta + 0.15).abs() < 1e-9);
+        assert!(deltas[0].significant);
+    }
+
+    #[test]
+    fn test_request_needs_current_window() {
+        let args = CompareArgs {
+            baseline_from: NaiveDate::from_ymd_opt(2024, 1, 1),
+            baseline_to: None,
+            min_change: 5.0,
+            min_volume: 10,
+        };
+        assert!(args.request(&ShipmentFilter::default()).is_err());
+        let filter = ShipmentFilter {
+            from: NaiveDate::from_ymd_opt(2024, 4, 1),
+            ..Default::default()
+        };
+        let request = args.request(&filter).unwrap().unwrap();
+        assert_eq!(request.current.label(), "2024-04-01..");
+        assert!((request.min_change - 0.05).abs() < 1e-9);
+    }
+}
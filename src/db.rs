@@ -1,54 +1,148 @@
-use anyhow::Result;
-use surrealdb::engine::local::{Db, RocksDb};
+//! Database connection, shared by every binary.
+//!
+//! A database is named by an engine URL, so the same `--db` flag selects an
+//! embedded store, a throwaway in-memory one or a shared SurrealDB server:
+//!
+//! - `rocksdb://data/lastmile.db` (a bare path means the same)
+//! - `mem://` (empty on every connect; tests load fixtures into it)
+//! - `ws://localhost:8000` (signed in as root when `SURREAL_USER` and
+//!   `SURREAL_PASS` are set)
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use surrealdb::engine::any::{self, Any};
+use surrealdb::opt::auth::Root;
 use surrealdb::Surreal;
 
-pub type DbConn = Surreal<Db>;
+pub type DbConn = Surreal<Any>;
+
+/// Namespace shared by every binary and schema flavor. Databases from the
+/// binaries that used other namespaces are listed in
+/// [`crate::migrations::LEGACY_DATABASES`].
+pub const NAMESPACE: &str = "lastmile";
+/// Database shared by every binary and schema flavor
+pub const DATABASE: &str = "nyc";
+
+/// Default `--db` for binaries that read the main database
+pub const DEFAULT_DB: &str = "rocksdb://data/lastmile.db";
+
+/// Environment variables with root credentials for a remote server
+pub const USER_ENV: &str = "SURREAL_USER";
+pub const PASS_ENV: &str = "SURREAL_PASS";
+
+/// `--db` flag shared by the binaries
+#[derive(Debug, Clone, clap::Args)]
+pub struct DbArgs {
+    /// Database URL: rocksdb://PATH, mem:// or ws://HOST:PORT (a bare path is a RocksDB directory)
+    #[arg(long = "db", value_name = "URL", default_value = DEFAULT_DB, global = true)]
+    pub url: String,
+}
+
+impl DbArgs {
+    /// Connect to the database named by `--db`
+    pub async fn connect(&self) -> Result<DbConn> {
+        connect(&self.url).await
+    }
+
+    /// Directory of an embedded on-disk database (see [`local_path`])
+    pub fn local_path(&self) -> Option<&Path> {
+        local_path(&self.url)
+    }
+}
+
+/// Engine URL for a `--db` value; a bare path keeps meaning a RocksDB directory
+pub fn endpoint(target: &str) -> String {
+    if target.contains("://") || target == "memory" {
+        target.to_string()
+    } else {
+        format!("rocksdb://{}", target)
+    }
+}
+
+/// Directory of an embedded on-disk database, `None` for memory and remote engines
+pub fn local_path(target: &str) -> Option<&Path> {
+    if !target.contains("://") {
+        return Some(Path::new(target));
+    }
+    ["rocksdb://", "surrealkv://"]
+        .iter()
+        .find_map(|scheme| target.strip_prefix(scheme))
+        .map(Path::new)
+}
 
-/// Initialize database connection with RocksDB backend
-pub async fn connect(path: &str) -> Result<DbConn> {
-    let db = Surreal::new::<RocksDb>(path).await?;
-    db.use_ns("lastmile").use_db("nyc").await?;
+/// Whether `target` names a SurrealDB server rather than an embedded engine
+pub fn is_remote(target: &str) -> bool {
+    target.starts_with("ws://") || target.starts_with("wss://")
+}
+
+/// Connect to the database at `target` (see the module docs for the URL forms)
+/// and select the shared namespace and database
+pub async fn connect(target: &str) -> Result<DbConn> {
+    let endpoint = endpoint(target);
+    let db = any::connect(endpoint.as_str())
+        .await
+        .with_context(|| format!("connecting to {}", endpoint))?;
+    if is_remote(&endpoint) {
+        if let (Ok(username), Ok(password)) = (std::env::var(USER_ENV), std::env::var(PASS_ENV)) {
+            db.signin(Root { username: &username, password: &password })
+                .await
+                .with_context(|| format!("signing in to {} as {}", endpoint, username))?;
+        }
+    }
+    db.use_ns(NAMESPACE).use_db(DATABASE).await?;
     Ok(db)
 }
 
-/// Initialize database schema
+/// Initialize database schema by applying any pending migrations
+/// (see [`crate::migrations`])
 pub async fn init_schema(db: &DbConn) -> Result<()> {
-    // Create tables with schema
-    db.query(
-        r#"
-        -- Shipment table (schemaless for flexibility)
-        DEFINE TABLE shipment SCHEMALESS;
-        DEFINE INDEX idx_load_id ON shipment FIELDS load_id UNIQUE;
-        DEFINE INDEX idx_ship_date ON shipment FIELDS actual_ship;
-        DEFINE INDEX idx_otd ON shipment FIELDS otd;
-        DEFINE INDEX idx_carrier_mode ON shipment FIELDS carrier_mode;
-
-        -- Carrier table
-        DEFINE TABLE carrier SCHEMAFULL;
-        DEFINE FIELD carrier_id ON carrier TYPE string;
-        DEFINE INDEX idx_carrier_id ON carrier FIELDS carrier_id UNIQUE;
-
-        -- Location table (ZIP3 regions)
-        DEFINE TABLE location SCHEMAFULL;
-        DEFINE FIELD zip3 ON location TYPE string;
-        DEFINE FIELD state ON location TYPE option<string>;
-        DEFINE INDEX idx_zip3 ON location FIELDS zip3 UNIQUE;
-
-        -- Lane table
-        DEFINE TABLE lane SCHEMAFULL;
-        DEFINE FIELD lane_id ON lane TYPE string;
-        DEFINE FIELD zip3_pair ON lane TYPE string;
-        DEFINE INDEX idx_lane_id ON lane FIELDS lane_id UNIQUE;
-
-        -- Relationships (graph edges)
-        DEFINE TABLE shipped_by SCHEMAFULL;  -- shipment -> carrier
-        DEFINE TABLE origin_at SCHEMAFULL;   -- shipment -> location (origin)
-        DEFINE TABLE dest_at SCHEMAFULL;     -- shipment -> location (destination)
-        DEFINE TABLE on_lane SCHEMAFULL;     -- shipment -> lane
-        DEFINE TABLE connects SCHEMAFULL;    -- lane -> origin location, dest location
-        "#,
-    )
-    .await?;
-
+    crate::migrations::migrate(db).await?;
     Ok(())
 }
+
+/// Bump the data version after a load changes shipments.
+///
+/// Long-lived readers (the API service) compare this against the version
+/// their caches were built from.
+pub async fn bump_data_version(db: &DbConn) -> Result<i64> {
+    let version: Option<i64> = db
+        .query("UPSERT meta:data_version SET version = (version ?? 0) + 1, updated_at = time::now() RETURN VALUE version")
+        .await?
+        .take(0)?;
+    Ok(version.unwrap_or_default())
+}
+
+/// Current data version (0 if nothing has been loaded since versioning began)
+pub async fn data_version(db: &DbConn) -> Result<i64> {
+    let version: Option<i64> = db
+        .query("SELECT VALUE version FROM ONLY meta:data_version")
+        .await?
+        .take(0)?;
+    Ok(version.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(endpoint("data/lastmile.db"), "rocksdb://data/lastmile.db");
+        assert_eq!(endpoint("mem://"), "mem://");
+        assert_eq!(endpoint("ws://localhost:8000"), "ws://localhost:8000");
+
+        assert_eq!(local_path("data/lastmile.db"), Some(Path::new("data/lastmile.db")));
+        assert_eq!(local_path("rocksdb:///var/lib/lastmile"), Some(Path::new("/var/lib/lastmile")));
+        assert_eq!(local_path("mem://"), None);
+        assert!(is_remote("wss://db.example.com") && !is_remote("rocksdb://data/lastmile.db"));
+    }
+
+    #[tokio::test]
+    async fn test_memory_engine() {
+        let db = connect("mem://").await.unwrap();
+        init_schema(&db).await.unwrap();
+        assert_eq!(data_version(&db).await.unwrap(), 0);
+        assert_eq!(bump_data_version(&db).await.unwrap(), 1);
+        assert_eq!(data_version(&db).await.unwrap(), 1);
+    }
+}
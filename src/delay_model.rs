@@ -0,0 +1,1073 @@
+//! Shipment delay model
+//!
+//! Logistic regression on P(late) for a single shipment. [`DelayFeatures`]
+//! are extracted from shipment records: carrier mode, carrier, lane, ship
+//! weekday and month, distance and goal transit days. [`FeatureEncoder`]
+//! one-hot encodes the categories, keeping carriers and lanes seen at least
+//! `min_category_count` times and pooling the rest into "other", and
+//! standardizes the numeric features.
+//!
+//! [`train`] holds out the most recent shipments, fits the weights on the
+//! rest with full-batch Adam and an L2 penalty, and scores the holdout with
+//! [`evaluate`] (AUC, Brier score against a constant-rate baseline, and a
+//! calibration table). The fitted [`DelayModel`] is saved as JSON,
+//! [`DEFAULT_MODEL_PATH`] by default, and loaded for per-shipment scoring.
+//!
+//! [`predict_delay`] scores a planned shipment with the model, or, before
+//! one has been trained, from the historical late rates of its lane,
+//! carrier, ship weekday and mode ([`HistoricalRates`]). Those come from a
+//! [`RateTable`] loaded once for the whole history, not per prediction.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::carrier_names::{find_carrier_id, get_carrier_name};
+use crate::clustering::distance_bucket_miles;
+use crate::db::DbConn;
+use crate::filter::{normalize_zip3, ShipmentFilter};
+use crate::location_names::format_lane_short;
+use crate::models::{CarrierMode, Shipment};
+
+/// Where `delay_model train` writes the model and the API looks for it
+pub const DEFAULT_MODEL_PATH: &str = "models/delay_model.json";
+
+/// Bumped when the model file layout changes
+pub const MODEL_FORMAT: u32 = 1;
+
+/// Names of the one-hot feature groups, in encoding order
+const CATEGORIES: [&str; 5] = ["mode", "carrier", "lane", "weekday", "month"];
+
+/// Carriers and lanes need this many training shipments for their own weight
+const DEFAULT_MIN_CATEGORY_COUNT: usize = 30;
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+// ============================================================================
+// Features
+// ============================================================================
+
+/// Model inputs for one shipment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DelayFeatures {
+    pub carrier_mode: String,
+    pub carrier_ref: String,
+    pub origin_zip: String,
+    pub dest_zip: String,
+    /// Ship weekday, 0 = Sunday as stored on shipments
+    pub ship_dow: i32,
+    pub ship_month: i32,
+    /// Customer distance in miles, when known
+    pub distance_miles: Option<f64>,
+    pub distance_bucket: Option<String>,
+    pub goal_transit_days: i32,
+}
+
+impl DelayFeatures {
+    /// Features of a stored shipment; carrier and lane come from its links
+    pub fn from_shipment(shipment: &Shipment, carrier_ref: &str, origin_zip: &str, dest_zip: &str) -> Self {
+        Self {
+            carrier_mode: shipment.carrier_mode.as_str().to_string(),
+            carrier_ref: carrier_ref.to_string(),
+            origin_zip: origin_zip.to_string(),
+            dest_zip: dest_zip.to_string(),
+            ship_dow: shipment.ship_dow,
+            ship_month: shipment.ship_month,
+            distance_miles: shipment.customer_distance,
+            distance_bucket: Some(shipment.distance_bucket.clone()),
+            goal_transit_days: shipment.goal_transit_days,
+        }
+    }
+
+    /// Features of a shipment that has not shipped yet
+    pub fn planned(
+        carrier_mode: CarrierMode,
+        carrier_ref: &str,
+        origin_zip: &str,
+        dest_zip: &str,
+        ship_date: NaiveDate,
+        goal_transit_days: i32,
+    ) -> Self {
+        Self {
+            carrier_mode: carrier_mode.as_str().to_string(),
+            carrier_ref: carrier_ref.to_string(),
+            origin_zip: origin_zip.to_string(),
+            dest_zip: dest_zip.to_string(),
+            ship_dow: ship_date.weekday().num_days_from_sunday() as i32,
+            ship_month: ship_date.month() as i32,
+            distance_miles: None,
+            distance_bucket: None,
+            goal_transit_days,
+        }
+    }
+
+    pub fn lane(&self) -> String {
+        format!("{}{}", self.origin_zip, self.dest_zip)
+    }
+
+    /// Display spelling of the mode, e.g. `TL Flatbed`
+    pub fn mode_label(&self) -> &str {
+        CarrierMode::from_name(&self.carrier_mode).map_or(&self.carrier_mode, |mode| mode.label())
+    }
+
+    fn category_values(&self) -> [String; 5] {
+        [
+            self.carrier_mode.clone(),
+            self.carrier_ref.clone(),
+            self.lane(),
+            self.ship_dow.to_string(),
+            self.ship_month.to_string(),
+        ]
+    }
+
+    /// Known distance: the customer distance, else the bucket midpoint
+    fn known_miles(&self) -> Option<f64> {
+        self.distance_miles
+            .filter(|m| *m > 0.0)
+            .or_else(|| self.distance_bucket.as_deref().and_then(distance_bucket_miles))
+    }
+}
+
+/// A shipment with its outcome
+#[derive(Debug, Clone)]
+pub struct LabeledShipment {
+    pub features: DelayFeatures,
+    pub late: bool,
+    pub ship_date: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShipmentRow {
+    carrier_mode: String,
+    carrier_ref: String,
+    origin_zip: String,
+    dest_zip: String,
+    ship_dow: i32,
+    ship_month: i32,
+    customer_distance: Option<f64>,
+    distance_bucket: Option<String>,
+    goal_transit_days: i32,
+    otd: String,
+    actual_ship: String,
+}
+
+/// Labeled shipments matching `filter`, oldest first
+pub async fn load_shipments(db: &DbConn, filter: &ShipmentFilter) -> Result<Vec<LabeledShipment>> {
+    let rows: Vec<ShipmentRow> = filter
+        .query(db, r#"
+            SELECT
+                carrier_mode,
+                carrier_ref,
+                (origin_zip ?? origin_zip3) as origin_zip,
+                (dest_zip ?? dest_zip3) as dest_zip,
+                ship_dow,
+                ship_month,
+                customer_distance,
+                distance_bucket,
+                goal_transit_days,
+                otd,
+                actual_ship
+            FROM shipment
+            WHERE {filter}
+            ORDER BY actual_ship
+        "#)
+        .await?
+        .take(0)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| LabeledShipment {
+            late: r.otd == "Late",
+            ship_date: r.actual_ship.chars().take(10).collect(),
+            features: DelayFeatures {
+                carrier_mode: CarrierMode::from_name(&r.carrier_mode)
+                    .map_or(r.carrier_mode, |mode| mode.as_str().to_string()),
+                carrier_ref: r.carrier_ref,
+                origin_zip: r.origin_zip,
+                dest_zip: r.dest_zip,
+                ship_dow: r.ship_dow,
+                ship_month: r.ship_month,
+                distance_miles: r.customer_distance,
+                distance_bucket: r.distance_bucket,
+                goal_transit_days: r.goal_transit_days,
+            },
+        })
+        .collect())
+}
+
+// ============================================================================
+// Encoding
+// ============================================================================
+
+/// Maps [`DelayFeatures`] to a sparse feature vector
+///
+/// Layout: goal transit days, log distance, then one block per entry of
+/// [`CATEGORIES`] with one slot per kept value plus a trailing "other" slot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureEncoder {
+    pub min_category_count: usize,
+    /// Kept values per category, in [`CATEGORIES`] order
+    pub categories: Vec<Vec<String>>,
+    goal_mean: f64,
+    goal_std: f64,
+    log_miles_mean: f64,
+    log_miles_std: f64,
+    /// Mean log distance of each training lane, for inputs without a distance
+    lane_log_miles: HashMap<String, f64>,
+}
+
+/// Number of numeric features ahead of the one-hot blocks
+const NUMERIC: usize = 2;
+
+impl FeatureEncoder {
+    /// Fit vocabularies and scaling on the training shipments
+    pub fn fit(rows: &[LabeledShipment], min_category_count: usize) -> Self {
+        let mut counts: Vec<HashMap<String, usize>> = vec![HashMap::new(); CATEGORIES.len()];
+        for row in rows {
+            for (c, value) in row.features.category_values().into_iter().enumerate() {
+                *counts[c].entry(value).or_default() += 1;
+            }
+        }
+        let categories = counts
+            .into_iter()
+            .enumerate()
+            .map(|(c, counts)| {
+                // Carriers and lanes are long-tailed; the small groups keep everything
+                let floor = if CATEGORIES[c] == "carrier" || CATEGORIES[c] == "lane" { min_category_count } else { 1 };
+                let mut values: Vec<String> = counts.into_iter().filter(|(_, n)| *n >= floor).map(|(v, _)| v).collect();
+                values.sort();
+                values
+            })
+            .collect();
+
+        let (goal_mean, goal_std) = mean_std(rows.iter().map(|r| r.features.goal_transit_days as f64));
+        let log_miles: Vec<(String, f64)> = rows
+            .iter()
+            .filter_map(|r| r.features.known_miles().map(|m| (r.features.lane(), m.ln_1p())))
+            .collect();
+        let (log_miles_mean, log_miles_std) = mean_std(log_miles.iter().map(|(_, m)| *m));
+        let mut lane_sums: HashMap<String, (f64, usize)> = HashMap::new();
+        for (lane, m) in log_miles {
+            let entry = lane_sums.entry(lane).or_default();
+            entry.0 += m;
+            entry.1 += 1;
+        }
+
+        Self {
+            min_category_count,
+            categories,
+            goal_mean,
+            goal_std,
+            log_miles_mean,
+            log_miles_std,
+            lane_log_miles: lane_sums.into_iter().map(|(lane, (sum, n))| (lane, sum / n as f64)).collect(),
+        }
+    }
+
+    /// Length of the feature vector
+    pub fn len(&self) -> usize {
+        NUMERIC + self.categories.iter().map(|values| values.len() + 1).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sparse `(index, value)` encoding. Category values match
+    /// case-insensitively; unseen ones use the block's "other" slot.
+    pub fn encode(&self, features: &DelayFeatures) -> Vec<(usize, f64)> {
+        let log_miles = features
+            .known_miles()
+            .map(f64::ln_1p)
+            .or_else(|| self.lane_log_miles.get(&features.lane()).copied())
+            .unwrap_or(self.log_miles_mean);
+        let mut encoded = vec![
+            (0, (features.goal_transit_days as f64 - self.goal_mean) / self.goal_std),
+            (1, (log_miles - self.log_miles_mean) / self.log_miles_std),
+        ];
+
+        let mut offset = NUMERIC;
+        for (values, value) in self.categories.iter().zip(features.category_values()) {
+            let slot = values
+                .iter()
+                .position(|v| v.eq_ignore_ascii_case(&value))
+                .unwrap_or(values.len());
+            encoded.push((offset + slot, 1.0));
+            offset += values.len() + 1;
+        }
+        encoded
+    }
+
+    /// Readable name of feature `index`, e.g. `carrier=XPO Logistics`
+    pub fn feature_name(&self, index: usize) -> String {
+        match index {
+            0 => return "goal_transit_days".to_string(),
+            1 => return "distance".to_string(),
+            _ => {}
+        }
+        let mut offset = NUMERIC;
+        for (c, values) in self.categories.iter().enumerate() {
+            if index < offset + values.len() + 1 {
+                let value = match values.get(index - offset) {
+                    None => "other".to_string(),
+                    Some(v) => match CATEGORIES[c] {
+                        "carrier" => get_carrier_name(v),
+                        "lane" if v.len() == 10 => format_lane_short(&v[..5], &v[5..]),
+                        "weekday" => v.parse::<usize>().ok().and_then(|d| WEEKDAYS.get(d)).map_or(v.clone(), |d| d.to_string()),
+                        _ => v.clone(),
+                    },
+                };
+                return format!("{}={}", CATEGORIES[c], value);
+            }
+            offset += values.len() + 1;
+        }
+        format!("feature_{}", index)
+    }
+}
+
+fn mean_std(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return (0.0, 1.0);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let std = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    (mean, if std > 1e-9 { std } else { 1.0 })
+}
+
+// ============================================================================
+// Model
+// ============================================================================
+
+/// Training parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainConfig {
+    /// Full-batch gradient steps
+    pub iterations: usize,
+    pub learning_rate: f64,
+    /// L2 penalty on the weights (not the bias)
+    pub l2: f64,
+    pub min_category_count: usize,
+    /// Most recent share of shipments held out for evaluation
+    pub test_fraction: f64,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 500,
+            learning_rate: 0.05,
+            l2: 1e-3,
+            min_category_count: DEFAULT_MIN_CATEGORY_COUNT,
+            test_fraction: 0.2,
+        }
+    }
+}
+
+/// A fitted logistic-regression delay model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelayModel {
+    pub format: u32,
+    pub trained_at: String,
+    pub config: TrainConfig,
+    pub encoder: FeatureEncoder,
+    pub bias: f64,
+    pub weights: Vec<f64>,
+    pub training_rows: usize,
+    pub training_late_rate: f64,
+    /// Ship date range of the training shipments
+    pub trained_from: String,
+    pub trained_to: String,
+    /// Scores on the held-out shipments
+    pub holdout: Option<Evaluation>,
+}
+
+/// One feature's push on a prediction, in log-odds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contribution {
+    pub feature: String,
+    pub log_odds: f64,
+}
+
+impl DelayModel {
+    /// P(late) for a shipment
+    pub fn predict(&self, features: &DelayFeatures) -> f64 {
+        sigmoid(self.log_odds(&self.encoder.encode(features)))
+    }
+
+    fn log_odds(&self, encoded: &[(usize, f64)]) -> f64 {
+        self.bias + encoded.iter().map(|(i, x)| self.weights[*i] * x).sum::<f64>()
+    }
+
+    /// The `n` features that move this shipment's log-odds the most
+    pub fn explain(&self, features: &DelayFeatures, n: usize) -> Vec<Contribution> {
+        let mut contributions: Vec<Contribution> = self
+            .encoder
+            .encode(features)
+            .into_iter()
+            .map(|(i, x)| Contribution { feature: self.encoder.feature_name(i), log_odds: self.weights[i] * x })
+            // Skip effects too small to show at three decimals
+            .filter(|c| c.log_odds.abs() >= 5e-4)
+            .collect();
+        contributions.sort_by(|a, b| b.log_odds.abs().total_cmp(&a.log_odds.abs()));
+        contributions.truncate(n);
+        contributions
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing model to {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading model from {}", path.display()))?;
+        let model: Self = serde_json::from_str(&text)
+            .with_context(|| format!("invalid model file {}", path.display()))?;
+        if model.format != MODEL_FORMAT {
+            bail!("{} is model format {}, expected {}; retrain it", path.display(), model.format, MODEL_FORMAT);
+        }
+        if model.weights.len() != model.encoder.len() {
+            bail!("{} has {} weights for {} features", path.display(), model.weights.len(), model.encoder.len());
+        }
+        Ok(model)
+    }
+}
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+/// Fit a model on `rows` (oldest first), holding out the most recent
+/// `config.test_fraction` for evaluation
+pub fn train(rows: &[LabeledShipment], config: &TrainConfig) -> Result<DelayModel> {
+    let holdout_len = (rows.len() as f64 * config.test_fraction.clamp(0.0, 0.9)).round() as usize;
+    let (fit_rows, holdout) = rows.split_at(rows.len() - holdout_len);
+    if fit_rows.is_empty() {
+        bail!("no shipments to train on");
+    }
+    let late = fit_rows.iter().filter(|r| r.late).count();
+    if late == 0 || late == fit_rows.len() {
+        bail!("training shipments are all {}; nothing to learn", if late == 0 { "on time" } else { "late" });
+    }
+
+    let encoder = FeatureEncoder::fit(fit_rows, config.min_category_count);
+    let encoded: Vec<Vec<(usize, f64)>> = fit_rows.iter().map(|r| encoder.encode(&r.features)).collect();
+    let labels: Vec<f64> = fit_rows.iter().map(|r| if r.late { 1.0 } else { 0.0 }).collect();
+    let late_rate = late as f64 / fit_rows.len() as f64;
+
+    // Start from the base rate so early iterations only learn the differences
+    let mut params = vec![0.0; encoder.len() + 1];
+    let bias = encoder.len();
+    params[bias] = (late_rate / (1.0 - late_rate)).ln();
+
+    let (beta1, beta2, eps) = (0.9, 0.999, 1e-8);
+    let mut m = vec![0.0; params.len()];
+    let mut v = vec![0.0; params.len()];
+    let n = fit_rows.len() as f64;
+    for step in 1..=config.iterations {
+        let mut grad = vec![0.0; params.len()];
+        for (x, y) in encoded.iter().zip(&labels) {
+            let z = params[bias] + x.iter().map(|(i, xi)| params[*i] * xi).sum::<f64>();
+            let err = sigmoid(z) - y;
+            for (i, xi) in x {
+                grad[*i] += err * xi / n;
+            }
+            grad[bias] += err / n;
+        }
+        for (g, w) in grad.iter_mut().zip(&params).take(bias) {
+            *g += config.l2 * w;
+        }
+        let (c1, c2) = (1.0 - f64::powi(beta1, step as i32), 1.0 - f64::powi(beta2, step as i32));
+        for j in 0..params.len() {
+            m[j] = beta1 * m[j] + (1.0 - beta1) * grad[j];
+            v[j] = beta2 * v[j] + (1.0 - beta2) * grad[j] * grad[j];
+            params[j] -= config.learning_rate * (m[j] / c1) / ((v[j] / c2).sqrt() + eps);
+        }
+    }
+
+    let bias_value = params.pop().unwrap_or_default();
+    let mut model = DelayModel {
+        format: MODEL_FORMAT,
+        trained_at: Utc::now().to_rfc3339(),
+        config: config.clone(),
+        encoder,
+        bias: bias_value,
+        weights: params,
+        training_rows: fit_rows.len(),
+        training_late_rate: late_rate,
+        trained_from: fit_rows.first().map(|r| r.ship_date.clone()).unwrap_or_default(),
+        trained_to: fit_rows.last().map(|r| r.ship_date.clone()).unwrap_or_default(),
+        holdout: None,
+    };
+    if !holdout.is_empty() {
+        model.holdout = Some(evaluate(&model, holdout));
+    }
+    Ok(model)
+}
+
+// ============================================================================
+// Scoring
+// ============================================================================
+
+/// A planned shipment to score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelayRequest {
+    /// Origin ZIP3, with or without the "xx" suffix
+    pub origin_zip3: String,
+    pub dest_zip3: String,
+    /// Carrier ID or display name
+    pub carrier: String,
+    /// [`DEFAULT_MODE`] when missing or blank
+    #[serde(default)]
+    pub mode: String,
+    pub ship_date: NaiveDate,
+    pub goal_days: i32,
+}
+
+/// Mode of a delay request that doesn't name one
+pub const DEFAULT_MODE: CarrierMode = CarrierMode::LTL;
+
+impl DelayRequest {
+    /// Reject requests without a lane or carrier, or with a goal under a day
+    pub fn validate(&self) -> Result<()> {
+        if self.origin_zip3.trim().is_empty() || self.dest_zip3.trim().is_empty() || self.carrier.trim().is_empty() {
+            bail!("origin_zip3, dest_zip3 and carrier are required");
+        }
+        if self.goal_days < 1 {
+            bail!("goal_days must be at least 1, got {}", self.goal_days);
+        }
+        Ok(())
+    }
+
+    /// The requested mode, [`DEFAULT_MODE`] when blank; an error for unknown modes
+    pub fn mode(&self) -> Result<CarrierMode> {
+        if self.mode.trim().is_empty() {
+            Ok(DEFAULT_MODE)
+        } else {
+            CarrierMode::resolve(&self.mode)
+        }
+    }
+
+    /// Model inputs for a valid request (see [`DelayRequest::validate`])
+    pub fn features(&self) -> Result<DelayFeatures> {
+        self.validate()?;
+        let carrier_ref = find_carrier_id(&self.carrier)
+            .map(str::to_string)
+            .unwrap_or_else(|| self.carrier.trim().to_string());
+        Ok(DelayFeatures::planned(
+            self.mode()?,
+            &carrier_ref,
+            &normalize_zip3(&self.origin_zip3),
+            &normalize_zip3(&self.dest_zip3),
+            self.ship_date,
+            self.goal_days,
+        ))
+    }
+}
+
+/// What a prediction was computed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PredictionSource {
+    /// A trained [`DelayModel`]
+    Model,
+    /// Historical lane, carrier, weekday and mode late rates
+    Empirical,
+}
+
+impl PredictionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PredictionSource::Model => "model",
+            PredictionSource::Empirical => "empirical",
+        }
+    }
+}
+
+/// Late risk of a planned shipment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelayPrediction {
+    pub late_probability: f64,
+    /// P(late) times the average days late of comparable late shipments
+    pub expected_delay_days: f64,
+    /// Average days past goal when shipments like this one are late
+    pub avg_days_late: f64,
+    pub factors: Vec<Contribution>,
+    pub source: PredictionSource,
+}
+
+/// Shipments, late shipments and total days late of one slice of history
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct LateCounts {
+    pub shipments: i64,
+    pub late: i64,
+    pub days_late: f64,
+}
+
+impl LateCounts {
+    fn avg_days_late(&self) -> Option<f64> {
+        (self.late > 0).then(|| self.days_late / self.late as f64)
+    }
+}
+
+/// History behind an empirical prediction: the network plus each slice that
+/// matches the planned shipment
+#[derive(Debug, Clone, Default)]
+pub struct HistoricalRates {
+    pub network: LateCounts,
+    pub lane: LateCounts,
+    pub carrier: LateCounts,
+    pub weekday: LateCounts,
+    pub mode: LateCounts,
+}
+
+/// Pseudo-shipments at the network rate added to each slice, so a lane with
+/// three shipments barely moves the estimate
+const PRIOR_SHIPMENTS: f64 = 20.0;
+
+/// Late shipments a slice needs before its average days late is used
+const MIN_LATE_FOR_DAYS: i64 = 5;
+
+/// Late counts of every lane, carrier, weekday and mode, loaded once with
+/// [`load_rate_table`] and looked up for each prediction
+#[derive(Debug, Clone, Default)]
+pub struct RateTable {
+    pub network: LateCounts,
+    /// Keyed by (origin ZIP, destination ZIP)
+    pub lanes: HashMap<(String, String), LateCounts>,
+    pub carriers: HashMap<String, LateCounts>,
+    pub weekdays: HashMap<i32, LateCounts>,
+    pub modes: HashMap<String, LateCounts>,
+}
+
+impl RateTable {
+    /// The slices of history that match `features`
+    pub fn rates(&self, features: &DelayFeatures) -> HistoricalRates {
+        let lane = (features.origin_zip.clone(), features.dest_zip.clone());
+        HistoricalRates {
+            network: self.network,
+            lane: self.lanes.get(&lane).copied().unwrap_or_default(),
+            carrier: self.carriers.get(&features.carrier_ref).copied().unwrap_or_default(),
+            weekday: self.weekdays.get(&features.ship_dow).copied().unwrap_or_default(),
+            mode: self.modes.get(&features.carrier_mode).copied().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SliceRow<K> {
+    key: K,
+    shipments: i64,
+    late: i64,
+    days_late: f64,
+}
+
+impl<K: std::hash::Hash + Eq> SliceRow<K> {
+    fn index(rows: Vec<Self>) -> HashMap<K, LateCounts> {
+        rows.into_iter()
+            .map(|r| (r.key, LateCounts { shipments: r.shipments, late: r.late, days_late: r.days_late }))
+            .collect()
+    }
+}
+
+/// Late counts for the network and every lane, carrier, weekday and mode in
+/// one round trip
+pub async fn load_rate_table(db: &DbConn) -> Result<RateTable> {
+    let counts = r#"
+        count() as shipments,
+        count(IF otd = "Late" THEN 1 END) as late,
+        math::sum(IF otd = "Late" THEN actual_transit_days - goal_transit_days ELSE 0 END) as days_late
+    "#;
+    let sql = format!(
+        "SELECT {c} FROM shipment GROUP ALL;\n\
+         SELECT [origin_zip, dest_zip] as key, {c} FROM (\n\
+             SELECT (origin_zip ?? origin_zip3) as origin_zip, (dest_zip ?? dest_zip3) as dest_zip,\n\
+                 otd, actual_transit_days, goal_transit_days FROM shipment\n\
+         ) WHERE origin_zip != NONE AND dest_zip != NONE GROUP BY key;\n\
+         SELECT carrier_ref as key, {c} FROM shipment WHERE carrier_ref != NONE GROUP BY key;\n\
+         SELECT ship_dow as key, {c} FROM shipment WHERE ship_dow != NONE GROUP BY key;\n\
+         SELECT carrier_mode as key, {c} FROM shipment WHERE carrier_mode != NONE GROUP BY key;",
+        c = counts
+    );
+    let mut response = db.query(sql).await?;
+
+    let network: Vec<LateCounts> = response.take(0)?;
+    let lanes: Vec<SliceRow<(String, String)>> = response.take(1)?;
+    let carriers: Vec<SliceRow<String>> = response.take(2)?;
+    let weekdays: Vec<SliceRow<i32>> = response.take(3)?;
+    let modes: Vec<SliceRow<String>> = response.take(4)?;
+    Ok(RateTable {
+        network: network.into_iter().next().unwrap_or_default(),
+        lanes: SliceRow::index(lanes),
+        carriers: SliceRow::index(carriers),
+        weekdays: SliceRow::index(weekdays),
+        modes: SliceRow::index(modes),
+    })
+}
+
+fn logit(p: f64) -> f64 {
+    let p = p.clamp(1e-6, 1.0 - 1e-6);
+    (p / (1.0 - p)).ln()
+}
+
+impl HistoricalRates {
+    /// P(late) from the network rate shifted by each slice's log-odds
+    /// difference, treating the slices as independent. Slice rates are shrunk
+    /// toward the network rate by [`PRIOR_SHIPMENTS`].
+    pub fn predict(&self, features: &DelayFeatures) -> (f64, Vec<Contribution>) {
+        let network = &self.network;
+        if network.shipments == 0 {
+            return (0.0, vec![]);
+        }
+        let base = network.late as f64 / network.shipments as f64;
+        let weekday = usize::try_from(features.ship_dow).ok().and_then(|d| WEEKDAYS.get(d)).copied().unwrap_or("?");
+        let slices = [
+            (format!("lane={}", format_lane_short(&features.origin_zip, &features.dest_zip)), &self.lane),
+            (format!("carrier={}", get_carrier_name(&features.carrier_ref)), &self.carrier),
+            (format!("weekday={}", weekday), &self.weekday),
+            (format!("mode={}", features.mode_label()), &self.mode),
+        ];
+
+        let mut factors: Vec<Contribution> = slices
+            .into_iter()
+            .filter(|(_, counts)| counts.shipments > 0)
+            .map(|(feature, counts)| {
+                let shrunk = (counts.late as f64 + PRIOR_SHIPMENTS * base) / (counts.shipments as f64 + PRIOR_SHIPMENTS);
+                Contribution { feature, log_odds: logit(shrunk) - logit(base) }
+            })
+            .collect();
+        let p = sigmoid(logit(base) + factors.iter().map(|f| f.log_odds).sum::<f64>());
+        factors.retain(|f| f.log_odds.abs() >= 5e-4);
+        factors.sort_by(|a, b| b.log_odds.abs().total_cmp(&a.log_odds.abs()));
+        (p, factors)
+    }
+
+    /// Average days late of the most specific slice with enough late shipments
+    pub fn avg_days_late(&self) -> f64 {
+        [&self.lane, &self.carrier, &self.network]
+            .into_iter()
+            .find(|c| c.late >= MIN_LATE_FOR_DAYS)
+            .or(Some(&self.network))
+            .and_then(LateCounts::avg_days_late)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Score a planned shipment with `model` when one is loaded, otherwise with
+/// the historical rates in `history`. The expected delay always comes from
+/// history.
+pub fn predict_delay(
+    model: Option<&DelayModel>,
+    history: &RateTable,
+    features: &DelayFeatures,
+    factors: usize,
+) -> DelayPrediction {
+    let rates = history.rates(features);
+    let (late_probability, mut contributions, source) = match model {
+        Some(model) => (model.predict(features), model.explain(features, factors), PredictionSource::Model),
+        None => {
+            let (p, contributions) = rates.predict(features);
+            (p, contributions, PredictionSource::Empirical)
+        }
+    };
+    contributions.truncate(factors);
+    let avg_days_late = rates.avg_days_late();
+    DelayPrediction {
+        late_probability,
+        expected_delay_days: late_probability * avg_days_late,
+        avg_days_late,
+        factors: contributions,
+        source,
+    }
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+/// Scores of a model on labeled shipments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Evaluation {
+    pub shipments: usize,
+    pub late_rate: f64,
+    /// Area under the ROC curve (`None` when only one class is present)
+    pub auc: Option<f64>,
+    /// Mean squared error of the probabilities
+    pub brier: f64,
+    /// Brier score of always predicting the training late rate
+    pub baseline_brier: f64,
+    pub log_loss: f64,
+    pub calibration: Vec<CalibrationBin>,
+}
+
+impl Evaluation {
+    /// Improvement over the constant-rate baseline (1 = perfect, 0 = none)
+    pub fn brier_skill(&self) -> f64 {
+        if self.baseline_brier > 0.0 { 1.0 - self.brier / self.baseline_brier } else { 0.0 }
+    }
+}
+
+/// Shipments whose predicted probability falls in `[lower, upper)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub shipments: usize,
+    pub mean_predicted: f64,
+    pub observed_late_rate: f64,
+}
+
+/// Number of equal-width calibration bins
+const CALIBRATION_BINS: usize = 10;
+
+/// Score `model` on labeled shipments
+pub fn evaluate(model: &DelayModel, rows: &[LabeledShipment]) -> Evaluation {
+    let scored: Vec<(f64, bool)> = rows.iter().map(|r| (model.predict(&r.features), r.late)).collect();
+    let n = scored.len().max(1) as f64;
+    let outcome = |late: bool| if late { 1.0 } else { 0.0 };
+
+    let brier = scored.iter().map(|(p, late)| (p - outcome(*late)).powi(2)).sum::<f64>() / n;
+    let base = model.training_late_rate;
+    let baseline_brier = scored.iter().map(|(_, late)| (base - outcome(*late)).powi(2)).sum::<f64>() / n;
+    let log_loss = -scored
+        .iter()
+        .map(|(p, late)| {
+            let p = p.clamp(1e-12, 1.0 - 1e-12);
+            if *late { p.ln() } else { (1.0 - p).ln() }
+        })
+        .sum::<f64>()
+        / n;
+
+    let mut bins: Vec<(usize, f64, usize)> = vec![(0, 0.0, 0); CALIBRATION_BINS];
+    for (p, late) in &scored {
+        let b = ((p * CALIBRATION_BINS as f64) as usize).min(CALIBRATION_BINS - 1);
+        bins[b].0 += 1;
+        bins[b].1 += p;
+        bins[b].2 += *late as usize;
+    }
+    let calibration = bins
+        .into_iter()
+        .enumerate()
+        .filter(|(_, (count, _, _))| *count > 0)
+        .map(|(b, (count, sum, late))| CalibrationBin {
+            lower: b as f64 / CALIBRATION_BINS as f64,
+            upper: (b + 1) as f64 / CALIBRATION_BINS as f64,
+            shipments: count,
+            mean_predicted: sum / count as f64,
+            observed_late_rate: late as f64 / count as f64,
+        })
+        .collect();
+
+    Evaluation {
+        shipments: scored.len(),
+        late_rate: scored.iter().filter(|(_, late)| *late).count() as f64 / n,
+        auc: auc(&scored),
+        brier,
+        baseline_brier,
+        log_loss,
+        calibration,
+    }
+}
+
+/// ROC AUC via the rank-sum statistic, with tied scores sharing their rank
+pub fn auc(scored: &[(f64, bool)]) -> Option<f64> {
+    let positives = scored.iter().filter(|(_, late)| *late).count();
+    let negatives = scored.len() - positives;
+    if positives == 0 || negatives == 0 {
+        return None;
+    }
+    let mut sorted: Vec<&(f64, bool)> = scored.iter().collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut positive_rank_sum = 0.0;
+    let mut i = 0;
+    while i < sorted.len() {
+        let mut j = i;
+        while j < sorted.len() && sorted[j].0 == sorted[i].0 {
+            j += 1;
+        }
+        // Ranks i+1..=j share their average
+        let rank = (i + 1 + j) as f64 / 2.0;
+        positive_rank_sum += rank * sorted[i..j].iter().filter(|(_, late)| *late).count() as f64;
+        i = j;
+    }
+    let (p, q) = (positives as f64, negatives as f64);
+    Some((positive_rank_sum - p * (p + 1.0) / 2.0) / (p * q))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shipment(carrier: &str, dow: i32, goal: i32, late: bool) -> LabeledShipment {
+        LabeledShipment {
+            features: DelayFeatures {
+                carrier_mode: "LTL".to_string(),
+                carrier_ref: carrier.to_string(),
+                origin_zip: "750xx".to_string(),
+                dest_zip: "432xx".to_string(),
+                ship_dow: dow,
+                ship_month: 3,
+                distance_miles: Some(900.0),
+                distance_bucket: Some("500-1k".to_string()),
+                goal_transit_days: goal,
+            },
+            late,
+            ship_date: "2024-03-01".to_string(),
+        }
+    }
+
+    /// Carrier "bad" is late 70% of the time, "good" 10%
+    fn history() -> Vec<LabeledShipment> {
+        let mut rows = Vec::new();
+        for i in 0..400 {
+            rows.push(shipment("bad", i % 7, 3, i % 10 < 7));
+            rows.push(shipment("good", i % 7, 3, i % 10 < 1));
+        }
+        rows
+    }
+
+    #[test]
+    fn test_empirical_rates() {
+        let counts = |shipments, late, days_late| LateCounts { shipments, late, days_late };
+        let rates = HistoricalRates {
+            network: counts(1000, 200, 400.0),
+            lane: counts(0, 0, 0.0),
+            carrier: counts(200, 100, 300.0),
+            weekday: counts(150, 30, 60.0),
+            mode: counts(1000, 200, 400.0),
+        };
+        let features = shipment("bad", 1, 3, false).features;
+        let (p, factors) = rates.predict(&features);
+
+        // Only the carrier moves the estimate: 50% shrunk toward 20% by 20 shipments
+        let shrunk = (100.0 + 20.0 * 0.2) / 220.0;
+        assert!((p - shrunk).abs() < 1e-9, "{}", p);
+        assert_eq!(factors.len(), 1, "slices at the network rate or without history add no factor");
+        assert!(factors[0].feature.starts_with("carrier="));
+
+        // Lane has no late shipments, so days late come from the carrier
+        assert_eq!(rates.avg_days_late(), 3.0);
+        assert_eq!(HistoricalRates::default().predict(&features).0, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_rate_table() {
+        let db = crate::db::connect("mem://").await.unwrap();
+        crate::db::init_schema(&db).await.unwrap();
+        // 10 shipments on one lane, every fifth two days late
+        db.query(r#"
+            FOR $i IN 0..10 {
+                CREATE shipment SET origin_zip = "750xx", dest_zip = "432xx", carrier_ref = "C1",
+                    carrier_mode = "LTL", ship_dow = 1, goal_transit_days = 3,
+                    actual_transit_days = IF $i % 5 = 0 THEN 5 ELSE 3 END,
+                    otd = IF $i % 5 = 0 THEN "Late" ELSE "OnTime" END;
+            };
+            CREATE shipment SET origin_zip3 = "100", dest_zip3 = "200", carrier_ref = "C2",
+                carrier_mode = "TL", ship_dow = 2, goal_transit_days = 2,
+                actual_transit_days = 2, otd = "OnTime";
+        "#).await.unwrap().check().unwrap();
+
+        let table = load_rate_table(&db).await.unwrap();
+        assert_eq!((table.network.shipments, table.network.late), (11, 2));
+        assert_eq!(table.lanes.len(), 2);
+        let features = shipment("C1", 1, 3, false).features;
+        let rates = table.rates(&features);
+        assert_eq!((rates.lane.shipments, rates.lane.late, rates.lane.days_late), (10, 2, 4.0));
+        assert_eq!(rates.carrier.shipments, 10);
+        assert_eq!(rates.weekday.shipments, 10);
+        assert_eq!(rates.mode.shipments, 10);
+        assert_eq!(table.rates(&shipment("C9", 4, 3, false).features).carrier.shipments, 0);
+    }
+
+    #[test]
+    fn test_request_mode_matches_training_rows() {
+        let request = |mode: &str| DelayRequest {
+            origin_zip3: "750".to_string(),
+            dest_zip3: "432".to_string(),
+            carrier: "bad".to_string(),
+            mode: mode.to_string(),
+            ship_date: NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(),
+            goal_days: 3,
+        };
+        // Ingest stores the mode as CarrierMode::as_str, which training reads back
+        let mut rows = history();
+        for (i, row) in rows.iter_mut().enumerate() {
+            row.features.carrier_mode = if i % 2 == 0 { CarrierMode::TLFlatbed } else { CarrierMode::LTL }
+                .as_str()
+                .to_string();
+        }
+        let model = train(&rows, &TrainConfig { min_category_count: 10, ..Default::default() }).unwrap();
+
+        for mode in ["TL Flatbed", "tlflatbed", "TLFlatbed"] {
+            let features = request(mode).features().unwrap();
+            assert_eq!(features.carrier_mode, "TLFlatbed");
+            assert_eq!(features.mode_label(), "TL Flatbed");
+            assert!(model.encoder.categories[0].contains(&features.carrier_mode), "{} falls in \"other\"", mode);
+        }
+        assert!(request("Rail").features().is_err());
+        assert_eq!(request(" ").features().unwrap().carrier_mode, DEFAULT_MODE.as_str());
+        assert!(DelayRequest { goal_days: 0, ..request("LTL") }.features().is_err());
+        assert!(DelayRequest { carrier: " ".to_string(), ..request("LTL") }.features().is_err());
+    }
+
+    #[test]
+    fn test_auc() {
+        let perfect = [(0.9, true), (0.8, true), (0.2, false), (0.1, false)];
+        assert_eq!(auc(&perfect), Some(1.0));
+        let ties = [(0.5, true), (0.5, false)];
+        assert_eq!(auc(&ties), Some(0.5));
+        let mixed = [(0.9, true), (0.7, false), (0.6, true), (0.1, false)];
+        assert_eq!(auc(&mixed), Some(0.75));
+        assert_eq!(auc(&[(0.3, false)]), None);
+    }
+
+    #[test]
+    fn test_train_learns_carrier_effect() {
+        let config = TrainConfig { min_category_count: 10, ..Default::default() };
+        let model = train(&history(), &config).unwrap();
+
+        let bad = model.predict(&shipment("bad", 1, 3, false).features);
+        let good = model.predict(&shipment("good", 1, 3, false).features);
+        assert!((bad - 0.7).abs() < 0.08, "{}", bad);
+        assert!((good - 0.1).abs() < 0.08, "{}", good);
+
+        let holdout = model.holdout.as_ref().unwrap();
+        assert_eq!(holdout.shipments, 160);
+        assert!(holdout.auc.unwrap() > 0.75, "{:?}", holdout.auc);
+        assert!(holdout.brier < holdout.baseline_brier);
+        let top = &model.explain(&shipment("bad", 1, 3, false).features, 1)[0];
+        assert_eq!(top.feature, format!("carrier={}", get_carrier_name("bad")));
+    }
+
+    #[test]
+    fn test_unseen_values_and_round_trip() {
+        let config = TrainConfig { min_category_count: 10, test_fraction: 0.0, ..Default::default() };
+        let model = train(&history(), &config).unwrap();
+        assert!(model.holdout.is_none());
+
+        let mut planned = DelayFeatures::planned(
+            CarrierMode::LTL, "new-carrier", "100xx", "200xx",
+            NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(), 3,
+        );
+        assert_eq!(planned.ship_dow, 1);
+        let p = model.predict(&planned);
+        assert!(p > 0.0 && p < 1.0);
+        planned.carrier_ref = "good".to_string();
+        assert!(model.predict(&planned) < p);
+
+        let path = std::env::temp_dir().join(format!("delay_model_test_{}.json", std::process::id()));
+        model.save(&path).unwrap();
+        let loaded = DelayModel::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.predict(&planned), model.predict(&planned));
+    }
+}
@@ -0,0 +1,467 @@
+//! ETA prediction from empirical transit-time distributions
+//!
+//! [`estimate_eta`] turns the transit times of comparable historical
+//! shipments into P50/P80/P95 delivery dates for a planned shipment. The
+//! comparison set is the most specific of lane + carrier, lane, and the
+//! lane's distance bucket that has at least [`MIN_SAMPLES`] shipments, all
+//! restricted to the requested mode. When none does, the most specific
+//! non-empty set is used and `samples` shows how thin it is.
+//!
+//! The distribution is a histogram of the recorded `actual_transit_days`,
+//! built in the database. Transit is counted in calendar days unless fewer
+//! than [`WEEKEND_DELIVERY_SHARE`] of the comparison set was delivered on a
+//! Saturday or Sunday. Then it is counted in business days, and ETAs skip
+//! weekends: a Friday shipment with a one-day P50 lands on Monday.
+//!
+//! Shipments planned at or after [`SHIP_CUTOFF_HOUR`] leave the next day.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike, Weekday};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::carrier_names::{find_carrier_id, get_carrier_name};
+use crate::clustering::distance_bucket_for_miles;
+use crate::db::DbConn;
+use crate::filter::normalize_zip3;
+use crate::location_names::format_lane_short;
+use crate::models::CarrierMode;
+
+/// Shipments a comparison set needs before a less specific one is tried
+pub const MIN_SAMPLES: usize = 30;
+
+/// Below this share of weekend deliveries, carriers are taken not to deliver
+/// on weekends. An even spread would put 2/7 (29%) on Saturday and Sunday.
+pub const WEEKEND_DELIVERY_SHARE: f64 = 0.05;
+
+/// Hour of the day from which a planned shipment leaves the next day
+pub const SHIP_CUTOFF_HOUR: u32 = 17;
+
+/// Percentiles every estimate reports
+pub const PERCENTILES: [u8; 3] = [50, 80, 95];
+
+/// A planned shipment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EtaRequest {
+    /// Origin ZIP3, with or without the "xx" suffix
+    pub origin_zip3: String,
+    pub dest_zip3: String,
+    /// Carrier ID or display name
+    pub carrier: String,
+    /// Carrier mode; all modes when omitted
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Ship date, optionally with a time (`2024-12-02` or `2024-12-02T14:30`);
+    /// from [`SHIP_CUTOFF_HOUR`] on, the shipment leaves the next day
+    #[serde(deserialize_with = "deserialize_ship")]
+    pub ship: NaiveDateTime,
+    /// Lane distance, used to pick the distance bucket for lanes without
+    /// history
+    #[serde(default)]
+    pub distance_miles: Option<f64>,
+}
+
+impl EtaRequest {
+    /// The requested mode; an error for unknown modes
+    pub fn carrier_mode(&self) -> Result<Option<CarrierMode>> {
+        let mode = self.mode.as_deref().map(str::trim).filter(|m| !m.is_empty());
+        mode.map(CarrierMode::resolve).transpose()
+    }
+}
+
+/// Parse a ship date or date-time; a bare date is midnight
+pub fn parse_ship_datetime(value: &str) -> Result<NaiveDateTime> {
+    let value = value.trim();
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(datetime);
+        }
+    }
+    match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => Ok(date.and_time(Default::default())),
+        Err(_) => bail!("invalid ship date '{}', expected YYYY-MM-DD or YYYY-MM-DDTHH:MM", value),
+    }
+}
+
+fn deserialize_ship<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDateTime, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_ship_datetime(&value).map_err(serde::de::Error::custom)
+}
+
+/// Which history an estimate was drawn from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EtaBasis {
+    LaneCarrier,
+    Lane,
+    DistanceBucket,
+}
+
+impl EtaBasis {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EtaBasis::LaneCarrier => "lane_carrier",
+            EtaBasis::Lane => "lane",
+            EtaBasis::DistanceBucket => "distance_bucket",
+        }
+    }
+}
+
+/// One percentile of the ETA
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EtaQuantile {
+    pub percentile: u8,
+    /// Calendar or business days, see [`EtaEstimate::skip_weekends`]
+    pub transit_days: i64,
+    pub delivery_date: NaiveDate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EtaEstimate {
+    pub lane: String,
+    pub carrier: String,
+    pub mode: Option<String>,
+    pub ship: NaiveDateTime,
+    /// Day transit starts: the ship date, or the next day after the cutoff
+    pub departure: NaiveDate,
+    pub basis: EtaBasis,
+    /// The comparison set, e.g. "DAL→COL via XPO Logistics" or "500-1k mi"
+    pub basis_label: String,
+    pub samples: usize,
+    /// Transit counted in business days and weekends skipped
+    pub skip_weekends: bool,
+    /// Fraction (0-1) of the comparison set delivered on a weekend; the REST,
+    /// gRPC and MCP responses send it as `weekend_delivery_rate` in percent
+    pub weekend_delivery_share: f64,
+    /// P50, P80 and P95, in that order
+    pub quantiles: Vec<EtaQuantile>,
+}
+
+impl EtaEstimate {
+    pub fn quantile(&self, percentile: u8) -> Option<&EtaQuantile> {
+        self.quantiles.iter().find(|q| q.percentile == percentile)
+    }
+}
+
+/// Shipments by recorded transit days, and how many of them were delivered
+/// on a weekend
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransitHistogram {
+    pub days: BTreeMap<i64, usize>,
+    pub weekend_deliveries: usize,
+}
+
+impl TransitHistogram {
+    pub fn add(&mut self, transit_days: i64, shipments: usize, weekend_deliveries: usize) {
+        *self.days.entry(transit_days.max(0)).or_default() += shipments;
+        self.weekend_deliveries += weekend_deliveries;
+    }
+
+    pub fn shipments(&self) -> usize {
+        self.days.values().sum()
+    }
+
+    /// Nearest-rank percentile
+    fn percentile(&self, percentile: u8) -> i64 {
+        let rank = ((percentile as f64 / 100.0 * self.shipments() as f64).ceil() as usize).max(1);
+        let mut seen = 0;
+        for (&days, &n) in &self.days {
+            seen += n;
+            if seen >= rank {
+                return days;
+            }
+        }
+        self.days.keys().next_back().copied().unwrap_or_default()
+    }
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// The `days`-th weekday after `ship`; zero days rolls a weekend ship date to
+/// Monday
+pub fn add_business_days(ship: NaiveDate, days: i64) -> NaiveDate {
+    if days <= 0 {
+        return ship.iter_days().find(|d| !is_weekend(*d)).unwrap_or(ship);
+    }
+    ship.iter_days()
+        .skip(1)
+        .filter(|d| !is_weekend(*d))
+        .nth(days as usize - 1)
+        .unwrap_or(ship)
+}
+
+/// Day a shipment planned at `ship` starts transit
+pub fn departure_date(ship: NaiveDateTime) -> NaiveDate {
+    if ship.hour() >= SHIP_CUTOFF_HOUR {
+        ship.date() + Duration::days(1)
+    } else {
+        ship.date()
+    }
+}
+
+/// P50/P80/P95 delivery dates for a shipment starting transit on `departure`
+/// with the transit distribution `history`. Returns `(skip_weekends,
+/// weekend_delivery_share, quantiles)`; an empty history gives no quantiles.
+pub fn quantiles_from(history: &TransitHistogram, departure: NaiveDate) -> (bool, f64, Vec<EtaQuantile>) {
+    let shipments = history.shipments();
+    if shipments == 0 {
+        return (false, 0.0, vec![]);
+    }
+    let weekend_share = history.weekend_deliveries as f64 / shipments as f64;
+    let skip_weekends = weekend_share < WEEKEND_DELIVERY_SHARE;
+
+    let quantiles = PERCENTILES
+        .iter()
+        .map(|&percentile| {
+            let transit_days = history.percentile(percentile);
+            let delivery_date = if skip_weekends {
+                add_business_days(departure, transit_days)
+            } else {
+                departure + Duration::days(transit_days)
+            };
+            EtaQuantile { percentile, transit_days, delivery_date }
+        })
+        .collect();
+    (skip_weekends, weekend_share, quantiles)
+}
+
+/// Shipments of one carrier and distance bucket with the same transit days
+#[derive(Debug, Deserialize)]
+struct TransitBin {
+    #[serde(default)]
+    carrier_ref: Option<String>,
+    #[serde(default)]
+    distance_bucket: Option<String>,
+    transit_days: i64,
+    shipments: usize,
+    weekend_deliveries: usize,
+}
+
+fn histogram<'a>(bins: impl IntoIterator<Item = &'a TransitBin>) -> TransitHistogram {
+    let mut histogram = TransitHistogram::default();
+    for bin in bins {
+        histogram.add(bin.transit_days, bin.shipments, bin.weekend_deliveries);
+    }
+    histogram
+}
+
+/// Transit-day histogram of the shipments matching `condition`, by carrier
+/// and distance bucket
+async fn load_transits(db: &DbConn, condition: &str, binds: Vec<(&'static str, String)>) -> Result<Vec<TransitBin>> {
+    let sql = format!(
+        r#"
+        SELECT carrier_ref, distance_bucket, transit_days, count() AS shipments,
+            math::sum(weekend) AS weekend_deliveries
+        FROM (
+            SELECT carrier_ref, distance_bucket, actual_transit_days AS transit_days,
+                (IF time::wday(<datetime> actual_delivery) >= 6 THEN 1 ELSE 0 END) AS weekend
+            FROM shipment WHERE {} AND actual_transit_days != NONE AND actual_delivery != NONE
+        )
+        GROUP BY carrier_ref, distance_bucket, transit_days
+        "#,
+        condition
+    );
+    let mut query = db.query(sql);
+    for bind in binds {
+        query = query.bind(bind);
+    }
+    Ok(query.await?.take(0)?)
+}
+
+/// Estimate P50/P80/P95 delivery dates for `request`. `None` when neither
+/// the lane nor its distance bucket has any shipments in the mode; lanes
+/// without history need `distance_miles` to reach the bucket.
+pub async fn estimate_eta(db: &DbConn, request: &EtaRequest) -> Result<Option<EtaEstimate>> {
+    let origin = normalize_zip3(&request.origin_zip3);
+    let dest = normalize_zip3(&request.dest_zip3);
+    let carrier_ref = find_carrier_id(&request.carrier)
+        .map(str::to_string)
+        .unwrap_or_else(|| request.carrier.trim().to_string());
+    let mode = request.carrier_mode()?;
+
+    let mode_condition = if mode.is_some() { " AND carrier_mode = $mode" } else { "" };
+    let mode_bind = mode.map(|m| ("mode", m.as_str().to_string()));
+
+    let lane_bins = load_transits(
+        db,
+        &format!("(origin_zip ?? origin_zip3) = $origin AND (dest_zip ?? dest_zip3) = $dest{}", mode_condition),
+        [("origin", origin.clone()), ("dest", dest.clone())].into_iter().chain(mode_bind.clone()).collect(),
+    )
+    .await?;
+
+    let lane = format_lane_short(&origin, &dest);
+    let carrier = get_carrier_name(&carrier_ref);
+    let lane_carrier = histogram(lane_bins.iter().filter(|b| b.carrier_ref.as_deref() == Some(carrier_ref.as_str())));
+    let lane_all = histogram(&lane_bins);
+
+    let mut levels = vec![
+        (EtaBasis::LaneCarrier, format!("{} via {}", lane, carrier), lane_carrier),
+        (EtaBasis::Lane, lane.clone(), lane_all),
+    ];
+
+    if levels.iter().all(|(_, _, h)| h.shipments() < MIN_SAMPLES) {
+        let bucket = request
+            .distance_miles
+            .map(|miles| distance_bucket_for_miles(miles).to_string())
+            .or_else(|| most_common_bucket(&lane_bins));
+        if let Some(bucket) = bucket {
+            let bins = load_transits(
+                db,
+                &format!("distance_bucket = $bucket{}", mode_condition),
+                std::iter::once(("bucket", bucket.clone())).chain(mode_bind).collect(),
+            )
+            .await?;
+            levels.push((EtaBasis::DistanceBucket, format!("{} mi", bucket), histogram(&bins)));
+        }
+    }
+
+    let chosen = levels
+        .iter()
+        .position(|(_, _, h)| h.shipments() >= MIN_SAMPLES)
+        .or_else(|| levels.iter().position(|(_, _, h)| h.shipments() > 0));
+    let Some(chosen) = chosen else {
+        return Ok(None);
+    };
+    let (basis, basis_label, history) = levels.swap_remove(chosen);
+
+    let departure = departure_date(request.ship);
+    let (skip_weekends, weekend_delivery_share, quantiles) = quantiles_from(&history, departure);
+    Ok(Some(EtaEstimate {
+        lane,
+        carrier,
+        mode: mode.map(|m| m.label().to_string()),
+        ship: request.ship,
+        departure,
+        basis,
+        basis_label,
+        samples: history.shipments(),
+        skip_weekends,
+        weekend_delivery_share,
+        quantiles,
+    }))
+}
+
+/// Distance bucket most of the lane's shipments fall in
+fn most_common_bucket(bins: &[TransitBin]) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for bin in bins {
+        let Some(bucket) = bin.distance_bucket.as_deref() else { continue };
+        if bucket != "unknown" && !bucket.is_empty() {
+            *counts.entry(bucket).or_default() += bin.shipments;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(bucket, _)| bucket.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_business_days() {
+        // 2024-03-01 is a Friday
+        assert_eq!(add_business_days(date("2024-03-01"), 1), date("2024-03-04"));
+        assert_eq!(add_business_days(date("2024-03-01"), 6), date("2024-03-11"));
+        assert_eq!(add_business_days(date("2024-03-02"), 0), date("2024-03-04"));
+        assert_eq!(add_business_days(date("2024-03-04"), 0), date("2024-03-04"));
+    }
+
+    #[test]
+    fn test_quantiles_skip_weekends_only_when_data_does() {
+        // 1-4 days in transit, never delivered on a weekend
+        let mut weekday_only = TransitHistogram::default();
+        for days in 1..=4 {
+            weekday_only.add(days, 25, 0);
+        }
+        let (skip, share, quantiles) = quantiles_from(&weekday_only, date("2024-03-08"));
+        assert!(skip);
+        assert_eq!(share, 0.0);
+        let days: Vec<i64> = quantiles.iter().map(|q| q.transit_days).collect();
+        assert_eq!(days, vec![2, 4, 4]);
+        // Friday + 2 business days is Tuesday
+        assert_eq!(quantiles[0].delivery_date, date("2024-03-12"));
+
+        // A quarter delivered on weekends: calendar days
+        let mut every_day = TransitHistogram::default();
+        for days in 1..=3 {
+            every_day.add(days, 20, 5);
+        }
+        let (skip, share, quantiles) = quantiles_from(&every_day, date("2024-03-08"));
+        assert!(!skip);
+        assert_eq!(share, 0.25);
+        assert_eq!(quantiles[2].transit_days, 3);
+        assert_eq!(quantiles[2].delivery_date, date("2024-03-11"));
+
+        assert!(quantiles_from(&TransitHistogram::default(), date("2024-03-08")).2.is_empty());
+    }
+
+    #[test]
+    fn test_departure_after_cutoff() {
+        let ship = |s: &str| parse_ship_datetime(s).unwrap();
+        assert_eq!(departure_date(ship("2024-03-08T09:00")), date("2024-03-08"));
+        assert_eq!(departure_date(ship("2024-03-08T17:30")), date("2024-03-09"));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_from_histogram() {
+        let db = crate::db::connect("mem://").await.unwrap();
+        crate::db::init_schema(&db).await.unwrap();
+        // 2024-03-04 is a Monday; 40 shipments on the lane, none on weekends
+        db.query(r#"
+            FOR $i IN 0..40 {
+                CREATE shipment SET origin_zip = "750xx", dest_zip = "432xx", carrier_ref = "C1",
+                    carrier_mode = "LTL", distance_bucket = "500-1k",
+                    actual_transit_days = 1 + $i % 2,
+                    actual_ship = d"2024-03-04T10:00:00Z",
+                    actual_delivery = d"2024-03-04T10:00:00Z" + <duration> string::concat(1 + $i % 2, "d");
+            };
+        "#).await.unwrap().check().unwrap();
+
+        let request = EtaRequest {
+            origin_zip3: "750".into(),
+            dest_zip3: "432".into(),
+            carrier: "C1".into(),
+            mode: Some("ltl".into()),
+            ship: parse_ship_datetime("2024-03-08T18:00").unwrap(),
+            distance_miles: None,
+        };
+        let estimate = estimate_eta(&db, &request).await.unwrap().unwrap();
+        assert_eq!(estimate.basis, EtaBasis::LaneCarrier);
+        assert_eq!(estimate.samples, 40);
+        assert!(estimate.skip_weekends);
+        // Leaves Saturday after the cutoff: one business day lands on Monday,
+        // two on Tuesday
+        assert_eq!(estimate.departure, date("2024-03-09"));
+        let p50 = estimate.quantile(50).unwrap();
+        assert_eq!((p50.transit_days, p50.delivery_date), (1, date("2024-03-11")));
+        let p95 = estimate.quantile(95).unwrap();
+        assert_eq!((p95.transit_days, p95.delivery_date), (2, date("2024-03-12")));
+
+        // A quarter of them delivered on Saturday: calendar days
+        db.query(r#"UPDATE (SELECT VALUE id FROM shipment LIMIT 10) SET actual_delivery = d"2024-03-09T10:00:00Z""#)
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        let estimate = estimate_eta(&db, &request).await.unwrap().unwrap();
+        assert!(!estimate.skip_weekends);
+        assert_eq!(estimate.weekend_delivery_share, 0.25);
+    }
+
+    #[test]
+    fn test_parse_ship_datetime() {
+        assert_eq!(parse_ship_datetime("2024-12-02").unwrap().to_string(), "2024-12-02 00:00:00");
+        assert_eq!(parse_ship_datetime("2024-12-02T14:30").unwrap().to_string(), "2024-12-02 14:30:00");
+        assert!(parse_ship_datetime("12/02/2024").is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! Shipment filters shared by the analytics binaries
+//!
+//! [`ShipmentFilter`] is flattened into each binary's CLI and narrows every
+//! query to a ship-date window and/or one carrier mode, carrier, origin ZIP3
+//! or distance bucket. Queries mark where the condition goes with
+//! `WHERE {filter}` and run through [`ShipmentFilter::query`], which
+//! substitutes the condition and binds its values as `$f_*` parameters.
+
+use chrono::{Days, NaiveDate};
+use serde::Serialize;
+use surrealdb::engine::any::Any;
+use surrealdb::method::Query;
+use surrealdb::sql::Datetime;
+
+use crate::carrier_names::find_carrier_id;
+use crate::db::DbConn;
+use crate::models::CarrierMode;
+
+/// Placeholder replaced by the filter condition
+pub const PLACEHOLDER: &str = "{filter}";
+
+/// `--from/--to/--mode/--carrier/--origin-zip3/--distance-bucket` flags
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct ShipmentFilter {
+    /// First ship date to include (YYYY-MM-DD)
+    #[arg(long)]
+    pub from: Option<NaiveDate>,
+
+    /// Last ship date to include (YYYY-MM-DD)
+    #[arg(long)]
+    pub to: Option<NaiveDate>,
+
+    /// Carrier mode, e.g. LTL, Truckload or "TL Flatbed" (case-insensitive)
+    #[arg(long, value_parser = parse_mode)]
+    pub mode: Option<CarrierMode>,
+
+    /// Carrier ID or display name
+    #[arg(long)]
+    pub carrier: Option<String>,
+
+    /// Origin ZIP3, with or without the "xx" suffix
+    #[arg(long = "origin-zip3")]
+    pub origin_zip3: Option<String>,
+
+    /// Distance bucket, e.g. 250-500 or 2k+
+    #[arg(long = "distance-bucket")]
+    pub distance_bucket: Option<String>,
+}
+
+fn parse_mode(value: &str) -> Result<CarrierMode, String> {
+    CarrierMode::resolve(value).map_err(|e| e.to_string())
+}
+
+/// Values bound for the filter condition
+#[derive(Debug, Clone, Serialize)]
+pub struct FilterParams {
+    f_from: Option<Datetime>,
+    f_until: Option<Datetime>,
+    f_mode: Option<String>,
+    f_carrier: Option<String>,
+    f_origin_zip3: Option<String>,
+    f_distance_bucket: Option<String>,
+}
+
+impl ShipmentFilter {
+    pub fn is_empty(&self) -> bool {
+        self.conditions().is_empty()
+    }
+
+    fn conditions(&self) -> Vec<&'static str> {
+        let mut conditions = Vec::new();
+        if self.from.is_some() {
+            conditions.push("actual_ship >= $f_from");
+        }
+        if self.to.is_some() {
+            conditions.push("actual_ship < $f_until");
+        }
+        if self.mode.is_some() {
+            conditions.push("carrier_mode = $f_mode");
+        }
+        if self.carrier.is_some() {
+            conditions.push("carrier_ref = $f_carrier");
+        }
+        if self.origin_zip3.is_some() {
+            conditions.push("(origin_zip ?? origin_zip3) = $f_origin_zip3");
+        }
+        if self.distance_bucket.is_some() {
+            conditions.push("distance_bucket = $f_distance_bucket");
+        }
+        conditions
+    }
+
+    /// SurrealQL condition for a `WHERE` clause (`true` when unfiltered)
+    pub fn condition(&self) -> String {
+        let conditions = self.conditions();
+        if conditions.is_empty() {
+            "true".to_string()
+        } else {
+            conditions.join(" AND ")
+        }
+    }
+
+    /// Parameter values referenced by [`condition`](Self::condition)
+    pub fn params(&self) -> FilterParams {
+        let day_start = |date: NaiveDate| Datetime::from(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+        FilterParams {
+            f_from: self.from.map(day_start),
+            // --to is inclusive, so compare against the start of the next day
+            f_until: self.to.and_then(|d| d.checked_add_days(Days::new(1))).map(day_start),
+            f_mode: self.mode.map(|m| m.as_str().to_string()),
+            f_carrier: self
+                .carrier
+                .as_ref()
+                .map(|c| find_carrier_id(c).map(str::to_string).unwrap_or_else(|| c.clone())),
+            f_origin_zip3: self.origin_zip3.as_ref().map(|z| normalize_zip3(z)),
+            f_distance_bucket: self.distance_bucket.clone(),
+        }
+    }
+
+    /// Substitute the condition for `{filter}` in `sql`
+    pub fn sql(&self, sql: &str) -> String {
+        sql.replace(PLACEHOLDER, &self.condition())
+    }
+
+    /// Run `sql` with the filter applied and its parameters bound
+    pub fn query<'a>(&self, db: &'a DbConn, sql: &str) -> Query<'a, Any> {
+        db.query(self.sql(sql)).bind(self.params())
+    }
+
+    /// Human-readable summary, e.g. `ship 2024-01-01..2024-03-31, mode LTL`
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        match (self.from, self.to) {
+            (Some(from), Some(to)) => parts.push(format!("ship {}..{}", from, to)),
+            (Some(from), None) => parts.push(format!("ship from {}", from)),
+            (None, Some(to)) => parts.push(format!("ship until {}", to)),
+            (None, None) => {}
+        }
+        if let Some(mode) = &self.mode {
+            parts.push(format!("mode {}", mode));
+        }
+        if let Some(carrier) = &self.carrier {
+            parts.push(format!("carrier {}", carrier));
+        }
+        if let Some(zip3) = &self.origin_zip3 {
+            parts.push(format!("origin {}", normalize_zip3(zip3)));
+        }
+        if let Some(bucket) = &self.distance_bucket {
+            parts.push(format!("distance {}", bucket));
+        }
+        parts.join(", ")
+    }
+}
+
+/// `"432"` -> `"432xx"`, the form stored on shipments
+pub fn normalize_zip3(zip3: &str) -> String {
+    let zip3 = zip3.trim().to_lowercase();
+    if zip3.len() == 3 {
+        format!("{}xx", zip3)
+    } else {
+        zip3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_filter_is_true() {
+        let filter = ShipmentFilter::default();
+        assert!(filter.is_empty());
+        assert_eq!(
+            filter.sql("SELECT * FROM shipment WHERE {filter} GROUP ALL"),
+            "SELECT * FROM shipment WHERE true GROUP ALL"
+        );
+        assert_eq!(filter.describe(), "");
+    }
+
+    #[test]
+    fn test_condition_and_params() {
+        let filter = ShipmentFilter {
+            from: NaiveDate::from_ymd_opt(2024, 1, 1),
+            to: NaiveDate::from_ymd_opt(2024, 3, 31),
+            mode: Some(CarrierMode::LTL),
+            origin_zip3: Some("432".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.condition(),
+            "actual_ship >= $f_from AND actual_ship < $f_until \
+             AND carrier_mode = $f_mode \
+             AND (origin_zip ?? origin_zip3) = $f_origin_zip3"
+        );
+        let params = filter.params();
+        assert_eq!(params.f_mode.as_deref(), Some("LTL"));
+        assert_eq!(params.f_origin_zip3.as_deref(), Some("432xx"));
+        assert_eq!(
+            params.f_until.map(|d| d.to_string()),
+            Some("d'2024-04-01T00:00:00Z'".to_string())
+        );
+        assert_eq!(filter.describe(), "ship 2024-01-01..2024-03-31, mode LTL, origin 432xx");
+    }
+
+    #[test]
+    fn test_carrier_name_resolves_to_id() {
+        let filter = ShipmentFilter {
+            carrier: Some("xpo logistics".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(filter.params().f_carrier.as_deref(), Some("0e32a59c0c8e"));
+    }
+
+    #[test]
+    fn test_mode_matches_stored_name() {
+        assert_eq!(parse_mode("TL Flatbed"), Ok(CarrierMode::TLFlatbed));
+        assert_eq!(parse_mode("tl dry"), Ok(CarrierMode::TLDry));
+        assert_eq!(parse_mode("TLFlatbed"), Ok(CarrierMode::TLFlatbed));
+        assert!(parse_mode("Rail").is_err());
+
+        let filter = ShipmentFilter {
+            mode: Some(CarrierMode::TLFlatbed),
+            ..Default::default()
+        };
+        assert_eq!(filter.params().f_mode.as_deref(), Some("TLFlatbed"));
+        assert_eq!(filter.describe(), "mode TL Flatbed");
+    }
+}
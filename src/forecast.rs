@@ -0,0 +1,588 @@
+//! Weekly volume forecasting
+//!
+//! Shipments are counted per ship week (Monday to Sunday) for the network,
+//! each origin terminal or each lane, and every series is projected N weeks
+//! ahead with 80% and 95% prediction intervals. [`Method::Auto`] uses
+//! additive Holt-Winters with a 52-week season once a series has two years
+//! of history, seasonal naive with year-over-year drift once it has one, and
+//! Holt's linear trend before that.
+//!
+//! Intervals assume normal one-step errors and grow with the horizon the way
+//! each method accumulates error. [`backtest`] refits on all but the last
+//! weeks of a series and reports the MAPE of the forecast for them.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbConn;
+use crate::filter::ShipmentFilter;
+use crate::location_names::{format_lane_short, get_location_long, get_location_short};
+
+/// Season length in weeks
+pub const SEASON_WEEKS: usize = 52;
+
+/// Shortest history any method forecasts from
+pub const MIN_HISTORY_WEEKS: usize = 4;
+
+/// Extra weeks beyond one season before seasonal naive is preferred to Holt
+const SEASONAL_NAIVE_EXTRA_WEEKS: usize = 8;
+
+const Z_80: f64 = 1.281552;
+const Z_95: f64 = 1.959964;
+
+/// Smoothing parameter grid searched when fitting
+const ALPHAS: [f64; 5] = [0.1, 0.2, 0.4, 0.6, 0.8];
+const BETAS: [f64; 4] = [0.01, 0.05, 0.1, 0.2];
+const GAMMAS: [f64; 4] = [0.05, 0.1, 0.3, 0.5];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Method {
+    /// Pick by history length
+    Auto,
+    /// Additive Holt-Winters (needs two seasons of history)
+    HoltWinters,
+    /// Same week last year plus the average year-over-year change
+    SeasonalNaive,
+    /// Holt's linear trend, no seasonality
+    Holt,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Auto => "auto",
+            Method::HoltWinters => "holt-winters",
+            Method::SeasonalNaive => "seasonal-naive",
+            Method::Holt => "holt",
+        }
+    }
+
+    /// The method [`Method::Auto`] stands for with `weeks` of history
+    pub fn resolve(self, weeks: usize) -> Method {
+        match self {
+            Method::Auto if weeks >= 2 * SEASON_WEEKS => Method::HoltWinters,
+            Method::Auto if weeks >= SEASON_WEEKS + SEASONAL_NAIVE_EXTRA_WEEKS => Method::SeasonalNaive,
+            Method::Auto => Method::Holt,
+            method => method,
+        }
+    }
+
+    fn min_history(&self) -> usize {
+        match self {
+            Method::HoltWinters => 2 * SEASON_WEEKS,
+            Method::SeasonalNaive => SEASON_WEEKS + 1,
+            Method::Auto | Method::Holt => MIN_HISTORY_WEEKS,
+        }
+    }
+}
+
+/// What a series is counted over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// All shipments matching the filters
+    Network,
+    /// Per origin ZIP3
+    Terminal,
+    /// Per origin → destination ZIP3
+    Lane,
+}
+
+/// Weekly shipment counts of one terminal, lane or the network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeeklySeries {
+    /// Origin ZIP3, `origin→dest`, or "network"
+    pub key: String,
+    /// Display name, e.g. "DFW (Dallas, TX)" or "DFW→AUS"
+    pub label: String,
+    /// Monday of the first week
+    pub start: NaiveDate,
+    pub counts: Vec<f64>,
+}
+
+impl WeeklySeries {
+    /// Monday of week `i`, which may lie past the end of the history
+    pub fn week(&self, i: usize) -> NaiveDate {
+        self.start + Duration::weeks(i as i64)
+    }
+
+    pub fn total(&self) -> f64 {
+        self.counts.iter().sum()
+    }
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Roll `(key, ship date, shipments)` rows up to aligned weekly series. All
+/// series share the weeks of the whole data set, with zeros where a key
+/// shipped nothing. A first week the data starts after Monday, or a last
+/// week it ends before Friday, is only partly covered and is dropped.
+pub fn bucket_weeks(rows: &[(String, NaiveDate, i64)]) -> Vec<(String, NaiveDate, Vec<f64>)> {
+    let (Some(first), Some(last)) = (rows.iter().map(|r| r.1).min(), rows.iter().map(|r| r.1).max()) else {
+        return vec![];
+    };
+    let mut start = week_start(first);
+    if first.weekday() != Weekday::Mon {
+        start += Duration::weeks(1);
+    }
+    let mut end = week_start(last);
+    if last.weekday().num_days_from_monday() < Weekday::Fri.num_days_from_monday() {
+        end -= Duration::weeks(1);
+    }
+    if end < start {
+        return vec![];
+    }
+    let weeks = ((end - start).num_weeks() + 1) as usize;
+
+    let mut series: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for (key, date, shipments) in rows {
+        let week = week_start(*date);
+        if week < start || week > end {
+            continue;
+        }
+        let i = ((week - start).num_weeks()) as usize;
+        series.entry(key.as_str()).or_insert_with(|| vec![0.0; weeks])[i] += *shipments as f64;
+    }
+    series.into_iter().map(|(key, counts)| (key.to_string(), start, counts)).collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct DailyCount {
+    #[serde(default)]
+    origin_zip: Option<String>,
+    #[serde(default)]
+    dest_zip: Option<String>,
+    day: String,
+    shipments: i64,
+}
+
+/// Weekly series at `level` for the shipments matching `filter`, largest
+/// first
+pub async fn load_weekly(db: &DbConn, filter: &ShipmentFilter, level: Level) -> Result<Vec<WeeklySeries>> {
+    let group = match level {
+        Level::Network => "",
+        Level::Terminal => "origin_zip,",
+        Level::Lane => "origin_zip, dest_zip,",
+    };
+    let sql = format!(
+        r#"
+        SELECT {group} day, count() as shipments FROM (
+            SELECT
+                (origin_zip ?? origin_zip3) as origin_zip,
+                (dest_zip ?? dest_zip3) as dest_zip,
+                time::format(actual_ship, "%Y-%m-%d") as day
+            FROM shipment WHERE {{filter}}
+        )
+        GROUP BY {group} day
+        "#,
+        group = group
+    );
+    let daily: Vec<DailyCount> = filter.query(db, &sql).await?.take(0)?;
+
+    let rows: Vec<(String, NaiveDate, i64)> = daily
+        .into_iter()
+        .filter_map(|d| {
+            let date = NaiveDate::parse_from_str(&d.day, "%Y-%m-%d").ok()?;
+            let key = match level {
+                Level::Network => "network".to_string(),
+                Level::Terminal => d.origin_zip?,
+                Level::Lane => format!("{}→{}", d.origin_zip?, d.dest_zip?),
+            };
+            Some((key, date, d.shipments))
+        })
+        .collect();
+
+    let mut series: Vec<WeeklySeries> = bucket_weeks(&rows)
+        .into_iter()
+        .map(|(key, start, counts)| {
+            let label = match level {
+                Level::Network => "Network".to_string(),
+                Level::Terminal => format!("{} ({})", get_location_short(&key), get_location_long(&key)),
+                Level::Lane => {
+                    let (origin, dest) = key.split_once('→').unwrap_or((&key, ""));
+                    format_lane_short(origin, dest)
+                }
+            };
+            WeeklySeries { key, label, start, counts }
+        })
+        .collect();
+    series.sort_by(|a, b| b.total().total_cmp(&a.total()));
+    Ok(series)
+}
+
+// ============================================================================
+// Methods
+// ============================================================================
+
+/// Point forecasts and their standard errors for the weeks after a history
+#[derive(Debug, Clone)]
+pub struct Projection {
+    /// The method used; never [`Method::Auto`]
+    pub method: Method,
+    pub mean: Vec<f64>,
+    pub std_err: Vec<f64>,
+}
+
+/// Standard error `h` steps ahead from the one-step variance and the
+/// method's error weights `psi_1..psi_{h-1}`
+fn std_errors(variance: f64, horizon: usize, psi: impl Fn(usize) -> f64) -> Vec<f64> {
+    let mut sum = 1.0;
+    (1..=horizon)
+        .map(|h| {
+            if h > 1 {
+                sum += psi(h - 1).powi(2);
+            }
+            (variance * sum).sqrt()
+        })
+        .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+/// Holt's linear trend: one-step SSE and the final level and trend
+fn holt_run(y: &[f64], alpha: f64, beta: f64) -> (f64, usize, f64, f64) {
+    let (mut level, mut trend) = (y[0], y[1] - y[0]);
+    let mut sse = 0.0;
+    for &value in &y[1..] {
+        let error = value - (level + trend);
+        sse += error * error;
+        let previous = level;
+        level = alpha * value + (1.0 - alpha) * (level + trend);
+        trend = beta * (level - previous) + (1.0 - beta) * trend;
+    }
+    (sse, y.len() - 1, level, trend)
+}
+
+fn holt(y: &[f64], horizon: usize) -> Projection {
+    let (_, alpha, beta) = ALPHAS
+        .iter()
+        .flat_map(|&a| BETAS.iter().map(move |&b| (holt_run(y, a, b).0, a, b)))
+        .min_by(|x, y| x.0.total_cmp(&y.0))
+        .unwrap_or((0.0, ALPHAS[0], BETAS[0]));
+    let (sse, errors, level, trend) = holt_run(y, alpha, beta);
+    Projection {
+        method: Method::Holt,
+        mean: (1..=horizon).map(|h| level + h as f64 * trend).collect(),
+        std_err: std_errors(sse / errors as f64, horizon, |j| alpha * (1.0 + j as f64 * beta)),
+    }
+}
+
+/// Additive Holt-Winters: one-step SSE, error count, level, trend and the
+/// last season of seasonal terms
+fn holt_winters_run(y: &[f64], alpha: f64, beta: f64, gamma: f64) -> (f64, usize, f64, f64, Vec<f64>) {
+    let m = SEASON_WEEKS;
+    // Start from the first season's mean and the change to the second's.
+    // Seasonal terms are taken about the trend line through the first
+    // season, or its slope would be read as a step at every year end.
+    let first = mean(&y[..m]);
+    let mut trend = (mean(&y[m..2 * m]) - first) / m as f64;
+    let middle = (m - 1) as f64 / 2.0;
+    let mut level = first + trend * middle;
+    let mut season: Vec<f64> = y[..m]
+        .iter()
+        .enumerate()
+        .map(|(i, v)| v - (first + trend * (i as f64 - middle)))
+        .collect();
+    let mut sse = 0.0;
+    for (t, &value) in y.iter().enumerate().skip(m) {
+        let s = season[t - m];
+        let error = value - (level + trend + s);
+        sse += error * error;
+        let previous = level;
+        level = alpha * (value - s) + (1.0 - alpha) * (level + trend);
+        trend = beta * (level - previous) + (1.0 - beta) * trend;
+        season.push(gamma * (value - level) + (1.0 - gamma) * s);
+    }
+    let last_season = season.split_off(season.len() - m);
+    (sse, y.len() - m, level, trend, last_season)
+}
+
+fn holt_winters(y: &[f64], horizon: usize) -> Projection {
+    let m = SEASON_WEEKS;
+    let mut best = (f64::INFINITY, ALPHAS[0], BETAS[0], GAMMAS[0]);
+    for &alpha in &ALPHAS {
+        for &beta in &BETAS {
+            for &gamma in &GAMMAS {
+                let sse = holt_winters_run(y, alpha, beta, gamma).0;
+                if sse < best.0 {
+                    best = (sse, alpha, beta, gamma);
+                }
+            }
+        }
+    }
+    let (_, alpha, beta, gamma) = best;
+    let (sse, errors, level, trend, season) = holt_winters_run(y, alpha, beta, gamma);
+    Projection {
+        method: Method::HoltWinters,
+        mean: (1..=horizon)
+            .map(|h| level + h as f64 * trend + season[(h - 1) % m])
+            .collect(),
+        std_err: std_errors(sse / errors as f64, horizon, |j| {
+            alpha * (1.0 + j as f64 * beta) + if j % m == 0 { gamma } else { 0.0 }
+        }),
+    }
+}
+
+fn seasonal_naive(y: &[f64], horizon: usize) -> Projection {
+    let m = SEASON_WEEKS;
+    let n = y.len();
+    let changes: Vec<f64> = (m..n).map(|t| y[t] - y[t - m]).collect();
+    let drift = mean(&changes);
+    let variance = changes.iter().map(|c| (c - drift).powi(2)).sum::<f64>() / changes.len().max(1) as f64;
+    Projection {
+        method: Method::SeasonalNaive,
+        mean: (1..=horizon)
+            .map(|h| {
+                let seasons = (h - 1) / m + 1;
+                y[n - 1 + h - m * seasons] + seasons as f64 * drift
+            })
+            .collect(),
+        std_err: (1..=horizon)
+            .map(|h| (variance * ((h - 1) / m + 1) as f64).sqrt())
+            .collect(),
+    }
+}
+
+/// Project `y` `horizon` weeks ahead
+pub fn project(y: &[f64], horizon: usize, method: Method) -> Result<Projection> {
+    let method = method.resolve(y.len());
+    if y.len() < method.min_history() {
+        bail!(
+            "{} needs {} weeks of history, have {}",
+            method.as_str(),
+            method.min_history(),
+            y.len()
+        );
+    }
+    Ok(match method {
+        Method::HoltWinters => holt_winters(y, horizon),
+        Method::SeasonalNaive => seasonal_naive(y, horizon),
+        Method::Auto | Method::Holt => holt(y, horizon),
+    })
+}
+
+// ============================================================================
+// Forecasts
+// ============================================================================
+
+/// Forecast shipments for one week. Bounds are clipped at zero.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForecastPoint {
+    /// Monday of the week
+    pub week: NaiveDate,
+    pub forecast: f64,
+    pub lower_80: f64,
+    pub upper_80: f64,
+    pub lower_95: f64,
+    pub upper_95: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesForecast {
+    pub key: String,
+    pub label: String,
+    pub method: Method,
+    pub history_weeks: usize,
+    /// Shipments in the last complete week of history
+    pub last_week: f64,
+    pub points: Vec<ForecastPoint>,
+}
+
+/// Forecast `series` `horizon` weeks past its last week
+pub fn forecast(series: &WeeklySeries, horizon: usize, method: Method) -> Result<SeriesForecast> {
+    let projection = project(&series.counts, horizon, method)?;
+    let n = series.counts.len();
+    let points = projection
+        .mean
+        .iter()
+        .zip(&projection.std_err)
+        .enumerate()
+        .map(|(h, (&mean, &se))| {
+            let mean = mean.max(0.0);
+            ForecastPoint {
+                week: series.week(n + h),
+                forecast: mean,
+                lower_80: (mean - Z_80 * se).max(0.0),
+                upper_80: mean + Z_80 * se,
+                lower_95: (mean - Z_95 * se).max(0.0),
+                upper_95: mean + Z_95 * se,
+            }
+        })
+        .collect();
+    Ok(SeriesForecast {
+        key: series.key.clone(),
+        label: series.label.clone(),
+        method: projection.method,
+        history_weeks: n,
+        last_week: series.counts.last().copied().unwrap_or(0.0),
+        points,
+    })
+}
+
+/// Forecast shipments in one calendar month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonthlyForecast {
+    /// `2024-12`
+    pub month: String,
+    /// Forecast weeks assigned to the month
+    pub weeks: usize,
+    pub forecast: f64,
+}
+
+/// Sum weekly forecasts by month. A week counts toward the month its
+/// Thursday falls in, as with ISO weeks, so a month has four or five weeks.
+pub fn monthly_totals(points: &[ForecastPoint]) -> Vec<MonthlyForecast> {
+    let mut months: BTreeMap<(i32, u32), (usize, f64)> = BTreeMap::new();
+    for point in points {
+        let thursday = point.week + Duration::days(3);
+        let entry = months.entry((thursday.year(), thursday.month())).or_default();
+        entry.0 += 1;
+        entry.1 += point.forecast;
+    }
+    months
+        .into_iter()
+        .map(|((year, month), (weeks, forecast))| MonthlyForecast {
+            month: format!("{}-{:02}", year, month),
+            weeks,
+            forecast,
+        })
+        .collect()
+}
+
+/// Accuracy of a forecast made without the last `weeks` of a series
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backtest {
+    pub key: String,
+    pub label: String,
+    pub method: Method,
+    pub weeks: usize,
+    /// Mean absolute percentage error over the held-out weeks with
+    /// shipments; `None` when all of them were zero
+    pub mape: Option<f64>,
+    /// Share of held-out weeks inside the 95% interval
+    pub coverage_95: f64,
+}
+
+/// Refit `series` without its last `weeks` and score the forecast for them
+pub fn backtest(series: &WeeklySeries, weeks: usize, method: Method) -> Result<Backtest> {
+    let n = series.counts.len();
+    if weeks == 0 || weeks >= n {
+        bail!("cannot hold out {} of {} weeks", weeks, n);
+    }
+    let training = WeeklySeries { counts: series.counts[..n - weeks].to_vec(), ..series.clone() };
+    let forecast = forecast(&training, weeks, method)?;
+    let actual = &series.counts[n - weeks..];
+
+    let errors: Vec<f64> = actual
+        .iter()
+        .zip(&forecast.points)
+        .filter(|(a, _)| **a > 0.0)
+        .map(|(a, p)| (a - p.forecast).abs() / a)
+        .collect();
+    let covered = actual
+        .iter()
+        .zip(&forecast.points)
+        .filter(|(a, p)| **a >= p.lower_95 && **a <= p.upper_95)
+        .count();
+    Ok(Backtest {
+        key: series.key.clone(),
+        label: series.label.clone(),
+        method: forecast.method,
+        weeks,
+        mape: (!errors.is_empty()).then(|| mean(&errors)),
+        coverage_95: covered as f64 / weeks as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    /// Three years of weekly volume: trend, yearly cycle and a little noise
+    fn seasonal(weeks: usize) -> Vec<f64> {
+        (0..weeks)
+            .map(|t| {
+                let cycle = (2.0 * std::f64::consts::PI * t as f64 / SEASON_WEEKS as f64).sin();
+                let noise = ((t * 7919) % 17) as f64 - 8.0;
+                200.0 + 0.5 * t as f64 + 60.0 * cycle + noise
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bucket_weeks_drops_partial_edges() {
+        // 2024-03-06 is a Wednesday, 2024-03-26 a Tuesday
+        let rows: Vec<(String, NaiveDate, i64)> = vec![
+            ("a".into(), date("2024-03-06"), 5),
+            ("a".into(), date("2024-03-11"), 2),
+            ("a".into(), date("2024-03-15"), 3),
+            ("b".into(), date("2024-03-20"), 4),
+            ("b".into(), date("2024-03-26"), 9),
+        ];
+        let series = bucket_weeks(&rows);
+        assert_eq!(series.len(), 2);
+        let (key, start, counts) = &series[0];
+        assert_eq!((key.as_str(), *start), ("a", date("2024-03-11")));
+        assert_eq!(counts, &vec![5.0, 0.0]);
+        assert_eq!(series[1].2, vec![0.0, 4.0]);
+    }
+
+    #[test]
+    fn test_auto_method_by_history() {
+        assert_eq!(Method::Auto.resolve(30), Method::Holt);
+        assert_eq!(Method::Auto.resolve(70), Method::SeasonalNaive);
+        assert_eq!(Method::Auto.resolve(104), Method::HoltWinters);
+        assert!(project(&seasonal(80), 4, Method::HoltWinters).is_err());
+        assert!(project(&[1.0, 2.0], 4, Method::Auto).is_err());
+    }
+
+    #[test]
+    fn test_holt_winters_tracks_season() {
+        let history = seasonal(3 * SEASON_WEEKS);
+        let future = &seasonal(3 * SEASON_WEEKS + 12)[3 * SEASON_WEEKS..];
+        let projection = project(&history, 12, Method::Auto).unwrap();
+        assert_eq!(projection.method, Method::HoltWinters);
+        for (forecast, actual) in projection.mean.iter().zip(future) {
+            assert!((forecast - actual).abs() < 20.0, "{} vs {}", forecast, actual);
+        }
+        // Intervals widen with the horizon
+        assert!(projection.std_err[11] > projection.std_err[0]);
+
+        let naive = project(&history, 12, Method::SeasonalNaive).unwrap();
+        for (forecast, actual) in naive.mean.iter().zip(future) {
+            assert!((forecast - actual).abs() < 25.0, "{} vs {}", forecast, actual);
+        }
+    }
+
+    #[test]
+    fn test_backtest_and_monthly_totals() {
+        let series = WeeklySeries {
+            key: "network".into(),
+            label: "Network".into(),
+            start: date("2022-01-03"),
+            counts: seasonal(3 * SEASON_WEEKS),
+        };
+        let result = backtest(&series, 8, Method::Auto).unwrap();
+        assert!(result.mape.unwrap() < 0.1, "{:?}", result.mape);
+        assert!(result.coverage_95 >= 0.75, "{}", result.coverage_95);
+
+        let forecast = forecast(&series, 9, Method::Auto).unwrap();
+        assert_eq!(forecast.points[0].week, date("2024-12-30"));
+        let months = monthly_totals(&forecast.points);
+        assert_eq!(months[0].month, "2025-01");
+        assert_eq!(months[0].weeks, 5);
+        assert_eq!(months.iter().map(|m| m.weeks).sum::<usize>(), 9);
+    }
+}
@@ -0,0 +1,1254 @@
+//! Streaming, batched ingestion with resumable checkpoints
+//!
+//! Records are read one row at a time and written to SurrealDB in multi-row
+//! transactions. Each transaction also stores a checkpoint (rows committed and
+//! the byte offset of the next row), so an interrupted load can resume from
+//! the last committed batch without creating duplicate `load_id`s. CSV,
+//! Parquet and NDJSON sources are read through [`RowReader`], which renames
+//! source fields onto the CSV column names.
+//!
+//! Every row passes through [`Validate`] first; rejected rows are counted in
+//! the run's [`ValidationReport`] and optionally copied to a reject file.
+//!
+//! In incremental mode rows are upserted by `load_id`, so new weeks of data
+//! can be appended to an existing database. Shipments whose carrier, location
+//! or lane changed have their graph edges rebuilt, and the data version is
+//! bumped so long-lived readers drop cached aggregates.
+//!
+//! The target schema is chosen with [`SchemaFlavor`]: the basic graph, the
+//! enhanced schema with record links on lanes and shipments, or the ZIP5 graph
+//! used for synthetic data. All flavors share one namespace/database pair
+//! (see [`db::NAMESPACE`]) and use deterministic record ids for entities.
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use csv::StringRecord;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use surrealdb::sql::{Datetime, Thing};
+use tracing::{info, warn};
+
+use crate::db;
+use crate::models::{CsvRecord, SyntheticCsvRecord};
+use crate::sources::{ColumnMapping, InputFormat, RowReader};
+use crate::validation::{Reject, RejectReason, RejectWriter, Validate, ValidationReport};
+
+/// Table holding one checkpoint record per ingested source file
+pub const CHECKPOINT_TABLE: &str = "ingest_checkpoint";
+
+// ============================================================================
+// Options and Results
+// ============================================================================
+
+/// Schema an ingest run targets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaFlavor {
+    /// Shipments with string references to carriers, locations and lanes
+    #[default]
+    Basic,
+    /// Basic plus record links (`carrier`, `lane`, `origin`, `destination`)
+    /// and performance vectors
+    Enhanced,
+    /// Basic plus ZIP5 locations and lanes (synthetic data format)
+    Zip5,
+}
+
+impl SchemaFlavor {
+    /// Bring the database to the latest schema. Every flavor shares one
+    /// versioned schema; the flavor only decides how rows are mapped.
+    pub async fn init_schema(&self, db: &db::DbConn) -> Result<()> {
+        db::init_schema(db).await
+    }
+
+    /// Whether shipments and lanes carry record links to their entities
+    pub fn record_links(&self) -> bool {
+        matches!(self, SchemaFlavor::Enhanced)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IngestOptions {
+    /// Target schema
+    pub schema: SchemaFlavor,
+    /// Rows written per transaction
+    pub batch_size: usize,
+    /// Continue from the stored checkpoint instead of starting at row 0
+    pub resume: bool,
+    /// Sidecar CSV for rejected rows (created on the first reject)
+    pub rejects_path: Option<PathBuf>,
+    /// Upsert by `load_id` instead of failing on loads already stored
+    pub incremental: bool,
+    /// Relate shipments to their carrier, locations and lanes as they are written
+    pub graph: bool,
+    /// Source format; guessed from the file extension when `None`
+    pub format: Option<InputFormat>,
+    /// Renames source fields onto the CSV column names
+    pub columns: ColumnMapping,
+}
+
+impl Default for IngestOptions {
+    fn default() -> Self {
+        Self {
+            schema: SchemaFlavor::Basic,
+            batch_size: 1000,
+            resume: true,
+            rejects_path: None,
+            incremental: false,
+            graph: false,
+            format: None,
+            columns: ColumnMapping::default(),
+        }
+    }
+}
+
+/// Progress marker stored alongside each committed batch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub source: String,
+    /// Data rows consumed from the file (including rejected rows)
+    pub rows: u64,
+    /// Byte offset of the next unread row (unused for Parquet)
+    pub byte: u64,
+    /// Line number of the next unread row
+    pub line: u64,
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IngestStats {
+    /// Rows skipped because an earlier run already committed them
+    pub resumed_rows: u64,
+    pub rows_read: u64,
+    /// Shipments inserted
+    pub shipments: u64,
+    /// Stored shipments replaced by an incremental load
+    pub updated: u64,
+    /// Updated shipments whose graph edges were rebuilt
+    pub relinked: u64,
+    pub synthetic: u64,
+    /// Valid rows the database refused (e.g. duplicate `load_id`)
+    pub errors: u64,
+    pub carriers: usize,
+    pub locations: usize,
+    pub locations_zip5: usize,
+    pub lanes: usize,
+    pub lanes_zip5: usize,
+    /// Validation outcome for the rows read in this run
+    pub report: ValidationReport,
+}
+
+// ============================================================================
+// Row Mapping
+// ============================================================================
+
+/// Document written to the `shipment` table.
+///
+/// Fields that only exist in one CSV flavor are skipped when absent, so
+/// basic and synthetic loads keep their original document shape.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShipmentDoc {
+    pub load_id: String,
+    pub carrier_mode: String,
+    pub actual_ship: Datetime,
+    pub actual_delivery: Datetime,
+    pub carrier_posted_service_days: Option<f64>,
+    pub customer_distance: Option<f64>,
+    pub truckload_service_days: Option<f64>,
+    pub goal_transit_days: i32,
+    pub actual_transit_days: i32,
+    pub otd: String,
+    pub ship_dow: i32,
+    pub ship_week: i32,
+    pub ship_month: i32,
+    pub ship_year: i32,
+    pub distance_bucket: String,
+    pub carrier_ref: String,
+    pub lane_ref: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin_zip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dest_zip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin_zip3: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dest_zip3: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin_zip5: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dest_zip5: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lane_zip3_pair: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lane_zip5_pair: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_synthetic: Option<bool>,
+    /// Record links, only written for [`SchemaFlavor::Enhanced`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub carrier: Option<Thing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lane: Option<Thing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Thing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination: Option<Thing>,
+}
+
+impl ShipmentDoc {
+    /// Fill the record link fields from the string references
+    fn link_records(&mut self) {
+        let origin = self.origin_zip.as_ref().or(self.origin_zip3.as_ref());
+        let dest = self.dest_zip.as_ref().or(self.dest_zip3.as_ref());
+        self.carrier = Some(record_id("carrier", &self.carrier_ref));
+        self.lane = Some(record_id("lane", &self.lane_ref));
+        self.origin = origin.map(|zip| record_id("location", zip));
+        self.destination = dest.map(|zip| record_id("location", zip));
+    }
+}
+
+fn to_datetime(dt: NaiveDateTime) -> Datetime {
+    Datetime::from(dt.and_utc())
+}
+
+/// Record key for an entity: non-alphanumeric characters become `_`
+pub fn record_key(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Deterministic record id, e.g. `location:750xx` or `lane:750xx_100xx`
+pub fn record_id(table: &str, value: &str) -> Thing {
+    Thing::from((table, record_key(value).as_str()))
+}
+
+/// Split a lane pair written as "750xx→100xx" (or "750xx-100xx")
+fn split_pair(pair: &str) -> Option<(&str, &str)> {
+    pair.split_once('→').or_else(|| pair.split_once('-'))
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CarrierRow {
+    id: Thing,
+    carrier_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LocationRow {
+    id: Thing,
+    zip3: String,
+    state: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Location5Row {
+    id: Thing,
+    zip5: String,
+    zip3: String,
+    state: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LaneRow {
+    id: Thing,
+    lane_id: String,
+    zip3_pair: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    origin: Option<Thing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    destination: Option<Thing>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Lane5Row {
+    id: Thing,
+    zip5_pair: String,
+    zip3_pair: String,
+    origin_zip5: String,
+    dest_zip5: String,
+}
+
+/// Entities first seen in the current batch
+#[derive(Debug, Default)]
+pub struct EntityBatch {
+    carriers: Vec<CarrierRow>,
+    locations: Vec<LocationRow>,
+    locations5: Vec<Location5Row>,
+    lanes: Vec<LaneRow>,
+    lanes5: Vec<Lane5Row>,
+}
+
+impl EntityBatch {
+    fn is_empty(&self) -> bool {
+        self.carriers.is_empty()
+            && self.locations.is_empty()
+            && self.locations5.is_empty()
+            && self.lanes.is_empty()
+            && self.lanes5.is_empty()
+    }
+}
+
+/// Entities already present in the database (or queued in a pending batch)
+#[derive(Debug, Default)]
+pub struct KnownEntities {
+    carriers: HashSet<String>,
+    locations: HashSet<String>,
+    locations5: HashSet<String>,
+    lanes: HashSet<String>,
+    lanes5: HashSet<String>,
+    /// Give new lanes record links to their origin and destination
+    record_links: bool,
+}
+
+impl KnownEntities {
+    /// Load the keys of existing carriers, locations and lanes
+    pub async fn load(db: &db::DbConn, schema: SchemaFlavor) -> Result<Self> {
+        let mut response = db
+            .query("SELECT VALUE carrier_id FROM carrier")
+            .query("SELECT VALUE zip3 FROM location")
+            .query("SELECT VALUE zip5 FROM location5")
+            .query("SELECT VALUE lane_id FROM lane")
+            .query("SELECT VALUE zip5_pair FROM lane5")
+            .await?;
+
+        let carriers: Vec<String> = response.take(0)?;
+        let locations: Vec<String> = response.take(1)?;
+        let locations5: Vec<String> = response.take(2)?;
+        let lanes: Vec<String> = response.take(3)?;
+        let lanes5: Vec<String> = response.take(4)?;
+
+        Ok(Self {
+            carriers: carriers.into_iter().collect(),
+            locations: locations.into_iter().collect(),
+            locations5: locations5.into_iter().collect(),
+            lanes: lanes.into_iter().collect(),
+            lanes5: lanes5.into_iter().collect(),
+            record_links: schema.record_links(),
+        })
+    }
+
+    fn carrier(&mut self, carrier_id: &str, batch: &mut EntityBatch) {
+        if self.carriers.insert(carrier_id.to_string()) {
+            batch.carriers.push(CarrierRow {
+                id: record_id("carrier", carrier_id),
+                carrier_id: carrier_id.to_string(),
+            });
+        }
+    }
+
+    fn location(&mut self, zip3: &str, batch: &mut EntityBatch) {
+        if self.locations.insert(zip3.to_string()) {
+            batch.locations.push(LocationRow {
+                id: record_id("location", zip3),
+                zip3: zip3.to_string(),
+                state: extract_state(zip3),
+            });
+        }
+    }
+
+    fn location5(&mut self, zip5: &str, batch: &mut EntityBatch) {
+        if self.locations5.insert(zip5.to_string()) {
+            batch.locations5.push(Location5Row {
+                id: record_id("location5", zip5),
+                zip5: zip5.to_string(),
+                zip3: SyntheticCsvRecord::zip5_to_zip3(zip5),
+                state: extract_state(zip5),
+            });
+        }
+    }
+
+    fn lane(&mut self, lane_id: &str, zip3_pair: &str, batch: &mut EntityBatch) {
+        if self.lanes.insert(lane_id.to_string()) {
+            let ends = split_pair(zip3_pair).filter(|_| self.record_links);
+            batch.lanes.push(LaneRow {
+                id: record_id("lane", lane_id),
+                lane_id: lane_id.to_string(),
+                zip3_pair: zip3_pair.to_string(),
+                origin: ends.map(|(origin, _)| record_id("location", origin)),
+                destination: ends.map(|(_, dest)| record_id("location", dest)),
+            });
+        }
+    }
+
+    fn lane5(&mut self, zip5_pair: &str, batch: &mut EntityBatch) {
+        let Some((origin, dest)) = zip5_pair.split_once('→') else {
+            return;
+        };
+        if self.lanes5.insert(zip5_pair.to_string()) {
+            batch.lanes5.push(Lane5Row {
+                id: record_id("lane5", zip5_pair),
+                zip5_pair: zip5_pair.to_string(),
+                zip3_pair: format!(
+                    "{}→{}",
+                    SyntheticCsvRecord::zip5_to_zip3(origin),
+                    SyntheticCsvRecord::zip5_to_zip3(dest)
+                ),
+                origin_zip5: origin.to_string(),
+                dest_zip5: dest.to_string(),
+            });
+        }
+    }
+}
+
+/// A CSV row type that can be streamed into the shipment graph
+pub trait IngestRecord: DeserializeOwned + Validate {
+    /// CSV column names, in file order
+    const COLUMNS: &'static [&'static str];
+
+    /// Build the `shipment` document for this row
+    fn shipment_doc(&self) -> Result<ShipmentDoc>;
+
+    /// Register the carrier, locations and lanes referenced by this row
+    fn collect_entities(&self, known: &mut KnownEntities, batch: &mut EntityBatch);
+
+    fn is_synthetic(&self) -> bool {
+        false
+    }
+}
+
+impl IngestRecord for CsvRecord {
+    const COLUMNS: &'static [&'static str] = &[
+        "carrier_mode",
+        "actual_ship",
+        "actual_delivery",
+        "carrier_posted_service_days",
+        "customer_distance",
+        "truckload_service_days",
+        "all_modes_goal_transit_days",
+        "actual_transit_days",
+        "otd_designation",
+        "load_id_pseudo",
+        "carrier_pseudo",
+        "origin_zip_3d",
+        "dest_zip_3d",
+        "ship_dow",
+        "ship_week",
+        "ship_month",
+        "ship_year",
+        "lane_zip3_pair",
+        "lane_id",
+        "distance_bucket",
+    ];
+
+    fn shipment_doc(&self) -> Result<ShipmentDoc> {
+        let shipment = self.to_shipment()?;
+        Ok(ShipmentDoc {
+            load_id: shipment.load_id,
+            carrier_mode: shipment.carrier_mode.as_str().to_string(),
+            actual_ship: to_datetime(shipment.actual_ship),
+            actual_delivery: to_datetime(shipment.actual_delivery),
+            carrier_posted_service_days: shipment.carrier_posted_service_days,
+            customer_distance: shipment.customer_distance,
+            truckload_service_days: shipment.truckload_service_days,
+            goal_transit_days: shipment.goal_transit_days,
+            actual_transit_days: shipment.actual_transit_days,
+            otd: format!("{:?}", shipment.otd),
+            ship_dow: shipment.ship_dow,
+            ship_week: shipment.ship_week,
+            ship_month: shipment.ship_month,
+            ship_year: shipment.ship_year,
+            distance_bucket: shipment.distance_bucket,
+            carrier_ref: self.carrier_pseudo.clone(),
+            lane_ref: self.lane_id.clone(),
+            origin_zip: Some(self.origin_zip_3d.clone()),
+            dest_zip: Some(self.dest_zip_3d.clone()),
+            origin_zip3: None,
+            dest_zip3: None,
+            origin_zip5: None,
+            dest_zip5: None,
+            lane_zip3_pair: None,
+            lane_zip5_pair: None,
+            is_synthetic: None,
+            carrier: None,
+            lane: None,
+            origin: None,
+            destination: None,
+        })
+    }
+
+    fn collect_entities(&self, known: &mut KnownEntities, batch: &mut EntityBatch) {
+        known.carrier(&self.carrier_pseudo, batch);
+        known.location(&self.origin_zip_3d, batch);
+        known.location(&self.dest_zip_3d, batch);
+        known.lane(&self.lane_id, &self.lane_zip3_pair, batch);
+    }
+}
+
+impl IngestRecord for SyntheticCsvRecord {
+    const COLUMNS: &'static [&'static str] = &[
+        "carrier_mode",
+        "actual_ship",
+        "actual_delivery",
+        "carrier_posted_service_days",
+        "customer_distance",
+        "truckload_service_days",
+        "all_modes_goal_transit_days",
+        "actual_transit_days",
+        "otd_designation",
+        "load_id_pseudo",
+        "carrier_pseudo",
+        "origin_zip_3d",
+        "dest_zip_3d",
+        "origin_zip5",
+        "dest_zip5",
+        "ship_dow",
+        "ship_week",
+        "ship_month",
+        "ship_year",
+        "lane_zip3_pair",
+        "lane_zip5_pair",
+        "lane_id",
+        "distance_bucket",
+        "is_synthetic",
+    ];
+
+    fn shipment_doc(&self) -> Result<ShipmentDoc> {
+        let shipment = self.to_shipment_extended()?;
+        Ok(ShipmentDoc {
+            load_id: shipment.load_id,
+            carrier_mode: shipment.carrier_mode.as_str().to_string(),
+            actual_ship: to_datetime(shipment.actual_ship),
+            actual_delivery: to_datetime(shipment.actual_delivery),
+            carrier_posted_service_days: shipment.carrier_posted_service_days,
+            customer_distance: shipment.customer_distance,
+            truckload_service_days: shipment.truckload_service_days,
+            goal_transit_days: shipment.goal_transit_days,
+            actual_transit_days: shipment.actual_transit_days,
+            otd: format!("{:?}", shipment.otd),
+            ship_dow: shipment.ship_dow,
+            ship_week: shipment.ship_week,
+            ship_month: shipment.ship_month,
+            ship_year: shipment.ship_year,
+            distance_bucket: shipment.distance_bucket,
+            carrier_ref: self.carrier_pseudo.clone(),
+            lane_ref: self.lane_id.clone(),
+            origin_zip: None,
+            dest_zip: None,
+            origin_zip3: Some(self.origin_zip_3d.clone()),
+            dest_zip3: Some(self.dest_zip_3d.clone()),
+            origin_zip5: Some(shipment.origin_zip5),
+            dest_zip5: Some(shipment.dest_zip5),
+            lane_zip3_pair: Some(self.lane_zip3_pair.clone()),
+            lane_zip5_pair: Some(self.lane_zip5_pair.clone()),
+            is_synthetic: Some(shipment.is_synthetic),
+            carrier: None,
+            lane: None,
+            origin: None,
+            destination: None,
+        })
+    }
+
+    fn collect_entities(&self, known: &mut KnownEntities, batch: &mut EntityBatch) {
+        known.carrier(&self.carrier_pseudo, batch);
+        known.location(&self.origin_zip_3d, batch);
+        known.location(&self.dest_zip_3d, batch);
+        known.location5(&self.origin_zip5, batch);
+        known.location5(&self.dest_zip5, batch);
+        known.lane(&self.lane_id, &self.lane_zip3_pair, batch);
+        known.lane5(&self.lane_zip5_pair, batch);
+    }
+
+    fn is_synthetic(&self) -> bool {
+        self.is_synthetic
+    }
+}
+
+/// Derive a state code from a ZIP code, a bare state code or a "PA→TX" pair
+pub fn extract_state(zip: &str) -> Option<String> {
+    // If it's a 2-letter state code, return it
+    if zip.len() == 2 && zip.chars().all(|c| c.is_ascii_uppercase()) {
+        return Some(zip.to_string());
+    }
+    // Check if it contains state info like "PA→TX"
+    if let Some((origin, _)) = zip.split_once('→') {
+        if origin.len() == 2 {
+            return Some(origin.to_string());
+        }
+    }
+    // Map ZIP3 prefix to state (simplified)
+    let prefix = zip.get(..2)?;
+    let state = match prefix {
+        "75" | "76" | "77" | "78" | "79" => "TX",
+        "15" | "16" | "17" | "18" | "19" => "PA",
+        "43" | "44" | "45" => "OH",
+        "90" | "91" | "92" | "93" | "94" | "95" | "96" => "CA",
+        "10" | "11" | "12" | "13" | "14" => "NY",
+        "32" | "33" | "34" => "FL",
+        "60" | "61" | "62" => "IL",
+        "30" | "31" => "GA",
+        _ => return None,
+    };
+    Some(state.to_string())
+}
+
+// ============================================================================
+// Checkpoints
+// ============================================================================
+
+pub async fn load_checkpoint(db: &db::DbConn, source: &str) -> Result<Option<Checkpoint>> {
+    let checkpoint: Option<Checkpoint> = db
+        .query("SELECT source, rows, byte, line, completed FROM type::thing($table, $source)")
+        .bind(("table", CHECKPOINT_TABLE))
+        .bind(("source", source.to_string()))
+        .await?
+        .take(0)?;
+    Ok(checkpoint)
+}
+
+pub async fn clear_checkpoint(db: &db::DbConn, source: &str) -> Result<()> {
+    db.query("DELETE type::thing($table, $source)")
+        .bind(("table", CHECKPOINT_TABLE))
+        .bind(("source", source.to_string()))
+        .await?
+        .check()?;
+    Ok(())
+}
+
+// ============================================================================
+// Pipeline
+// ============================================================================
+
+const ENTITY_STATEMENTS: &str = r#"
+    FOR $c IN $carriers { CREATE carrier CONTENT $c; };
+    FOR $l IN $locations { CREATE location CONTENT $l; };
+    FOR $l IN $locations5 { CREATE location5 CONTENT $l; };
+    FOR $l IN $lanes { CREATE lane CONTENT $l; };
+    FOR $l IN $lanes5 { CREATE lane5 CONTENT $l; };
+"#;
+
+/// Relate shipment `$id` (document `$s`) to its carrier, locations and lanes
+const LINK_STATEMENTS: &str = r#"
+        LET $carrier = (SELECT VALUE id FROM carrier WHERE carrier_id = $s.carrier_ref)[0];
+        IF $carrier != NONE { RELATE $id->shipped_by->$carrier; };
+        LET $origin = (SELECT VALUE id FROM location WHERE zip3 = ($s.origin_zip ?? $s.origin_zip3))[0];
+        IF $origin != NONE { RELATE $id->origin_at->$origin; };
+        LET $dest = (SELECT VALUE id FROM location WHERE zip3 = ($s.dest_zip ?? $s.dest_zip3))[0];
+        IF $dest != NONE { RELATE $id->dest_at->$dest; };
+        LET $lane = (SELECT VALUE id FROM lane WHERE lane_id = $s.lane_ref)[0];
+        IF $lane != NONE { RELATE $id->on_lane->$lane; };
+        IF $s.origin_zip5 != NONE {
+            LET $origin5 = (SELECT VALUE id FROM location5 WHERE zip5 = $s.origin_zip5)[0];
+            IF $origin5 != NONE { RELATE $id->origin5_at->$origin5; };
+            LET $dest5 = (SELECT VALUE id FROM location5 WHERE zip5 = $s.dest_zip5)[0];
+            IF $dest5 != NONE { RELATE $id->dest5_at->$dest5; };
+            LET $lane5 = (SELECT VALUE id FROM lane5 WHERE zip5_pair = $s.lane_zip5_pair)[0];
+            IF $lane5 != NONE { RELATE $id->on_lane5->$lane5; };
+        };
+"#;
+
+/// Drop every outgoing edge of shipment `$id`
+const UNLINK_STATEMENTS: &str = r#"
+        DELETE $id->shipped_by;
+        DELETE $id->origin_at;
+        DELETE $id->dest_at;
+        DELETE $id->on_lane;
+        DELETE $id->origin5_at;
+        DELETE $id->dest5_at;
+        DELETE $id->on_lane5;
+"#;
+
+const SAVE_CHECKPOINT: &str =
+    "UPSERT type::thing($table, $checkpoint.source) CONTENT $checkpoint;";
+
+/// Set `$linked` to whether shipment `$id` has any outgoing edge
+const LINKED_STATEMENT: &str = r#"
+        LET $linked = array::len(array::concat(
+            $id->shipped_by, $id->origin_at, $id->dest_at, $id->on_lane,
+            $id->origin5_at, $id->dest5_at, $id->on_lane5
+        )) > 0;
+"#;
+
+/// Statements writing `$shipments` (new loads) and `$updates` (stored loads).
+///
+/// Updated loads whose carrier, location or lane keys changed have their
+/// edges rebuilt. With `graph` set, new loads are related as well; without
+/// it, only loads that already had edges are rebuilt, so the rest are left
+/// for [`link_existing`].
+fn shipment_statements(graph: bool) -> String {
+    if graph {
+        format!(
+            r#"
+    FOR $s IN $shipments {{
+        LET $id = (CREATE shipment CONTENT $s RETURN VALUE id)[0];
+        {link}
+    }};
+    FOR $u IN $updates {{
+        UPDATE $u.id CONTENT $u.doc;
+        IF $u.relink {{
+            LET $id = $u.id;
+            LET $s = $u.doc;
+            {unlink}
+            {link}
+        }};
+    }};
+"#,
+            link = LINK_STATEMENTS,
+            unlink = UNLINK_STATEMENTS
+        )
+    } else {
+        format!(
+            r#"
+    FOR $s IN $shipments {{ CREATE shipment CONTENT $s; }};
+    FOR $u IN $updates {{
+        UPDATE $u.id CONTENT $u.doc;
+        IF $u.relink {{
+            LET $id = $u.id;
+            LET $s = $u.doc;
+            {linked}
+            IF $linked {{
+                {unlink}
+                {link}
+            }};
+        }};
+    }};
+"#,
+            linked = LINKED_STATEMENT,
+            link = LINK_STATEMENTS,
+            unlink = UNLINK_STATEMENTS
+        )
+    }
+}
+
+/// Fields that determine which edges a shipment has
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+struct EdgeKeys {
+    carrier_ref: Option<String>,
+    lane_ref: Option<String>,
+    origin_zip: Option<String>,
+    dest_zip: Option<String>,
+    origin_zip3: Option<String>,
+    dest_zip3: Option<String>,
+    origin_zip5: Option<String>,
+    dest_zip5: Option<String>,
+    lane_zip5_pair: Option<String>,
+}
+
+impl ShipmentDoc {
+    fn edge_keys(&self) -> EdgeKeys {
+        EdgeKeys {
+            carrier_ref: Some(self.carrier_ref.clone()),
+            lane_ref: Some(self.lane_ref.clone()),
+            origin_zip: self.origin_zip.clone(),
+            dest_zip: self.dest_zip.clone(),
+            origin_zip3: self.origin_zip3.clone(),
+            dest_zip3: self.dest_zip3.clone(),
+            origin_zip5: self.origin_zip5.clone(),
+            dest_zip5: self.dest_zip5.clone(),
+            lane_zip5_pair: self.lane_zip5_pair.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StoredShipment {
+    id: Thing,
+    load_id: String,
+    #[serde(flatten)]
+    keys: EdgeKeys,
+}
+
+/// Replacement document for a load that is already stored
+#[derive(Debug, Clone, Serialize)]
+struct ShipmentUpdate {
+    id: Thing,
+    doc: ShipmentDoc,
+    relink: bool,
+}
+
+/// Rows of one write, split into inserts and upserts of stored loads
+#[derive(Debug, Default)]
+struct ShipmentWrites {
+    created: Vec<ShipmentDoc>,
+    updates: Vec<ShipmentUpdate>,
+    /// Earlier rows superseded by a later row for the same load in this batch
+    superseded: u64,
+}
+
+impl ShipmentWrites {
+    fn inserts(shipments: Vec<ShipmentDoc>) -> Self {
+        Self {
+            created: shipments,
+            ..Default::default()
+        }
+    }
+
+    /// Match rows against stored shipments by `load_id`.
+    ///
+    /// When a load repeats within the rows, the last occurrence wins.
+    async fn upserts(db: &db::DbConn, shipments: Vec<ShipmentDoc>) -> Result<Self> {
+        let total = shipments.len();
+        let mut seen = HashSet::new();
+        let mut latest: Vec<ShipmentDoc> = shipments
+            .into_iter()
+            .rev()
+            .filter(|doc| seen.insert(doc.load_id.clone()))
+            .collect();
+        latest.reverse();
+
+        let load_ids: Vec<String> = latest.iter().map(|doc| doc.load_id.clone()).collect();
+        let stored: Vec<StoredShipment> = db
+            .query(
+                "SELECT id, load_id, carrier_ref, lane_ref, origin_zip, dest_zip, origin_zip3, \
+                 dest_zip3, origin_zip5, dest_zip5, lane_zip5_pair \
+                 FROM shipment WHERE load_id IN $load_ids",
+            )
+            .bind(("load_ids", load_ids))
+            .await?
+            .take(0)?;
+        let mut stored: HashMap<String, StoredShipment> = stored
+            .into_iter()
+            .map(|s| (s.load_id.clone(), s))
+            .collect();
+
+        let mut writes = Self {
+            superseded: (total - latest.len()) as u64,
+            ..Default::default()
+        };
+        for doc in latest {
+            match stored.remove(&doc.load_id) {
+                Some(prev) => writes.updates.push(ShipmentUpdate {
+                    id: prev.id,
+                    relink: prev.keys != doc.edge_keys(),
+                    doc,
+                }),
+                None => writes.created.push(doc),
+            }
+        }
+        Ok(writes)
+    }
+
+    fn record(&self, stats: &mut IngestStats) {
+        stats.shipments += self.created.len() as u64;
+        stats.updated += self.updates.len() as u64 + self.superseded;
+        stats.relinked += self.updates.iter().filter(|u| u.relink).count() as u64;
+    }
+}
+
+struct PendingBatch {
+    entities: EntityBatch,
+    shipments: Vec<ShipmentDoc>,
+    synthetic: u64,
+}
+
+impl PendingBatch {
+    fn new() -> Self {
+        Self {
+            entities: EntityBatch::default(),
+            shipments: Vec::new(),
+            synthetic: 0,
+        }
+    }
+}
+
+/// Write entities, shipments and (optionally) the checkpoint in one transaction
+async fn write_batch(
+    db: &db::DbConn,
+    entities: &EntityBatch,
+    shipments: Vec<ShipmentDoc>,
+    checkpoint: Option<&Checkpoint>,
+    options: &IngestOptions,
+) -> Result<ShipmentWrites> {
+    let writes = if options.incremental && !shipments.is_empty() {
+        ShipmentWrites::upserts(db, shipments).await?
+    } else {
+        ShipmentWrites::inserts(shipments)
+    };
+
+    let mut sql = String::from("BEGIN TRANSACTION;");
+    sql.push_str(ENTITY_STATEMENTS);
+    sql.push_str(&shipment_statements(options.graph));
+    if checkpoint.is_some() {
+        sql.push_str(SAVE_CHECKPOINT);
+    }
+    sql.push_str("COMMIT TRANSACTION;");
+
+    let mut query = db
+        .query(sql)
+        .bind(("carriers", entities.carriers.clone()))
+        .bind(("locations", entities.locations.clone()))
+        .bind(("locations5", entities.locations5.clone()))
+        .bind(("lanes", entities.lanes.clone()))
+        .bind(("lanes5", entities.lanes5.clone()))
+        .bind(("shipments", writes.created.clone()))
+        .bind(("updates", writes.updates.clone()));
+    if let Some(checkpoint) = checkpoint {
+        query = query
+            .bind(("table", CHECKPOINT_TABLE))
+            .bind(("checkpoint", checkpoint.clone()));
+    }
+    query.await?.check()?;
+
+    Ok(writes)
+}
+
+async fn save_checkpoint(db: &db::DbConn, checkpoint: &Checkpoint) -> Result<()> {
+    db.query(SAVE_CHECKPOINT)
+        .bind(("table", CHECKPOINT_TABLE))
+        .bind(("checkpoint", checkpoint.clone()))
+        .await?
+        .check()?;
+    Ok(())
+}
+
+/// Write one batch and its checkpoint atomically.
+///
+/// If the transaction is rejected (for example a `load_id` repeated within
+/// the file on a non-incremental load), the batch is retried row by row so
+/// only the offending rows are counted as errors.
+async fn commit_batch(
+    db: &db::DbConn,
+    batch: PendingBatch,
+    checkpoint: &Checkpoint,
+    options: &IngestOptions,
+    stats: &mut IngestStats,
+) -> Result<()> {
+    stats.carriers += batch.entities.carriers.len();
+    stats.locations += batch.entities.locations.len();
+    stats.locations_zip5 += batch.entities.locations5.len();
+    stats.lanes += batch.entities.lanes.len();
+    stats.lanes_zip5 += batch.entities.lanes5.len();
+
+    let result = write_batch(
+        db,
+        &batch.entities,
+        batch.shipments.clone(),
+        Some(checkpoint),
+        options,
+    )
+    .await;
+
+    match result {
+        Ok(writes) => {
+            writes.record(stats);
+            stats.synthetic += batch.synthetic;
+        }
+        Err(e) => {
+            warn!("Batch ending at row {} rejected ({}), retrying row by row", checkpoint.rows, e);
+            if !batch.entities.is_empty() {
+                write_batch(db, &batch.entities, Vec::new(), None, options).await?;
+            }
+            let no_entities = EntityBatch::default();
+            for doc in batch.shipments {
+                let synthetic = doc.is_synthetic.unwrap_or(false);
+                let load_id = doc.load_id.clone();
+                match write_batch(db, &no_entities, vec![doc], None, options).await {
+                    Ok(writes) => {
+                        writes.record(stats);
+                        if synthetic {
+                            stats.synthetic += 1;
+                        }
+                    }
+                    Err(e) => {
+                        if stats.errors < 5 {
+                            warn!("Insert failed for load {}: {}", load_id, e);
+                        }
+                        stats.errors += 1;
+                    }
+                }
+            }
+            save_checkpoint(db, checkpoint).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Relate lanes that have no `connects` / `connects5` edges yet to their
+/// origin and destination locations
+const LINK_LANES: &str = r#"
+    FOR $lane IN (SELECT id, zip3_pair FROM lane WHERE count(->connects) = 0) {
+        LET $zips = string::split($lane.zip3_pair, '→');
+        LET $origin = (SELECT VALUE id FROM location WHERE zip3 = $zips[0])[0];
+        LET $dest = (SELECT VALUE id FROM location WHERE zip3 = $zips[1])[0];
+        IF $origin != NONE AND $dest != NONE {
+            RELATE ($lane.id)->connects->$origin SET direction = 'origin';
+            RELATE ($lane.id)->connects->$dest SET direction = 'dest';
+        };
+    };
+    FOR $lane IN (SELECT id, origin_zip5, dest_zip5 FROM lane5 WHERE count(->connects5) = 0) {
+        LET $origin = (SELECT VALUE id FROM location5 WHERE zip5 = $lane.origin_zip5)[0];
+        LET $dest = (SELECT VALUE id FROM location5 WHERE zip5 = $lane.dest_zip5)[0];
+        IF $origin != NONE AND $dest != NONE {
+            RELATE ($lane.id)->connects5->$origin SET direction = 'origin';
+            RELATE ($lane.id)->connects5->$dest SET direction = 'dest';
+        };
+    };
+"#;
+
+pub async fn link_lanes(db: &db::DbConn) -> Result<()> {
+    db.query(LINK_LANES).await?.check()?;
+    Ok(())
+}
+
+/// Create graph edges for shipments stored without any, then for lanes.
+///
+/// Used to add edges to a database that was loaded without `graph`.
+pub async fn link_existing(db: &db::DbConn) -> Result<()> {
+    let sql = format!(
+        r#"
+    FOR $row IN (SELECT * FROM shipment WHERE count(->shipped_by) = 0) {{
+        LET $id = $row.id;
+        LET $s = $row;
+        {link}
+    }};
+"#,
+        link = LINK_STATEMENTS
+    );
+    db.query(sql).await?.check()?;
+    link_lanes(db).await
+}
+
+/// Deserialize, validate and map one raw row
+fn prepare_row<R: IngestRecord>(
+    raw: &StringRecord,
+    headers: &StringRecord,
+) -> Result<(R, ShipmentDoc), Reject> {
+    let parse_error = |e: &dyn std::fmt::Display| Reject::new(RejectReason::ParseError, e.to_string());
+    let record: R = raw.deserialize(Some(headers)).map_err(|e| parse_error(&e))?;
+    record.validate()?;
+    let doc = record.shipment_doc().map_err(|e| parse_error(&e))?;
+    Ok((record, doc))
+}
+
+/// Stream a CSV, Parquet or NDJSON file into the database in batches.
+///
+/// The checkpoint is keyed by the path as given, so resume with the same path.
+pub async fn ingest_file<R: IngestRecord>(
+    db: &db::DbConn,
+    path: &Path,
+    options: &IngestOptions,
+) -> Result<IngestStats> {
+    let source = path.display().to_string();
+    let batch_size = options.batch_size.max(1);
+    let mut stats = IngestStats {
+        report: ValidationReport::new(&source),
+        ..Default::default()
+    };
+
+    let format = options.format.unwrap_or_else(|| InputFormat::from_path(path));
+    let mut reader = RowReader::open(path, format, &options.columns, R::COLUMNS)?;
+    let headers = reader.headers().clone();
+
+    let mut checkpoint = Checkpoint {
+        source: source.clone(),
+        ..Default::default()
+    };
+
+    if options.resume {
+        if let Some(saved) = load_checkpoint(db, &source).await? {
+            if saved.completed && !options.incremental {
+                info!("{} was already fully ingested ({} rows)", source, saved.rows);
+                stats.resumed_rows = saved.rows;
+                return Ok(stats);
+            }
+            if saved.completed {
+                info!("{} was ingested before, re-applying it incrementally", source);
+            } else if saved.rows > 0 {
+                info!("Resuming {} after row {} (byte {})", source, saved.rows, saved.byte);
+                reader.seek(saved.rows, saved.byte, saved.line)?;
+                stats.resumed_rows = saved.rows;
+                checkpoint = saved;
+            }
+        }
+    } else {
+        clear_checkpoint(db, &source).await?;
+    }
+
+    let mut known = KnownEntities::load(db, options.schema).await?;
+    let mut batch = PendingBatch::new();
+    let mut rejects: Option<RejectWriter> = None;
+
+    while let Some(result) = reader.next_record() {
+        stats.rows_read += 1;
+        checkpoint.rows += 1;
+
+        let (raw, outcome) = match result {
+            Ok(raw) => {
+                let outcome = prepare_row::<R>(&raw, &headers);
+                (raw, outcome)
+            }
+            Err(e) => (
+                StringRecord::new(),
+                Err(Reject::new(RejectReason::ParseError, e.to_string())),
+            ),
+        };
+        match outcome {
+            Ok((record, mut doc)) => {
+                stats.report.accept();
+                if options.schema.record_links() {
+                    doc.link_records();
+                }
+                record.collect_entities(&mut known, &mut batch.entities);
+                if record.is_synthetic() {
+                    batch.synthetic += 1;
+                }
+                batch.shipments.push(doc);
+            }
+            Err(reject) => {
+                if stats.report.rejected < 5 {
+                    warn!("Rejected record {}: {}", checkpoint.rows, reject);
+                }
+                stats.report.reject(reject.reason);
+                if let Some(path) = &options.rejects_path {
+                    if rejects.is_none() {
+                        let append = stats.resumed_rows > 0;
+                        rejects = Some(RejectWriter::open(path, &headers, append)?);
+                        stats.report.rejects_file = Some(path.clone());
+                    }
+                    if let Some(writer) = rejects.as_mut() {
+                        writer.write(&raw, &reject)?;
+                    }
+                }
+            }
+        }
+
+        if batch.shipments.len() >= batch_size {
+            if let Some(writer) = rejects.as_mut() {
+                writer.flush()?;
+            }
+            (checkpoint.byte, checkpoint.line) = reader.position();
+            let full = std::mem::replace(&mut batch, PendingBatch::new());
+            commit_batch(db, full, &checkpoint, options, &mut stats).await?;
+            info!("Committed {} rows ({} shipments so far)", checkpoint.rows, stats.shipments);
+        }
+    }
+
+    if let Some(writer) = rejects.as_mut() {
+        writer.flush()?;
+    }
+    (checkpoint.byte, checkpoint.line) = reader.position();
+    checkpoint.completed = true;
+    commit_batch(db, batch, &checkpoint, options, &mut stats).await?;
+
+    if options.graph {
+        link_lanes(db).await?;
+    }
+    if stats.shipments + stats.updated > 0 {
+        db::bump_data_version(db).await?;
+    }
+
+    info!(
+        "Ingestion of {} complete: {} rows read, {} inserted, {} updated, {} rejected, {} errors",
+        source,
+        stats.rows_read,
+        stats.shipments,
+        stats.updated,
+        stats.report.rejected,
+        stats.errors
+    );
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_state() {
+        assert_eq!(extract_state("TX"), Some("TX".to_string()));
+        assert_eq!(extract_state("PA→TX"), Some("PA".to_string()));
+        assert_eq!(extract_state("750xx"), Some("TX".to_string()));
+        assert_eq!(extract_state("44101"), Some("OH".to_string()));
+        assert_eq!(extract_state("999xx"), None);
+    }
+
+    #[test]
+    fn test_known_entities_dedupe() {
+        let mut known = KnownEntities::default();
+        let mut batch = EntityBatch::default();
+        known.carrier("abc", &mut batch);
+        known.carrier("abc", &mut batch);
+        known.lane5("75001→44101", &mut batch);
+        known.lane5("bad-pair", &mut batch);
+        assert_eq!(batch.carriers.len(), 1);
+        assert_eq!(batch.lanes5.len(), 1);
+        assert_eq!(batch.lanes5[0].zip3_pair, "750xx→441xx");
+    }
+
+    fn shipment(load_id: &str, carrier_ref: &str, lane_ref: &str) -> ShipmentDoc {
+        let date = Datetime::from(chrono::Utc::now());
+        ShipmentDoc {
+            load_id: load_id.to_string(),
+            carrier_mode: "LTL".to_string(),
+            actual_ship: date.clone(),
+            actual_delivery: date,
+            carrier_posted_service_days: None,
+            customer_distance: None,
+            truckload_service_days: None,
+            goal_transit_days: 3,
+            actual_transit_days: 3,
+            otd: "OnTime".to_string(),
+            ship_dow: 1,
+            ship_week: 1,
+            ship_month: 1,
+            ship_year: 2024,
+            distance_bucket: "250-500".to_string(),
+            carrier_ref: carrier_ref.to_string(),
+            lane_ref: lane_ref.to_string(),
+            origin_zip: Some("750xx".to_string()),
+            dest_zip: Some("432xx".to_string()),
+            origin_zip3: None,
+            dest_zip3: None,
+            origin_zip5: None,
+            dest_zip5: None,
+            lane_zip3_pair: None,
+            lane_zip5_pair: None,
+            is_synthetic: None,
+            carrier: None,
+            lane: None,
+            origin: None,
+            destination: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_incremental_update_without_graph_keeps_edges() {
+        let db = db::connect("mem://").await.unwrap();
+        db::init_schema(&db).await.unwrap();
+        let mut known = KnownEntities::default();
+        let mut entities = EntityBatch::default();
+        known.carrier("c1", &mut entities);
+        known.carrier("c2", &mut entities);
+        known.location("750xx", &mut entities);
+        known.location("432xx", &mut entities);
+        known.lane("lane1", "750xx→432xx", &mut entities);
+
+        let graph = IngestOptions { graph: true, ..Default::default() };
+        write_batch(&db, &entities, vec![shipment("L1", "c1", "lane1")], None, &graph).await.unwrap();
+
+        let incremental = IngestOptions { incremental: true, ..Default::default() };
+        let writes = write_batch(&db, &EntityBatch::default(), vec![shipment("L1", "c2", "lane1")], None, &incremental)
+            .await
+            .unwrap();
+        assert!(writes.updates[0].relink);
+
+        let edges: Vec<Vec<Vec<String>>> = db
+            .query(
+                "SELECT VALUE [->shipped_by->carrier.carrier_id, ->origin_at->location.zip3, \
+                 ->dest_at->location.zip3, ->on_lane->lane.lane_id] FROM shipment WHERE load_id = 'L1'",
+            )
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(edges, [[["c2"], ["750xx"], ["432xx"], ["lane1"]]]);
+
+        // A load stored without edges stays unlinked for link_existing
+        write_batch(&db, &EntityBatch::default(), vec![shipment("L2", "c1", "lane1")], None, &incremental)
+            .await
+            .unwrap();
+        write_batch(&db, &EntityBatch::default(), vec![shipment("L2", "c2", "lane1")], None, &incremental)
+            .await
+            .unwrap();
+        let unlinked: Vec<String> = db
+            .query("SELECT VALUE load_id FROM shipment WHERE count(->shipped_by) = 0")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(unlinked, ["L2"]);
+    }
+}
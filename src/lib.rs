@@ -1,6 +1,23 @@
 pub mod db;
 pub mod db_enhanced;
 pub mod models;
+pub mod migrations;
+pub mod ingest;
+pub mod sources;
+pub mod validation;
+pub mod report;
+pub mod filter;
+pub mod stats;
+pub mod compare;
+pub mod playbook;
+pub mod clustering;
+pub mod delay_model;
+pub mod eta;
+pub mod forecast;
+pub mod reallocation;
+pub mod simulation;
+pub mod sla;
+pub mod alerts;
 pub mod carrier_names;
 pub mod location_names;
 pub mod api;
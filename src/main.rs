@@ -1,14 +1,23 @@
 use anyhow::Result;
+use clap::Parser;
 use nyc_last_mile::db;
 use tracing::info;
 
+/// Example queries against the main database
+#[derive(Parser, Debug)]
+struct Args {
+    #[command(flatten)]
+    db: db::DbArgs,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter("info")
         .init();
 
-    let db = db::connect("data/lastmile.db").await?;
+    let args = Args::parse();
+    let db = args.db.connect().await?;
 
     info!("Connected to SurrealDB");
 
@@ -0,0 +1,464 @@
+//! Versioned schema migrations
+//!
+//! The schema is built by an ordered list of up-migrations. The applied
+//! version lives in the `schema_version:current` record and every applied
+//! step is logged in `schema_migration`, so [`migrate`] only runs what is
+//! missing. Each migration commits in one transaction together with its
+//! version bump.
+//!
+//! Databases created before versioning have no `schema_version` record and
+//! start at version 0. Entity tables, fields and indexes use `IF NOT EXISTS`
+//! so they are left untouched; edge tables use `OVERWRITE` because older
+//! releases defined them as normal tables, which `RELATE` rejects.
+//!
+//! Databases from the binaries that wrote other namespaces
+//! ([`LEGACY_DATABASES`]) are brought over with [`import_legacy`].
+//!
+//! To change the schema, append a migration. Never edit one that has shipped.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::info;
+
+use crate::db::DbConn;
+
+/// One schema change
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// All migrations, in the order they are applied
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "base_graph",
+        sql: r#"
+        -- Shipment table (schemaless for flexibility)
+        DEFINE TABLE IF NOT EXISTS shipment SCHEMALESS;
+        DEFINE INDEX IF NOT EXISTS idx_load_id ON shipment FIELDS load_id UNIQUE;
+        DEFINE INDEX IF NOT EXISTS idx_ship_date ON shipment FIELDS actual_ship;
+        DEFINE INDEX IF NOT EXISTS idx_otd ON shipment FIELDS otd;
+        DEFINE INDEX IF NOT EXISTS idx_carrier_mode ON shipment FIELDS carrier_mode;
+
+        -- Carrier table
+        DEFINE TABLE IF NOT EXISTS carrier SCHEMAFULL;
+        DEFINE FIELD IF NOT EXISTS carrier_id ON carrier TYPE string;
+        DEFINE INDEX IF NOT EXISTS idx_carrier_id ON carrier FIELDS carrier_id UNIQUE;
+
+        -- Location table (ZIP3 regions)
+        DEFINE TABLE IF NOT EXISTS location SCHEMAFULL;
+        DEFINE FIELD IF NOT EXISTS zip3 ON location TYPE string;
+        DEFINE FIELD IF NOT EXISTS state ON location TYPE option<string>;
+        DEFINE INDEX IF NOT EXISTS idx_zip3 ON location FIELDS zip3 UNIQUE;
+
+        -- Lane table
+        DEFINE TABLE IF NOT EXISTS lane SCHEMAFULL;
+        DEFINE FIELD IF NOT EXISTS lane_id ON lane TYPE string;
+        DEFINE FIELD IF NOT EXISTS zip3_pair ON lane TYPE string;
+        DEFINE INDEX IF NOT EXISTS idx_lane_id ON lane FIELDS lane_id UNIQUE;
+
+        -- Relationships (graph edges)
+        DEFINE TABLE OVERWRITE shipped_by TYPE RELATION SCHEMAFULL;  -- shipment -> carrier
+        DEFINE TABLE OVERWRITE origin_at TYPE RELATION SCHEMAFULL;   -- shipment -> location (origin)
+        DEFINE TABLE OVERWRITE dest_at TYPE RELATION SCHEMAFULL;     -- shipment -> location (destination)
+        DEFINE TABLE OVERWRITE on_lane TYPE RELATION SCHEMAFULL;     -- shipment -> lane
+        DEFINE TABLE OVERWRITE connects TYPE RELATION SCHEMAFULL;    -- lane -> origin location, dest location
+        DEFINE FIELD IF NOT EXISTS direction ON connects TYPE string;
+        "#,
+    },
+    Migration {
+        version: 2,
+        name: "record_links_and_vectors",
+        sql: r#"
+        -- Carrier display names and performance vector:
+        -- [otd_rate, avg_transit, volume_normalized, variance]
+        DEFINE FIELD IF NOT EXISTS display_name ON carrier TYPE option<string>;
+        DEFINE FIELD IF NOT EXISTS perf_vector ON carrier TYPE option<array<float>>;
+
+        -- Lane record links to origin and destination, and performance vector:
+        -- [otd_rate, avg_transit, volume_normalized, distance_normalized]
+        DEFINE FIELD IF NOT EXISTS origin ON lane TYPE option<record<location>>;
+        DEFINE FIELD IF NOT EXISTS destination ON lane TYPE option<record<location>>;
+        DEFINE FIELD IF NOT EXISTS perf_vector ON lane TYPE option<array<float>>;
+
+        -- Shipment record links
+        DEFINE INDEX IF NOT EXISTS idx_carrier ON shipment FIELDS carrier;
+        DEFINE INDEX IF NOT EXISTS idx_lane ON shipment FIELDS lane;
+
+        -- Typed edge endpoints
+        DEFINE TABLE OVERWRITE shipped_by SCHEMAFULL TYPE RELATION FROM shipment TO carrier;
+        DEFINE TABLE OVERWRITE origin_at SCHEMAFULL TYPE RELATION FROM shipment TO location;
+        DEFINE TABLE OVERWRITE dest_at SCHEMAFULL TYPE RELATION FROM shipment TO location;
+        DEFINE TABLE OVERWRITE on_lane SCHEMAFULL TYPE RELATION FROM shipment TO lane;
+        DEFINE TABLE OVERWRITE connects SCHEMAFULL TYPE RELATION FROM lane TO location;
+
+        -- carrier -[operates_on]-> lane (derived relationship)
+        DEFINE TABLE OVERWRITE operates_on SCHEMAFULL TYPE RELATION FROM carrier TO lane;
+        DEFINE FIELD IF NOT EXISTS shipment_count ON operates_on TYPE int;
+        DEFINE FIELD IF NOT EXISTS otd_rate ON operates_on TYPE float;
+        "#,
+    },
+    Migration {
+        version: 3,
+        name: "zip5_graph",
+        sql: r#"
+        -- Location5 table (ZIP5 level)
+        DEFINE TABLE IF NOT EXISTS location5 SCHEMAFULL;
+        DEFINE FIELD IF NOT EXISTS zip5 ON location5 TYPE string;
+        DEFINE FIELD IF NOT EXISTS zip3 ON location5 TYPE string;
+        DEFINE FIELD IF NOT EXISTS state ON location5 TYPE option<string>;
+        DEFINE INDEX IF NOT EXISTS idx_zip5 ON location5 FIELDS zip5 UNIQUE;
+
+        -- Lane5 table (ZIP5 level)
+        DEFINE TABLE IF NOT EXISTS lane5 SCHEMAFULL;
+        DEFINE FIELD IF NOT EXISTS zip5_pair ON lane5 TYPE string;
+        DEFINE FIELD IF NOT EXISTS zip3_pair ON lane5 TYPE string;
+        DEFINE FIELD IF NOT EXISTS origin_zip5 ON lane5 TYPE string;
+        DEFINE FIELD IF NOT EXISTS dest_zip5 ON lane5 TYPE string;
+        DEFINE INDEX IF NOT EXISTS idx_lane5_pair ON lane5 FIELDS zip5_pair UNIQUE;
+
+        -- ZIP5 shipment indexes
+        DEFINE INDEX IF NOT EXISTS idx_carrier_ref ON shipment FIELDS carrier_ref;
+        DEFINE INDEX IF NOT EXISTS idx_origin_zip5 ON shipment FIELDS origin_zip5;
+        DEFINE INDEX IF NOT EXISTS idx_dest_zip5 ON shipment FIELDS dest_zip5;
+        DEFINE INDEX IF NOT EXISTS idx_is_synthetic ON shipment FIELDS is_synthetic;
+
+        -- ZIP5 graph edges
+        DEFINE TABLE OVERWRITE origin5_at TYPE RELATION SCHEMALESS;  -- shipment -> location5 (origin)
+        DEFINE TABLE OVERWRITE dest5_at TYPE RELATION SCHEMALESS;    -- shipment -> location5 (destination)
+        DEFINE TABLE OVERWRITE on_lane5 TYPE RELATION SCHEMALESS;    -- shipment -> lane5
+        DEFINE TABLE OVERWRITE connects5 TYPE RELATION SCHEMALESS;   -- lane5 -> origin/dest location5
+        "#,
+    },
+    Migration {
+        version: 4,
+        name: "lane_clusters",
+        sql: r#"
+        -- Latest clustering run, one row per lane (see clustering::save)
+        DEFINE TABLE IF NOT EXISTS lane_cluster SCHEMALESS;
+        -- The busiest shipment lane_ref of the lane's ZIP3s; NONE without one
+        DEFINE FIELD IF NOT EXISTS lane_ref ON lane_cluster TYPE option<string>;
+        DEFINE INDEX IF NOT EXISTS idx_lane_cluster_ref ON lane_cluster FIELDS lane_ref UNIQUE;
+        DEFINE INDEX IF NOT EXISTS idx_lane_cluster_id ON lane_cluster FIELDS cluster_id;
+
+        -- Clustering run log: parameters, silhouette and learned clusters
+        DEFINE TABLE IF NOT EXISTS cluster_run SCHEMALESS;
+        DEFINE INDEX IF NOT EXISTS idx_cluster_run_created ON cluster_run FIELDS created_at;
+        "#,
+    },
+    Migration {
+        version: 5,
+        name: "sla_proposals",
+        sql: r#"
+        -- Proposed goal transit days awaiting review (see sla::save)
+        DEFINE TABLE IF NOT EXISTS sla_proposal SCHEMALESS;
+        -- The busiest shipment lane_ref of the lane's ZIP3s
+        DEFINE FIELD IF NOT EXISTS lane_ref ON sla_proposal TYPE string;
+        DEFINE INDEX IF NOT EXISTS idx_sla_proposal_lane ON sla_proposal FIELDS origin_zip, dest_zip, carrier_ref;
+        DEFINE INDEX IF NOT EXISTS idx_sla_proposal_status ON sla_proposal FIELDS status;
+        "#,
+    },
+    Migration {
+        version: 6,
+        name: "exception_alerts",
+        sql: r#"
+        -- Exception alerts with open/acknowledged/resolved state (see alerts::sync)
+        DEFINE TABLE IF NOT EXISTS exception_alert SCHEMALESS;
+        DEFINE INDEX IF NOT EXISTS idx_exception_alert_key ON exception_alert FIELDS key, state;
+        DEFINE INDEX IF NOT EXISTS idx_exception_alert_first_seen ON exception_alert FIELDS first_seen;
+        -- At most one unresolved alert per key: open_key mirrors key until the
+        -- alert is resolved, and unique indexes skip NONE
+        DEFINE FIELD IF NOT EXISTS open_key ON exception_alert TYPE option<string>;
+        DEFINE INDEX IF NOT EXISTS idx_exception_alert_open ON exception_alert FIELDS open_key UNIQUE;
+        "#,
+    },
+];
+
+/// Version the schema is at after every migration has run
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// A migration recorded in `schema_migration`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    pub applied_at: surrealdb::sql::Datetime,
+}
+
+/// Schema version of the database (0 before any migration ran)
+pub async fn current_version(db: &DbConn) -> Result<u32> {
+    let version: Option<u32> = db
+        .query("SELECT VALUE version FROM ONLY schema_version:current")
+        .await?
+        .take(0)?;
+    Ok(version.unwrap_or_default())
+}
+
+/// Migrations already applied, oldest first
+pub async fn history(db: &DbConn) -> Result<Vec<AppliedMigration>> {
+    let applied: Vec<AppliedMigration> = db
+        .query("SELECT version, name, applied_at FROM schema_migration ORDER BY version")
+        .await?
+        .take(0)?;
+    Ok(applied)
+}
+
+/// Migrations newer than `version`, up to and including `target`
+pub fn pending(version: u32, target: u32) -> Vec<&'static Migration> {
+    MIGRATIONS
+        .iter()
+        .filter(|m| m.version > version && m.version <= target)
+        .collect()
+}
+
+/// Apply one migration and record it, atomically
+async fn apply(db: &DbConn, migration: &Migration) -> Result<()> {
+    let sql = format!(
+        "BEGIN TRANSACTION;
+        {sql}
+        UPSERT schema_version:current SET version = $version, name = $name, updated_at = time::now();
+        CREATE type::thing('schema_migration', $version) SET version = $version, name = $name, applied_at = time::now();
+        COMMIT TRANSACTION;",
+        sql = migration.sql
+    );
+    db.query(sql)
+        .bind(("version", migration.version))
+        .bind(("name", migration.name))
+        .await?
+        .check()
+        .with_context(|| format!("migration {} ({}) failed", migration.version, migration.name))?;
+    Ok(())
+}
+
+/// Bring the schema up to `target` (default: the latest version).
+///
+/// Returns the migrations that were applied.
+pub async fn migrate_to(db: &DbConn, target: Option<u32>) -> Result<Vec<&'static Migration>> {
+    let target = target.unwrap_or_else(latest_version);
+    if target > latest_version() {
+        bail!("unknown schema version {} (latest is {})", target, latest_version());
+    }
+    let current = current_version(db).await?;
+    if current > latest_version() {
+        bail!(
+            "database schema version {} is newer than this build supports ({})",
+            current,
+            latest_version()
+        );
+    }
+
+    let steps = pending(current, target);
+    for migration in &steps {
+        info!("Applying migration {} ({})", migration.version, migration.name);
+        apply(db, migration).await?;
+    }
+    Ok(steps)
+}
+
+/// Bring the schema up to the latest version
+pub async fn migrate(db: &DbConn) -> Result<Vec<&'static Migration>> {
+    migrate_to(db, None).await
+}
+
+// ============================================================================
+// Legacy namespaces
+// ============================================================================
+
+/// A namespace/database written by the binaries before they all shared
+/// [`crate::db::NAMESPACE`]/[`crate::db::DATABASE`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegacyDatabase {
+    pub namespace: &'static str,
+    pub database: &'static str,
+    pub written_by: &'static str,
+}
+
+impl std::fmt::Display for LegacyDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{} ({})", self.namespace, self.database, self.written_by)
+    }
+}
+
+pub const LEGACY_DATABASES: &[LegacyDatabase] = &[
+    LegacyDatabase { namespace: "lastmile", database: "shipping", written_by: "ingest_synthetic, add_graph_edges" },
+    LegacyDatabase { namespace: "lastmile_enhanced", database: "nyc", written_by: "ingest_enhanced" },
+    LegacyDatabase { namespace: "lastmile", database: "analytics", written_by: "mcp_server_http" },
+];
+
+/// Legacy edge tables and the tables their records move to
+const LEGACY_EDGES: &[(&str, &str)] = &[
+    ("shipped_by", "shipped_by"),
+    ("origin_at", "origin_at"),
+    ("dest_at", "dest_at"),
+    ("from_origin", "origin_at"),
+    ("to_destination", "dest_at"),
+    ("on_lane", "on_lane"),
+    ("connects", "connects"),
+    ("operates_on", "operates_on"),
+    ("origin5_at", "origin5_at"),
+    ("dest5_at", "dest5_at"),
+    ("on_lane5", "on_lane5"),
+    ("connects5", "connects5"),
+];
+
+/// Tables that describe a database's own schema state and are never copied
+const SCHEMA_TABLES: &[&str] = &["schema_version", "schema_migration"];
+
+/// Records copied per statement by [`import_legacy`]
+const IMPORT_BATCH: usize = 1000;
+
+/// Number of shipments in the currently selected database
+pub async fn shipment_count(db: &DbConn) -> Result<i64> {
+    record_count(db, "shipment").await
+}
+
+async fn record_count(db: &DbConn, table: &str) -> Result<i64> {
+    let count: Option<i64> = db
+        .query("SELECT count() FROM type::table($table) GROUP ALL")
+        .bind(("table", table.to_string()))
+        .await?
+        .take((0, "count"))?;
+    Ok(count.unwrap_or_default())
+}
+
+async fn use_database(db: &DbConn, namespace: &str, database: &str) -> Result<()> {
+    db.use_ns(namespace).use_db(database).await?;
+    Ok(())
+}
+
+async fn use_shared(db: &DbConn) -> Result<()> {
+    use_database(db, crate::db::NAMESPACE, crate::db::DATABASE).await
+}
+
+/// Legacy databases in this store that hold shipments, with their counts
+pub async fn find_legacy(db: &DbConn) -> Result<Vec<(LegacyDatabase, i64)>> {
+    let mut found = Vec::new();
+    for legacy in LEGACY_DATABASES {
+        use_database(db, legacy.namespace, legacy.database).await?;
+        let count = shipment_count(db).await;
+        use_shared(db).await?;
+        let count = count?;
+        if count > 0 {
+            found.push((*legacy, count));
+        }
+    }
+    Ok(found)
+}
+
+/// Copy every record of a legacy database into the shared one.
+///
+/// The shared database must already be migrated. Entity records keep their
+/// ids; edges get new ids, and the old enhanced `from_origin`/`to_destination`
+/// edges become `origin_at`/`dest_at`. The legacy database is left as it was.
+/// Returns the number of records copied.
+pub async fn import_legacy(db: &DbConn, legacy: &LegacyDatabase) -> Result<u64> {
+    use_database(db, legacy.namespace, legacy.database).await?;
+    let tables: Result<Option<BTreeMap<String, String>>> = async {
+        Ok(db.query("INFO FOR DB").await?.take((0, "tables"))?)
+    }
+    .await;
+    use_shared(db).await?;
+
+    let mut copied = 0;
+    for table in tables?.unwrap_or_default().into_keys() {
+        if SCHEMA_TABLES.contains(&table.as_str()) {
+            continue;
+        }
+        let edge = LEGACY_EDGES.iter().find(|(from, _)| *from == table).map(|(_, to)| *to);
+        let (select, insert) = match edge {
+            Some(to) => ("SELECT * OMIT id FROM type::table($table)", format!("INSERT RELATION INTO `{}` $rows", to)),
+            None => ("SELECT * FROM type::table($table)", format!("INSERT INTO `{}` $rows", table)),
+        };
+
+        use_database(db, legacy.namespace, legacy.database).await?;
+        let count = record_count(db, &table).await;
+        use_shared(db).await?;
+        let count = count? as usize;
+
+        for start in (0..count).step_by(IMPORT_BATCH) {
+            use_database(db, legacy.namespace, legacy.database).await?;
+            let rows: Result<surrealdb::Value> = async {
+                Ok(db
+                    .query(format!("{} START $start LIMIT $limit", select))
+                    .bind(("table", table.clone()))
+                    .bind(("start", start))
+                    .bind(("limit", IMPORT_BATCH))
+                    .await?
+                    .take(0)?)
+            }
+            .await;
+            use_shared(db).await?;
+
+            db.query(insert.as_str())
+                .bind(("rows", rows?))
+                .await?
+                .check()
+                .with_context(|| format!("copying {} from {}", table, legacy))?;
+        }
+        copied += count as u64;
+        info!("Copied {} {} records from {}", count, table, legacy);
+    }
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_ordered() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, i + 1, "{}", migration.name);
+        }
+        assert_eq!(latest_version(), MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn test_pending() {
+        let names: Vec<_> = pending(1, latest_version()).iter().map(|m| m.name).collect();
+        assert_eq!(names, ["record_links_and_vectors", "zip5_graph", "lane_clusters", "sla_proposals", "exception_alerts"]);
+        assert!(pending(latest_version(), latest_version()).is_empty());
+        assert_eq!(pending(0, 1).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_legacy_enhanced() {
+        let db = crate::db::connect("mem://").await.unwrap();
+        let legacy = LEGACY_DATABASES[1];
+        use_database(&db, legacy.namespace, legacy.database).await.unwrap();
+        db.query(r#"
+            DEFINE TABLE from_origin SCHEMAFULL TYPE RELATION FROM shipment TO location;
+            DEFINE TABLE to_destination SCHEMAFULL TYPE RELATION FROM shipment TO location;
+            CREATE shipment:l1 SET load_id = "l1", otd = "Late", carrier_mode = "LTL";
+            CREATE location:z750 SET zip3 = "750";
+            CREATE location:z432 SET zip3 = "432";
+            RELATE shipment:l1->from_origin->location:z750;
+            RELATE shipment:l1->to_destination->location:z432;
+        "#)
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+        use_shared(&db).await.unwrap();
+
+        assert_eq!(shipment_count(&db).await.unwrap(), 0);
+        assert_eq!(find_legacy(&db).await.unwrap(), [(legacy, 1)]);
+
+        migrate(&db).await.unwrap();
+        assert_eq!(import_legacy(&db, &legacy).await.unwrap(), 5);
+        assert_eq!(shipment_count(&db).await.unwrap(), 1);
+        let zips: Vec<(Vec<String>, Vec<String>)> = db
+            .query("SELECT VALUE [->origin_at->location.zip3, ->dest_at->location.zip3] FROM shipment:l1")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(zips, [(vec!["750".to_string()], vec!["432".to_string()])]);
+    }
+}
@@ -28,7 +28,7 @@ pub struct CsvRecord {
 }
 
 /// Carrier mode enum
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum CarrierMode {
     LTL,
     Truckload,
@@ -36,35 +36,110 @@ pub enum CarrierMode {
     TLDry,
 }
 
+impl CarrierMode {
+    pub const ALL: [CarrierMode; 4] = [
+        CarrierMode::LTL,
+        CarrierMode::Truckload,
+        CarrierMode::TLFlatbed,
+        CarrierMode::TLDry,
+    ];
+
+    /// Strict parse of the CSV value; `None` for unrecognized modes
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.label() == s)
+    }
+
+    /// Parse a mode given by a user: the CSV spelling or the stored name, in
+    /// any case (`TL Flatbed`, `tlflatbed`, `ltl`)
+    pub fn from_name(s: &str) -> Option<Self> {
+        let name: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        Self::ALL.into_iter().find(|mode| mode.as_str().eq_ignore_ascii_case(&name))
+    }
+
+    /// [`from_name`](Self::from_name), with an error listing the known modes
+    pub fn resolve(s: &str) -> anyhow::Result<Self> {
+        Self::from_name(s).ok_or_else(|| {
+            let known: Vec<&str> = Self::ALL.iter().map(|mode| mode.label()).collect();
+            anyhow::anyhow!("unknown carrier mode {:?} (expected one of {})", s, known.join(", "))
+        })
+    }
+
+    /// Value stored in `shipment.carrier_mode`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CarrierMode::LTL => "LTL",
+            CarrierMode::Truckload => "Truckload",
+            CarrierMode::TLFlatbed => "TLFlatbed",
+            CarrierMode::TLDry => "TLDry",
+        }
+    }
+
+    /// CSV spelling, e.g. `TL Flatbed`
+    pub fn label(&self) -> &'static str {
+        match self {
+            CarrierMode::LTL => "LTL",
+            CarrierMode::Truckload => "Truckload",
+            CarrierMode::TLFlatbed => "TL Flatbed",
+            CarrierMode::TLDry => "TL Dry",
+        }
+    }
+}
+
+impl std::fmt::Display for CarrierMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
 impl From<&str> for CarrierMode {
     fn from(s: &str) -> Self {
-        match s {
-            "LTL" => CarrierMode::LTL,
-            "Truckload" => CarrierMode::Truckload,
-            "TL Flatbed" => CarrierMode::TLFlatbed,
-            "TL Dry" => CarrierMode::TLDry,
-            _ => CarrierMode::Truckload, // default
-        }
+        CarrierMode::parse(s).unwrap_or(CarrierMode::Truckload) // default
     }
 }
 
 /// On-time delivery designation
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum OtdDesignation {
     Early,
     OnTime,
     Late,
 }
 
-impl From<&str> for OtdDesignation {
-    fn from(s: &str) -> Self {
+impl OtdDesignation {
+    /// Strict parse of the CSV value; `None` for unrecognized designations
+    pub fn parse(s: &str) -> Option<Self> {
         match s {
-            "Delivered Early" => OtdDesignation::Early,
-            "On Time" => OtdDesignation::OnTime,
-            "Late" => OtdDesignation::Late,
-            _ => OtdDesignation::OnTime,
+            "Delivered Early" => Some(OtdDesignation::Early),
+            "On Time" => Some(OtdDesignation::OnTime),
+            "Late" => Some(OtdDesignation::Late),
+            _ => None,
+        }
+    }
+
+    /// Designation of a shipment taking `transit` days against a goal of
+    /// `goal` days
+    pub fn from_transit(transit: i64, goal: i64) -> Self {
+        match transit.cmp(&goal) {
+            std::cmp::Ordering::Less => OtdDesignation::Early,
+            std::cmp::Ordering::Equal => OtdDesignation::OnTime,
+            std::cmp::Ordering::Greater => OtdDesignation::Late,
         }
     }
+
+    /// Value stored in `shipment.otd`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OtdDesignation::Early => "Early",
+            OtdDesignation::OnTime => "OnTime",
+            OtdDesignation::Late => "Late",
+        }
+    }
+}
+
+impl From<&str> for OtdDesignation {
+    fn from(s: &str) -> Self {
+        OtdDesignation::parse(s).unwrap_or(OtdDesignation::OnTime)
+    }
 }
 
 /// Shipment record for SurrealDB
@@ -0,0 +1,263 @@
+//! Lane cluster definitions and playbooks
+//!
+//! Cluster names, descriptions, recommended actions, the behavior prototypes
+//! learned clusters are matched against, and the fixed rule thresholds all
+//! come from one playbook file, so the REST/gRPC API, both MCP servers and
+//! `analytics_clustering` serve the same text and ops can edit it without a
+//! rebuild.
+//!
+//! The file is resolved once per process: an explicit path passed to
+//! [`init`] (the servers' `--playbooks` flag), then `LASTMILE_PLAYBOOKS`,
+//! then `config/playbooks.toml`, then the copy of that file compiled into
+//! the binary. Files ending in `.json` are parsed as JSON, anything else as
+//! TOML.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Environment variable naming the playbook file
+pub const PLAYBOOKS_ENV: &str = "LASTMILE_PLAYBOOKS";
+
+/// Playbook file used when `LASTMILE_PLAYBOOKS` is not set
+pub const DEFAULT_PLAYBOOKS_PATH: &str = "config/playbooks.toml";
+
+/// Cluster that low-volume lanes and outliers fall into
+pub const MIXED_CLUSTER: u8 = 5;
+
+/// Number of behavior clusters; a playbook file defines IDs 1 through this
+pub const CLUSTER_COUNT: u8 = 5;
+
+const BUILT_IN: &str = include_str!("../config/playbooks.toml");
+
+/// Cluster definitions plus the rule-based thresholds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Playbooks {
+    #[serde(default)]
+    pub rules: RuleThresholds,
+    pub clusters: Vec<ClusterPlaybook>,
+}
+
+/// Thresholds of the fixed-rule classification. Rates are fractions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleThresholds {
+    /// Lanes with fewer shipments are "Low Volume / Mixed"
+    pub min_volume: i64,
+    pub early_max_delay: f64,
+    pub early_max_variance: f64,
+    pub early_min_rate: f64,
+    pub late_min_rate: f64,
+    pub jitter_min_variance: f64,
+    pub reliable_min_on_time: f64,
+    pub reliable_max_variance: f64,
+}
+
+impl Default for RuleThresholds {
+    fn default() -> Self {
+        Self {
+            min_volume: 20,
+            early_max_delay: -0.3,
+            early_max_variance: 2.0,
+            early_min_rate: 0.3,
+            late_min_rate: 0.45,
+            jitter_min_variance: 3.5,
+            reliable_min_on_time: 0.55,
+            reliable_max_variance: 2.5,
+        }
+    }
+}
+
+/// One behavior cluster and its playbook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterPlaybook {
+    pub id: u8,
+    pub name: String,
+    /// Status marker shown next to the cluster in terminal output
+    #[serde(default)]
+    pub indicator: String,
+    pub description: String,
+    #[serde(default)]
+    pub strategy: String,
+    pub actions: Vec<String>,
+    #[serde(default)]
+    pub kpis_to_watch: Vec<String>,
+    /// Typical lane of the cluster. Clusters without one are matched at the
+    /// network average.
+    #[serde(default)]
+    pub prototype: Option<Prototype>,
+}
+
+/// Lane metrics a learned cluster centroid is compared with
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Prototype {
+    pub avg_delay: f64,
+    pub transit_variance: f64,
+    pub early_rate: f64,
+    pub on_time_rate: f64,
+    pub late_rate: f64,
+}
+
+impl Prototype {
+    /// `[avg_delay, transit_variance, early_rate, on_time_rate, late_rate]`
+    pub fn to_array(&self) -> [f64; 5] {
+        [self.avg_delay, self.transit_variance, self.early_rate, self.on_time_rate, self.late_rate]
+    }
+}
+
+impl Playbooks {
+    /// Parse TOML playbooks
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let playbooks: Self = toml::from_str(text)?;
+        playbooks.validate()?;
+        Ok(playbooks)
+    }
+
+    /// Parse JSON playbooks
+    pub fn from_json(text: &str) -> Result<Self> {
+        let playbooks: Self = serde_json::from_str(text)?;
+        playbooks.validate()?;
+        Ok(playbooks)
+    }
+
+    /// Read a `.toml` or `.json` playbook file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading playbooks from {}", path.display()))?;
+        let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let parsed = if is_json { Self::from_json(&text) } else { Self::from_toml(&text) };
+        parsed.with_context(|| format!("invalid playbook file {}", path.display()))
+    }
+
+    /// The playbooks compiled into the binary
+    pub fn built_in() -> Self {
+        Self::from_toml(BUILT_IN).expect("built-in config/playbooks.toml is valid")
+    }
+
+    /// Every cluster ID from 1 to [`CLUSTER_COUNT`] exactly once, each with a
+    /// name and at least one action
+    fn validate(&self) -> Result<()> {
+        for id in 1..=CLUSTER_COUNT {
+            match self.clusters.iter().filter(|c| c.id == id).count() {
+                0 => bail!("cluster {} is missing", id),
+                1 => {}
+                n => bail!("cluster {} is defined {} times", id, n),
+            }
+        }
+        for c in &self.clusters {
+            if !(1..=CLUSTER_COUNT).contains(&c.id) {
+                bail!("cluster ID {} is out of range (1-{})", c.id, CLUSTER_COUNT);
+            }
+            if c.name.trim().is_empty() {
+                bail!("cluster {} has no name", c.id);
+            }
+            if c.actions.is_empty() {
+                bail!("cluster {} has no actions", c.id);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn cluster(&self, id: u8) -> Option<&ClusterPlaybook> {
+        self.clusters.iter().find(|c| c.id == id)
+    }
+
+    /// Display name of a cluster; unknown IDs read as the mixed cluster
+    pub fn name(&self, id: u8) -> &str {
+        self.cluster(id)
+            .or_else(|| self.cluster(MIXED_CLUSTER))
+            .map(|c| c.name.as_str())
+            .unwrap_or("Unknown")
+    }
+
+    /// Clusters in ID order
+    pub fn sorted(&self) -> Vec<&ClusterPlaybook> {
+        let mut clusters: Vec<&ClusterPlaybook> = self.clusters.iter().collect();
+        clusters.sort_by_key(|c| c.id);
+        clusters
+    }
+}
+
+static ACTIVE: OnceLock<Playbooks> = OnceLock::new();
+
+/// Load the playbooks for this process from `path`, or from the default
+/// locations when `None`. Unlike [`playbooks`], a bad file is an error, so
+/// servers fail at startup instead of serving the built-in text.
+pub fn init(path: Option<&Path>) -> Result<&'static Playbooks> {
+    let loaded = match path {
+        Some(path) => Playbooks::load(path)?,
+        None => resolve()?,
+    };
+    if ACTIVE.set(loaded).is_err() {
+        bail!("playbooks were already loaded");
+    }
+    Ok(playbooks())
+}
+
+/// The active playbooks, loaded from the default locations on first use. A
+/// file that fails to load is logged and the built-in playbooks are used.
+pub fn playbooks() -> &'static Playbooks {
+    ACTIVE.get_or_init(|| {
+        resolve().unwrap_or_else(|e| {
+            warn!("{:#}; using built-in playbooks", e);
+            Playbooks::built_in()
+        })
+    })
+}
+
+fn resolve() -> Result<Playbooks> {
+    if let Ok(path) = std::env::var(PLAYBOOKS_ENV) {
+        return Playbooks::load(Path::new(&path));
+    }
+    let default = Path::new(DEFAULT_PLAYBOOKS_PATH);
+    if default.exists() {
+        return Playbooks::load(default);
+    }
+    Ok(Playbooks::built_in())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_playbooks() {
+        let playbooks = Playbooks::built_in();
+        assert_eq!(playbooks.clusters.len(), CLUSTER_COUNT as usize);
+        assert_eq!(playbooks.name(4), "Systematically Late");
+        assert_eq!(playbooks.name(42), playbooks.name(MIXED_CLUSTER));
+        assert!(playbooks.cluster(MIXED_CLUSTER).unwrap().prototype.is_none());
+        assert_eq!(playbooks.rules.min_volume, 20);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = serde_json::to_string(&Playbooks::built_in()).unwrap();
+        let playbooks = Playbooks::from_json(&json).unwrap();
+        assert_eq!(playbooks.cluster(1).unwrap().actions.len(), 4);
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut playbooks = Playbooks::built_in();
+        playbooks.clusters.retain(|c| c.id != 3);
+        let missing = serde_json::to_string(&playbooks).unwrap();
+        let err = Playbooks::from_json(&missing).unwrap_err();
+        assert!(err.to_string().contains("cluster 3 is missing"), "{}", err);
+
+        let mut playbooks = Playbooks::built_in();
+        playbooks.clusters[0].actions.clear();
+        let no_actions = serde_json::to_string(&playbooks).unwrap();
+        assert!(Playbooks::from_json(&no_actions).is_err());
+
+        // Omitted thresholds keep their defaults
+        let full: String = (1..=CLUSTER_COUNT)
+            .map(|id| format!("[[clusters]]\nid = {}\nname = \"C{}\"\ndescription = \"\"\nactions = [\"act\"]\n", id, id))
+            .collect();
+        let playbooks = Playbooks::from_toml(&full).unwrap();
+        assert_eq!(playbooks.rules.late_min_rate, 0.45);
+    }
+}
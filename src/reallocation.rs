@@ -0,0 +1,633 @@
+//! Capacity-constrained carrier reallocation
+//!
+//! Moves lane volume between the carriers already serving each lane so the
+//! expected number of late shipments is as small as possible without loading
+//! any carrier past its capacity in any week. Capacity defaults to each carrier's
+//! busiest week in the window (optionally with headroom) and can be set per
+//! carrier from a capacity file.
+//!
+//! Late rates per lane and carrier are shrunk toward the lane's rate, so a
+//! carrier with a dozen clean shipments doesn't attract the whole lane. The
+//! assignment itself is a transportation problem and is solved exactly, one
+//! ISO week at a time, as a min-cost flow: source → lane (its volume that
+//! week) → carrier (cost: late rate) → sink (the carrier's weekly capacity). Volume moved to a carrier costs an extra
+//! [`ReallocationConfig::min_improvement`], so a shift only happens when it
+//! cuts the late rate by at least that much.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::carrier_names::find_carrier_id;
+use crate::db::DbConn;
+use crate::filter::ShipmentFilter;
+
+/// Rates are scaled to integer parts per million for the flow costs
+const COST_SCALE: f64 = 1_000_000.0;
+
+/// Tunables of the reallocation
+#[derive(Debug, Clone)]
+pub struct ReallocationConfig {
+    /// Shipments a carrier needs on a lane before volume moves to it
+    pub min_lane_volume: i64,
+    /// Pseudo-shipments at the lane's late rate added to each carrier's record
+    pub prior_shipments: f64,
+    /// Late-rate cut (fraction) a shift has to deliver
+    pub min_improvement: f64,
+    /// Extra capacity over the busiest historical week, e.g. 0.1 for +10%
+    pub headroom: f64,
+}
+
+impl Default for ReallocationConfig {
+    fn default() -> Self {
+        Self {
+            min_lane_volume: 10,
+            prior_shipments: 20.0,
+            min_improvement: 0.02,
+            headroom: 0.0,
+        }
+    }
+}
+
+/// Shipments of one carrier on one lane over the window
+#[derive(Debug, Clone)]
+pub struct LaneCarrierVolume {
+    pub origin_zip: String,
+    pub dest_zip: String,
+    pub carrier_ref: String,
+    pub shipments: i64,
+    pub late: i64,
+    pub on_time: i64,
+    /// Shipments per ISO week (`YYYY-WW`)
+    pub weekly: BTreeMap<String, i64>,
+}
+
+/// Historical volume the optimizer works from
+#[derive(Debug, Clone, Default)]
+pub struct ReallocationInput {
+    pub lanes: Vec<LaneCarrierVolume>,
+    /// Busiest ISO week per carrier
+    pub max_weekly: HashMap<String, i64>,
+    /// ISO weeks with at least one shipment
+    pub weeks: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeeklyVolumeRow {
+    origin_zip: Option<String>,
+    dest_zip: Option<String>,
+    carrier_ref: String,
+    week: String,
+    shipments: i64,
+    late: i64,
+    on_time: i64,
+}
+
+/// Lane × carrier volume and per-carrier peak weeks for the shipments
+/// matching `filter`
+pub async fn load_input(db: &DbConn, filter: &ShipmentFilter) -> Result<ReallocationInput> {
+    let rows: Vec<WeeklyVolumeRow> = filter
+        .query(db, r#"
+            SELECT
+                origin_zip, dest_zip, carrier_ref, week,
+                count() as shipments,
+                count(IF otd = "Late" THEN 1 END) as late,
+                count(IF otd = "OnTime" THEN 1 END) as on_time
+            FROM (
+                SELECT
+                    (origin_zip ?? origin_zip3) as origin_zip,
+                    (dest_zip ?? dest_zip3) as dest_zip,
+                    carrier_ref,
+                    otd,
+                    time::format(actual_ship, "%G-%V") as week
+                FROM shipment WHERE {filter}
+            )
+            GROUP BY origin_zip, dest_zip, carrier_ref, week
+        "#)
+        .await?
+        .take(0)?;
+
+    let mut lanes: BTreeMap<(String, String, String), LaneCarrierVolume> = BTreeMap::new();
+    let mut carrier_weeks: HashMap<(String, String), i64> = HashMap::new();
+    let mut weeks = BTreeSet::new();
+    for row in rows {
+        let (Some(origin), Some(dest)) = (row.origin_zip, row.dest_zip) else { continue };
+        *carrier_weeks.entry((row.carrier_ref.clone(), row.week.clone())).or_default() += row.shipments;
+        weeks.insert(row.week.clone());
+        let entry = lanes
+            .entry((origin.clone(), dest.clone(), row.carrier_ref.clone()))
+            .or_insert_with(|| LaneCarrierVolume {
+                origin_zip: origin,
+                dest_zip: dest,
+                carrier_ref: row.carrier_ref,
+                shipments: 0,
+                late: 0,
+                on_time: 0,
+                weekly: BTreeMap::new(),
+            });
+        *entry.weekly.entry(row.week).or_default() += row.shipments;
+        entry.shipments += row.shipments;
+        entry.late += row.late;
+        entry.on_time += row.on_time;
+    }
+
+    let mut max_weekly: HashMap<String, i64> = HashMap::new();
+    for ((carrier, _), shipments) in carrier_weeks {
+        let peak = max_weekly.entry(carrier).or_default();
+        *peak = (*peak).max(shipments);
+    }
+
+    Ok(ReallocationInput { lanes: lanes.into_values().collect(), max_weekly, weeks: weeks.len() })
+}
+
+/// Capacity file: weekly shipments per carrier ID or display name
+#[derive(Debug, Deserialize)]
+struct CapacityFile {
+    carriers: BTreeMap<String, f64>,
+}
+
+/// Read a `.toml` or `.json` capacity file, keyed by carrier ID
+///
+/// ```toml
+/// [carriers]
+/// "XPO Logistics" = 450
+/// C17 = 120
+/// ```
+pub fn load_capacity(path: &Path) -> Result<HashMap<String, f64>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("reading capacity file {}", path.display()))?;
+    let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let parsed: CapacityFile = if is_json {
+        serde_json::from_str(&text).map_err(anyhow::Error::from)
+    } else {
+        toml::from_str(&text).map_err(anyhow::Error::from)
+    }
+    .with_context(|| format!("parsing capacity file {}", path.display()))?;
+
+    let mut capacity = HashMap::new();
+    for (carrier, weekly) in parsed.carriers {
+        if !weekly.is_finite() || weekly < 0.0 {
+            bail!("capacity for {} must be a non-negative number of shipments per week", carrier);
+        }
+        let carrier_ref = find_carrier_id(&carrier).map(str::to_string).unwrap_or(carrier);
+        capacity.insert(carrier_ref, weekly);
+    }
+    Ok(capacity)
+}
+
+/// Where a carrier's capacity came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapacitySource {
+    /// Busiest historical week, plus headroom
+    History,
+    /// Capacity file
+    File,
+}
+
+impl CapacitySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::History => "history",
+            Self::File => "file",
+        }
+    }
+}
+
+/// Volume moved from one carrier to another on a lane
+#[derive(Debug, Clone, Serialize)]
+pub struct VolumeShift {
+    pub origin_zip: String,
+    pub dest_zip: String,
+    pub from_carrier: String,
+    pub to_carrier: String,
+    /// Shipments over the whole window
+    pub shipments: i64,
+    pub weekly_shipments: f64,
+    pub from_late_rate: f64,
+    pub to_late_rate: f64,
+    /// Expected late shipments avoided over the window
+    pub late_avoided: f64,
+}
+
+/// A carrier's weekly load before and after the reallocation
+#[derive(Debug, Clone, Serialize)]
+pub struct CarrierLoad {
+    pub carrier_ref: String,
+    pub current_weekly: f64,
+    pub planned_weekly: f64,
+    /// Current volume no carrier has room for in its week; not part of
+    /// `planned_weekly`
+    pub unplaced_weekly: f64,
+    pub capacity_weekly: f64,
+    pub capacity_source: CapacitySource,
+}
+
+impl CarrierLoad {
+    pub fn utilization(&self) -> f64 {
+        if self.capacity_weekly > 0.0 { self.planned_weekly / self.capacity_weekly } else { 0.0 }
+    }
+}
+
+/// Volume-shift plan with the projected network on-time rate
+#[derive(Debug, Clone, Serialize)]
+pub struct ReallocationPlan {
+    pub weeks: usize,
+    pub shipments: i64,
+    pub current_late_rate: f64,
+    pub planned_late_rate: f64,
+    pub current_otd: f64,
+    /// On-time rate if every avoided late shipment arrives on time
+    pub planned_otd: f64,
+    pub late_avoided: f64,
+    /// Shipments no carrier has room for in their week; they are left out
+    /// of every carrier's planned load
+    pub unplaced: i64,
+    /// Largest saving first
+    pub shifts: Vec<VolumeShift>,
+    /// Busiest carrier first
+    pub carriers: Vec<CarrierLoad>,
+}
+
+/// Reassign lane volume to minimize expected late shipments. `capacity`
+/// overrides the historical weekly capacity of the carriers it names.
+pub fn optimize(input: &ReallocationInput, capacity: &HashMap<String, f64>, config: &ReallocationConfig) -> ReallocationPlan {
+    let weeks = input.weeks.max(1) as f64;
+
+    // Lanes and their carriers, with late rates shrunk toward the lane's
+    let mut lane_index: BTreeMap<(&str, &str), Vec<usize>> = BTreeMap::new();
+    for (i, row) in input.lanes.iter().enumerate() {
+        lane_index.entry((&row.origin_zip, &row.dest_zip)).or_default().push(i);
+    }
+    let mut rates = vec![0.0; input.lanes.len()];
+    for members in lane_index.values() {
+        let shipments: i64 = members.iter().map(|&i| input.lanes[i].shipments).sum();
+        let late: i64 = members.iter().map(|&i| input.lanes[i].late).sum();
+        let lane_rate = late as f64 / shipments.max(1) as f64;
+        for &i in members {
+            let row = &input.lanes[i];
+            rates[i] = (row.late as f64 + config.prior_shipments * lane_rate)
+                / (row.shipments as f64 + config.prior_shipments);
+        }
+    }
+
+    let carriers: Vec<&str> = input
+        .lanes
+        .iter()
+        .map(|r| r.carrier_ref.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let carrier_node: HashMap<&str, usize> = carriers
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (*c, 2 + lane_index.len() + i))
+        .collect();
+    let capacity_weekly: Vec<(f64, CapacitySource)> = carriers
+        .iter()
+        .map(|c| match capacity.get(*c) {
+            Some(weekly) => (*weekly, CapacitySource::File),
+            None => {
+                let peak = input.max_weekly.get(*c).copied().unwrap_or(0) as f64;
+                (peak * (1.0 + config.headroom), CapacitySource::History)
+            }
+        })
+        .collect();
+
+    // Each week is its own flow: source → lane → carrier → sink
+    const SOURCE: usize = 0;
+    const SINK: usize = 1;
+    let penalty = (config.min_improvement * COST_SCALE).round() as i64;
+    let week_labels: BTreeSet<&str> =
+        input.lanes.iter().flat_map(|r| r.weekly.keys().map(String::as_str)).collect();
+    let mut planned = vec![0i64; input.lanes.len()];
+    let mut unplaced = vec![0i64; input.lanes.len()];
+    let mut moved: BTreeMap<(usize, usize), i64> = BTreeMap::new();
+    for week in week_labels {
+        let current: Vec<i64> =
+            input.lanes.iter().map(|r| r.weekly.get(week).copied().unwrap_or(0)).collect();
+        let mut flow = MinCostFlow::new(2 + lane_index.len() + carriers.len());
+        let mut edges = Vec::new();
+        for (lane, members) in lane_index.values().enumerate() {
+            let volume: i64 = members.iter().map(|&i| current[i]).sum();
+            if volume == 0 {
+                continue;
+            }
+            let node = 2 + lane;
+            flow.add_edge(SOURCE, node, volume, 0);
+            for &i in members {
+                let to = carrier_node[input.lanes[i].carrier_ref.as_str()];
+                let cost = (rates[i] * COST_SCALE).round() as i64;
+                if current[i] > 0 {
+                    edges.push((i, flow.add_edge(node, to, current[i], cost)));
+                }
+                if input.lanes[i].shipments >= config.min_lane_volume {
+                    edges.push((i, flow.add_edge(node, to, volume, cost + penalty)));
+                }
+            }
+        }
+        for (c, carrier) in carriers.iter().enumerate() {
+            flow.add_edge(carrier_node[carrier], SINK, capacity_weekly[c].0.floor() as i64, 0);
+        }
+        flow.run(SOURCE, SINK);
+
+        let mut week_planned = vec![0i64; input.lanes.len()];
+        for (i, edge) in edges {
+            week_planned[i] += flow.flow(edge);
+        }
+
+        // Pair each lane's losers (worst first) with its gainers (best
+        // first); what no gainer takes has no room this week
+        for members in lane_index.values() {
+            let mut losers: Vec<(usize, i64)> = members
+                .iter()
+                .filter(|&&i| week_planned[i] < current[i])
+                .map(|&i| (i, current[i] - week_planned[i]))
+                .collect();
+            let mut gainers: Vec<(usize, i64)> = members
+                .iter()
+                .filter(|&&i| week_planned[i] > current[i])
+                .map(|&i| (i, week_planned[i] - current[i]))
+                .collect();
+            losers.sort_by(|a, b| rates[b.0].total_cmp(&rates[a.0]));
+            gainers.sort_by(|a, b| rates[a.0].total_cmp(&rates[b.0]));
+
+            let mut g = 0;
+            for (from, mut remaining) in losers {
+                while remaining > 0 && g < gainers.len() {
+                    let (to, available) = &mut gainers[g];
+                    let shipments = remaining.min(*available);
+                    *moved.entry((from, *to)).or_default() += shipments;
+                    remaining -= shipments;
+                    *available -= shipments;
+                    if *available == 0 {
+                        g += 1;
+                    }
+                }
+                unplaced[from] += remaining;
+            }
+        }
+        for (total, week) in planned.iter_mut().zip(week_planned) {
+            *total += week;
+        }
+    }
+
+    let mut shifts: Vec<VolumeShift> = moved
+        .into_iter()
+        .map(|((from, to), shipments)| {
+            let row = &input.lanes[from];
+            VolumeShift {
+                origin_zip: row.origin_zip.clone(),
+                dest_zip: row.dest_zip.clone(),
+                from_carrier: row.carrier_ref.clone(),
+                to_carrier: input.lanes[to].carrier_ref.clone(),
+                shipments,
+                weekly_shipments: shipments as f64 / weeks,
+                from_late_rate: rates[from],
+                to_late_rate: rates[to],
+                late_avoided: shipments as f64 * (rates[from] - rates[to]),
+            }
+        })
+        .collect();
+    shifts.sort_by(|a, b| b.late_avoided.total_cmp(&a.late_avoided));
+
+    let mut loads: Vec<CarrierLoad> = carriers
+        .iter()
+        .enumerate()
+        .map(|(c, carrier)| {
+            let rows = input.lanes.iter().enumerate().filter(|(_, r)| r.carrier_ref == *carrier);
+            let (current, plan, left) = rows.fold((0, 0, 0), |(cur, plan, left), (i, r)| {
+                (cur + r.shipments, plan + planned[i], left + unplaced[i])
+            });
+            CarrierLoad {
+                carrier_ref: carrier.to_string(),
+                current_weekly: current as f64 / weeks,
+                planned_weekly: plan as f64 / weeks,
+                unplaced_weekly: left as f64 / weeks,
+                capacity_weekly: capacity_weekly[c].0,
+                capacity_source: capacity_weekly[c].1,
+            }
+        })
+        .collect();
+    loads.sort_by(|a, b| b.current_weekly.total_cmp(&a.current_weekly));
+
+    let shipments: i64 = input.lanes.iter().map(|r| r.shipments).sum();
+    let late: i64 = input.lanes.iter().map(|r| r.late).sum();
+    let on_time: i64 = input.lanes.iter().map(|r| r.on_time).sum();
+    let late_avoided: f64 = shifts.iter().map(|s| s.late_avoided).sum();
+    let total = shipments.max(1) as f64;
+    ReallocationPlan {
+        weeks: input.weeks,
+        shipments,
+        current_late_rate: late as f64 / total,
+        planned_late_rate: (late as f64 - late_avoided) / total,
+        current_otd: on_time as f64 / total,
+        planned_otd: (on_time as f64 + late_avoided) / total,
+        late_avoided,
+        unplaced: unplaced.iter().sum(),
+        shifts,
+        carriers: loads,
+    }
+}
+
+// ============================================================================
+// Min-cost flow
+// ============================================================================
+
+#[derive(Debug, Clone)]
+struct Edge {
+    to: usize,
+    rev: usize,
+    cap: i64,
+    cost: i64,
+}
+
+/// Successive shortest paths with Dijkstra on reduced costs. Edge costs
+/// must be non-negative.
+struct MinCostFlow {
+    graph: Vec<Vec<Edge>>,
+    /// (node, index) of each forward edge with its original capacity
+    edges: Vec<(usize, usize, i64)>,
+}
+
+impl MinCostFlow {
+    fn new(nodes: usize) -> Self {
+        Self { graph: vec![Vec::new(); nodes], edges: Vec::new() }
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, cap: i64, cost: i64) -> usize {
+        let (fwd, bwd) = (self.graph[from].len(), self.graph[to].len() + usize::from(from == to));
+        self.graph[from].push(Edge { to, rev: bwd, cap, cost });
+        self.graph[to].push(Edge { to: from, rev: fwd, cap: 0, cost: -cost });
+        self.edges.push((from, fwd, cap));
+        self.edges.len() - 1
+    }
+
+    /// Flow on an edge returned by `add_edge`
+    fn flow(&self, edge: usize) -> i64 {
+        let (node, index, cap) = self.edges[edge];
+        cap - self.graph[node][index].cap
+    }
+
+    /// Push as much flow as possible at minimum cost; returns (flow, cost)
+    fn run(&mut self, source: usize, sink: usize) -> (i64, i64) {
+        let n = self.graph.len();
+        let mut potential = vec![0i64; n];
+        let (mut total_flow, mut total_cost) = (0, 0);
+        loop {
+            let mut dist = vec![i64::MAX; n];
+            let mut prev: Vec<Option<(usize, usize)>> = vec![None; n];
+            let mut heap = BinaryHeap::new();
+            dist[source] = 0;
+            heap.push(Reverse((0i64, source)));
+            while let Some(Reverse((d, u))) = heap.pop() {
+                if d > dist[u] {
+                    continue;
+                }
+                for (i, e) in self.graph[u].iter().enumerate() {
+                    if e.cap <= 0 {
+                        continue;
+                    }
+                    let next = d + e.cost + potential[u] - potential[e.to];
+                    if next < dist[e.to] {
+                        dist[e.to] = next;
+                        prev[e.to] = Some((u, i));
+                        heap.push(Reverse((next, e.to)));
+                    }
+                }
+            }
+            if dist[sink] == i64::MAX {
+                break;
+            }
+            for v in 0..n {
+                if dist[v] < i64::MAX {
+                    potential[v] += dist[v];
+                }
+            }
+
+            let mut push = i64::MAX;
+            let mut v = sink;
+            while let Some((u, i)) = prev[v] {
+                push = push.min(self.graph[u][i].cap);
+                v = u;
+            }
+            let mut v = sink;
+            while let Some((u, i)) = prev[v] {
+                let rev = self.graph[u][i].rev;
+                self.graph[u][i].cap -= push;
+                self.graph[v][rev].cap += push;
+                total_cost += push * self.graph[u][i].cost;
+                v = u;
+            }
+            total_flow += push;
+        }
+        (total_flow, total_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lane row with `weekly` shipments in consecutive weeks
+    fn row(dest: &str, carrier: &str, weekly: &[i64], late: i64) -> LaneCarrierVolume {
+        let shipments = weekly.iter().sum();
+        LaneCarrierVolume {
+            origin_zip: "750".into(),
+            dest_zip: dest.into(),
+            carrier_ref: carrier.into(),
+            shipments,
+            late,
+            on_time: shipments - late,
+            weekly: weekly.iter().enumerate().map(|(w, n)| (format!("2024-{:02}", w + 1), *n)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_min_cost_flow() {
+        // Two units must cross; the cheap path only takes one
+        let mut flow = MinCostFlow::new(4);
+        flow.add_edge(0, 1, 2, 0);
+        let cheap = flow.add_edge(1, 2, 1, 1);
+        let dear = flow.add_edge(1, 3, 5, 4);
+        flow.add_edge(2, 3, 5, 1);
+        assert_eq!(flow.run(0, 3), (2, 6));
+        assert_eq!(flow.flow(cheap), 1);
+        assert_eq!(flow.flow(dear), 1);
+    }
+
+    #[test]
+    fn test_capacity_limits_shift() {
+        // 100 shipments/week: A is 40% late, B 5% late with 20 spare per week
+        let input = ReallocationInput {
+            lanes: vec![row("432", "A", &[80; 10], 320), row("432", "B", &[20; 10], 10)],
+            max_weekly: HashMap::from([("A".to_string(), 80), ("B".to_string(), 40)]),
+            weeks: 10,
+        };
+        let plan = optimize(&input, &HashMap::new(), &ReallocationConfig::default());
+        assert_eq!(plan.unplaced, 0);
+        assert_eq!(plan.shifts.len(), 1);
+        let shift = &plan.shifts[0];
+        assert_eq!((shift.from_carrier.as_str(), shift.to_carrier.as_str()), ("A", "B"));
+        assert_eq!(shift.shipments, 200);
+        assert!(plan.planned_late_rate < plan.current_late_rate);
+        let b = plan.carriers.iter().find(|c| c.carrier_ref == "B").unwrap();
+        assert!((b.planned_weekly - 40.0).abs() < 1e-9);
+
+        // A file capacity of 20/week for B leaves nothing to gain
+        let capped = optimize(&input, &HashMap::from([("B".to_string(), 20.0)]), &ReallocationConfig::default());
+        assert!(capped.shifts.is_empty());
+        assert_eq!(capped.late_avoided, 0.0);
+    }
+
+    #[test]
+    fn test_small_samples_and_threshold() {
+        // C has too few shipments on the lane to receive volume; D is barely
+        // better than A, below the minimum improvement
+        let input = ReallocationInput {
+            lanes: vec![
+                row("100", "A", &[50; 10], 100),
+                row("100", "C", &[1, 1, 1, 1, 1, 0, 0, 0, 0, 0], 0),
+                row("100", "D", &[10; 10], 19),
+            ],
+            max_weekly: HashMap::from([
+                ("A".to_string(), 100),
+                ("C".to_string(), 100),
+                ("D".to_string(), 100),
+            ]),
+            weeks: 10,
+        };
+        let plan = optimize(&input, &HashMap::new(), &ReallocationConfig::default());
+        assert!(plan.shifts.is_empty());
+        assert!((plan.current_otd - plan.planned_otd).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_capacity_applies_per_week() {
+        // All of A's volume ships in week 1; B takes 20 a week and has
+        // nothing in week 1, so only 20 can move even though B averages 5
+        let input = ReallocationInput {
+            lanes: vec![row("432", "A", &[100, 0], 40), row("432", "B", &[0, 10], 0)],
+            max_weekly: HashMap::from([("A".to_string(), 100), ("B".to_string(), 10)]),
+            weeks: 2,
+        };
+        let capacity = HashMap::from([("B".to_string(), 20.0)]);
+        let plan = optimize(&input, &capacity, &ReallocationConfig::default());
+        assert_eq!(plan.unplaced, 0);
+        assert_eq!(plan.shifts.len(), 1);
+        assert_eq!(plan.shifts[0].shipments, 20);
+
+        // Capping A at 50 a week leaves 30 of week 1 without a carrier
+        let capacity = HashMap::from([("A".to_string(), 50.0), ("B".to_string(), 20.0)]);
+        let plan = optimize(&input, &capacity, &ReallocationConfig::default());
+        assert_eq!(plan.unplaced, 30);
+        let a = plan.carriers.iter().find(|c| c.carrier_ref == "A").unwrap();
+        assert!((a.planned_weekly - 25.0).abs() < 1e-9);
+        assert!((a.unplaced_weekly - 15.0).abs() < 1e-9);
+        let b = plan.carriers.iter().find(|c| c.carrier_ref == "B").unwrap();
+        assert!((b.planned_weekly - 15.0).abs() < 1e-9);
+    }
+}
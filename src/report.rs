@@ -0,0 +1,330 @@
+//! Structured output for the analytics binaries
+//!
+//! Each binary prints its human-readable tables as before and also records
+//! the rows behind them in a [`Report`]. With `--format json|csv|markdown`
+//! the tables are suppressed (see [`table_println!`]) and the report is
+//! written instead, so results can feed BI tools or be diffed between runs.
+//!
+//! Rows are any flat `Serialize` struct; column order follows field order.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Output format selected on the command line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned console tables (the default)
+    #[default]
+    Table,
+    Json,
+    /// One CSV block per table; with `--output DIR`, one file per table
+    Csv,
+    Markdown,
+}
+
+/// `--format` / `--output` flags shared by the analytics binaries
+#[derive(Debug, Clone, clap::Args)]
+pub struct OutputArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+
+    /// Write the report to this file instead of stdout (a directory for CSV)
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+impl OutputArgs {
+    /// Empty report for this run's format
+    pub fn report(&self, title: &str) -> Report {
+        Report::new(title, self.format)
+    }
+
+    /// Write the finished report to `--output` or stdout
+    pub fn write(&self, report: &Report) -> Result<()> {
+        report.write(self.output.as_deref())
+    }
+}
+
+/// `println!` for [`OutputFormat::Table`] only; other formats stay silent
+/// until [`Report::write`]
+#[macro_export]
+macro_rules! table_println {
+    ($report:expr) => {
+        if $report.is_table() {
+            println!();
+        }
+    };
+    ($report:expr, $($arg:tt)*) => {
+        if $report.is_table() {
+            println!($($arg)*);
+        }
+    };
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportTable {
+    pub key: String,
+    pub title: String,
+    pub rows: Vec<serde_json::Value>,
+    #[serde(skip)]
+    columns: Vec<String>,
+    #[serde(skip)]
+    cells: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportSection {
+    pub key: String,
+    pub title: String,
+    pub tables: Vec<ReportTable>,
+}
+
+/// Collected results of one analytics run
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub report: String,
+    /// Applied shipment filter (see [`crate::filter::ShipmentFilter::describe`])
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub filter: String,
+    pub sections: Vec<ReportSection>,
+    #[serde(skip)]
+    format: OutputFormat,
+    #[serde(skip)]
+    subsection: String,
+}
+
+impl Report {
+    pub fn new(report: impl Into<String>, format: OutputFormat) -> Self {
+        Self {
+            report: report.into(),
+            filter: String::new(),
+            sections: Vec::new(),
+            format,
+            subsection: String::new(),
+        }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    pub fn is_table(&self) -> bool {
+        self.format == OutputFormat::Table
+    }
+
+    /// Start a section; prints the section banner for table output
+    pub fn section(&mut self, key: &str, title: &str) {
+        if self.is_table() {
+            println!("\n{}", "═".repeat(80));
+            println!("  {}", title);
+            println!("{}\n", "═".repeat(80));
+        }
+        self.sections.push(ReportSection {
+            key: key.to_string(),
+            title: title.to_string(),
+            tables: Vec::new(),
+        });
+        self.subsection.clear();
+    }
+
+    /// Start a subsection; its title labels the tables added after it
+    pub fn subsection(&mut self, title: &str) {
+        if self.is_table() {
+            println!("\n{}", title);
+            println!("{}", "─".repeat(70));
+        }
+        self.subsection = title.to_string();
+    }
+
+    /// Record the rows of one table in the current section
+    pub fn add<T: Serialize>(&mut self, key: &str, rows: &[T]) -> Result<()> {
+        let mut writer = csv::WriterBuilder::new().from_writer(Vec::new());
+        for row in rows {
+            writer.serialize(row)?;
+        }
+        let bytes = writer.into_inner().context("flushing report rows")?;
+        let mut reader = csv::ReaderBuilder::new().from_reader(bytes.as_slice());
+        let columns = reader.headers()?.iter().map(str::to_string).collect();
+        let cells = reader
+            .records()
+            .map(|r| r.map(|r| r.iter().map(str::to_string).collect()))
+            .collect::<std::result::Result<Vec<Vec<String>>, _>>()?;
+        let rows = rows
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        if self.sections.is_empty() {
+            self.sections.push(ReportSection {
+                key: "report".to_string(),
+                title: self.report.clone(),
+                tables: Vec::new(),
+            });
+        }
+        let title = self.subsection.clone();
+        if let Some(section) = self.sections.last_mut() {
+            section.tables.push(ReportTable {
+                key: key.to_string(),
+                title,
+                rows,
+                columns,
+                cells,
+            });
+        }
+        Ok(())
+    }
+
+    /// Record a single-row table
+    pub fn add_one<T: Serialize>(&mut self, key: &str, row: &T) -> Result<()> {
+        self.add(key, std::slice::from_ref(row))
+    }
+
+    /// Write the report in the selected format to `output` or stdout.
+    ///
+    /// For CSV, `output` is a directory receiving `<section>_<table>.csv`
+    /// files. Table output was already printed, so nothing is written.
+    pub fn write(&self, output: Option<&Path>) -> Result<()> {
+        let rendered = match self.format {
+            OutputFormat::Table => return Ok(()),
+            OutputFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+            OutputFormat::Markdown => self.to_markdown(),
+            OutputFormat::Csv => match output {
+                Some(dir) => return self.write_csv_dir(dir),
+                None => self.to_csv_blocks()?,
+            },
+        };
+        match output {
+            Some(path) => std::fs::write(path, rendered)
+                .with_context(|| format!("writing report to {:?}", path))?,
+            None => std::io::stdout().write_all(rendered.as_bytes())?,
+        }
+        Ok(())
+    }
+
+    fn tables(&self) -> impl Iterator<Item = (&ReportSection, &ReportTable)> {
+        self.sections
+            .iter()
+            .flat_map(|s| s.tables.iter().map(move |t| (s, t)))
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n", self.report);
+        if !self.filter.is_empty() {
+            let _ = write!(out, "\n_Filter: {}_\n", self.filter);
+        }
+        for section in &self.sections {
+            let _ = write!(out, "\n## {}\n", section.title);
+            for table in &section.tables {
+                if !table.title.is_empty() {
+                    let _ = write!(out, "\n### {}\n", table.title);
+                }
+                out.push('\n');
+                if table.cells.is_empty() {
+                    out.push_str("_No rows._\n");
+                    continue;
+                }
+                let _ = writeln!(out, "| {} |", table.columns.join(" | "));
+                let _ = writeln!(out, "|{}", "---|".repeat(table.columns.len()));
+                for row in &table.cells {
+                    let cells: Vec<String> = row.iter().map(|c| markdown_cell(c)).collect();
+                    let _ = writeln!(out, "| {} |", cells.join(" | "));
+                }
+            }
+        }
+        out
+    }
+
+    fn csv_bytes(table: &ReportTable) -> Result<Vec<u8>> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        if table.columns.is_empty() {
+            // No rows to take the header from
+            return Ok(Vec::new());
+        }
+        writer.write_record(&table.columns)?;
+        for row in &table.cells {
+            writer.write_record(row)?;
+        }
+        writer.into_inner().context("flushing csv")
+    }
+
+    fn to_csv_blocks(&self) -> Result<String> {
+        let mut out = String::new();
+        for (section, table) in self.tables() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            let _ = writeln!(out, "# {}.{}", section.key, table.key);
+            out.push_str(&String::from_utf8(Self::csv_bytes(table)?)?);
+        }
+        Ok(out)
+    }
+
+    fn write_csv_dir(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        for (section, table) in self.tables() {
+            let path = dir.join(format!("{}_{}.csv", section.key, table.key));
+            std::fs::write(&path, Self::csv_bytes(table)?)
+                .with_context(|| format!("writing {:?}", path))?;
+        }
+        Ok(())
+    }
+}
+
+/// Round long floats for display and escape pipes
+fn markdown_cell(value: &str) -> String {
+    if value.contains('.') {
+        if let Ok(v) = value.parse::<f64>() {
+            return format!("{:.2}", v);
+        }
+    }
+    value.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Row {
+        name: &'static str,
+        total: i64,
+        rate: f64,
+    }
+
+    fn sample(format: OutputFormat) -> Report {
+        let mut report = Report::new("Test Report", format);
+        report.section("kpi", "1. KPIs");
+        report.subsection("By Carrier");
+        report
+            .add(
+                "by_carrier",
+                &[
+                    Row { name: "a|b", total: 3, rate: 0.123456 },
+                    Row { name: "c", total: 1, rate: 1.0 },
+                ],
+            )
+            .unwrap();
+        report
+    }
+
+    #[test]
+    fn test_markdown_keeps_field_order() {
+        let md = sample(OutputFormat::Markdown).to_markdown();
+        assert!(md.contains("### By Carrier"));
+        assert!(md.contains("| name | total | rate |"));
+        assert!(md.contains("| a\\|b | 3 | 0.12 |"));
+    }
+
+    #[test]
+    fn test_csv_blocks_and_json() {
+        let report = sample(OutputFormat::Csv);
+        let csv = report.to_csv_blocks().unwrap();
+        assert!(csv.starts_with("# kpi.by_carrier\nname,total,rate\n"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["sections"][0]["tables"][0]["rows"][1]["total"], 1);
+    }
+}
@@ -0,0 +1,828 @@
+//! What-if simulation of network on-time performance
+//!
+//! The baseline groups every shipment into cells of lane, carrier, mode, OTD
+//! designation and actual/goal transit days, so a scenario can move volume
+//! between carriers or modes and keep each lane's transit distribution.
+//! Scenario levers apply in order:
+//!
+//! - **switch carrier**: the lanes' volume (or only one carrier's) takes on
+//!   the target carrier's record on the same lane
+//! - **convert mode**: the same for a carrier mode
+//! - **add buffer days**: goal transit grows by N days and every shipment is
+//!   designated again against the new goal, so late shipments within N days
+//!   turn on time or early and on-time ones turn early
+//! - **drop lanes**: the lanes leave the network
+//!
+//! Switches need [`MIN_SEGMENT_SHIPMENTS`] of history for the target on the
+//! lane; lanes without it are left alone and reported as warnings. A lane a
+//! lever touches keeps its cluster unless the fixed cluster rules classify
+//! its projected metrics differently from its baseline ones.
+//!
+//! [`simulate`] also ranks single-lane levers (best carrier, best mode or one
+//! buffer day, whichever gains most) by on-time shipments gained and takes
+//! them in order until the target on-time rate is reached. Dropping lanes is
+//! never recommended.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::carrier_names::{find_carrier_id, get_carrier_name};
+use crate::clustering::{self, rule_based_cluster, LaneFeatures};
+use crate::db::DbConn;
+use crate::filter::normalize_zip3;
+use crate::location_names::format_lane_short;
+use crate::models::{CarrierMode, OtdDesignation};
+use crate::playbook::playbooks;
+
+/// History a carrier or mode needs on a lane before volume moves to it
+pub const MIN_SEGMENT_SHIPMENTS: f64 = 10.0;
+
+/// Recommendations returned when the request doesn't say
+pub const DEFAULT_MAX_RECOMMENDATIONS: usize = 20;
+
+// ============================================================================
+// Scenario
+// ============================================================================
+
+/// One scenario change. Lanes are `"ORIGIN-DEST"` ZIP3 pairs, e.g.
+/// `"750-432"`; an empty list means every lane.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Lever {
+    /// Move the lanes' volume, or only carrier `from`'s, to carrier `to`
+    SwitchCarrier {
+        #[serde(default)]
+        lanes: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        from: Option<String>,
+        to: String,
+    },
+    /// Move the lanes' volume, or only mode `from`'s, to mode `to`. Modes
+    /// are given as in the CSV (`TL Flatbed`) or as stored (`TLFlatbed`),
+    /// in any case
+    ConvertMode {
+        #[serde(default)]
+        lanes: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        from: Option<String>,
+        to: String,
+    },
+    /// Add days to the goal transit time
+    AddBufferDays {
+        #[serde(default)]
+        lanes: Vec<String>,
+        days: i64,
+    },
+    /// Remove the lanes from the network
+    DropLanes { lanes: Vec<String> },
+}
+
+impl Lever {
+    fn lanes(&self) -> &[String] {
+        match self {
+            Self::SwitchCarrier { lanes, .. }
+            | Self::ConvertMode { lanes, .. }
+            | Self::AddBufferDays { lanes, .. }
+            | Self::DropLanes { lanes } => lanes,
+        }
+    }
+
+    /// Short description, e.g. `switch to XPO Logistics on DFW→COL`
+    pub fn describe(&self) -> String {
+        let lanes = match self.lanes() {
+            [] => "all lanes".to_string(),
+            [lane] => lane_label(lane),
+            many => format!("{} lanes", many.len()),
+        };
+        match self {
+            Self::SwitchCarrier { from, to, .. } => match from {
+                Some(from) => format!("switch {} to {} on {}", carrier_label(from), carrier_label(to), lanes),
+                None => format!("switch to {} on {}", carrier_label(to), lanes),
+            },
+            Self::ConvertMode { from, to, .. } => match from {
+                Some(from) => format!("convert {} to {} on {}", mode_label(from), mode_label(to), lanes),
+                None => format!("convert to {} on {}", mode_label(to), lanes),
+            },
+            Self::AddBufferDays { days, .. } => {
+                format!("add {} buffer day{} on {}", days, if *days == 1 { "" } else { "s" }, lanes)
+            }
+            Self::DropLanes { .. } => format!("drop {}", lanes),
+        }
+    }
+}
+
+/// Levers to apply and the on-time rate to reach
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SimulationRequest {
+    #[serde(default)]
+    pub levers: Vec<Lever>,
+    /// Target on-time rate, 0-1
+    pub target_on_time_rate: Option<f64>,
+    pub max_recommendations: Option<usize>,
+}
+
+/// Parse `"750-432"` (or `750→432`, `750>432`) into normalized ZIP3s
+pub fn parse_lane(lane: &str) -> Result<(String, String)> {
+    let parts: Vec<&str> = lane
+        .split(['-', '→', '>', ',', ' '])
+        .filter(|p| !p.is_empty())
+        .collect();
+    match parts.as_slice() {
+        [origin, dest] => Ok((normalize_zip3(origin), normalize_zip3(dest))),
+        _ => bail!("lane '{}' should look like ORIGIN-DEST, e.g. 750-432", lane),
+    }
+}
+
+/// Reject levers and targets that can't be simulated
+pub fn validate(request: &SimulationRequest) -> Result<()> {
+    for lever in &request.levers {
+        for lane in lever.lanes() {
+            parse_lane(lane)?;
+        }
+        match lever {
+            Lever::SwitchCarrier { to, .. } | Lever::ConvertMode { to, .. } if to.trim().is_empty() => {
+                bail!("{}: 'to' is required", lever_type(lever))
+            }
+            Lever::ConvertMode { from, to, .. } => {
+                for mode in from.iter().chain([to]) {
+                    if let Err(e) = CarrierMode::resolve(mode) {
+                        bail!("convert_mode: {}", e)
+                    }
+                }
+            }
+            Lever::AddBufferDays { days, .. } if *days < 1 => {
+                bail!("add_buffer_days: days must be at least 1, got {}", days)
+            }
+            Lever::DropLanes { lanes } if lanes.is_empty() => bail!("drop_lanes: list the lanes to drop"),
+            _ => {}
+        }
+    }
+    if let Some(target) = request.target_on_time_rate {
+        if !(target > 0.0 && target <= 1.0) {
+            bail!("target on-time rate must be above 0% and at most 100%");
+        }
+    }
+    Ok(())
+}
+
+fn lever_type(lever: &Lever) -> &'static str {
+    match lever {
+        Lever::SwitchCarrier { .. } => "switch_carrier",
+        Lever::ConvertMode { .. } => "convert_mode",
+        Lever::AddBufferDays { .. } => "add_buffer_days",
+        Lever::DropLanes { .. } => "drop_lanes",
+    }
+}
+
+fn lane_label(lane: &str) -> String {
+    match parse_lane(lane) {
+        Ok((origin, dest)) => format_lane_short(&origin, &dest),
+        Err(_) => lane.to_string(),
+    }
+}
+
+fn carrier_ref(carrier: &str) -> String {
+    find_carrier_id(carrier).map(str::to_string).unwrap_or_else(|| carrier.trim().to_string())
+}
+
+fn carrier_label(carrier: &str) -> String {
+    get_carrier_name(&carrier_ref(carrier))
+}
+
+/// Stored `carrier_mode` for a mode given as in the CSV or as stored
+fn mode_ref(mode: &str) -> String {
+    CarrierMode::from_name(mode).map(|m| m.as_str().to_string()).unwrap_or_else(|| mode.trim().to_string())
+}
+
+fn mode_label(mode: &str) -> String {
+    CarrierMode::from_name(mode).map(|m| m.label().to_string()).unwrap_or_else(|| mode.trim().to_string())
+}
+
+// ============================================================================
+// Baseline
+// ============================================================================
+
+/// Shipments sharing a carrier, mode, designation and transit/goal days
+#[derive(Debug, Clone)]
+struct Cell {
+    carrier_ref: String,
+    mode: String,
+    otd: OtdDesignation,
+    transit: i64,
+    goal: i64,
+    count: f64,
+}
+
+#[derive(Debug, Clone)]
+struct Lane {
+    origin_zip: String,
+    dest_zip: String,
+    cells: Vec<Cell>,
+    /// Cluster the rest of the API reports for the lane
+    cluster_id: u8,
+    /// What the fixed rules make of the baseline metrics
+    rule_cluster: u8,
+    touched: bool,
+}
+
+/// Every lane's shipments, ready to run scenarios against
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    lanes: Vec<Lane>,
+    index: HashMap<(String, String), usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CellRow {
+    origin_zip: Option<String>,
+    dest_zip: Option<String>,
+    carrier_ref: String,
+    carrier_mode: Option<String>,
+    otd: String,
+    transit: i64,
+    goal: i64,
+    shipments: i64,
+}
+
+/// Load the baseline from the shipment table. Clusters come from the last
+/// saved clustering run, falling back to the fixed rules like `/lanes` does.
+pub async fn load_baseline(db: &DbConn) -> Result<Baseline> {
+    let rows: Vec<CellRow> = db
+        .query(r#"
+            SELECT origin_zip, dest_zip, carrier_ref, carrier_mode, otd, transit, goal, count() as shipments
+            FROM (
+                SELECT
+                    (origin_zip ?? origin_zip3) as origin_zip,
+                    (dest_zip ?? dest_zip3) as dest_zip,
+                    carrier_ref, carrier_mode, otd,
+                    actual_transit_days as transit,
+                    goal_transit_days as goal
+                FROM shipment
+            )
+            GROUP BY origin_zip, dest_zip, carrier_ref, carrier_mode, otd, transit, goal
+        "#)
+        .await?
+        .take(0)?;
+
+    let mut cells: BTreeMap<(String, String), Vec<Cell>> = BTreeMap::new();
+    for row in rows {
+        let (Some(origin), Some(dest)) = (row.origin_zip, row.dest_zip) else { continue };
+        let otd = match row.otd.as_str() {
+            "Early" => OtdDesignation::Early,
+            "OnTime" => OtdDesignation::OnTime,
+            "Late" => OtdDesignation::Late,
+            _ => continue,
+        };
+        cells.entry((origin, dest)).or_default().push(Cell {
+            carrier_ref: row.carrier_ref,
+            mode: row.carrier_mode.unwrap_or_default(),
+            otd,
+            transit: row.transit,
+            goal: row.goal,
+            count: row.shipments as f64,
+        });
+    }
+
+    let stored = clustering::load_assignments(db).await?;
+    Ok(Baseline::new(cells, &stored))
+}
+
+impl Baseline {
+    fn new(cells: BTreeMap<(String, String), Vec<Cell>>, stored: &HashMap<(String, String), u8>) -> Self {
+        let min_volume = playbooks().rules.min_volume;
+        let lanes: Vec<Lane> = cells
+            .into_iter()
+            .map(|((origin_zip, dest_zip), cells)| {
+                let rule_cluster = rule_based_cluster(&features(&origin_zip, &dest_zip, &cells), min_volume);
+                let cluster_id = stored.get(&(origin_zip.clone(), dest_zip.clone())).copied().unwrap_or(rule_cluster);
+                Lane { origin_zip, dest_zip, cells, cluster_id, rule_cluster, touched: false }
+            })
+            .collect();
+        let index = lanes
+            .iter()
+            .enumerate()
+            .map(|(i, l)| ((l.origin_zip.clone(), l.dest_zip.clone()), i))
+            .collect();
+        Self { lanes, index }
+    }
+
+    /// Lane indexes a lever applies to, plus the lanes it names that have
+    /// no shipments
+    fn select(&self, lanes: &[String]) -> (Vec<usize>, Vec<String>) {
+        if lanes.is_empty() {
+            return ((0..self.lanes.len()).collect(), Vec::new());
+        }
+        let mut selected = Vec::new();
+        let mut missing = Vec::new();
+        for lane in lanes {
+            match parse_lane(lane).ok().and_then(|key| self.index.get(&key)) {
+                Some(&i) => selected.push(i),
+                None => missing.push(lane.clone()),
+            }
+        }
+        (selected, missing)
+    }
+
+    /// Apply a lever; returns the lanes it changed and any warnings
+    fn apply(&mut self, lever: &Lever) -> (usize, Vec<String>) {
+        let (selected, missing) = self.select(lever.lanes());
+        let mut warnings: Vec<String> = missing
+            .into_iter()
+            .map(|lane| format!("{}: no shipments on lane {}", lever.describe(), lane))
+            .collect();
+        let mut changed = 0;
+        let mut no_history = 0;
+        for i in selected {
+            let lane = &mut self.lanes[i];
+            let result = match lever {
+                Lever::SwitchCarrier { from, to, .. } => reassign(
+                    &mut lane.cells,
+                    |c| &c.carrier_ref,
+                    from.as_deref().map(carrier_ref).as_deref(),
+                    &carrier_ref(to),
+                ),
+                Lever::ConvertMode { from, to, .. } => reassign(
+                    &mut lane.cells,
+                    |c| &c.mode,
+                    from.as_deref().map(mode_ref).as_deref(),
+                    &mode_ref(to),
+                ),
+                Lever::AddBufferDays { days, .. } => Some(add_buffer_days(&mut lane.cells, *days)),
+                Lever::DropLanes { .. } => {
+                    let had_volume = !lane.cells.is_empty();
+                    lane.cells.clear();
+                    Some(had_volume)
+                }
+            };
+            match result {
+                Some(true) => {
+                    lane.touched = true;
+                    changed += 1;
+                }
+                Some(false) => {}
+                None => no_history += 1,
+            }
+        }
+        if no_history > 0 {
+            warnings.push(format!("{}: skipped {} lane{} with fewer than {} shipments of history for the target",
+                                  lever.describe(), no_history, if no_history == 1 { "" } else { "s" },
+                                  MIN_SEGMENT_SHIPMENTS));
+        }
+        (changed, warnings)
+    }
+
+    /// Network totals and the cluster distribution
+    pub fn summary(&self) -> NetworkSummary {
+        let min_volume = playbooks().rules.min_volume;
+        let mut totals = Totals::default();
+        let mut clusters: BTreeMap<u8, (usize, f64)> = BTreeMap::new();
+        for lane in &self.lanes {
+            let lane_totals = Totals::of(&lane.cells);
+            if lane_totals.shipments <= 0.0 {
+                continue;
+            }
+            totals.add(&lane_totals);
+            let cluster = if lane.touched {
+                let projected = rule_based_cluster(&features(&lane.origin_zip, &lane.dest_zip, &lane.cells), min_volume);
+                if projected == lane.rule_cluster { lane.cluster_id } else { projected }
+            } else {
+                lane.cluster_id
+            };
+            let entry = clusters.entry(cluster).or_default();
+            entry.0 += 1;
+            entry.1 += lane_totals.shipments;
+        }
+
+        let shipments = totals.shipments.max(f64::MIN_POSITIVE);
+        NetworkSummary {
+            lanes: clusters.values().map(|(lanes, _)| lanes).sum(),
+            shipments: totals.shipments,
+            on_time: totals.on_time,
+            late: totals.late,
+            early: totals.early,
+            on_time_rate: totals.on_time / shipments,
+            late_rate: totals.late / shipments,
+            early_rate: totals.early / shipments,
+            clusters: playbooks()
+                .sorted()
+                .into_iter()
+                .map(|def| {
+                    let (lanes, volume) = clusters.get(&def.id).copied().unwrap_or_default();
+                    ClusterCount { cluster_id: def.id, cluster_name: def.name.clone(), lanes, volume }
+                })
+                .collect(),
+        }
+    }
+
+    /// Best single lever for a lane: (lever, on-time gain, late reduction)
+    fn best_lever(&self, i: usize) -> Option<(Lever, f64, f64)> {
+        let lane = &self.lanes[i];
+        let before = Totals::of(&lane.cells);
+        if before.shipments <= 0.0 {
+            return None;
+        }
+        let zip3 = |zip: &'_ str| zip.strip_suffix("xx").unwrap_or(zip).to_string();
+        let key = format!("{}-{}", zip3(&lane.origin_zip), zip3(&lane.dest_zip));
+
+        let mut candidates = Vec::new();
+        if let Some(carrier) = best_segment(&lane.cells, |c| &c.carrier_ref) {
+            candidates.push(Lever::SwitchCarrier { lanes: vec![key.clone()], from: None, to: carrier });
+        }
+        if let Some(mode) = best_segment(&lane.cells, |c| &c.mode) {
+            candidates.push(Lever::ConvertMode { lanes: vec![key.clone()], from: None, to: mode });
+        }
+        candidates.push(Lever::AddBufferDays { lanes: vec![key], days: 1 });
+
+        candidates
+            .into_iter()
+            .filter_map(|lever| {
+                let mut cells = lane.cells.clone();
+                match &lever {
+                    Lever::SwitchCarrier { to, .. } => reassign(&mut cells, |c| &c.carrier_ref, None, to),
+                    Lever::ConvertMode { to, .. } => reassign(&mut cells, |c| &c.mode, None, to),
+                    Lever::AddBufferDays { days, .. } => Some(add_buffer_days(&mut cells, *days)),
+                    Lever::DropLanes { .. } => None,
+                }?;
+                let after = Totals::of(&cells);
+                Some((lever, after.on_time - before.on_time, before.late - after.late))
+            })
+            .filter(|(_, gain, _)| *gain > 1e-9)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// Move volume of segment `from` (or every other segment) to segment `to`,
+/// which takes it on in proportion to its own record on the lane. `None`
+/// when `to` has too little history there.
+fn reassign(cells: &mut Vec<Cell>, key: impl Fn(&Cell) -> &String, from: Option<&str>, to: &str) -> Option<bool> {
+    let target: f64 = cells.iter().filter(|c| key(c) == to).map(|c| c.count).sum();
+    if target < MIN_SEGMENT_SHIPMENTS {
+        return None;
+    }
+    let moves = |c: &Cell| key(c) != to && from.is_none_or(|f| key(c) == f);
+    let moved: f64 = cells.iter().filter(|c| moves(c)).map(|c| c.count).sum();
+    if moved <= 0.0 {
+        return Some(false);
+    }
+    cells.retain(|c| !moves(c));
+    let scale = (target + moved) / target;
+    for cell in cells.iter_mut().filter(|c| key(c) == to) {
+        cell.count *= scale;
+    }
+    Some(true)
+}
+
+fn add_buffer_days(cells: &mut [Cell], days: i64) -> bool {
+    for cell in cells.iter_mut() {
+        cell.goal += days;
+        cell.otd = OtdDesignation::from_transit(cell.transit, cell.goal);
+    }
+    !cells.is_empty()
+}
+
+/// Segment with the best on-time rate on the lane, if it beats the lane
+fn best_segment(cells: &[Cell], key: impl Fn(&Cell) -> &String) -> Option<String> {
+    let mut segments: BTreeMap<&String, Totals> = BTreeMap::new();
+    for cell in cells {
+        segments.entry(key(cell)).or_default().add_cell(cell);
+    }
+    if segments.len() < 2 {
+        return None;
+    }
+    let lane = Totals::of(cells);
+    segments
+        .into_iter()
+        .filter(|(_, t)| t.shipments >= MIN_SEGMENT_SHIPMENTS && t.on_time_rate() > lane.on_time_rate())
+        .max_by(|a, b| a.1.on_time_rate().total_cmp(&b.1.on_time_rate()))
+        .map(|(segment, _)| segment.clone())
+}
+
+fn features(origin: &str, dest: &str, cells: &[Cell]) -> LaneFeatures {
+    let totals = Totals::of(cells);
+    let n = totals.shipments.max(f64::MIN_POSITIVE);
+    let mean_transit = cells.iter().map(|c| c.count * c.transit as f64).sum::<f64>() / n;
+    let squares: f64 = cells.iter().map(|c| c.count * (c.transit as f64 - mean_transit).powi(2)).sum();
+    LaneFeatures {
+        lane_ref: None,
+        origin_zip: origin.to_string(),
+        dest_zip: dest.to_string(),
+        volume: totals.shipments.round() as i64,
+        avg_delay: cells.iter().map(|c| c.count * (c.transit - c.goal) as f64).sum::<f64>() / n,
+        // Sample variance, like math::variance in the lane queries
+        transit_variance: if totals.shipments > 1.0 { squares / (totals.shipments - 1.0) } else { 0.0 },
+        avg_transit: 0.0,
+        early_rate: totals.early / n,
+        on_time_rate: totals.on_time / n,
+        late_rate: totals.late / n,
+        distance_miles: 0.0,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    shipments: f64,
+    early: f64,
+    on_time: f64,
+    late: f64,
+}
+
+impl Totals {
+    fn of(cells: &[Cell]) -> Self {
+        let mut totals = Self::default();
+        for cell in cells {
+            totals.add_cell(cell);
+        }
+        totals
+    }
+
+    fn add_cell(&mut self, cell: &Cell) {
+        self.shipments += cell.count;
+        match cell.otd {
+            OtdDesignation::Early => self.early += cell.count,
+            OtdDesignation::OnTime => self.on_time += cell.count,
+            OtdDesignation::Late => self.late += cell.count,
+        }
+    }
+
+    fn add(&mut self, other: &Totals) {
+        self.shipments += other.shipments;
+        self.early += other.early;
+        self.on_time += other.on_time;
+        self.late += other.late;
+    }
+
+    fn on_time_rate(&self) -> f64 {
+        if self.shipments > 0.0 { self.on_time / self.shipments } else { 0.0 }
+    }
+}
+
+// ============================================================================
+// Results
+// ============================================================================
+
+/// Lanes and shipments in one cluster
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterCount {
+    pub cluster_id: u8,
+    pub cluster_name: String,
+    pub lanes: usize,
+    pub volume: f64,
+}
+
+/// Network totals; counts are fractional once volume has been reassigned
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkSummary {
+    pub lanes: usize,
+    pub shipments: f64,
+    pub on_time: f64,
+    pub late: f64,
+    pub early: f64,
+    pub on_time_rate: f64,
+    pub late_rate: f64,
+    pub early_rate: f64,
+    pub clusters: Vec<ClusterCount>,
+}
+
+/// A requested lever and how many lanes it changed
+#[derive(Debug, Clone, Serialize)]
+pub struct AppliedLever {
+    pub lever: Lever,
+    pub description: String,
+    pub lanes_changed: usize,
+}
+
+/// A recommended lever, in the order to take them
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedLever {
+    pub rank: usize,
+    pub lever: Lever,
+    pub description: String,
+    pub on_time_gain: f64,
+    pub late_reduction: f64,
+    /// Network on-time rate with this and every earlier recommendation
+    pub cumulative_on_time_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationResult {
+    pub baseline: NetworkSummary,
+    /// After the requested levers
+    pub projected: NetworkSummary,
+    pub applied: Vec<AppliedLever>,
+    pub warnings: Vec<String>,
+    pub target_on_time_rate: Option<f64>,
+    /// Whether the recommendations reach the target
+    pub target_reached: Option<bool>,
+    pub recommendations: Vec<RankedLever>,
+    /// After the requested levers and the recommendations
+    pub with_recommendations: Option<NetworkSummary>,
+}
+
+/// Run a scenario against the baseline and rank the levers that would move
+/// the network toward the target
+pub fn simulate(baseline: &Baseline, request: &SimulationRequest) -> Result<SimulationResult> {
+    validate(request)?;
+    let before = baseline.summary();
+
+    let mut scenario = baseline.clone();
+    let mut applied = Vec::new();
+    let mut warnings = Vec::new();
+    for lever in &request.levers {
+        let (lanes_changed, lever_warnings) = scenario.apply(lever);
+        warnings.extend(lever_warnings);
+        applied.push(AppliedLever { lever: lever.clone(), description: lever.describe(), lanes_changed });
+    }
+    let projected = scenario.summary();
+
+    // One lever per lane, so gains add up across lanes
+    let mut candidates: Vec<(Lever, f64, f64)> = (0..scenario.lanes.len())
+        .filter_map(|i| scenario.best_lever(i))
+        .collect();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let limit = request.max_recommendations.unwrap_or(DEFAULT_MAX_RECOMMENDATIONS);
+    let shipments = projected.shipments.max(f64::MIN_POSITIVE);
+    let mut on_time = projected.on_time;
+    let mut recommendations = Vec::new();
+    for (lever, gain, late_reduction) in candidates {
+        if recommendations.len() >= limit || request.target_on_time_rate.is_some_and(|t| on_time / shipments >= t) {
+            break;
+        }
+        on_time += gain;
+        recommendations.push(RankedLever {
+            rank: recommendations.len() + 1,
+            description: lever.describe(),
+            lever,
+            on_time_gain: gain,
+            late_reduction,
+            cumulative_on_time_rate: on_time / shipments,
+        });
+    }
+
+    let with_recommendations = (!recommendations.is_empty()).then(|| {
+        for r in &recommendations {
+            scenario.apply(&r.lever);
+        }
+        scenario.summary()
+    });
+
+    Ok(SimulationResult {
+        baseline: before,
+        projected,
+        applied,
+        warnings,
+        target_on_time_rate: request.target_on_time_rate,
+        target_reached: request.target_on_time_rate.map(|t| on_time / shipments >= t - 1e-12),
+        recommendations,
+        with_recommendations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(carrier: &str, mode: &str, otd: OtdDesignation, transit: i64, count: f64) -> Cell {
+        Cell { carrier_ref: carrier.into(), mode: mode.into(), otd, transit, goal: 3, count }
+    }
+
+    /// 750→432: A is half late (one day over), B mostly on time
+    /// 152→100: all on time, one carrier
+    fn baseline() -> Baseline {
+        let mut cells = BTreeMap::new();
+        cells.insert(("750xx".to_string(), "432xx".to_string()), vec![
+            cell("A", "LTL", OtdDesignation::OnTime, 3, 50.0),
+            cell("A", "LTL", OtdDesignation::Late, 4, 50.0),
+            cell("B", "LTL", OtdDesignation::OnTime, 3, 18.0),
+            cell("B", "LTL", OtdDesignation::Late, 6, 2.0),
+        ]);
+        cells.insert(("152xx".to_string(), "100xx".to_string()), vec![
+            cell("A", "Truckload", OtdDesignation::OnTime, 2, 80.0),
+        ]);
+        Baseline::new(cells, &HashMap::new())
+    }
+
+    #[test]
+    fn test_parse_lane() {
+        let lane = ("750xx".to_string(), "432xx".to_string());
+        assert_eq!(parse_lane("750-432").unwrap(), lane);
+        assert_eq!(parse_lane("750→432").unwrap(), lane);
+        assert_eq!(parse_lane("750xx > 432xx").unwrap(), lane);
+        assert!(parse_lane("750").is_err());
+    }
+
+    #[test]
+    fn test_levers() {
+        let base = baseline();
+        let summary = base.summary();
+        assert_eq!(summary.shipments, 200.0);
+        assert_eq!(summary.late, 52.0);
+
+        // Switching to B gives the lane B's 90% on-time record
+        let request = SimulationRequest {
+            levers: vec![Lever::SwitchCarrier { lanes: vec!["750-432".into()], from: None, to: "B".into() }],
+            ..Default::default()
+        };
+        let result = simulate(&base, &request).unwrap();
+        assert_eq!(result.applied[0].lanes_changed, 1);
+        assert!((result.projected.on_time - (108.0 + 80.0)).abs() < 1e-9);
+        assert!((result.projected.late - 12.0).abs() < 1e-9);
+
+        // One buffer day rescues the shipments one day late, not six, and
+        // turns the on-time ones early
+        let request = SimulationRequest {
+            levers: vec![Lever::AddBufferDays { lanes: vec![], days: 1 }],
+            ..Default::default()
+        };
+        let result = simulate(&base, &request).unwrap();
+        assert!((result.projected.late - 2.0).abs() < 1e-9);
+        assert!((result.projected.on_time - 50.0).abs() < 1e-9);
+        assert!((result.projected.early - 148.0).abs() < 1e-9);
+
+        // With two, the rescued shipments arrive ahead of the new goal
+        let request = SimulationRequest {
+            levers: vec![Lever::AddBufferDays { lanes: vec!["750-432".into()], days: 2 }],
+            ..Default::default()
+        };
+        let result = simulate(&base, &request).unwrap();
+        assert!((result.projected.late - 2.0).abs() < 1e-9);
+        assert!((result.projected.on_time - 80.0).abs() < 1e-9);
+        assert!((result.projected.early - 118.0).abs() < 1e-9);
+
+        // Switching to a carrier without history on the lane is skipped
+        let request = SimulationRequest {
+            levers: vec![Lever::ConvertMode { lanes: vec!["750-432".into()], from: None, to: "Truckload".into() }],
+            ..Default::default()
+        };
+        let result = simulate(&base, &request).unwrap();
+        assert_eq!(result.applied[0].lanes_changed, 0);
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.projected.on_time, result.baseline.on_time);
+
+        let request = SimulationRequest {
+            levers: vec![Lever::DropLanes { lanes: vec!["152-100".into()] }],
+            ..Default::default()
+        };
+        let result = simulate(&base, &request).unwrap();
+        assert_eq!(result.projected.lanes, 1);
+        assert_eq!(result.projected.shipments, 120.0);
+    }
+
+    #[test]
+    fn test_recommendations_reach_target() {
+        let request = SimulationRequest { target_on_time_rate: Some(0.9), ..Default::default() };
+        let result = simulate(&baseline(), &request).unwrap();
+        assert_eq!(result.target_reached, Some(true));
+        assert_eq!(result.recommendations.len(), 1);
+        // Switching to B (+40) beats a buffer day, which turns on-time
+        // shipments early (-18)
+        let top = &result.recommendations[0];
+        assert_eq!(top.lever, Lever::SwitchCarrier { lanes: vec!["750-432".into()], from: None, to: "B".into() });
+        assert!((top.on_time_gain - 40.0).abs() < 1e-9);
+        assert!((result.with_recommendations.unwrap().on_time_rate - top.cumulative_on_time_rate).abs() < 1e-9);
+
+        let unreachable = SimulationRequest { target_on_time_rate: Some(1.0), ..Default::default() };
+        let result = simulate(&baseline(), &unreachable).unwrap();
+        assert_eq!(result.target_reached, Some(false));
+    }
+
+    #[test]
+    fn test_validation() {
+        let bad_days = SimulationRequest {
+            levers: vec![Lever::AddBufferDays { lanes: vec![], days: 0 }],
+            ..Default::default()
+        };
+        assert!(validate(&bad_days).is_err());
+        let bad_lane = SimulationRequest {
+            levers: vec![Lever::DropLanes { lanes: vec!["750".into()] }],
+            ..Default::default()
+        };
+        assert!(validate(&bad_lane).is_err());
+        let json = r#"{"levers":[{"type":"switch_carrier","lanes":["750-432"],"to":"B"}],"target_on_time_rate":0.8}"#;
+        let parsed: SimulationRequest = serde_json::from_str(json).unwrap();
+        assert!(validate(&parsed).is_ok());
+        let bad_mode = SimulationRequest {
+            levers: vec![Lever::ConvertMode { lanes: vec![], from: None, to: "Rail".into() }],
+            ..Default::default()
+        };
+        assert!(validate(&bad_mode).is_err());
+    }
+
+    #[test]
+    fn test_convert_mode_matches_stored_name() {
+        let mut cells = BTreeMap::new();
+        cells.insert(("750xx".to_string(), "432xx".to_string()), vec![
+            cell("A", "LTL", OtdDesignation::Late, 4, 20.0),
+            cell("A", "TLFlatbed", OtdDesignation::OnTime, 3, 20.0),
+        ]);
+        let base = Baseline::new(cells, &HashMap::new());
+        let lever = Lever::ConvertMode { lanes: vec!["750-432".into()], from: Some("ltl".into()), to: "TL Flatbed".into() };
+        assert_eq!(lever.describe(), "convert LTL to TL Flatbed on DFW→COL");
+        let request = SimulationRequest { levers: vec![lever], ..Default::default() };
+        let result = simulate(&base, &request).unwrap();
+        assert_eq!(result.applied[0].lanes_changed, 1);
+        assert!((result.projected.on_time - 40.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,562 @@
+//! SLA proposals: goal transit days from a target on-time percentile
+//!
+//! For each lane, and each carrier on it with enough history, the proposed
+//! goal is the smallest whole number of days within which the target share
+//! of shipments was delivered (nearest-rank percentile of actual transit).
+//! A carrier gets its own proposal only where it differs from its lane's.
+//!
+//! [`rescore`] replays the shipments against the proposals. A shipment whose
+//! goal changes is classified Early/OnTime/Late by comparing its actual
+//! transit days with the proposed goal; the others keep the OTD designation
+//! recorded in the source data, which ingest stores as given rather than
+//! deriving it from transit days. A carrier's own proposal takes precedence
+//! over its lane's.
+//!
+//! Proposals are stored in the `sla_proposal` table with a review status so
+//! commercial teams can approve or reject them before promises change.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Datetime;
+
+use crate::db::DbConn;
+use crate::filter::ShipmentFilter;
+use crate::models::OtdDesignation;
+
+/// Share of shipments the proposed goal should cover
+pub const DEFAULT_TARGET_PERCENTILE: f64 = 0.8;
+
+/// Shipments a lane, or a carrier on a lane, needs for a proposal
+pub const DEFAULT_MIN_SHIPMENTS: i64 = 30;
+
+#[derive(Debug, Clone)]
+pub struct SlaConfig {
+    /// Fraction of shipments to deliver within the goal, e.g. 0.8
+    pub target_percentile: f64,
+    pub min_shipments: i64,
+}
+
+impl Default for SlaConfig {
+    fn default() -> Self {
+        Self {
+            target_percentile: DEFAULT_TARGET_PERCENTILE,
+            min_shipments: DEFAULT_MIN_SHIPMENTS,
+        }
+    }
+}
+
+// ============================================================================
+// History
+// ============================================================================
+
+/// Shipments of one lane and carrier with the same transit, goal and
+/// designation
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransitCell {
+    pub lane_ref: String,
+    pub origin_zip: String,
+    pub dest_zip: String,
+    pub carrier_ref: String,
+    pub transit: i64,
+    pub goal: i64,
+    pub otd: String,
+    pub shipments: i64,
+}
+
+/// Transit history of the shipments matching `filter`
+pub async fn load_transits(db: &DbConn, filter: &ShipmentFilter) -> Result<Vec<TransitCell>> {
+    let cells: Vec<TransitCell> = filter
+        .query(db, r#"
+            SELECT lane_ref, origin_zip, dest_zip, carrier_ref, transit, goal, otd, count() as shipments
+            FROM (
+                SELECT
+                    lane_ref,
+                    (origin_zip ?? origin_zip3) as origin_zip,
+                    (dest_zip ?? dest_zip3) as dest_zip,
+                    carrier_ref, otd,
+                    actual_transit_days as transit,
+                    goal_transit_days as goal
+                FROM shipment WHERE {filter}
+            )
+            WHERE lane_ref != NONE AND origin_zip != NONE AND dest_zip != NONE
+            GROUP BY lane_ref, origin_zip, dest_zip, carrier_ref, transit, goal, otd
+        "#)
+        .await?
+        .take(0)?;
+    Ok(cells)
+}
+
+// ============================================================================
+// Proposals
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaScope {
+    Lane,
+    LaneCarrier,
+}
+
+impl SlaScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lane => "lane",
+            Self::LaneCarrier => "lane_carrier",
+        }
+    }
+}
+
+/// Proposed goal for a lane or a carrier on a lane, re-scored on its own
+/// shipments. Rates are fractions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaProposal {
+    pub scope: SlaScope,
+    /// The lane's `lane_ref`, the busiest one if its ZIP3s have several
+    pub lane_ref: String,
+    pub origin_zip: String,
+    pub dest_zip: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carrier_ref: Option<String>,
+    pub shipments: i64,
+    /// Most common goal today
+    pub current_goal_days: i64,
+    pub proposed_goal_days: i64,
+    pub target_percentile: f64,
+    pub current_on_time_rate: f64,
+    pub current_late_rate: f64,
+    pub proposed_on_time_rate: f64,
+    pub proposed_late_rate: f64,
+}
+
+impl SlaProposal {
+    pub fn change_days(&self) -> i64 {
+        self.proposed_goal_days - self.current_goal_days
+    }
+}
+
+/// Early/on-time/late shares
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct OtdRates {
+    pub early_rate: f64,
+    pub on_time_rate: f64,
+    pub late_rate: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Counts {
+    early: i64,
+    on_time: i64,
+    late: i64,
+}
+
+impl Counts {
+    fn add(&mut self, otd: &str, n: i64) {
+        match otd {
+            "Early" => self.early += n,
+            "OnTime" => self.on_time += n,
+            "Late" => self.late += n,
+            _ => {}
+        }
+    }
+
+    /// Count `n` shipments of `transit` days against `goal`
+    fn add_scored(&mut self, transit: i64, goal: i64, n: i64) {
+        self.add(designation(transit, goal), n);
+    }
+
+    /// Count `cell` under `goal`: re-designated when the goal changes,
+    /// otherwise with its recorded OTD
+    fn add_under(&mut self, cell: &TransitCell, goal: i64) {
+        if goal == cell.goal {
+            self.add(&cell.otd, cell.shipments);
+        } else {
+            self.add_scored(cell.transit, goal, cell.shipments);
+        }
+    }
+
+    fn total(&self) -> i64 {
+        self.early + self.on_time + self.late
+    }
+
+    fn rates(&self) -> OtdRates {
+        let total = self.total().max(1) as f64;
+        OtdRates {
+            early_rate: self.early as f64 / total,
+            on_time_rate: self.on_time as f64 / total,
+            late_rate: self.late as f64 / total,
+        }
+    }
+}
+
+fn designation(transit: i64, goal: i64) -> &'static str {
+    OtdDesignation::from_transit(transit, goal).as_str()
+}
+
+/// Smallest transit (at least one day) covering `target` of the shipments
+fn percentile_goal(cells: &[&TransitCell], target: f64) -> i64 {
+    let mut by_transit: BTreeMap<i64, i64> = BTreeMap::new();
+    for c in cells {
+        *by_transit.entry(c.transit).or_default() += c.shipments;
+    }
+    let total: i64 = by_transit.values().sum();
+    let rank = ((target * total as f64).ceil() as i64).max(1);
+    let mut seen = 0;
+    for (transit, n) in by_transit {
+        seen += n;
+        if seen >= rank {
+            return transit.max(1);
+        }
+    }
+    1
+}
+
+fn proposal(scope: SlaScope, cells: &[&TransitCell], goal: i64, config: &SlaConfig) -> SlaProposal {
+    let first = cells[0];
+    let mut goals: HashMap<i64, i64> = HashMap::new();
+    let mut lane_refs: HashMap<&str, i64> = HashMap::new();
+    let (mut current, mut proposed) = (Counts::default(), Counts::default());
+    for c in cells {
+        *goals.entry(c.goal).or_default() += c.shipments;
+        *lane_refs.entry(&c.lane_ref).or_default() += c.shipments;
+        current.add(&c.otd, c.shipments);
+        proposed.add_under(c, goal);
+    }
+    let current_goal_days = goals
+        .into_iter()
+        .max_by_key(|(goal, n)| (*n, -goal))
+        .map(|(goal, _)| goal)
+        .unwrap_or(goal);
+    let lane_ref = lane_refs
+        .into_iter()
+        .max_by_key(|(lane_ref, n)| (*n, std::cmp::Reverse(*lane_ref)))
+        .map(|(lane_ref, _)| lane_ref.to_string())
+        .unwrap_or_default();
+    let (current, proposed) = (current.rates(), proposed.rates());
+    SlaProposal {
+        scope,
+        lane_ref,
+        origin_zip: first.origin_zip.clone(),
+        dest_zip: first.dest_zip.clone(),
+        carrier_ref: (scope == SlaScope::LaneCarrier).then(|| first.carrier_ref.clone()),
+        shipments: cells.iter().map(|c| c.shipments).sum(),
+        current_goal_days,
+        proposed_goal_days: goal,
+        target_percentile: config.target_percentile,
+        current_on_time_rate: current.on_time_rate,
+        current_late_rate: current.late_rate,
+        proposed_on_time_rate: proposed.on_time_rate,
+        proposed_late_rate: proposed.late_rate,
+    }
+}
+
+/// Proposals for every lane with enough shipments, each followed by the
+/// carriers on it whose own goal would differ; busiest lanes first
+pub fn propose(cells: &[TransitCell], config: &SlaConfig) -> Vec<SlaProposal> {
+    let mut lanes: BTreeMap<(&str, &str), Vec<&TransitCell>> = BTreeMap::new();
+    for c in cells {
+        lanes.entry((&c.origin_zip, &c.dest_zip)).or_default().push(c);
+    }
+    let mut by_lane: Vec<Vec<SlaProposal>> = Vec::new();
+    for lane_cells in lanes.values() {
+        let shipments: i64 = lane_cells.iter().map(|c| c.shipments).sum();
+        if shipments < config.min_shipments {
+            continue;
+        }
+        let lane_goal = percentile_goal(lane_cells, config.target_percentile);
+        let mut proposals = vec![proposal(SlaScope::Lane, lane_cells, lane_goal, config)];
+
+        let mut carriers: BTreeMap<&str, Vec<&TransitCell>> = BTreeMap::new();
+        for c in lane_cells {
+            carriers.entry(&c.carrier_ref).or_default().push(c);
+        }
+        for carrier_cells in carriers.values() {
+            if carrier_cells.iter().map(|c| c.shipments).sum::<i64>() < config.min_shipments {
+                continue;
+            }
+            let goal = percentile_goal(carrier_cells, config.target_percentile);
+            if goal != lane_goal {
+                proposals.push(proposal(SlaScope::LaneCarrier, carrier_cells, goal, config));
+            }
+        }
+        by_lane.push(proposals);
+    }
+    by_lane.sort_by_key(|p| -p[0].shipments);
+    by_lane.into_iter().flatten().collect()
+}
+
+// ============================================================================
+// Re-score
+// ============================================================================
+
+/// Network performance as recorded and under the proposals
+#[derive(Debug, Clone, Serialize)]
+pub struct Rescore {
+    pub shipments: i64,
+    /// Shipments whose lane or carrier has a proposal
+    pub covered_shipments: i64,
+    /// Covered shipments whose goal changes
+    pub changed_shipments: i64,
+    pub current: OtdRates,
+    pub proposed: OtdRates,
+}
+
+/// One OTD outcome's share, as recorded and under the proposals
+#[derive(Debug, Clone, Serialize)]
+pub struct RescoreOutcome {
+    pub outcome: &'static str,
+    pub recorded_rate: f64,
+    pub proposed_rate: f64,
+}
+
+impl Rescore {
+    /// Early, on-time and late shares side by side, for reporting
+    pub fn outcomes(&self) -> Vec<RescoreOutcome> {
+        [
+            ("Early", self.current.early_rate, self.proposed.early_rate),
+            ("OnTime", self.current.on_time_rate, self.proposed.on_time_rate),
+            ("Late", self.current.late_rate, self.proposed.late_rate),
+        ]
+        .into_iter()
+        .map(|(outcome, recorded_rate, proposed_rate)| RescoreOutcome { outcome, recorded_rate, proposed_rate })
+        .collect()
+    }
+}
+
+/// Replay `cells` with the proposed goals
+pub fn rescore(cells: &[TransitCell], proposals: &[SlaProposal]) -> Rescore {
+    let mut lane_goals: HashMap<(&str, &str), i64> = HashMap::new();
+    let mut carrier_goals: HashMap<(&str, &str, &str), i64> = HashMap::new();
+    for p in proposals {
+        match (&p.scope, &p.carrier_ref) {
+            (SlaScope::LaneCarrier, Some(carrier)) => {
+                carrier_goals.insert((&p.origin_zip, &p.dest_zip, carrier), p.proposed_goal_days);
+            }
+            _ => {
+                lane_goals.insert((&p.origin_zip, &p.dest_zip), p.proposed_goal_days);
+            }
+        }
+    }
+
+    let (mut current, mut proposed) = (Counts::default(), Counts::default());
+    let (mut covered, mut changed) = (0, 0);
+    for c in cells {
+        current.add(&c.otd, c.shipments);
+        let goal = carrier_goals
+            .get(&(c.origin_zip.as_str(), c.dest_zip.as_str(), c.carrier_ref.as_str()))
+            .or_else(|| lane_goals.get(&(c.origin_zip.as_str(), c.dest_zip.as_str())));
+        match goal {
+            Some(&goal) => {
+                covered += c.shipments;
+                if goal != c.goal {
+                    changed += c.shipments;
+                }
+                proposed.add_under(c, goal);
+            }
+            None => proposed.add(&c.otd, c.shipments),
+        }
+    }
+
+    Rescore {
+        shipments: current.total(),
+        covered_shipments: covered,
+        changed_shipments: changed,
+        current: current.rates(),
+        proposed: proposed.rates(),
+    }
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Review state of a stored proposal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Proposed,
+    Approved,
+    Rejected,
+}
+
+impl ProposalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Proposed => "proposed",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// A proposal as stored in `sla_proposal`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredProposal {
+    #[serde(flatten)]
+    pub proposal: SlaProposal,
+    pub status: ProposalStatus,
+    pub created_at: String,
+    #[serde(default)]
+    pub reviewed_at: Option<String>,
+}
+
+/// Store `proposals` for review, replacing earlier ones still awaiting it.
+/// Approved and rejected proposals are kept.
+pub async fn save(db: &DbConn, proposals: &[SlaProposal]) -> Result<()> {
+    #[derive(Serialize)]
+    struct Row {
+        #[serde(flatten)]
+        proposal: SlaProposal,
+        status: ProposalStatus,
+        created_at: Datetime,
+    }
+    let created_at = Datetime::from(Utc::now());
+    let rows: Vec<Row> = proposals
+        .iter()
+        .map(|proposal| Row {
+            proposal: proposal.clone(),
+            status: ProposalStatus::Proposed,
+            created_at: created_at.clone(),
+        })
+        .collect();
+
+    db.query(r#"
+        BEGIN TRANSACTION;
+        DELETE sla_proposal WHERE status = "proposed";
+        INSERT INTO sla_proposal $rows;
+        COMMIT TRANSACTION;
+    "#)
+    .bind(("rows", rows))
+    .await?
+    .check()?;
+    Ok(())
+}
+
+/// Stored proposals, optionally with one status, busiest first
+pub async fn load(db: &DbConn, status: Option<ProposalStatus>) -> Result<Vec<StoredProposal>> {
+    let condition = if status.is_some() { "WHERE status = $status" } else { "" };
+    let sql = format!(
+        "SELECT * OMIT id FROM sla_proposal {} ORDER BY shipments DESC, lane_ref, carrier_ref",
+        condition
+    );
+    let mut query = db.query(sql);
+    if let Some(status) = status {
+        query = query.bind(("status", status.as_str()));
+    }
+    let rows: Vec<StoredProposal> = query.await?.take(0)?;
+    Ok(rows)
+}
+
+/// Which stored proposals a review applies to
+#[derive(Debug, Clone, Default)]
+pub struct ProposalSelector {
+    /// Normalized origin and destination; `None` selects every lane
+    pub lane: Option<(String, String)>,
+    /// Only this carrier's proposal on the lane
+    pub carrier_ref: Option<String>,
+}
+
+/// Set the status of the selected proposals that are still awaiting review;
+/// returns how many changed
+pub async fn review(db: &DbConn, selector: &ProposalSelector, status: ProposalStatus) -> Result<usize> {
+    let mut conditions = vec![r#"status = "proposed""#];
+    if selector.lane.is_some() {
+        conditions.push("origin_zip = $origin AND dest_zip = $dest");
+    }
+    if selector.carrier_ref.is_some() {
+        conditions.push("carrier_ref = $carrier");
+    }
+    let sql = format!(
+        "UPDATE sla_proposal SET status = $status, reviewed_at = time::now() WHERE {} RETURN lane_ref",
+        conditions.join(" AND ")
+    );
+    let (origin, dest) = selector.lane.clone().unwrap_or_default();
+    let updated: Vec<serde_json::Value> = db
+        .query(sql)
+        .bind(("status", status.as_str()))
+        .bind(("origin", origin))
+        .bind(("dest", dest))
+        .bind(("carrier", selector.carrier_ref.clone().unwrap_or_default()))
+        .await?
+        .take(0)?;
+    Ok(updated.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(carrier: &str, transit: i64, shipments: i64) -> TransitCell {
+        TransitCell {
+            lane_ref: "109c918ef6db".into(),
+            origin_zip: "750xx".into(),
+            dest_zip: "432xx".into(),
+            carrier_ref: carrier.into(),
+            transit,
+            goal: 3,
+            otd: designation(transit, 3).into(),
+            shipments,
+        }
+    }
+
+    #[test]
+    fn test_percentile_goal() {
+        let cells = [cell("A", 2, 50), cell("A", 3, 30), cell("A", 5, 20)];
+        let refs: Vec<&TransitCell> = cells.iter().collect();
+        assert_eq!(percentile_goal(&refs, 0.5), 2);
+        assert_eq!(percentile_goal(&refs, 0.8), 3);
+        assert_eq!(percentile_goal(&refs, 0.81), 5);
+    }
+
+    #[test]
+    fn test_propose_and_rescore() {
+        // A: 80% within 3 days. B: 60 of 100 within 3, 90 within 4
+        let cells = vec![
+            cell("A", 2, 40), cell("A", 3, 40), cell("A", 4, 20),
+            cell("B", 3, 60), cell("B", 4, 30), cell("B", 6, 10),
+        ];
+        let proposals = propose(&cells, &SlaConfig::default());
+        assert_eq!(proposals.len(), 2);
+        let lane = &proposals[0];
+        assert_eq!(lane.scope, SlaScope::Lane);
+        assert_eq!(lane.lane_ref, "109c918ef6db");
+        assert_eq!((lane.current_goal_days, lane.proposed_goal_days), (3, 4));
+        assert_eq!(lane.shipments, 200);
+        // A stays at 3 days, so it gets its own proposal
+        let carrier = &proposals[1];
+        assert_eq!(carrier.carrier_ref.as_deref(), Some("A"));
+        assert_eq!(carrier.proposed_goal_days, 3);
+        assert_eq!(carrier.change_days(), 0);
+
+        // Only B's shipments move to a 4-day goal
+        let rescore = rescore(&cells, &proposals);
+        assert_eq!(rescore.shipments, 200);
+        assert_eq!(rescore.covered_shipments, 200);
+        assert_eq!(rescore.changed_shipments, 100);
+        assert!((rescore.current.late_rate - 60.0 / 200.0).abs() < 1e-12);
+        assert!((rescore.proposed.late_rate - 30.0 / 200.0).abs() < 1e-12);
+        assert!((rescore.proposed.on_time_rate - 70.0 / 200.0).abs() < 1e-12);
+
+        // Too little history for a proposal
+        let config = SlaConfig { min_shipments: 500, ..Default::default() };
+        assert!(propose(&cells, &config).is_empty());
+    }
+
+    #[test]
+    fn test_rescore_keeps_recorded_otd_when_goal_unchanged() {
+        // A's 4-day shipments were recorded on time despite the 3-day goal
+        let mut cells = vec![
+            cell("A", 2, 40), cell("A", 3, 40), cell("A", 4, 20),
+            cell("B", 3, 60), cell("B", 4, 30), cell("B", 6, 10),
+        ];
+        cells[2].otd = "OnTime".into();
+        let proposals = propose(&cells, &SlaConfig::default());
+        let rescore = rescore(&cells, &proposals);
+        // A keeps its 3-day goal, so its recorded OTD carries over
+        assert!((rescore.current.late_rate - 40.0 / 200.0).abs() < 1e-12);
+        assert!((rescore.proposed.late_rate - 10.0 / 200.0).abs() < 1e-12);
+        assert!((rescore.proposed.on_time_rate - 90.0 / 200.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,458 @@
+//! Input formats for shipment ingestion
+//!
+//! CSV, Parquet and NDJSON (JSON Lines) files are all read into
+//! [`StringRecord`]s keyed by the canonical CSV column names, so validation,
+//! reject files and the batch writer handle every format the same way.
+//!
+//! Source fields are renamed onto canonical columns by a [`ColumnMapping`].
+//! Common TMS spellings (`carrier_id`, `load_id`, ...) are recognized out of
+//! the box; anything else can be mapped with a small JSON file:
+//!
+//! ```json
+//! { "carrier_scac": "carrier_pseudo", "shipped_at": "actual_ship" }
+//! ```
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use csv::{Position, ReaderBuilder, StringRecord};
+use parquet::file::reader::SerializedFileReader;
+use parquet::record::reader::RowIter;
+use parquet::record::Field;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+use tracing::warn;
+
+use crate::validation::DATETIME_FORMAT;
+
+/// Source field names used by common TMS exports, mapped to canonical columns
+pub const DEFAULT_ALIASES: &[(&str, &str)] = &[
+    ("carrier_id", "carrier_pseudo"),
+    ("carrier", "carrier_pseudo"),
+    ("load_id", "load_id_pseudo"),
+    ("origin_zip3", "origin_zip_3d"),
+    ("dest_zip3", "dest_zip_3d"),
+    ("goal_transit_days", "all_modes_goal_transit_days"),
+    ("otd", "otd_designation"),
+];
+
+/// Columns holding timestamps; ISO 8601 and date-only values are normalized
+/// to [`DATETIME_FORMAT`]
+const DATETIME_COLUMNS: &[&str] = &["actual_ship", "actual_delivery"];
+
+/// File format of an ingest source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum InputFormat {
+    Csv,
+    Parquet,
+    /// Newline-delimited JSON objects (`.ndjson`, `.jsonl`)
+    Ndjson,
+}
+
+impl InputFormat {
+    /// Guess the format from the file extension, defaulting to CSV
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "parquet" | "pq" => InputFormat::Parquet,
+            "ndjson" | "jsonl" => InputFormat::Ndjson,
+            _ => InputFormat::Csv,
+        }
+    }
+}
+
+/// Renames source fields onto canonical column names.
+///
+/// Explicit entries take precedence over [`DEFAULT_ALIASES`]; unmapped
+/// fields keep their own name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ColumnMapping {
+    columns: BTreeMap<String, String>,
+}
+
+impl ColumnMapping {
+    /// Load a mapping of `source field -> canonical column` from a JSON file
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("opening column mapping {:?}", path))?;
+        serde_json::from_reader(file).with_context(|| format!("parsing column mapping {:?}", path))
+    }
+
+    pub fn insert(&mut self, source: impl Into<String>, canonical: impl Into<String>) {
+        self.columns.insert(source.into(), canonical.into());
+    }
+
+    /// Canonical column name for a source field
+    pub fn canonical<'a>(&'a self, source: &'a str) -> &'a str {
+        if let Some(target) = self.columns.get(source) {
+            return target;
+        }
+        DEFAULT_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == source)
+            .map(|(_, target)| *target)
+            .unwrap_or(source)
+    }
+}
+
+enum Inner {
+    Csv(csv::Reader<File>),
+    Ndjson {
+        reader: BufReader<File>,
+        byte: u64,
+        line: u64,
+    },
+    Parquet(Box<RowIter<'static>>),
+}
+
+/// Streams rows from any [`InputFormat`] as canonical string records
+pub struct RowReader {
+    inner: Inner,
+    headers: StringRecord,
+    /// Field index by canonical name, for formats that are not already laid
+    /// out as CSV columns
+    slots: HashMap<String, usize>,
+    mapping: ColumnMapping,
+    /// Canonical columns of the target record type
+    columns: Vec<String>,
+    checked: bool,
+}
+
+impl RowReader {
+    /// Open `path`. `columns` is the canonical column list of the target
+    /// record type; Parquet and NDJSON rows are laid out in that order.
+    pub fn open(
+        path: &Path,
+        format: InputFormat,
+        mapping: &ColumnMapping,
+        columns: &[&str],
+    ) -> Result<Self> {
+        let canonical: StringRecord = columns.iter().copied().collect();
+        let mut source_names = None;
+        let (inner, headers) = match format {
+            InputFormat::Csv => {
+                // Flexible so short or long rows reach validation instead of aborting
+                let mut reader = ReaderBuilder::new()
+                    .has_headers(true)
+                    .flexible(true)
+                    .from_path(path)?;
+                // An alias is only renamed when the file lacks the canonical column
+                let raw = reader.headers()?.clone();
+                let headers: StringRecord = raw
+                    .iter()
+                    .map(|h| match mapping.canonical(h) {
+                        target if raw.iter().any(|other| other == target) => h,
+                        target => target,
+                    })
+                    .collect();
+                source_names = Some(raw.iter().map(str::to_string).collect());
+                (Inner::Csv(reader), headers)
+            }
+            InputFormat::Ndjson => {
+                let reader = BufReader::new(File::open(path)?);
+                (Inner::Ndjson { reader, byte: 0, line: 1 }, canonical)
+            }
+            InputFormat::Parquet => {
+                let reader = SerializedFileReader::new(File::open(path)?)
+                    .with_context(|| format!("reading parquet metadata from {:?}", path))?;
+                (Inner::Parquet(Box::new(reader.into_iter())), canonical)
+            }
+        };
+        let slots = headers
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_string(), i))
+            .collect();
+
+        let mut reader = Self {
+            inner,
+            headers,
+            slots,
+            mapping: mapping.clone(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            checked: false,
+        };
+        if let Some(names) = source_names {
+            reader.check_columns(names);
+        }
+        Ok(reader)
+    }
+
+    /// Canonical header row, as written to reject files
+    pub fn headers(&self) -> &StringRecord {
+        &self.headers
+    }
+
+    /// Byte offset and line number of the next unread row.
+    ///
+    /// Parquet has no meaningful byte offset; resuming skips rows instead.
+    pub fn position(&self) -> (u64, u64) {
+        match &self.inner {
+            Inner::Csv(reader) => {
+                let pos = reader.position();
+                (pos.byte(), pos.line())
+            }
+            Inner::Ndjson { byte, line, .. } => (*byte, *line),
+            Inner::Parquet(_) => (0, 0),
+        }
+    }
+
+    /// Continue after `rows` data rows that ended at `byte`/`line`
+    pub fn seek(&mut self, rows: u64, byte: u64, line: u64) -> Result<()> {
+        match &mut self.inner {
+            Inner::Csv(reader) => {
+                let mut pos = Position::new();
+                pos.set_byte(byte).set_line(line).set_record(rows + 1);
+                reader.seek(pos)?;
+            }
+            Inner::Ndjson {
+                reader,
+                byte: current,
+                line: current_line,
+            } => {
+                reader.seek(SeekFrom::Start(byte))?;
+                *current = byte;
+                *current_line = line;
+            }
+            Inner::Parquet(rows_iter) => {
+                for _ in 0..rows {
+                    if rows_iter.next().is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the next row. A row that cannot be decoded yields `Err` and the
+    /// stream continues with the following row.
+    pub fn next_record(&mut self) -> Option<Result<StringRecord>> {
+        match &mut self.inner {
+            Inner::Csv(reader) => {
+                let mut record = StringRecord::new();
+                match reader.read_record(&mut record) {
+                    Ok(true) => Some(Ok(record)),
+                    Ok(false) => None,
+                    Err(e) => Some(Err(e.into())),
+                }
+            }
+            Inner::Ndjson { reader, byte, line } => {
+                let mut text = String::new();
+                loop {
+                    text.clear();
+                    let read = match reader.read_line(&mut text) {
+                        Ok(0) => return None,
+                        Ok(read) => read,
+                        Err(e) => return Some(Err(e.into())),
+                    };
+                    *byte += read as u64;
+                    *line += 1;
+                    if !text.trim().is_empty() {
+                        break;
+                    }
+                }
+                let at = *line - 1;
+                let object = match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&text) {
+                    Ok(object) => object,
+                    Err(e) => return Some(Err(anyhow::anyhow!("line {}: {}", at, e))),
+                };
+                if !self.checked {
+                    self.check_columns(object.keys().cloned().collect());
+                }
+                let fields = object.iter().map(|(name, value)| (name.as_str(), json_to_string(value)));
+                Some(Ok(self.layout(fields)))
+            }
+            Inner::Parquet(rows) => {
+                let row = match rows.next()? {
+                    Ok(row) => row,
+                    Err(e) => return Some(Err(e.into())),
+                };
+                if !self.checked {
+                    self.check_columns(row.get_column_iter().map(|(n, _)| n.clone()).collect());
+                }
+                let fields: Vec<(String, String)> = row
+                    .get_column_iter()
+                    .map(|(name, field)| (name.clone(), field_to_string(field)))
+                    .collect();
+                Some(Ok(self.layout(fields.iter().map(|(n, v)| (n.as_str(), v.clone())))))
+            }
+        }
+    }
+
+    /// Place named source values into canonical column order. A field named
+    /// exactly like its canonical column wins over an alias for it.
+    fn layout<'a>(&self, fields: impl Iterator<Item = (&'a str, String)>) -> StringRecord {
+        let mut values = vec![String::new(); self.headers.len()];
+        let mut exact = vec![false; self.headers.len()];
+        for (name, value) in fields {
+            let target = self.mapping.canonical(name);
+            if let Some(&slot) = self.slots.get(target) {
+                let is_exact = name == target;
+                if is_exact || !exact[slot] {
+                    values[slot] = if DATETIME_COLUMNS.contains(&target) {
+                        normalize_datetime(&value)
+                    } else {
+                        value
+                    };
+                    exact[slot] = is_exact;
+                }
+            }
+        }
+        values.into_iter().collect()
+    }
+
+    /// Warn once about source fields that map to nothing and required
+    /// columns that no source field provides
+    fn check_columns(&mut self, names: Vec<String>) {
+        self.checked = true;
+        let mut provided = Vec::new();
+        let mut unmapped = Vec::new();
+        for name in &names {
+            let target = self.mapping.canonical(name);
+            if self.columns.iter().any(|c| c == target) {
+                provided.push(target);
+            } else {
+                unmapped.push(name.as_str());
+            }
+        }
+        let missing: Vec<&str> = self
+            .columns
+            .iter()
+            .map(String::as_str)
+            .filter(|c| !provided.contains(c))
+            .collect();
+        if !unmapped.is_empty() {
+            warn!("Ignoring unmapped source fields: {}", unmapped.join(", "));
+        }
+        if !missing.is_empty() {
+            warn!("No source field maps to: {} (add them to --column-map)", missing.join(", "));
+        }
+    }
+}
+
+fn float_to_string(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        value.to_string()
+    }
+}
+
+fn json_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Number(n) if n.is_f64() => float_to_string(n.as_f64().unwrap_or_default()),
+        other => other.to_string(),
+    }
+}
+
+fn field_to_string(field: &Field) -> String {
+    let timestamp = |dt: Option<DateTime<chrono::Utc>>| {
+        dt.map(|dt| dt.naive_utc().format(DATETIME_FORMAT).to_string())
+            .unwrap_or_default()
+    };
+    match field {
+        Field::Null => String::new(),
+        Field::Str(s) => s.clone(),
+        Field::Float(f) => float_to_string(*f as f64),
+        Field::Double(f) => float_to_string(*f),
+        Field::Date(days) => NaiveDate::from_num_days_from_ce_opt(*days + 719_163)
+            .map(|d| d.format("%Y-%m-%d 00:00:00").to_string())
+            .unwrap_or_default(),
+        Field::TimestampMillis(ms) => timestamp(DateTime::from_timestamp_millis(*ms)),
+        Field::TimestampMicros(us) => timestamp(DateTime::from_timestamp_micros(*us)),
+        other => other.to_string(),
+    }
+}
+
+/// Rewrite ISO 8601 (`2024-01-02T08:00:00`, with or without offset) and
+/// date-only values as [`DATETIME_FORMAT`]; anything else is left for
+/// validation to reject
+pub fn normalize_datetime(value: &str) -> String {
+    let value = value.trim();
+    if NaiveDateTime::parse_from_str(value, DATETIME_FORMAT).is_ok() {
+        return value.to_string();
+    }
+    let parsed = DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.naive_local())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default())
+        });
+    match parsed {
+        Ok(dt) => dt.format(DATETIME_FORMAT).to_string(),
+        Err(_) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapping_precedence() {
+        let mut mapping = ColumnMapping::default();
+        assert_eq!(mapping.canonical("carrier_id"), "carrier_pseudo");
+        assert_eq!(mapping.canonical("ship_dow"), "ship_dow");
+        mapping.insert("carrier_id", "lane_id");
+        assert_eq!(mapping.canonical("carrier_id"), "lane_id");
+    }
+
+    #[test]
+    fn test_normalize_datetime() {
+        assert_eq!(normalize_datetime("2024-01-02 08:00:00"), "2024-01-02 08:00:00");
+        assert_eq!(normalize_datetime("2024-01-02T08:00:00"), "2024-01-02 08:00:00");
+        assert_eq!(normalize_datetime("2024-01-02T08:00:00-05:00"), "2024-01-02 08:00:00");
+        assert_eq!(normalize_datetime("2024-01-02"), "2024-01-02 00:00:00");
+        assert_eq!(normalize_datetime("yesterday"), "yesterday");
+    }
+
+    #[test]
+    fn test_ndjson_layout_and_resume() {
+        let dir = std::env::temp_dir().join(format!("sources-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rows.ndjson");
+        std::fs::write(
+            &path,
+            "{\"carrier_id\": \"C1\", \"actual_ship\": \"2024-01-02T08:00:00\", \"days\": 3.0}\n\
+             \n\
+             not json\n\
+             {\"carrier_pseudo\": \"C2\", \"carrier_id\": \"X\", \"days\": 2.5}\n",
+        )
+        .unwrap();
+        let mut mapping = ColumnMapping::default();
+        mapping.insert("days", "actual_transit_days");
+        let columns = ["carrier_pseudo", "actual_ship", "actual_transit_days", "ship_dow"];
+
+        let mut reader = RowReader::open(&path, InputFormat::Ndjson, &mapping, &columns).unwrap();
+        let first = reader.next_record().unwrap().unwrap();
+        assert_eq!(first.iter().collect::<Vec<_>>(), ["C1", "2024-01-02 08:00:00", "3", ""]);
+        assert!(reader.next_record().unwrap().is_err());
+        let (byte, line) = reader.position();
+        let third = reader.next_record().unwrap().unwrap();
+        assert_eq!(&third[0], "C2");
+        assert_eq!(&third[2], "2.5");
+        assert!(reader.next_record().is_none());
+
+        let mut resumed = RowReader::open(&path, InputFormat::Ndjson, &mapping, &columns).unwrap();
+        resumed.seek(2, byte, line).unwrap();
+        assert_eq!(&resumed.next_record().unwrap().unwrap()[0], "C2");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(InputFormat::from_path(Path::new("a/b.parquet")), InputFormat::Parquet);
+        assert_eq!(InputFormat::from_path(Path::new("b.JSONL")), InputFormat::Ndjson);
+        assert_eq!(InputFormat::from_path(Path::new("b.csv")), InputFormat::Csv);
+    }
+}
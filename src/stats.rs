@@ -0,0 +1,119 @@
+//! Small statistics helpers for the analytics
+
+use serde::{Deserialize, Serialize};
+
+/// Two-sided 95% critical value of the standard normal
+pub const Z_95: f64 = 1.959964;
+
+/// z statistic for the difference of two proportions `x2/n2 - x1/n1`
+/// (pooled variance). Returns 0 when either sample is empty or the pooled
+/// rate is 0 or 1.
+pub fn two_proportion_z(x1: i64, n1: i64, x2: i64, n2: i64) -> f64 {
+    if n1 <= 0 || n2 <= 0 {
+        return 0.0;
+    }
+    let (n1f, n2f) = (n1 as f64, n2 as f64);
+    let pooled = (x1 + x2) as f64 / (n1f + n2f);
+    let se = (pooled * (1.0 - pooled) * (1.0 / n1f + 1.0 / n2f)).sqrt();
+    if se == 0.0 {
+        return 0.0;
+    }
+    (x2 as f64 / n2f - x1 as f64 / n1f) / se
+}
+
+/// z statistic for the difference of two means `mean2 - mean1`, given each
+/// sample's variance and size (Welch standard error)
+pub fn mean_difference_z(mean1: f64, var1: f64, n1: i64, mean2: f64, var2: f64, n2: i64) -> f64 {
+    if n1 <= 0 || n2 <= 0 {
+        return 0.0;
+    }
+    let se = (var1 / n1 as f64 + var2 / n2 as f64).sqrt();
+    if se == 0.0 || !se.is_finite() {
+        return 0.0;
+    }
+    (mean2 - mean1) / se
+}
+
+/// Whether a z statistic is significant at the 95% level
+pub fn is_significant(z: f64) -> bool {
+    z.abs() >= Z_95
+}
+
+/// Lower and upper bound of a rate estimate, as fractions
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateInterval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl RateInterval {
+    /// The bounds in percent, rounded to one decimal like the API's rates
+    pub fn percent(&self) -> (f64, f64) {
+        ((self.lower * 1000.0).round() / 10.0, (self.upper * 1000.0).round() / 10.0)
+    }
+}
+
+/// Wilson score interval for `successes` out of `n` trials at critical value
+/// `z`. Unlike `rate ± z·se` it stays inside [0, 1] and widens sharply for
+/// small samples, so 2 late out of 3 ranks below 300 late out of 1000 when
+/// sorting by the lower bound. An empty sample gives [0, 1].
+pub fn wilson_interval(successes: i64, n: i64, z: f64) -> RateInterval {
+    if n <= 0 {
+        return RateInterval { lower: 0.0, upper: 1.0 };
+    }
+    let n = n as f64;
+    let p = (successes as f64 / n).clamp(0.0, 1.0);
+    let z2 = z * z;
+    let denom = 1.0 + z2 / n;
+    let centre = (p + z2 / (2.0 * n)) / denom;
+    let half = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denom;
+    RateInterval {
+        lower: (centre - half).max(0.0),
+        upper: (centre + half).min(1.0),
+    }
+}
+
+/// 95% Wilson interval (see [`wilson_interval`])
+pub fn wilson_95(successes: i64, n: i64) -> RateInterval {
+    wilson_interval(successes, n, Z_95)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_proportion_z() {
+        // 20% -> 30% late on 500 shipments each is clearly significant
+        let z = two_proportion_z(100, 500, 150, 500);
+        assert!((z - 3.65).abs() < 0.01, "{}", z);
+        assert!(is_significant(z));
+        // The same shift on 20 shipments each is not
+        assert!(!is_significant(two_proportion_z(4, 20, 6, 20)));
+        assert_eq!(two_proportion_z(0, 0, 5, 10), 0.0);
+        assert_eq!(two_proportion_z(10, 10, 10, 10), 0.0);
+    }
+
+    #[test]
+    fn test_mean_difference_z() {
+        let z = mean_difference_z(3.0, 4.0, 100, 4.0, 4.0, 100);
+        assert!((z - 3.5355).abs() < 0.001, "{}", z);
+        assert_eq!(mean_difference_z(3.0, 0.0, 10, 3.0, 0.0, 10), 0.0);
+    }
+
+    #[test]
+    fn test_wilson_interval() {
+        let ci = wilson_95(30, 100);
+        assert!((ci.lower - 0.2189).abs() < 0.001, "{:?}", ci);
+        assert!((ci.upper - 0.3958).abs() < 0.001, "{:?}", ci);
+        assert_eq!(ci.percent(), (21.9, 39.6));
+
+        // A small lane with a worse raw rate has a lower floor
+        assert!(wilson_95(2, 3).lower < wilson_95(300, 1000).lower);
+
+        let none_late = wilson_95(0, 50);
+        assert_eq!(none_late.lower, 0.0);
+        assert!(none_late.upper > 0.0 && none_late.upper < 0.1);
+        assert_eq!(wilson_95(0, 0), RateInterval { lower: 0.0, upper: 1.0 });
+    }
+}
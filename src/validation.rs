@@ -0,0 +1,411 @@
+//! Row validation for CSV ingestion
+//!
+//! Each row is classified before it is written to the graph. Rejected rows are
+//! copied to a sidecar CSV with `reject_reason` / `reject_detail` columns and
+//! counted in a [`ValidationReport`], so bad data is visible instead of being
+//! dropped or coerced into default modes and OTD values.
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDateTime, Weekday};
+use csv::{StringRecord, Writer, WriterBuilder};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+use crate::models::{CarrierMode, CsvRecord, OtdDesignation, SyntheticCsvRecord};
+
+/// Timestamp format used by the source CSVs
+pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Why a row was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    /// Row could not be deserialized or a timestamp did not parse
+    ParseError,
+    /// `carrier_mode` is not one of the known modes
+    UnknownMode,
+    /// `otd_designation` is not one of the known designations
+    UnknownOtd,
+    /// `actual_delivery` is earlier than `actual_ship`
+    DeliveryBeforeShip,
+    /// `actual_transit_days` disagrees with the ship/delivery dates
+    TransitDaysMismatch,
+    /// Origin or destination ZIP3 is malformed
+    BadZip3,
+}
+
+impl RejectReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectReason::ParseError => "parse_error",
+            RejectReason::UnknownMode => "unknown_mode",
+            RejectReason::UnknownOtd => "unknown_otd",
+            RejectReason::DeliveryBeforeShip => "delivery_before_ship",
+            RejectReason::TransitDaysMismatch => "transit_days_mismatch",
+            RejectReason::BadZip3 => "bad_zip3",
+        }
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A classified validation failure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reject {
+    pub reason: RejectReason,
+    pub detail: String,
+}
+
+impl Reject {
+    pub fn new(reason: RejectReason, detail: impl Into<String>) -> Self {
+        Self {
+            reason,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl fmt::Display for Reject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.reason, self.detail)
+    }
+}
+
+/// Row-level checks applied before a record is ingested
+pub trait Validate {
+    fn validate(&self) -> Result<(), Reject>;
+}
+
+/// Fields shared by every CSV flavor that the checks look at
+struct RowFields<'a> {
+    carrier_mode: &'a str,
+    otd_designation: &'a str,
+    actual_ship: &'a str,
+    actual_delivery: &'a str,
+    actual_transit_days: i32,
+    origin_zip3: &'a str,
+    dest_zip3: &'a str,
+}
+
+impl Validate for CsvRecord {
+    fn validate(&self) -> Result<(), Reject> {
+        validate_fields(&RowFields {
+            carrier_mode: &self.carrier_mode,
+            otd_designation: &self.otd_designation,
+            actual_ship: &self.actual_ship,
+            actual_delivery: &self.actual_delivery,
+            actual_transit_days: self.actual_transit_days,
+            origin_zip3: &self.origin_zip_3d,
+            dest_zip3: &self.dest_zip_3d,
+        })
+    }
+}
+
+impl Validate for SyntheticCsvRecord {
+    fn validate(&self) -> Result<(), Reject> {
+        validate_fields(&RowFields {
+            carrier_mode: &self.carrier_mode,
+            otd_designation: &self.otd_designation,
+            actual_ship: &self.actual_ship,
+            actual_delivery: &self.actual_delivery,
+            actual_transit_days: self.actual_transit_days,
+            origin_zip3: &self.origin_zip_3d,
+            dest_zip3: &self.dest_zip_3d,
+        })
+    }
+}
+
+fn validate_fields(row: &RowFields) -> Result<(), Reject> {
+    let ship = parse_timestamp("actual_ship", row.actual_ship)?;
+    let delivery = parse_timestamp("actual_delivery", row.actual_delivery)?;
+
+    if CarrierMode::parse(row.carrier_mode).is_none() {
+        return Err(Reject::new(
+            RejectReason::UnknownMode,
+            format!("carrier_mode '{}'", row.carrier_mode),
+        ));
+    }
+    if OtdDesignation::parse(row.otd_designation).is_none() {
+        return Err(Reject::new(
+            RejectReason::UnknownOtd,
+            format!("otd_designation '{}'", row.otd_designation),
+        ));
+    }
+
+    for (field, zip) in [("origin_zip_3d", row.origin_zip3), ("dest_zip_3d", row.dest_zip3)] {
+        if !is_valid_zip3(zip) {
+            return Err(Reject::new(
+                RejectReason::BadZip3,
+                format!("{} '{}'", field, zip),
+            ));
+        }
+    }
+
+    if delivery < ship {
+        return Err(Reject::new(
+            RejectReason::DeliveryBeforeShip,
+            format!("delivered {} before ship {}", row.actual_delivery, row.actual_ship),
+        ));
+    }
+
+    let (low, high) = transit_day_bounds(ship, delivery);
+    if row.actual_transit_days < low || row.actual_transit_days > high {
+        return Err(Reject::new(
+            RejectReason::TransitDaysMismatch,
+            format!(
+                "actual_transit_days {} outside {}..={} implied by dates",
+                row.actual_transit_days, low, high
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+fn parse_timestamp(field: &str, value: &str) -> Result<NaiveDateTime, Reject> {
+    NaiveDateTime::parse_from_str(value, DATETIME_FORMAT).map_err(|e| {
+        Reject::new(
+            RejectReason::ParseError,
+            format!("{} '{}': {}", field, value, e),
+        )
+    })
+}
+
+/// A ZIP3 is three digits, optionally followed by the masked "xx" suffix
+pub fn is_valid_zip3(zip: &str) -> bool {
+    let digits = zip.strip_suffix("xx").unwrap_or(zip);
+    digits.len() == 3 && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Range of transit-day values consistent with the ship and delivery dates.
+///
+/// Sources count either business or calendar days, and time-of-day can shift
+/// the count by one, so anything from business days - 1 through calendar
+/// days + 1 is accepted.
+pub fn transit_day_bounds(ship: NaiveDateTime, delivery: NaiveDateTime) -> (i32, i32) {
+    let calendar = (delivery.date() - ship.date()).num_days() as i32;
+    let business = business_days_between(ship, delivery);
+    ((business - 1).max(0), calendar + 1)
+}
+
+/// Weekdays elapsed after the ship date up to and including the delivery date
+fn business_days_between(ship: NaiveDateTime, delivery: NaiveDateTime) -> i32 {
+    ship.date()
+        .iter_days()
+        .skip(1)
+        .take_while(|d| *d <= delivery.date())
+        .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun))
+        .count() as i32
+}
+
+// ============================================================================
+// Report
+// ============================================================================
+
+/// Summary of a validation pass, serializable as the JSON report
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub source: String,
+    pub rows_checked: u64,
+    pub accepted: u64,
+    pub rejected: u64,
+    pub by_reason: BTreeMap<RejectReason, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejects_file: Option<PathBuf>,
+}
+
+impl ValidationReport {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn accept(&mut self) {
+        self.rows_checked += 1;
+        self.accepted += 1;
+    }
+
+    pub fn reject(&mut self, reason: RejectReason) {
+        self.rows_checked += 1;
+        self.rejected += 1;
+        *self.by_reason.entry(reason).or_insert(0) += 1;
+    }
+
+    /// Share of checked rows that were rejected
+    pub fn reject_rate(&self) -> f64 {
+        if self.rows_checked == 0 {
+            0.0
+        } else {
+            self.rejected as f64 / self.rows_checked as f64
+        }
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn print_summary(&self) {
+        println!("Validation summary for {}", self.source);
+        println!("  Rows checked: {}", self.rows_checked);
+        println!("  Accepted:     {}", self.accepted);
+        println!(
+            "  Rejected:     {} ({:.2}%)",
+            self.rejected,
+            self.reject_rate() * 100.0
+        );
+        for (reason, count) in &self.by_reason {
+            println!("    {:<24} {}", reason.as_str(), count);
+        }
+        if let Some(path) = &self.rejects_file {
+            println!("  Rejects file: {}", path.display());
+        }
+    }
+}
+
+// ============================================================================
+// Reject File
+// ============================================================================
+
+/// Default sidecar path: `<input>.rejects.csv` next to the input file
+pub fn default_rejects_path(input: &Path) -> PathBuf {
+    let mut name = input.file_stem().unwrap_or_default().to_os_string();
+    name.push(".rejects.csv");
+    input.with_file_name(name)
+}
+
+/// Writes rejected rows verbatim plus the reason columns
+pub struct RejectWriter {
+    writer: Writer<std::fs::File>,
+}
+
+impl RejectWriter {
+    /// Open the sidecar file. When `append` is set and the file already has
+    /// content (a resumed load), rows are appended without a second header.
+    pub fn open(path: &Path, headers: &StringRecord, append: bool) -> Result<Self> {
+        let existing = append && path.metadata().map(|m| m.len() > 0).unwrap_or(false);
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(existing)
+            .truncate(!existing)
+            .open(path)?;
+        let mut writer = WriterBuilder::new().flexible(true).from_writer(file);
+        if !existing {
+            let mut header = headers.clone();
+            header.push_field("reject_reason");
+            header.push_field("reject_detail");
+            writer.write_record(&header)?;
+        }
+        Ok(Self { writer })
+    }
+
+    pub fn write(&mut self, row: &StringRecord, reject: &Reject) -> Result<()> {
+        let mut out = row.clone();
+        out.push_field(reject.reason.as_str());
+        out.push_field(&reject.detail);
+        self.writer.write_record(&out)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> CsvRecord {
+        CsvRecord {
+            carrier_mode: "LTL".to_string(),
+            actual_ship: "2024-03-01 08:00:00".to_string(),
+            actual_delivery: "2024-03-05 09:00:00".to_string(),
+            carrier_posted_service_days: Some(3.0),
+            customer_distance: Some(500.0),
+            truckload_service_days: Some(2.0),
+            all_modes_goal_transit_days: 3,
+            actual_transit_days: 2,
+            otd_designation: "On Time".to_string(),
+            load_id_pseudo: "L1".to_string(),
+            carrier_pseudo: "C1".to_string(),
+            origin_zip_3d: "750xx".to_string(),
+            dest_zip_3d: "100xx".to_string(),
+            ship_dow: 4,
+            ship_week: 9,
+            ship_month: 3,
+            ship_year: 2024,
+            lane_zip3_pair: "750xx→100xx".to_string(),
+            lane_id: "750xx100xx".to_string(),
+            distance_bucket: "250-500".to_string(),
+        }
+    }
+
+    fn reason(r: &CsvRecord) -> Option<RejectReason> {
+        r.validate().err().map(|e| e.reason)
+    }
+
+    #[test]
+    fn test_valid_record_passes() {
+        assert_eq!(reason(&record()), None);
+    }
+
+    #[test]
+    fn test_reject_reasons() {
+        let mut r = record();
+        r.actual_ship = "03/01/2024".to_string();
+        assert_eq!(reason(&r), Some(RejectReason::ParseError));
+
+        let mut r = record();
+        r.carrier_mode = "Rail".to_string();
+        assert_eq!(reason(&r), Some(RejectReason::UnknownMode));
+
+        let mut r = record();
+        r.otd_designation = "Sometime".to_string();
+        assert_eq!(reason(&r), Some(RejectReason::UnknownOtd));
+
+        let mut r = record();
+        r.dest_zip_3d = "10x".to_string();
+        assert_eq!(reason(&r), Some(RejectReason::BadZip3));
+
+        let mut r = record();
+        r.actual_delivery = "2024-02-28 08:00:00".to_string();
+        assert_eq!(reason(&r), Some(RejectReason::DeliveryBeforeShip));
+
+        let mut r = record();
+        r.actual_transit_days = 9;
+        assert_eq!(reason(&r), Some(RejectReason::TransitDaysMismatch));
+    }
+
+    #[test]
+    fn test_transit_day_bounds_span_weekend() {
+        // Friday to Tuesday: 2 business days, 4 calendar days
+        let ship = NaiveDateTime::parse_from_str("2024-03-01 08:00:00", DATETIME_FORMAT).unwrap();
+        let delivery =
+            NaiveDateTime::parse_from_str("2024-03-05 09:00:00", DATETIME_FORMAT).unwrap();
+        assert_eq!(transit_day_bounds(ship, delivery), (1, 5));
+    }
+
+    #[test]
+    fn test_report_counts() {
+        let mut report = ValidationReport::new("x.csv");
+        report.accept();
+        report.reject(RejectReason::BadZip3);
+        report.reject(RejectReason::BadZip3);
+        assert_eq!(report.rows_checked, 3);
+        assert_eq!(report.by_reason[&RejectReason::BadZip3], 2);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["by_reason"]["bad_zip3"], 2);
+    }
+}